@@ -14,6 +14,8 @@ pub enum CliRequest {
         paths: Vec<String>,
         urls: Vec<String>,
         wait: bool,
+        #[serde(default)]
+        readonly: bool,
         open_new_workspace: Option<bool>,
         dev_server_token: Option<String>,
         env: Option<HashMap<String, String>>,
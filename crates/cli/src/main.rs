@@ -33,6 +33,9 @@ struct Args {
     /// Wait for all of the given paths to be opened/closed before exiting.
     #[arg(short, long)]
     wait: bool,
+    /// Open the given files without allowing them to be edited or saved.
+    #[arg(long)]
+    readonly: bool,
     /// Add files to the currently open workspace
     #[arg(short, long, overrides_with = "new")]
     add: bool,
@@ -42,6 +45,7 @@ struct Args {
     /// A sequence of space-separated paths that you want to open.
     ///
     /// Use `path:line:row` syntax to open a file at a specific location.
+    /// A preceding `+line` or `+line:row` argument works the same way for the path after it.
     /// Non-existing paths and directories will ignore `:line:row` suffix.
     paths_with_position: Vec<String>,
     /// Print Zed's version and the app path.
@@ -128,8 +132,11 @@ fn main() -> Result<()> {
     let mut paths = vec![];
     let mut urls = vec![];
     let mut stdin_tmp_file: Option<fs::File> = None;
+    let mut position = None;
     for path in args.paths_with_position.iter() {
-        if path.starts_with("zed://")
+        if let Some(row_column) = PathWithPosition::parse_row_column_argument(path) {
+            position = Some(row_column);
+        } else if path.starts_with("zed://")
             || path.starts_with("http://")
             || path.starts_with("https://")
             || path.starts_with("file://")
@@ -141,10 +148,22 @@ fn main() -> Result<()> {
             paths.push(file.path().to_string_lossy().to_string());
             let (file, _) = file.keep()?;
             stdin_tmp_file = Some(file);
+        } else if let Some((row, column)) = position.take() {
+            let path = PathWithPosition {
+                path: PathBuf::from(path),
+                row: Some(row),
+                column,
+            };
+            paths.push(parse_path_with_position(
+                &path.to_string(|path| path.display().to_string()),
+            )?)
         } else {
             paths.push(parse_path_with_position(path)?)
         }
     }
+    if let Some((row, _)) = position {
+        eprintln!("Ignoring +{row} argument that isn't followed by a path");
+    }
 
     let sender: JoinHandle<anyhow::Result<()>> = thread::spawn({
         let exit_status = exit_status.clone();
@@ -156,6 +175,7 @@ fn main() -> Result<()> {
                 paths,
                 urls,
                 wait: args.wait,
+                readonly: args.readonly,
                 open_new_workspace,
                 dev_server_token: args.dev_server_token,
                 env,
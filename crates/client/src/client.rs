@@ -14,9 +14,10 @@ use async_tungstenite::tungstenite::{
 };
 use chrono::{DateTime, Utc};
 use clock::SystemClock;
+use fs::Fs;
 use futures::{
-    channel::oneshot, future::BoxFuture, AsyncReadExt, FutureExt, SinkExt, Stream, StreamExt,
-    TryFutureExt as _, TryStreamExt,
+    channel::oneshot, future::BoxFuture, io::BufReader, AsyncReadExt, FutureExt, SinkExt, Stream,
+    StreamExt, TryFutureExt as _, TryStreamExt,
 };
 use gpui::{actions, AppContext, AsyncAppContext, Global, Model, Task, WeakModel};
 use http_client::{AsyncBody, HttpClient, HttpClientWithUrl};
@@ -28,7 +29,7 @@ use release_channel::{AppVersion, ReleaseChannel};
 use rpc::proto::{AnyTypedEnvelope, EnvelopedMessage, PeerId, RequestMessage};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use settings::{Settings, SettingsSources};
+use settings::{Settings, SettingsSources, SettingsStore};
 use socks::connect_socks_proxy_stream;
 use std::fmt;
 use std::pin::Pin;
@@ -40,7 +41,7 @@ use std::{
     marker::PhantomData,
     path::PathBuf,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc, LazyLock, Weak,
     },
     time::{Duration, Instant},
@@ -67,6 +68,18 @@ static ZED_SERVER_URL: LazyLock<Option<String>> =
     LazyLock::new(|| std::env::var("ZED_SERVER_URL").ok());
 static ZED_RPC_URL: LazyLock<Option<String>> = LazyLock::new(|| std::env::var("ZED_RPC_URL").ok());
 
+/// The capacity of the buffer wrapping the RPC socket, so that the websocket layer
+/// can decode many small frames without a read from the socket for each one.
+pub const DEFAULT_RPC_READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Overrides [`DEFAULT_RPC_READ_BUFFER_SIZE`]. Zero disables buffering.
+static ZED_RPC_READ_BUFFER_SIZE: LazyLock<usize> = LazyLock::new(|| {
+    std::env::var("ZED_RPC_READ_BUFFER_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(DEFAULT_RPC_READ_BUFFER_SIZE)
+});
+
 /// An environment variable whose presence indicates that the development auth
 /// provider should be used.
 ///
@@ -99,14 +112,40 @@ pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(20);
 
 actions!(client, [SignIn, SignOut, Reconnect]);
 
+/// Set when Zed is launched with `--offline`, overriding the `rpc_enabled` setting.
+static FORCE_OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Disables all connections to the collaboration server for the rest of the process.
+pub fn force_offline() {
+    FORCE_OFFLINE.store(true, Ordering::SeqCst);
+}
+
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ClientSettingsContent {
     server_url: Option<String>,
+    rpc_url: Option<String>,
+    pub rpc_enabled: Option<bool>,
+    pub reconnect_jitter: Option<f32>,
 }
 
 #[derive(Deserialize)]
 pub struct ClientSettings {
     pub server_url: String,
+    pub rpc_url: Option<String>,
+    /// Whether the user chose to connect to the collaboration server, or `None` if they
+    /// haven't chosen yet.
+    pub rpc_enabled: Option<bool>,
+    /// How much each reconnection delay is randomly shortened or lengthened, as a fraction of
+    /// the delay, so that clients disconnected together don't all reconnect at once.
+    pub reconnect_jitter: f32,
+}
+
+impl ClientSettings {
+    /// Whether connections to the collaboration server are allowed. Until the user chooses,
+    /// they're only made when the user signs in explicitly.
+    pub fn rpc_allowed(&self) -> bool {
+        self.rpc_enabled != Some(false)
+    }
 }
 
 impl Settings for ClientSettings {
@@ -119,10 +158,29 @@ impl Settings for ClientSettings {
         if let Some(server_url) = &*ZED_SERVER_URL {
             result.server_url.clone_from(server_url)
         }
+        if FORCE_OFFLINE.load(Ordering::SeqCst) {
+            result.rpc_enabled = Some(false);
+        }
+        result.reconnect_jitter = result.reconnect_jitter.clamp(0., 1.);
         Ok(result)
     }
 }
 
+/// Computes how long to wait before the given reconnection attempt, counting from zero.
+///
+/// The delay doubles with each attempt up to [`MAX_RECONNECTION_DELAY`], and is then randomly
+/// scaled by up to `jitter` in either direction.
+pub fn reconnection_delay(attempt: u32, jitter: f32, rng: &mut impl Rng) -> Duration {
+    let delay = INITIAL_RECONNECTION_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_RECONNECTION_DELAY);
+    let jitter = jitter.clamp(0., 1.);
+    if jitter == 0. {
+        return delay;
+    }
+    delay.mul_f32(rng.gen_range(1. - jitter..=1. + jitter))
+}
+
 #[derive(Default, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ProxySettingsContent {
     proxy: Option<String>,
@@ -154,11 +212,27 @@ pub fn init_settings(cx: &mut AppContext) {
     ProxySettings::register(cx);
 }
 
+/// Records that the user chose to connect to the collaboration server, unless they already
+/// made a choice, so that Zed connects on its own from now on.
+pub fn enable_rpc_if_unset(cx: &mut AppContext) {
+    if ClientSettings::get_global(cx).rpc_enabled.is_some() {
+        return;
+    }
+    // Without a file system there is no settings file to record the choice in.
+    let Some(fs) = <dyn Fs>::try_global(cx) else {
+        return;
+    };
+    settings::update_settings_file::<ClientSettings>(fs, cx, |settings, _| {
+        settings.rpc_enabled = Some(true)
+    });
+}
+
 pub fn init(client: &Arc<Client>, cx: &mut AppContext) {
     let client = Arc::downgrade(client);
     cx.on_action({
         let client = client.clone();
         move |_: &SignIn, cx| {
+            enable_rpc_if_unset(cx);
             if let Some(client) = client.upgrade() {
                 cx.spawn(
                     |cx| async move { client.authenticate_and_connect(true, &cx).log_err().await },
@@ -191,6 +265,80 @@ pub fn init(client: &Arc<Client>, cx: &mut AppContext) {
             }
         }
     });
+
+    cx.on_app_quit({
+        let client = client.clone();
+        move |cx| {
+            let shutdown = client
+                .upgrade()
+                .map(|client| client.shutdown(&cx.to_async()));
+            async move {
+                if let Some(shutdown) = shutdown {
+                    shutdown.await;
+                }
+            }
+        }
+    })
+    .detach();
+
+    let mut rpc_url = ClientSettings::get_global(cx).rpc_url.clone();
+    let mut rpc_enabled = ClientSettings::get_global(cx).rpc_enabled;
+    cx.observe_global::<SettingsStore>(move |cx| {
+        let settings = ClientSettings::get_global(cx);
+        if settings.rpc_url == rpc_url && settings.rpc_enabled == rpc_enabled {
+            return;
+        }
+        rpc_url = settings.rpc_url.clone();
+        rpc_enabled = settings.rpc_enabled;
+
+        if let Some(client) = client.upgrade() {
+            if rpc_enabled == Some(false) {
+                log::info!("rpc disabled, disconnecting");
+                client.disconnect(&cx.to_async());
+            } else if client.status().borrow().is_connected() {
+                log::info!("rpc url changed, reconnecting");
+                client.reconnect(&cx.to_async());
+            }
+        }
+    })
+    .detach();
+}
+
+/// The address of the collaboration server's RPC endpoint, derived from its URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectAddr {
+    Tcp { host: String, port: u16, tls: bool },
+    Unix(PathBuf),
+}
+
+impl ConnectAddr {
+    /// Parses an RPC URL. `unix://` URLs name a socket path, while `tcp://`, `http://`
+    /// and `https://` URLs name a host, with `https://` connecting over TLS.
+    pub fn from_rpc_url(url: &Url) -> Result<Self> {
+        let tls = match url.scheme() {
+            "unix" => {
+                let path = PathBuf::from(url.path());
+                if url.path().is_empty() || url.path() == "/" {
+                    bail!("missing socket path in rpc url: {}", url);
+                }
+                return Ok(Self::Unix(path));
+            }
+            "https" => true,
+            "http" | "tcp" => false,
+            _ => bail!("invalid rpc url: {}", url),
+        };
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("missing host in rpc url"))?;
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| anyhow!("missing port in rpc url"))?;
+        Ok(Self::Tcp {
+            host: host.to_string(),
+            port,
+            tls,
+        })
+    }
 }
 
 struct GlobalClient(Arc<Client>);
@@ -298,9 +446,33 @@ impl Status {
     }
 }
 
+/// A summary of [`Status`] for connection indicators, which also counts reconnection attempts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClientConnectionState {
+    Connected,
+    /// The connection was lost and the client is trying to restore it. `attempt` counts from 1.
+    Reconnecting {
+        attempt: u32,
+    },
+    Disconnected,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ClientDebugSnapshot {
+    pub status: String,
+    pub peer: PeerDebugSnapshot,
+    /// The names of the message types this client has registered handlers for.
+    pub message_handlers: Vec<&'static str>,
+}
+
 struct ClientState {
     credentials: Option<Credentials>,
     status: (watch::Sender<Status>, watch::Receiver<Status>),
+    connection_state: (
+        watch::Sender<ClientConnectionState>,
+        watch::Receiver<ClientConnectionState>,
+    ),
+    reconnect_attempt: Option<u32>,
     _reconnect_task: Option<Task<()>>,
 }
 
@@ -348,11 +520,26 @@ trait CredentialsProvider {
     ) -> Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
 }
 
+impl ClientState {
+    fn update_connection_state(&mut self) {
+        let connection_state = match (&*self.status.1.borrow(), self.reconnect_attempt) {
+            (Status::Connected { .. }, _) => ClientConnectionState::Connected,
+            (_, Some(attempt)) => ClientConnectionState::Reconnecting { attempt },
+            (_, None) => ClientConnectionState::Disconnected,
+        };
+        if *self.connection_state.1.borrow() != connection_state {
+            *self.connection_state.0.borrow_mut() = connection_state;
+        }
+    }
+}
+
 impl Default for ClientState {
     fn default() -> Self {
         Self {
             credentials: None,
             status: watch::channel_with(Status::SignedOut),
+            connection_state: watch::channel_with(ClientConnectionState::Disconnected),
+            reconnect_attempt: None,
             _reconnect_task: None,
         }
     }
@@ -383,6 +570,7 @@ impl Drop for Subscription {
                     let mut state = client.handler_set.lock();
                     let _ = state.entity_types_by_message_type.remove(id);
                     let _ = state.message_handlers.remove(id);
+                    let _ = state.message_handler_names.remove(id);
                 }
             }
         }
@@ -620,6 +808,32 @@ impl Client {
         self.state.read().status.1.clone()
     }
 
+    pub fn connection_state(&self) -> ClientConnectionState {
+        *self.state.read().connection_state.1.borrow()
+    }
+
+    /// Yields the current [`ClientConnectionState`] and then each change to it.
+    pub fn connection_states(&self) -> watch::Receiver<ClientConnectionState> {
+        self.state.read().connection_state.1.clone()
+    }
+
+    /// The token of the credentials this client authenticated with, if any.
+    pub fn access_token(&self) -> Option<String> {
+        match self.state.read().credentials.as_ref()? {
+            Credentials::User { access_token, .. } => Some(access_token.clone()),
+            Credentials::DevServer { token } => Some(token.0.clone()),
+        }
+    }
+
+    /// Captures the state of this client's connections and message handlers for diagnostics.
+    pub fn debug_snapshot(&self) -> ClientDebugSnapshot {
+        ClientDebugSnapshot {
+            status: format!("{:?}", *self.status().borrow()),
+            peer: self.peer.debug_snapshot(),
+            message_handlers: self.handler_set.lock().message_handler_names(),
+        }
+    }
+
     fn set_status(self: &Arc<Self>, status: Status, cx: &AsyncAppContext) {
         log::info!("set status on client {}: {:?}", self.id(), status);
         let mut state = self.state.write();
@@ -628,8 +842,10 @@ impl Client {
         match status {
             Status::Connected { .. } => {
                 state._reconnect_task = None;
+                state.reconnect_attempt = None;
             }
             Status::ConnectionLost => {
+                state.reconnect_attempt = Some(1);
                 let this = self.clone();
                 state._reconnect_task = Some(cx.spawn(move |cx| async move {
                     #[cfg(any(test, feature = "test-support"))]
@@ -637,10 +853,16 @@ impl Client {
                     #[cfg(not(any(test, feature = "test-support")))]
                     let mut rng = StdRng::from_entropy();
 
-                    let mut delay = INITIAL_RECONNECTION_DELAY;
+                    let mut attempt = 0;
                     while let Err(error) = this.authenticate_and_connect(true, &cx).await {
                         log::error!("failed to connect {}", error);
                         if matches!(*this.status().borrow(), Status::ConnectionError) {
+                            let jitter = cx
+                                .update(|cx| ClientSettings::get_global(cx).reconnect_jitter)
+                                .unwrap_or(0.);
+                            let delay = reconnection_delay(attempt, jitter, &mut rng);
+                            attempt += 1;
+                            this.state.write().reconnect_attempt = Some(attempt + 1);
                             this.set_status(
                                 Status::ReconnectionError {
                                     next_reconnection: Instant::now() + delay,
@@ -648,22 +870,27 @@ impl Client {
                                 &cx,
                             );
                             cx.background_executor().timer(delay).await;
-                            delay = delay
-                                .mul_f32(rng.gen_range(0.5..=2.5))
-                                .max(INITIAL_RECONNECTION_DELAY)
-                                .min(MAX_RECONNECTION_DELAY);
                         } else {
                             break;
                         }
                     }
+
+                    // The loop also gives up without connecting, e.g. when rpc gets disabled, so
+                    // stop reporting reconnection attempts that won't happen.
+                    let mut state = this.state.write();
+                    state.reconnect_attempt = None;
+                    state.update_connection_state();
                 }));
             }
             Status::SignedOut | Status::UpgradeRequired => {
                 self.telemetry.set_authenticated_user_info(None, false);
                 state._reconnect_task.take();
+                state.reconnect_attempt = None;
             }
             _ => {}
         }
+
+        state.update_connection_state();
     }
 
     pub fn subscribe_to_entity<T>(
@@ -729,6 +956,7 @@ impl Client {
         state
             .models_by_message_type
             .insert(message_type_id, entity.into());
+        state.message_handler_names.insert(message_type_id, M::NAME);
 
         let prev_handler = state.message_handlers.insert(
             message_type_id,
@@ -805,6 +1033,10 @@ impl Client {
         try_provider: bool,
         cx: &AsyncAppContext,
     ) -> anyhow::Result<()> {
+        if !cx.update(|cx| ClientSettings::get_global(cx).rpc_allowed())? {
+            return Err(anyhow!("not connecting because rpc is disabled"));
+        }
+
         let was_disconnected = match *self.status().borrow() {
             Status::SignedOut => true,
             Status::ConnectionError
@@ -1032,6 +1264,7 @@ impl Client {
         &self,
         http: Arc<HttpClientWithUrl>,
         release_channel: Option<ReleaseChannel>,
+        configured_url: Option<String>,
     ) -> impl Future<Output = Result<Url>> {
         #[cfg(any(test, feature = "test-support"))]
         let url_override = self.rpc_url.read().clone();
@@ -1046,6 +1279,10 @@ impl Client {
                 return Url::parse(url).context("invalid rpc url");
             }
 
+            if let Some(url) = configured_url {
+                return Url::parse(&url).context("invalid rpc url");
+            }
+
             let mut url = http.build_url("/rpc");
             if let Some(preview_param) =
                 release_channel.and_then(|channel| channel.release_query_param())
@@ -1088,39 +1325,43 @@ impl Client {
             .ok()
             .unwrap_or_default();
 
+        let configured_url = cx
+            .update(|cx| ClientSettings::get_global(cx).rpc_url.clone())
+            .ok()
+            .flatten();
+
         let http = self.http.clone();
         let proxy = http.proxy().cloned();
         let credentials = credentials.clone();
-        let rpc_url = self.rpc_url(http, release_channel);
+        let rpc_url = self.rpc_url(http, release_channel, configured_url);
         cx.background_executor().spawn(async move {
-            use HttpOrHttps::*;
-
-            #[derive(Debug)]
-            enum HttpOrHttps {
-                Http,
-                Https,
-            }
-
-            let mut rpc_url = rpc_url.await?;
-            let url_scheme = match rpc_url.scheme() {
-                "https" => Https,
-                "http" => Http,
-                _ => Err(anyhow!("invalid rpc url: {}", rpc_url))?,
+            let rpc_url = rpc_url.await?;
+            let addr = ConnectAddr::from_rpc_url(&rpc_url)?;
+            let (stream, rpc_url, tls) = match &addr {
+                ConnectAddr::Tcp { host, port, tls } => {
+                    let stream =
+                        connect_socks_proxy_stream(proxy.as_ref(), (host.as_str(), *port)).await?;
+                    let scheme = if *tls { "wss" } else { "ws" };
+                    let mut websocket_url = Url::parse(&format!("{scheme}://{host}:{port}"))
+                        .context("invalid rpc url")?;
+                    websocket_url.set_path(rpc_url.path());
+                    websocket_url.set_query(rpc_url.query());
+                    (stream, websocket_url, *tls)
+                }
+                #[cfg(unix)]
+                ConnectAddr::Unix(path) => {
+                    let stream = Box::new(smol::net::unix::UnixStream::connect(path).await?)
+                        as Box<dyn socks::AsyncReadWrite>;
+                    (stream, Url::parse("ws://localhost/rpc").unwrap(), false)
+                }
+                #[cfg(not(unix))]
+                ConnectAddr::Unix(_) => {
+                    Err(anyhow!("unix sockets are not supported on this platform"))?
+                }
             };
-            let rpc_host = rpc_url
-                .host_str()
-                .zip(rpc_url.port_or_known_default())
-                .ok_or_else(|| anyhow!("missing host in rpc url"))?;
-            let stream = connect_socks_proxy_stream(proxy.as_ref(), rpc_host).await?;
 
             log::info!("connected to rpc endpoint {}", rpc_url);
-
-            rpc_url
-                .set_scheme(match url_scheme {
-                    Https => "wss",
-                    Http => "ws",
-                })
-                .unwrap();
+            let stream = BufReader::with_capacity(*ZED_RPC_READ_BUFFER_SIZE, stream);
 
             // We call `into_client_request` to let `tungstenite` construct the WebSocket request
             // for us from the RPC URL.
@@ -1144,24 +1385,21 @@ impl Client {
                 HeaderValue::from_str(release_channel.map(|r| r.dev_name()).unwrap_or("unknown"))?,
             );
 
-            match url_scheme {
-                Https => {
-                    let (stream, _) =
-                        async_tungstenite::async_std::client_async_tls(request, stream).await?;
-                    Ok(Connection::new(
-                        stream
-                            .map_err(|error| anyhow!(error))
-                            .sink_map_err(|error| anyhow!(error)),
-                    ))
-                }
-                Http => {
-                    let (stream, _) = async_tungstenite::client_async(request, stream).await?;
-                    Ok(Connection::new(
-                        stream
-                            .map_err(|error| anyhow!(error))
-                            .sink_map_err(|error| anyhow!(error)),
-                    ))
-                }
+            if tls {
+                let (stream, _) =
+                    async_tungstenite::async_std::client_async_tls(request, stream).await?;
+                Ok(Connection::new(
+                    stream
+                        .map_err(|error| anyhow!(error))
+                        .sink_map_err(|error| anyhow!(error)),
+                ))
+            } else {
+                let (stream, _) = async_tungstenite::client_async(request, stream).await?;
+                Ok(Connection::new(
+                    stream
+                        .map_err(|error| anyhow!(error))
+                        .sink_map_err(|error| anyhow!(error)),
+                ))
             }
         })
     }
@@ -1426,6 +1664,14 @@ impl Client {
         self.set_status(Status::ConnectionLost, cx);
     }
 
+    /// Signs out, unregisters all message handlers, and disconnects, resolving once the
+    /// connections have fully closed. Used for a clean exit when the app quits.
+    pub fn shutdown(self: &Arc<Self>, cx: &AsyncAppContext) -> impl Future<Output = ()> {
+        self.handler_set.lock().clear();
+        self.set_status(Status::SignedOut, cx);
+        self.peer.shutdown_all()
+    }
+
     fn connection_id(&self) -> Result<ConnectionId> {
         if let Status::Connected { connection_id, .. } = *self.status().borrow() {
             Ok(connection_id)
@@ -1447,6 +1693,15 @@ impl Client {
             .map_ok(|envelope| envelope.payload)
     }
 
+    /// Measures the round-trip time to the server, e.g. for a connection latency indicator.
+    pub fn ping(&self, cx: &AsyncAppContext) -> impl Future<Output = Result<Duration>> {
+        let executor = cx.background_executor().clone();
+        let response = self
+            .connection_id()
+            .map(|conn_id| self.peer.ping(conn_id, move || executor.now()));
+        async move { response?.await }
+    }
+
     pub fn request_stream<T: RequestMessage>(
         &self,
         request: T,
@@ -1767,7 +2022,6 @@ mod tests {
     use http_client::FakeHttpClient;
     use parking_lot::Mutex;
     use proto::TypedEnvelope;
-    use settings::SettingsStore;
     use std::future;
 
     #[gpui::test(iterations = 10)]
@@ -1811,6 +2065,140 @@ mod tests {
         assert_eq!(server.auth_count(), 2); // Client re-authenticated due to an invalid token
     }
 
+    #[gpui::test(iterations = 10)]
+    async fn test_connection_state_transitions(cx: &mut TestAppContext) {
+        init_test(cx);
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let states = Arc::new(Mutex::new(Vec::new()));
+        cx.executor()
+            .spawn({
+                let states = states.clone();
+                let mut connection_states = client.connection_states();
+                async move {
+                    while let Some(state) = connection_states.next().await {
+                        states.lock().push(state);
+                    }
+                }
+            })
+            .detach();
+
+        let server = FakeServer::for_client(5, &client, cx).await;
+        let mut status = client.status();
+        assert!(matches!(
+            status.next().await,
+            Some(Status::Connected { .. })
+        ));
+
+        server.forbid_connections();
+        server.disconnect();
+        while !matches!(status.next().await, Some(Status::ReconnectionError { .. })) {}
+        assert_eq!(
+            client.connection_state(),
+            ClientConnectionState::Reconnecting { attempt: 2 }
+        );
+
+        server.allow_connections();
+        cx.executor().advance_clock(MAX_RECONNECTION_DELAY * 2);
+        while !matches!(status.next().await, Some(Status::Connected { .. })) {}
+        cx.executor().run_until_parked();
+
+        // Consecutive reconnection attempts are reported as one transition.
+        let mut transitions = states.lock().clone();
+        transitions.dedup_by_key(|state| std::mem::discriminant(state));
+        assert!(
+            matches!(
+                transitions.as_slice(),
+                [
+                    ClientConnectionState::Disconnected,
+                    ClientConnectionState::Connected,
+                    ClientConnectionState::Reconnecting { .. },
+                    ClientConnectionState::Connected
+                ]
+            ),
+            "unexpected transitions {:?}",
+            states.lock()
+        );
+    }
+
+    #[gpui::test]
+    async fn test_connection_state_after_giving_up_reconnecting(cx: &mut TestAppContext) {
+        init_test(cx);
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let server = FakeServer::for_client(5, &client, cx).await;
+        let mut status = client.status();
+        assert!(matches!(
+            status.next().await,
+            Some(Status::Connected { .. })
+        ));
+
+        server.forbid_connections();
+        server.disconnect();
+        while !matches!(status.next().await, Some(Status::ReconnectionError { .. })) {}
+        assert_eq!(
+            client.connection_state(),
+            ClientConnectionState::Reconnecting { attempt: 2 }
+        );
+
+        // Disabling rpc makes the next attempt fail without a connection error, which ends
+        // the reconnection loop.
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<ClientSettings>(cx, |settings| {
+                    settings.rpc_enabled = Some(false);
+                });
+            });
+        });
+        cx.executor().advance_clock(MAX_RECONNECTION_DELAY * 2);
+        cx.executor().run_until_parked();
+        assert!(matches!(
+            *client.status().borrow(),
+            Status::ReconnectionError { .. }
+        ));
+        assert_eq!(
+            client.connection_state(),
+            ClientConnectionState::Disconnected
+        );
+    }
+
+    #[test]
+    fn test_connect_addr_from_rpc_url() {
+        assert_eq!(
+            ConnectAddr::from_rpc_url(&Url::parse("unix:///tmp/zed/collab.sock").unwrap()).unwrap(),
+            ConnectAddr::Unix(PathBuf::from("/tmp/zed/collab.sock"))
+        );
+        assert_eq!(
+            ConnectAddr::from_rpc_url(&Url::parse("tcp://127.0.0.1:8080/rpc").unwrap()).unwrap(),
+            ConnectAddr::Tcp {
+                host: "127.0.0.1".into(),
+                port: 8080,
+                tls: false
+            }
+        );
+        assert_eq!(
+            ConnectAddr::from_rpc_url(&Url::parse("https://collab.zed.dev/rpc").unwrap()).unwrap(),
+            ConnectAddr::Tcp {
+                host: "collab.zed.dev".into(),
+                port: 443,
+                tls: true
+            }
+        );
+        assert!(ConnectAddr::from_rpc_url(&Url::parse("unix://").unwrap()).is_err());
+        assert!(ConnectAddr::from_rpc_url(&Url::parse("tcp://localhost").unwrap()).is_err());
+        assert!(ConnectAddr::from_rpc_url(&Url::parse("ftp://localhost").unwrap()).is_err());
+    }
+
     #[gpui::test(iterations = 10)]
     async fn test_connection_timeout(executor: BackgroundExecutor, cx: &mut TestAppContext) {
         init_test(cx);
@@ -1886,6 +2274,168 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_reconnection_delay_jitter() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for attempt in 0..10 {
+            let base = INITIAL_RECONNECTION_DELAY
+                .saturating_mul(2u32.pow(attempt))
+                .min(MAX_RECONNECTION_DELAY);
+            assert_eq!(reconnection_delay(attempt, 0., &mut rng), base);
+
+            for _ in 0..100 {
+                let delay = reconnection_delay(attempt, 0.25, &mut rng);
+                assert!(
+                    delay >= base.mul_f32(0.75) && delay <= base.mul_f32(1.25),
+                    "delay {delay:?} for attempt {attempt} is outside the jittered bounds"
+                );
+            }
+        }
+
+        // Delays stay spread out once the maximum is reached.
+        let delays = (0..10)
+            .map(|_| reconnection_delay(20, 0.5, &mut rng))
+            .collect::<Vec<_>>();
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+    }
+
+    #[gpui::test]
+    async fn test_no_connection_when_offline(cx: &mut TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<ClientSettings>(cx, |settings| {
+                    settings.rpc_enabled = Some(false);
+                });
+            });
+        });
+
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let connection_attempts = Arc::new(Mutex::new(0));
+        client.override_authenticate(|cx| {
+            cx.background_executor().spawn(async move {
+                Ok(Credentials::User {
+                    user_id: 1,
+                    access_token: "token".into(),
+                })
+            })
+        });
+        client.override_establish_connection({
+            let connection_attempts = connection_attempts.clone();
+            move |_, cx| {
+                *connection_attempts.lock() += 1;
+                cx.background_executor()
+                    .spawn(async move { Err(EstablishConnectionError::Unauthorized) })
+            }
+        });
+
+        let result = client.authenticate_and_connect(false, &cx.to_async()).await;
+        assert!(result.is_err());
+        assert_eq!(*connection_attempts.lock(), 0);
+        assert!(matches!(*client.status().borrow(), Status::SignedOut));
+        assert_eq!(client.debug_snapshot().peer.metrics.connection_count, 0);
+    }
+
+    #[test]
+    fn test_buffered_rpc_stream_reduces_reads() {
+        use async_tungstenite::{tungstenite::protocol::Role, WebSocketStream};
+        use futures::{io::Cursor, AsyncRead, AsyncWrite};
+        use std::{
+            io,
+            task::{Context, Poll},
+        };
+
+        struct CountingStream {
+            data: Cursor<Vec<u8>>,
+            reads: Arc<AtomicU64>,
+        }
+
+        impl AsyncRead for CountingStream {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<io::Result<usize>> {
+                self.reads.fetch_add(1, Ordering::SeqCst);
+                Pin::new(&mut self.data).poll_read(cx, buf)
+            }
+        }
+
+        impl AsyncWrite for CountingStream {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                _: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                Poll::Ready(Ok(buf.len()))
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        // Many small unmasked binary frames, as sent by the server, followed by a
+        // frame that is cut off before its payload ends.
+        let mut data = Vec::new();
+        for i in 0..2000 {
+            let payload = format!("message {i}");
+            data.extend([0x82, payload.len() as u8]);
+            data.extend(payload.as_bytes());
+        }
+        data.extend([0x82, 10, b'a', b'b']);
+
+        fn read_all<S: AsyncRead + AsyncWrite + Unpin>(stream: S) -> (Vec<Vec<u8>>, bool) {
+            smol::block_on(async move {
+                let mut stream = WebSocketStream::from_raw_socket(stream, Role::Client, None).await;
+                let mut messages = Vec::new();
+                while let Some(message) = stream.next().await {
+                    match message {
+                        Ok(message) => messages.push(message.into_data()),
+                        Err(_) => return (messages, true),
+                    }
+                }
+                (messages, false)
+            })
+        }
+
+        let unbuffered_reads = Arc::new(AtomicU64::new(0));
+        let (unbuffered_messages, unbuffered_error) = read_all(CountingStream {
+            data: Cursor::new(data.clone()),
+            reads: unbuffered_reads.clone(),
+        });
+
+        let buffered_reads = Arc::new(AtomicU64::new(0));
+        let (buffered_messages, buffered_error) = read_all(BufReader::with_capacity(
+            DEFAULT_RPC_READ_BUFFER_SIZE,
+            CountingStream {
+                data: Cursor::new(data),
+                reads: buffered_reads.clone(),
+            },
+        ));
+
+        assert_eq!(buffered_messages.len(), 2000);
+        assert_eq!(buffered_messages, unbuffered_messages);
+        assert_eq!(buffered_messages[1999], b"message 1999");
+        assert!(unbuffered_error && buffered_error);
+        assert!(
+            buffered_reads.load(Ordering::SeqCst) < unbuffered_reads.load(Ordering::SeqCst),
+            "buffered: {:?}, unbuffered: {:?}",
+            buffered_reads,
+            unbuffered_reads
+        );
+    }
+
     #[gpui::test(iterations = 10)]
     async fn test_authenticating_more_than_once(
         cx: &mut TestAppContext,
@@ -2028,6 +2578,59 @@ mod tests {
         done_rx2.next().await.unwrap();
     }
 
+    #[gpui::test]
+    async fn test_debug_snapshot_lists_message_handlers(cx: &mut TestAppContext) {
+        init_test(cx);
+        let user_id = 5;
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let _server = FakeServer::for_client(user_id, &client, cx).await;
+
+        let model = cx.new_model(|_| TestModel::default());
+        let subscription = client.add_message_handler(
+            model.downgrade(),
+            move |_, _: TypedEnvelope<proto::Ping>, _| async { Ok(()) },
+        );
+        let snapshot = client.debug_snapshot();
+        assert!(snapshot.message_handlers.contains(&"Ping"));
+        assert_eq!(snapshot.peer.metrics.connection_count, 1);
+
+        drop(subscription);
+        assert!(!client.debug_snapshot().message_handlers.contains(&"Ping"));
+    }
+
+    #[gpui::test]
+    async fn test_shutdown(cx: &mut TestAppContext) {
+        init_test(cx);
+        let user_id = 5;
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(FakeSystemClock::default()),
+                FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let _server = FakeServer::for_client(user_id, &client, cx).await;
+
+        let model = cx.new_model(|_| TestModel::default());
+        let _subscription = client.add_message_handler(
+            model.downgrade(),
+            move |_, _: TypedEnvelope<proto::Ping>, _| async { Ok(()) },
+        );
+        assert_eq!(client.debug_snapshot().peer.metrics.connection_count, 1);
+
+        client.shutdown(&cx.to_async()).await;
+        let snapshot = client.debug_snapshot();
+        assert_eq!(snapshot.peer.metrics.connection_count, 0);
+        assert!(snapshot.message_handlers.is_empty());
+        assert!(matches!(*client.status().borrow(), Status::SignedOut));
+    }
+
     #[gpui::test]
     async fn test_dropping_subscription_in_handler(cx: &mut TestAppContext) {
         init_test(cx);
@@ -2,7 +2,7 @@ use crate::{collab_panel, ChatPanelSettings};
 use anyhow::Result;
 use call::{room, ActiveCall};
 use channel::{ChannelChat, ChannelChatEvent, ChannelMessage, ChannelMessageId, ChannelStore};
-use client::{ChannelId, Client};
+use client::{ChannelId, Client, ClientSettings};
 use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
 use editor::{actions, Editor};
@@ -1135,7 +1135,9 @@ impl Panel for ChatPanel {
     }
 
     fn icon(&self, cx: &WindowContext) -> Option<ui::IconName> {
-        Some(ui::IconName::MessageBubbles).filter(|_| ChatPanelSettings::get_global(cx).button)
+        Some(ui::IconName::MessageBubbles).filter(|_| {
+            ChatPanelSettings::get_global(cx).button && ClientSettings::get_global(cx).rpc_allowed()
+        })
     }
 
     fn icon_tooltip(&self, _cx: &WindowContext) -> Option<&'static str> {
@@ -5,7 +5,7 @@ use self::channel_modal::ChannelModal;
 use crate::{channel_view::ChannelView, chat_panel::ChatPanel, CollaborationPanelSettings};
 use call::ActiveCall;
 use channel::{Channel, ChannelEvent, ChannelStore};
-use client::{ChannelId, Client, Contact, ProjectId, User, UserStore};
+use client::{ChannelId, Client, ClientSettings, Contact, ProjectId, User, UserStore};
 use contact_finder::ContactFinder;
 use db::kvp::KEY_VALUE_STORE;
 use editor::{Editor, EditorElement, EditorStyle};
@@ -2829,9 +2829,9 @@ impl Panel for CollabPanel {
     }
 
     fn icon(&self, cx: &gpui::WindowContext) -> Option<ui::IconName> {
-        CollaborationPanelSettings::get_global(cx)
-            .button
-            .then_some(ui::IconName::Collab)
+        (CollaborationPanelSettings::get_global(cx).button
+            && ClientSettings::get_global(cx).rpc_allowed())
+        .then_some(ui::IconName::Collab)
     }
 
     fn icon_tooltip(&self, _cx: &WindowContext) -> Option<&'static str> {
@@ -1,7 +1,7 @@
 use crate::{chat_panel::ChatPanel, NotificationPanelSettings};
 use anyhow::Result;
 use channel::ChannelStore;
-use client::{ChannelId, Client, Notification, User, UserStore};
+use client::{ChannelId, Client, ClientSettings, Notification, User, UserStore};
 use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
 use futures::StreamExt;
@@ -701,7 +701,8 @@ impl Panel for NotificationPanel {
     }
 
     fn icon(&self, cx: &gpui::WindowContext) -> Option<IconName> {
-        let show_button = NotificationPanelSettings::get_global(cx).button;
+        let show_button = NotificationPanelSettings::get_global(cx).button
+            && ClientSettings::get_global(cx).rpc_allowed();
         if !show_button {
             return None;
         }
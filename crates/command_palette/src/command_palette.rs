@@ -1,5 +1,6 @@
 use std::{
     cmp::{self, Reverse},
+    collections::VecDeque,
     sync::Arc,
     time::Duration,
 };
@@ -28,6 +29,7 @@ actions!(command_palette, [Toggle]);
 pub fn init(cx: &mut AppContext) {
     client::init_settings(cx);
     cx.set_global(HitCounts::default());
+    cx.set_global(RecentCommands::default());
     command_palette_hooks::init(cx);
     cx.observe_new_views(CommandPalette::register).detach();
 }
@@ -165,6 +167,23 @@ struct HitCounts(HashMap<String, usize>);
 
 impl Global for HitCounts {}
 
+/// The number of recently invoked commands shown at the top of an empty command palette.
+const MAX_RECENT_COMMANDS: usize = 5;
+
+/// Names of the commands most recently invoked via the command palette, most recent first.
+#[derive(Default, Clone)]
+struct RecentCommands(VecDeque<String>);
+
+impl Global for RecentCommands {}
+
+impl RecentCommands {
+    fn push(&mut self, name: String) {
+        self.0.retain(|recent| *recent != name);
+        self.0.push_front(name);
+        self.0.truncate(MAX_RECENT_COMMANDS);
+    }
+}
+
 impl CommandPaletteDelegate {
     fn new(
         command_palette: WeakView<CommandPalette>,
@@ -272,6 +291,7 @@ impl PickerDelegate for CommandPaletteDelegate {
         let task = cx.background_executor().spawn({
             let mut commands = self.all_commands.clone();
             let hit_counts = cx.global::<HitCounts>().clone();
+            let recent_commands = cx.global::<RecentCommands>().clone();
             let executor = cx.background_executor().clone();
             let query = trim_consecutive_whitespaces(query.as_str());
             async move {
@@ -281,6 +301,15 @@ impl PickerDelegate for CommandPaletteDelegate {
                         action.name.clone(),
                     )
                 });
+                if query.is_empty() {
+                    commands.sort_by_key(|command| {
+                        recent_commands
+                            .0
+                            .iter()
+                            .position(|recent| *recent == command.name)
+                            .unwrap_or(usize::MAX)
+                    });
+                }
 
                 let candidates = commands
                     .iter()
@@ -378,6 +407,9 @@ impl PickerDelegate for CommandPaletteDelegate {
 
         self.matches.clear();
         self.commands.clear();
+        RecentCommands::update_global(cx, |recent_commands, _cx| {
+            recent_commands.push(command.name.clone());
+        });
         HitCounts::update_global(cx, |hit_counts, _cx| {
             *hit_counts.0.entry(command.name).or_default() += 1;
         });
@@ -453,12 +485,15 @@ impl std::fmt::Debug for Command {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    };
 
     use super::*;
     use editor::Editor;
     use go_to_line::GoToLine;
-    use gpui::TestAppContext;
+    use gpui::{TestAppContext, VisualTestContext};
     use language::Point;
     use project::Project;
     use settings::KeymapFile;
@@ -551,6 +586,118 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_recent_commands(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        let editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_text("abc", cx);
+            editor
+        });
+        workspace.update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(editor.clone()), None, true, cx);
+            editor.update(cx, |editor, cx| editor.focus(cx))
+        });
+
+        let open_palette = |cx: &mut VisualTestContext| {
+            cx.simulate_keystrokes("cmd-shift-p");
+            workspace.update(cx, |workspace, cx| {
+                workspace
+                    .active_modal::<CommandPalette>(cx)
+                    .unwrap()
+                    .read(cx)
+                    .picker
+                    .clone()
+            })
+        };
+        let leading_commands = |palette: &View<Picker<CommandPaletteDelegate>>,
+                                cx: &mut VisualTestContext| {
+            palette.update(cx, |palette, _| {
+                palette.delegate.matches[..2]
+                    .iter()
+                    .map(|m| m.string.clone())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        open_palette(cx);
+        cx.simulate_input("bcksp");
+        cx.simulate_keystrokes("enter");
+        open_palette(cx);
+        cx.simulate_input("select all");
+        cx.simulate_keystrokes("enter");
+
+        let palette = open_palette(cx);
+        assert_eq!(
+            leading_commands(&palette, cx),
+            ["editor: select all", "editor: backspace"]
+        );
+        cx.simulate_input("bcksp");
+        cx.simulate_keystrokes("enter");
+
+        // Invoking a recent command again moves it to the front without duplicating it.
+        let palette = open_palette(cx);
+        assert_eq!(
+            leading_commands(&palette, cx),
+            ["editor: backspace", "editor: select all"]
+        );
+        palette.update(cx, |palette, _| {
+            assert_eq!(
+                palette
+                    .delegate
+                    .matches
+                    .iter()
+                    .filter(|m| m.string == "editor: backspace")
+                    .count(),
+                1
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_global_action(cx: &mut TestAppContext) {
+        gpui::actions!(test_commands, [ReticulateSplines]);
+
+        let app_state = init_test(cx);
+        let reticulated = Arc::new(AtomicUsize::new(0));
+        cx.update(|cx| {
+            let reticulated = reticulated.clone();
+            cx.on_action(move |_: &ReticulateSplines, _| {
+                reticulated.fetch_add(1, atomic::Ordering::SeqCst);
+            });
+        });
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+
+        cx.simulate_keystrokes("cmd-shift-p");
+        cx.simulate_input("splines");
+
+        let palette = workspace.update(cx, |workspace, cx| {
+            workspace
+                .active_modal::<CommandPalette>(cx)
+                .unwrap()
+                .read(cx)
+                .picker
+                .clone()
+        });
+        palette.update(cx, |palette, _| {
+            assert_eq!(
+                palette.delegate.matches[0].string,
+                "test commands: reticulate splines"
+            );
+        });
+
+        cx.simulate_keystrokes("enter");
+
+        workspace.update(cx, |workspace, cx| {
+            assert!(workspace.active_modal::<CommandPalette>(cx).is_none());
+        });
+        assert_eq!(reticulated.load(atomic::Ordering::SeqCst), 1);
+    }
+
     #[gpui::test]
     async fn test_go_to_line(cx: &mut TestAppContext) {
         let app_state = init_test(cx);
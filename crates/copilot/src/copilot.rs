@@ -1264,7 +1264,7 @@ mod tests {
             self.abs_path.clone()
         }
 
-        fn load(&self, _: &AppContext) -> Task<Result<String>> {
+        fn load(&self, _: &AppContext) -> Task<Result<(String, language::Encoding)>> {
             unimplemented!()
         }
     }
@@ -13,14 +13,16 @@ pub use sqlez_macros;
 
 use release_channel::ReleaseChannel;
 pub use release_channel::RELEASE_CHANNEL;
+use sqlez::connection::SqliteError;
 use sqlez::domain::Migrator;
 use sqlez::thread_safe_connection::ThreadSafeConnection;
 use sqlez_macros::sql;
 use std::env;
 use std::future::Future;
-use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 use util::{maybe, ResultExt};
 
 const CONNECTION_INITIALIZE_QUERY: &str = sql!(
@@ -29,7 +31,7 @@ const CONNECTION_INITIALIZE_QUERY: &str = sql!(
 
 const DB_INITIALIZE_QUERY: &str = sql!(
     PRAGMA journal_mode=WAL;
-    PRAGMA busy_timeout=1;
+    PRAGMA busy_timeout=500;
     PRAGMA case_sensitive_like=TRUE;
     PRAGMA synchronous=NORMAL;
 );
@@ -43,9 +45,16 @@ pub static ZED_STATELESS: LazyLock<bool> =
 
 pub static ALL_FILE_DB_FAILED: LazyLock<AtomicBool> = LazyLock::new(|| AtomicBool::new(false));
 
+/// Held while the main database file is opened, and recreated if it turns out to be corrupt.
+static DB_OPEN_LOCK: smol::lock::Mutex<()> = smol::lock::Mutex::new(());
+
+/// Set when the database file was found to be corrupt and was replaced with a fresh one.
+pub static DB_RECOVERED_FROM_CORRUPTION: LazyLock<AtomicBool> =
+    LazyLock::new(|| AtomicBool::new(false));
+
 /// Open or create a database at the given directory path.
-/// This will retry a couple times if there are failures. If opening fails once, the db directory
-/// is moved to a backup folder and a new one is created. If that fails, a shared in memory db is created.
+/// If the existing database file is corrupt, it is moved aside to a backup file and a new one is
+/// created. If opening still fails, a shared in memory db is created instead.
 /// In either case, static variables are set so that the user can be notified.
 pub async fn open_db<M: Migrator + 'static>(
     db_dir: &Path,
@@ -64,7 +73,25 @@ pub async fn open_db<M: Migrator + 'static>(
             .context("Could not create db directory")
             .log_err()?;
         let db_path = main_db_dir.join(Path::new(DB_FILE_NAME));
-        open_main_db(&db_path).await
+        // Every domain opens the same file, so serialize opening it. Otherwise several domains
+        // could find the file corrupt at once and each move a different file aside, including one
+        // that another domain has just recreated.
+        let _guard = DB_OPEN_LOCK.lock().await;
+        match open_main_db(&db_path).await {
+            Ok(connection) => Some(connection),
+            Err(error) if is_corruption(&error) => {
+                log::warn!("Database is corrupt, recreating it: {error:?}");
+                let backup_path = back_up_corrupt_db(&db_path).await.log_err()?;
+                log::warn!("Moved corrupt database to {backup_path:?}");
+                let connection = open_main_db(&db_path).await.log_err()?;
+                DB_RECOVERED_FROM_CORRUPTION.store(true, Ordering::Release);
+                Some(connection)
+            }
+            Err(error) => {
+                log::error!("{error:?}");
+                None
+            }
+        }
     })
     .await;
 
@@ -79,14 +106,41 @@ pub async fn open_db<M: Migrator + 'static>(
     open_fallback_db().await
 }
 
-async fn open_main_db<M: Migrator>(db_path: &Path) -> Option<ThreadSafeConnection<M>> {
+async fn open_main_db<M: Migrator>(db_path: &Path) -> anyhow::Result<ThreadSafeConnection<M>> {
     log::info!("Opening main db");
     ThreadSafeConnection::<M>::builder(db_path.to_string_lossy().as_ref(), true)
         .with_db_initialization_query(DB_INITIALIZE_QUERY)
         .with_connection_initialize_query(CONNECTION_INITIALIZE_QUERY)
         .build()
         .await
-        .log_err()
+}
+
+fn is_corruption(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<SqliteError>()
+            .map_or(false, SqliteError::is_corruption)
+    })
+}
+
+/// Moves a corrupt database file aside, along with its write-ahead log, so that a new database
+/// can be created in its place. Returns the path of the backup.
+async fn back_up_corrupt_db(db_path: &Path) -> anyhow::Result<PathBuf> {
+    static BACKUP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let count = BACKUP_COUNT.fetch_add(1, Ordering::Relaxed);
+    let backup_name = format!("{DB_FILE_NAME}.corrupt-{timestamp}-{count}");
+    let backup_path = db_path.with_file_name(backup_name);
+    smol::fs::rename(db_path, &backup_path)
+        .await
+        .context("Could not move corrupt database")?;
+    for suffix in ["-wal", "-shm"] {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        smol::fs::remove_file(path).await.ok();
+    }
+    Ok(backup_path)
 }
 
 async fn open_fallback_db<M: Migrator>() -> ThreadSafeConnection<M> {
@@ -194,12 +248,12 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::thread;
+    use std::{sync::atomic::Ordering, thread};
 
     use sqlez::domain::Domain;
     use sqlez_macros::sql;
 
-    use crate::open_db;
+    use crate::{open_db, DB_FILE_NAME, DB_RECOVERED_FROM_CORRUPTION};
 
     // Test bad migration panics
     #[gpui::test]
@@ -276,6 +330,62 @@ mod tests {
         );
     }
 
+    /// Test that a DB file that isn't a database is moved aside and recreated
+    #[gpui::test]
+    async fn test_garbage_db_is_recreated(cx: &mut gpui::TestAppContext) {
+        cx.executor().allow_parking();
+
+        enum GoodDB {}
+
+        impl Domain for GoodDB {
+            fn name() -> &'static str {
+                "db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[sql!(CREATE TABLE test2(value);)]
+            }
+        }
+
+        let tempdir = tempfile::Builder::new()
+            .prefix("DbTests")
+            .tempdir()
+            .unwrap();
+        let db_dir = tempdir.path().join(format!(
+            "0-{}",
+            release_channel::ReleaseChannel::Dev.dev_name()
+        ));
+        std::fs::create_dir_all(&db_dir).unwrap();
+        std::fs::write(
+            db_dir.join(DB_FILE_NAME),
+            "this is not a database".repeat(100),
+        )
+        .unwrap();
+
+        let good_db =
+            open_db::<GoodDB>(tempdir.path(), &release_channel::ReleaseChannel::Dev).await;
+        assert!(good_db.persistent());
+        assert!(
+            good_db.select_row::<usize>("SELECT * FROM test2").unwrap()()
+                .unwrap()
+                .is_none()
+        );
+        assert!(DB_RECOVERED_FROM_CORRUPTION.load(Ordering::Acquire));
+
+        let backups = std::fs::read_dir(&db_dir)
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&format!("{DB_FILE_NAME}.corrupt-"))
+            })
+            .count();
+        assert_eq!(backups, 1);
+    }
+
     /// Test that DB exists but corrupted (causing recreate)
     #[gpui::test(iterations = 30)]
     async fn test_simultaneous_db_corruption(cx: &mut gpui::TestAppContext) {
@@ -339,4 +449,73 @@ mod tests {
             assert!(guard.join().is_ok());
         }
     }
+
+    /// Test that a garbage DB file opened by many domains at once is only moved aside once
+    #[gpui::test(iterations = 10)]
+    async fn test_simultaneous_garbage_db_is_backed_up_once(cx: &mut gpui::TestAppContext) {
+        cx.executor().allow_parking();
+
+        enum GoodDB {}
+
+        impl Domain for GoodDB {
+            fn name() -> &'static str {
+                "db_tests"
+            }
+
+            fn migrations() -> &'static [&'static str] {
+                &[sql!(CREATE TABLE test2(value);)]
+            }
+        }
+
+        let tempdir = tempfile::Builder::new()
+            .prefix("DbTests")
+            .tempdir()
+            .unwrap();
+        let db_dir = tempdir.path().join(format!(
+            "0-{}",
+            release_channel::ReleaseChannel::Dev.dev_name()
+        ));
+        std::fs::create_dir_all(&db_dir).unwrap();
+        std::fs::write(
+            db_dir.join(DB_FILE_NAME),
+            "this is not a database".repeat(100),
+        )
+        .unwrap();
+
+        let mut guards = vec![];
+        for _ in 0..10 {
+            let tmp_path = tempdir.path().to_path_buf();
+            let guard = thread::spawn(move || {
+                let good_db = smol::block_on(open_db::<GoodDB>(
+                    tmp_path.as_path(),
+                    &release_channel::ReleaseChannel::Dev,
+                ));
+                assert!(good_db.persistent());
+                assert!(
+                    good_db.select_row::<usize>("SELECT * FROM test2").unwrap()()
+                        .unwrap()
+                        .is_none()
+                );
+            });
+
+            guards.push(guard);
+        }
+
+        for guard in guards.into_iter() {
+            assert!(guard.join().is_ok());
+        }
+
+        let backups = std::fs::read_dir(&db_dir)
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&format!("{DB_FILE_NAME}.corrupt-"))
+            })
+            .count();
+        assert_eq!(backups, 1);
+    }
 }
@@ -29,6 +29,12 @@ impl KeyValueStore {
             DELETE FROM kv_store WHERE key = (?)
         }
     }
+
+    query! {
+        pub async fn key_value_pairs() -> Result<Vec<(String, String)>> {
+            SELECT key, value FROM kv_store ORDER BY key
+        }
+    }
 }
 
 #[cfg(test)]
@@ -56,6 +62,14 @@ mod tests {
             .unwrap();
         assert_eq!(db.read_kvp("key-2").unwrap(), Some("two".to_string()));
 
+        assert_eq!(
+            db.key_value_pairs().await.unwrap(),
+            [
+                ("key-1".to_string(), "one-2".to_string()),
+                ("key-2".to_string(), "two".to_string())
+            ]
+        );
+
         db.delete_kvp("key-1".to_string()).await.unwrap();
         assert_eq!(db.read_kvp("key-1").unwrap(), None);
     }
@@ -153,6 +153,18 @@ pub struct DeleteToPreviousWordStart {
     pub ignore_newlines: bool,
 }
 
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct SortLinesCaseSensitive {
+    #[serde(default)]
+    pub descending: bool,
+}
+
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct SortLinesCaseInsensitive {
+    #[serde(default)]
+    pub descending: bool,
+}
+
 impl_actions!(
     editor,
     [
@@ -179,6 +191,8 @@ impl_actions!(
         SelectToEndOfLine,
         SelectUpByLines,
         ShowCompletions,
+        SortLinesCaseInsensitive,
+        SortLinesCaseSensitive,
         ToggleCodeActions,
         ToggleComments,
         UnfoldAt,
@@ -217,6 +231,8 @@ gpui::actions!(
         CopyRelativePath,
         Cut,
         CutToEndOfLine,
+        DecodeBase64,
+        DecodeHex,
         Delete,
         DeleteLine,
         DeleteToBeginningOfLine,
@@ -226,6 +242,9 @@ gpui::actions!(
         DisplayCursorNames,
         DuplicateLineDown,
         DuplicateLineUp,
+        DuplicateSelection,
+        EncodeBase64,
+        EncodeHex,
         ExpandAllHunkDiffs,
         ExpandMacroRecursively,
         FindAllReferences,
@@ -317,8 +336,6 @@ gpui::actions!(
         ShowInlineCompletion,
         ShowSignatureHelp,
         ShuffleLines,
-        SortLinesCaseInsensitive,
-        SortLinesCaseSensitive,
         SplitSelectionIntoLines,
         SwitchSourceHeader,
         Tab,
@@ -952,6 +952,24 @@ impl DisplaySnapshot {
     pub fn foldable_range(
         &self,
         buffer_row: MultiBufferRow,
+    ) -> Option<(Range<Point>, FoldPlaceholder)> {
+        self.crease_or_indent_foldable_range(buffer_row)
+            .or_else(|| {
+                if self.is_line_folded(buffer_row) {
+                    return None;
+                }
+                let range = self
+                    .syntax_foldable_ranges(buffer_row..buffer_row.next_row())
+                    .pop()?;
+                Some((range, self.fold_placeholder.clone()))
+            })
+    }
+
+    /// Returns the foldable range starting on the given row according to the creases and the
+    /// indentation, without consulting the syntax tree.
+    pub fn crease_or_indent_foldable_range(
+        &self,
+        buffer_row: MultiBufferRow,
     ) -> Option<(Range<Point>, FoldPlaceholder)> {
         let start = MultiBufferPoint::new(buffer_row.0, self.buffer_snapshot.line_len(buffer_row));
         if let Some(crease) = self
@@ -1004,6 +1022,69 @@ impl DisplaySnapshot {
         }
     }
 
+    /// Returns the ranges that can be folded according to the syntax tree and start on the given
+    /// rows, for blocks whose contents aren't indented relative to their first line.
+    ///
+    /// The brackets are queried once, in the excerpt containing the first row, so only the
+    /// ranges within that excerpt are returned.
+    pub fn syntax_foldable_ranges(&self, rows: Range<MultiBufferRow>) -> Vec<Range<Point>> {
+        let Some((buffer, line_range)) = self.buffer_snapshot.buffer_line_for_row(rows.start)
+        else {
+            return Vec::new();
+        };
+        let first_row = line_range.start.row;
+        let last_row =
+            (first_row + rows.end.0.saturating_sub(rows.start.0 + 1)).min(buffer.max_point().row);
+
+        // Map buffer rows back to rows of the multibuffer, as long as they're in the same excerpt.
+        let to_multibuffer_row = |row: u32| {
+            let multibuffer_row = MultiBufferRow(rows.start.0 + row - first_row);
+            let (row_buffer, row_range) =
+                self.buffer_snapshot.buffer_line_for_row(multibuffer_row)?;
+            (row_buffer.remote_id() == buffer.remote_id() && row_range.start.row == row)
+                .then_some(multibuffer_row)
+        };
+
+        buffer
+            .foldable_ranges(
+                Point::new(first_row, 0)..Point::new(last_row, buffer.line_len(last_row)),
+            )
+            .into_iter()
+            .filter(|range| (first_row..=last_row).contains(&range.start.row))
+            .filter_map(|range| {
+                let start_row = to_multibuffer_row(range.start.row)?;
+                let end_row = to_multibuffer_row(range.end.row)?;
+                Some(
+                    Point::new(start_row.0, self.buffer_snapshot.line_len(start_row))
+                        ..Point::new(end_row.0, self.buffer_snapshot.line_len(end_row)),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the innermost range that can be folded according to the syntax tree and contains
+    /// the given range, skipping the ones that are already folded.
+    pub fn enclosing_syntax_foldable_range(&self, range: Range<Point>) -> Option<Range<Point>> {
+        let buffer = &self.buffer_snapshot;
+        let (open, close) = buffer.innermost_enclosing_bracket_ranges(
+            range.clone(),
+            Some(&|open: Range<usize>, close: Range<usize>| {
+                let start_row = buffer.offset_to_point(open.end).row;
+                let close_row = buffer.offset_to_point(close.start).row;
+                close_row >= start_row + 2
+                    && close_row > range.start.row
+                    && !self.is_line_folded(MultiBufferRow(start_row))
+            }),
+        )?;
+
+        let start_row = MultiBufferRow(buffer.offset_to_point(open.end).row);
+        let end_row = MultiBufferRow(buffer.offset_to_point(close.start).row - 1);
+        Some(
+            Point::new(start_row.0, buffer.line_len(start_row))
+                ..Point::new(end_row.0, buffer.line_len(end_row)),
+        )
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     pub fn text_highlight_ranges<Tag: ?Sized + 'static>(
         &self,
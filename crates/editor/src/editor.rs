@@ -32,6 +32,7 @@ mod inline_completion_provider;
 pub mod items;
 mod linked_editing_ranges;
 mod lsp_ext;
+mod minimap;
 mod mouse_context_menu;
 pub mod movement;
 mod persistence;
@@ -51,6 +52,7 @@ use ::git::{parse_git_remote_url, BuildPermalinkParams, GitHostingProviderRegist
 pub(crate) use actions::*;
 use aho_corasick::AhoCorasick;
 use anyhow::{anyhow, Context as _, Result};
+use base64::prelude::*;
 use blink_manager::BlinkManager;
 use client::{Collaborator, ParticipantIndex};
 use clock::ReplicaId;
@@ -60,7 +62,8 @@ use debounced_delay::DebouncedDelay;
 use display_map::*;
 pub use display_map::{DisplayPoint, FoldPlaceholder};
 pub use editor_settings::{
-    CurrentLineHighlight, EditorSettings, ScrollBeyondLastLine, SearchSettings,
+    ColumnSelectionShortLines, CurrentLineHighlight, EditorSettings, LineNumbers,
+    ScrollBeyondLastLine, SearchSettings,
 };
 pub use editor_settings_controls::*;
 use element::LineWithInvisibles;
@@ -98,6 +101,7 @@ use language::{
 };
 use language::{point_to_lsp, BufferRow, CharClassifier, Runnable, RunnableRange};
 use linked_editing_ranges::refresh_linked_ranges;
+use minimap::MinimapState;
 use task::{ResolvedTask, TaskTemplate, TaskVariables};
 
 use hover_links::{find_file, HoverLink, HoveredLinkState, InlayHighlight};
@@ -301,6 +305,7 @@ pub fn init(cx: &mut AppContext) {
             workspace.register_action(Editor::new_file);
             workspace.register_action(Editor::new_file_vertical);
             workspace.register_action(Editor::new_file_horizontal);
+            workspace.register_action(Editor::open_at_revision);
         },
     )
     .detach();
@@ -492,6 +497,9 @@ pub struct Editor {
     /// typing enters text into each of them, even the ones that aren't focused.
     pub(crate) show_cursor_when_unfocused: bool,
     columnar_selection_tail: Option<Anchor>,
+    /// Ends of the lines that were too short for the last column selection, along with the
+    /// number of spaces to pad each one with when text is inserted at the selections.
+    columnar_selection_padding: Vec<(Anchor, u32)>,
     add_selections_state: Option<AddSelectionsState>,
     select_next_state: Option<SelectNextState>,
     select_prev_state: Option<SelectNextState>,
@@ -526,6 +534,7 @@ pub struct Editor {
     gutter_highlights: TreeMap<TypeId, GutterHighlight>,
     scrollbar_marker_state: ScrollbarMarkerState,
     active_indent_guides_state: ActiveIndentGuidesState,
+    minimap: Option<MinimapState>,
     nav_history: Option<ItemNavHistory>,
     context_menu: RwLock<Option<ContextMenu>>,
     mouse_context_menu: Option<MouseContextMenu>,
@@ -1839,6 +1848,7 @@ impl Editor {
             selections,
             scroll_manager: ScrollManager::new(cx),
             columnar_selection_tail: None,
+            columnar_selection_padding: Vec::new(),
             add_selections_state: None,
             select_next_state: None,
             select_prev_state: None,
@@ -1871,6 +1881,7 @@ impl Editor {
             gutter_highlights: TreeMap::default(),
             scrollbar_marker_state: ScrollbarMarkerState::default(),
             active_indent_guides_state: ActiveIndentGuidesState::default(),
+            minimap: None,
             nav_history: None,
             context_menu: RwLock::new(None),
             mouse_context_menu: None,
@@ -2077,6 +2088,34 @@ impl Editor {
         })
     }
 
+    fn open_at_revision(
+        workspace: &mut Workspace,
+        action: &workspace::OpenAtRevision,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let project = workspace.project().clone();
+        let open = match project.read(cx).find_project_path(&action.path, cx) {
+            Some(project_path) => project.update(cx, |project, cx| {
+                project.open_buffer_at_revision(project_path, action.revision.clone(), cx)
+            }),
+            None => Task::ready(Err(anyhow!("{:?} is not in the project", action.path))),
+        };
+        let title = format!("{} @ {}", action.path.display(), action.revision);
+
+        cx.spawn(|workspace, mut cx| async move {
+            let buffer = open.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let multibuffer =
+                    cx.new_model(|cx| MultiBuffer::singleton(buffer, cx).with_title(title));
+                let editor = cx.new_view(|cx| {
+                    Editor::for_multibuffer(multibuffer, Some(project.clone()), true, cx)
+                });
+                workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+            })
+        })
+        .detach_and_prompt_err("Failed to open file at revision", cx, |_, _| None);
+    }
+
     fn new_file_vertical(
         workspace: &mut Workspace,
         _: &workspace::NewFileSplitVertical,
@@ -2417,6 +2456,9 @@ impl Editor {
         self.select_next_state = None;
         self.select_prev_state = None;
         self.select_larger_syntax_node_stack.clear();
+        if self.columnar_selection_tail.is_none() {
+            self.columnar_selection_padding.clear();
+        }
         self.invalidate_autoclose_regions(&self.selections.disjoint_anchors(), buffer);
         self.snippet_stack
             .invalidate(&self.selections.disjoint_anchors(), buffer);
@@ -2905,7 +2947,6 @@ impl Editor {
     }
 
     fn end_selection(&mut self, cx: &mut ViewContext<Self>) {
-        self.columnar_selection_tail.take();
         if self.selections.pending_anchor().is_some() {
             let selections = self.selections.all::<usize>(cx);
             self.change_selections(None, cx, |s| {
@@ -2913,6 +2954,7 @@ impl Editor {
                 s.clear_pending();
             });
         }
+        self.columnar_selection_tail.take();
     }
 
     fn select_columns(
@@ -2929,10 +2971,19 @@ impl Editor {
         let end_column = cmp::max(tail.column(), goal_column);
         let reversed = start_column < tail.column();
 
+        let pad_short_lines = EditorSettings::get_global(cx).column_selection_short_lines
+            == ColumnSelectionShortLines::Pad;
+        self.columnar_selection_padding.clear();
+
         let selection_ranges = (start_row.0..=end_row.0)
             .map(DisplayRow)
             .filter_map(|row| {
-                if start_column <= display_map.line_len(row) && !display_map.is_block_line(row) {
+                if display_map.is_block_line(row) {
+                    return None;
+                }
+
+                let line_len = display_map.line_len(row);
+                if start_column <= line_len {
                     let start = display_map
                         .clip_point(DisplayPoint::new(row, start_column), Bias::Left)
                         .to_point(display_map);
@@ -2944,6 +2995,21 @@ impl Editor {
                     } else {
                         Some(start..end)
                     }
+                } else if pad_short_lines {
+                    // Only pad at the end of a buffer line, not where it is soft-wrapped.
+                    let line_end = DisplayPoint::new(row, line_len).to_point(display_map);
+                    if line_end.column
+                        != display_map
+                            .buffer_snapshot
+                            .line_len(MultiBufferRow(line_end.row))
+                    {
+                        return None;
+                    }
+                    self.columnar_selection_padding.push((
+                        display_map.buffer_snapshot.anchor_before(line_end),
+                        start_column - line_len,
+                    ));
+                    Some(line_end..line_end)
                 } else {
                     None
                 }
@@ -2956,6 +3022,65 @@ impl Editor {
         cx.notify();
     }
 
+    /// Inserts the padding recorded for short lines during a column selection, moving the
+    /// cursors on those lines to the selection's left edge. This happens when text is first
+    /// inserted at the selections, so that making a column selection doesn't edit the buffer.
+    fn insert_columnar_selection_padding(&mut self, cx: &mut ViewContext<Self>) {
+        let padding = mem::take(&mut self.columnar_selection_padding);
+        if padding.is_empty() {
+            return;
+        }
+
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let selections = self.selections.all::<usize>(cx);
+        // Skip padding for lines whose cursor has moved, or that have been edited since.
+        let mut padding = padding
+            .into_iter()
+            .map(|(anchor, len)| (anchor.to_offset(&buffer), len as usize))
+            .filter(|(offset, _)| {
+                let point = offset.to_point(&buffer);
+                point.column == buffer.line_len(MultiBufferRow(point.row))
+                    && selections
+                        .iter()
+                        .any(|selection| selection.is_empty() && selection.head() == *offset)
+            })
+            .collect::<Vec<_>>();
+        if padding.is_empty() {
+            return;
+        }
+        padding.sort_unstable_by_key(|(offset, _)| *offset);
+
+        // Shift each selection by the padding inserted at or before it, which places the
+        // cursors on padded lines after their padding.
+        let shift = |offset: usize| {
+            padding
+                .iter()
+                .take_while(|(padded_offset, _)| *padded_offset <= offset)
+                .map(|(_, len)| len)
+                .sum::<usize>()
+        };
+        let new_selections = selections
+            .into_iter()
+            .map(|selection| {
+                let start = selection.start + shift(selection.start);
+                let end = selection.end + shift(selection.end);
+                if selection.reversed {
+                    end..start
+                } else {
+                    start..end
+                }
+            })
+            .collect::<Vec<_>>();
+        let edits = padding
+            .iter()
+            .map(|(offset, len)| (*offset..*offset, " ".repeat(*len)))
+            .collect::<Vec<_>>();
+
+        self.buffer
+            .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+        self.change_selections(None, cx, |s| s.select_ranges(new_selections));
+    }
+
     pub fn has_pending_nonempty_selection(&self) -> bool {
         let pending_nonempty_selection = match self.selections.pending_anchor() {
             Some(Selection { start, end, .. }) => start != end,
@@ -3093,6 +3218,14 @@ impl Editor {
             return;
         }
 
+        if !self.columnar_selection_padding.is_empty() {
+            self.transact(cx, |this, cx| {
+                this.insert_columnar_selection_padding(cx);
+                this.handle_input(&text, cx);
+            });
+            return;
+        }
+
         let selections = self.selections.all_adjusted(cx);
         let mut bracket_inserted = false;
         let mut edits = Vec::new();
@@ -3720,6 +3853,7 @@ impl Editor {
 
         let text: Arc<str> = text.into();
         self.transact(cx, |this, cx| {
+            this.insert_columnar_selection_padding(cx);
             let old_selections = this.selections.all_adjusted(cx);
             let selection_anchors = this.buffer.update(cx, |buffer, cx| {
                 let anchors = {
@@ -5670,11 +5804,10 @@ impl Editor {
             }
 
             // Otherwise, insert a hard or soft tab.
-            let settings = buffer.settings_at(cursor, cx);
-            let tab_size = if settings.hard_tabs {
+            let (indent_kind, tab_size) = Self::indent_unit_at(buffer, &snapshot, cursor, cx);
+            let tab_size = if indent_kind == IndentKind::Tab {
                 IndentSize::tab()
             } else {
-                let tab_size = settings.tab_size.get();
                 let char_column = snapshot
                     .text_for_range(Point::new(cursor.row, 0)..cursor)
                     .flat_map(str::chars)
@@ -5730,13 +5863,7 @@ impl Editor {
         delta_for_start_row: u32,
         cx: &AppContext,
     ) -> u32 {
-        let settings = buffer.settings_at(selection.start, cx);
-        let tab_size = settings.tab_size.get();
-        let indent_kind = if settings.hard_tabs {
-            IndentKind::Tab
-        } else {
-            IndentKind::Space
-        };
+        let (indent_kind, tab_size) = Self::indent_unit_at(buffer, snapshot, selection.start, cx);
         let mut start_row = selection.start.row;
         let mut end_row = selection.end.row + 1;
 
@@ -5798,6 +5925,29 @@ impl Editor {
         }
     }
 
+    /// Returns the kind of indentation to insert at the given position and the number of
+    /// columns a single level of it occupies, honoring the `detect_indentation` setting.
+    fn indent_unit_at(
+        buffer: &MultiBuffer,
+        snapshot: &MultiBufferSnapshot,
+        position: Point,
+        cx: &AppContext,
+    ) -> (IndentKind, u32) {
+        let settings = buffer.settings_at(position, cx);
+        match snapshot.language_indent_size_at(position, cx) {
+            Some(IndentSize {
+                kind: IndentKind::Space,
+                len,
+            }) => (IndentKind::Space, len),
+            Some(IndentSize {
+                kind: IndentKind::Tab,
+                ..
+            }) => (IndentKind::Tab, settings.tab_size.get()),
+            None if settings.hard_tabs => (IndentKind::Tab, settings.tab_size.get()),
+            None => (IndentKind::Space, settings.tab_size.get()),
+        }
+    }
+
     pub fn outdent(&mut self, _: &Outdent, cx: &mut ViewContext<Self>) {
         if self.read_only(cx) {
             return;
@@ -5810,8 +5960,7 @@ impl Editor {
             let buffer = self.buffer.read(cx);
             let snapshot = buffer.snapshot(cx);
             for selection in &selections {
-                let settings = buffer.settings_at(selection.start, cx);
-                let tab_size = settings.tab_size.get();
+                let (_, tab_size) = Self::indent_unit_at(buffer, &snapshot, selection.start, cx);
                 let mut rows = selection.spanned_rows(false, &display_map);
 
                 // Avoid re-outdenting a row that has already been outdented by a
@@ -5986,12 +6135,24 @@ impl Editor {
         self.transact(cx, |this, cx| {
             for row_range in row_ranges.into_iter().rev() {
                 for row in row_range.iter_rows().rev() {
-                    let end_of_line = Point::new(row.0, snapshot.line_len(row));
                     let next_line_row = row.next_row();
+                    if next_line_row > snapshot.max_buffer_row() {
+                        continue;
+                    }
+
+                    // Collapse the whitespace on both sides of the join point.
+                    let line_len = snapshot.line_len(row);
+                    let trailing_whitespace_len = snapshot
+                        .reversed_chars_at(Point::new(row.0, line_len))
+                        .take_while(|c| *c == ' ' || *c == '\t')
+                        .count() as u32;
+                    let end_of_line = Point::new(row.0, line_len - trailing_whitespace_len);
                     let indent = snapshot.indent_size_for_line(next_line_row);
                     let start_of_next_line = Point::new(next_line_row.0, indent.len);
 
-                    let replace = if snapshot.line_len(next_line_row) > indent.len {
+                    let replace = if end_of_line.column > 0
+                        && snapshot.line_len(next_line_row) > indent.len
+                    {
                         " "
                     } else {
                         ""
@@ -6009,44 +6170,75 @@ impl Editor {
         });
     }
 
+    /// Sorts the selected lines, or all lines in the buffer when nothing is selected.
     pub fn sort_lines_case_sensitive(
         &mut self,
-        _: &SortLinesCaseSensitive,
+        action: &SortLinesCaseSensitive,
         cx: &mut ViewContext<Self>,
     ) {
-        self.manipulate_lines(cx, |lines| lines.sort())
+        self.select_all_if_nothing_selected(cx);
+        if action.descending {
+            self.manipulate_lines(cx, |lines| lines.sort_by(|a, b| b.cmp(a)))
+        } else {
+            self.manipulate_lines(cx, |lines| lines.sort())
+        }
     }
 
+    /// Sorts the selected lines ignoring case, or all lines in the buffer when nothing is selected.
     pub fn sort_lines_case_insensitive(
         &mut self,
-        _: &SortLinesCaseInsensitive,
+        action: &SortLinesCaseInsensitive,
         cx: &mut ViewContext<Self>,
     ) {
-        self.manipulate_lines(cx, |lines| lines.sort_by_key(|line| line.to_lowercase()))
+        self.select_all_if_nothing_selected(cx);
+        if action.descending {
+            self.manipulate_lines(cx, |lines| {
+                lines.sort_by_key(|line| Reverse(line.to_lowercase()))
+            })
+        } else {
+            self.manipulate_lines(cx, |lines| lines.sort_by_key(|line| line.to_lowercase()))
+        }
     }
 
+    /// Removes duplicates of the selected lines ignoring case, or of all lines in the buffer when
+    /// nothing is selected, keeping the first occurrence of each.
     pub fn unique_lines_case_insensitive(
         &mut self,
         _: &UniqueLinesCaseInsensitive,
         cx: &mut ViewContext<Self>,
     ) {
+        self.select_all_if_nothing_selected(cx);
         self.manipulate_lines(cx, |lines| {
             let mut seen = HashSet::default();
             lines.retain(|line| seen.insert(line.to_lowercase()));
         })
     }
 
+    /// Removes duplicates of the selected lines, or of all lines in the buffer when nothing is
+    /// selected, keeping the first occurrence of each.
     pub fn unique_lines_case_sensitive(
         &mut self,
         _: &UniqueLinesCaseSensitive,
         cx: &mut ViewContext<Self>,
     ) {
+        self.select_all_if_nothing_selected(cx);
         self.manipulate_lines(cx, |lines| {
             let mut seen = HashSet::default();
             lines.retain(|line| seen.insert(*line));
         })
     }
 
+    fn select_all_if_nothing_selected(&mut self, cx: &mut ViewContext<Self>) {
+        if self
+            .selections
+            .all::<usize>(cx)
+            .iter()
+            .all(|selection| selection.is_empty())
+        {
+            self.select_all(&SelectAll, cx);
+        }
+    }
+
     pub fn revert_file(&mut self, _: &RevertFile, cx: &mut ViewContext<Self>) {
         let mut revert_changes = HashMap::default();
         let multi_buffer_snapshot = self.buffer.read(cx).snapshot(cx);
@@ -6293,9 +6485,68 @@ impl Editor {
         })
     }
 
+    pub fn encode_base64(&mut self, _: &EncodeBase64, cx: &mut ViewContext<Self>) {
+        self.manipulate_text(cx, |text| BASE64_STANDARD.encode(text))
+    }
+
+    pub fn decode_base64(&mut self, _: &DecodeBase64, cx: &mut ViewContext<Self>) {
+        self.decode_text(cx, |text| Ok(BASE64_STANDARD.decode(text)?))
+    }
+
+    pub fn encode_hex(&mut self, _: &EncodeHex, cx: &mut ViewContext<Self>) {
+        self.manipulate_text(cx, |text| hex::encode(text.as_bytes()))
+    }
+
+    pub fn decode_hex(&mut self, _: &DecodeHex, cx: &mut ViewContext<Self>) {
+        self.decode_text(cx, |text| Ok(hex::decode(text)?))
+    }
+
+    /// Replaces each selection with its decoded contents. If any selection fails to decode, the
+    /// buffer is left unchanged and the error is shown to the user.
+    fn decode_text<Fn>(&mut self, cx: &mut ViewContext<Self>, mut decode: Fn)
+    where
+        Fn: FnMut(&str) -> Result<Vec<u8>>,
+    {
+        let result = self.try_manipulate_text(cx, |text| {
+            let bytes = decode(text)?;
+            String::from_utf8(bytes).context("decoded text is not valid UTF-8")
+        });
+
+        if let Err(err) = result {
+            let message = format!("Failed to decode selection: {err}");
+
+            Err::<(), anyhow::Error>(err).log_err();
+
+            if let Some(workspace) = self.workspace() {
+                workspace.update(cx, |workspace, cx| {
+                    struct DecodeText;
+
+                    workspace.show_toast(
+                        Toast::new(NotificationId::unique::<DecodeText>(), message),
+                        cx,
+                    )
+                })
+            }
+        }
+    }
+
     fn manipulate_text<Fn>(&mut self, cx: &mut ViewContext<Self>, mut callback: Fn)
     where
         Fn: FnMut(&str) -> String,
+    {
+        self.try_manipulate_text(cx, |text| Ok(callback(text)))
+            .log_err();
+    }
+
+    /// Like [`Self::manipulate_text`], but leaves the buffer untouched if the callback fails for
+    /// any selection.
+    fn try_manipulate_text<Fn>(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+        mut callback: Fn,
+    ) -> Result<()>
+    where
+        Fn: FnMut(&str) -> Result<String>,
     {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
@@ -6321,7 +6572,7 @@ impl Editor {
 
             let text = buffer.text_for_range(start..end).collect::<String>();
             let old_length = text.len() as i32;
-            let text = callback(&text);
+            let text = callback(&text)?;
 
             new_selections.push(Selection {
                 start: (start as i32 - selection_adjustment) as usize,
@@ -6346,6 +6597,7 @@ impl Editor {
 
             this.request_autoscroll(Autoscroll::fit(), cx);
         });
+        Ok(())
     }
 
     pub fn duplicate_line(&mut self, upwards: bool, cx: &mut ViewContext<Self>) {
@@ -6405,6 +6657,66 @@ impl Editor {
         self.duplicate_line(false, cx);
     }
 
+    /// Inserts a copy of each selection's text right after it and selects the copy. Empty
+    /// selections duplicate their line instead, with the cursor moving to the new line.
+    pub fn duplicate_selection(&mut self, _: &DuplicateSelection, cx: &mut ViewContext<Self>) {
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let selections = self.selections.all::<usize>(cx);
+
+        let mut edits = Vec::new();
+        let mut new_selection_anchors = Vec::with_capacity(selections.len());
+        let mut last_duplicated_row = None;
+        for selection in &selections {
+            if selection.is_empty() {
+                let row = selection.head().to_point(&buffer).row;
+                let line_start = Point::new(row, 0);
+                // Several cursors on one line only duplicate it once.
+                if last_duplicated_row != Some(row) {
+                    let line_end = Point::new(row, buffer.line_len(MultiBufferRow(row)));
+                    let text = buffer
+                        .text_for_range(line_start..line_end)
+                        .chain(Some("\n"))
+                        .collect::<String>();
+                    edits.push((line_start..line_start, text));
+                    last_duplicated_row = Some(row);
+                }
+                let head = buffer.anchor_after(selection.head());
+                new_selection_anchors.push((selection.id, head..head, false));
+            } else {
+                let text = buffer
+                    .text_for_range(selection.start..selection.end)
+                    .collect::<String>();
+                edits.push((selection.end..selection.end, text));
+                new_selection_anchors.push((
+                    selection.id,
+                    buffer.anchor_before(selection.end)..buffer.anchor_after(selection.end),
+                    selection.reversed,
+                ));
+            }
+        }
+
+        self.transact(cx, |this, cx| {
+            this.buffer.update(cx, |buffer, cx| {
+                buffer.edit(edits, None, cx);
+            });
+
+            let buffer = this.buffer.read(cx).snapshot(cx);
+            let new_selections = new_selection_anchors
+                .into_iter()
+                .map(|(id, range, reversed)| Selection {
+                    id,
+                    start: range.start.to_offset(&buffer),
+                    end: range.end.to_offset(&buffer),
+                    reversed,
+                    goal: SelectionGoal::None,
+                })
+                .collect::<Vec<_>>();
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select(new_selections);
+            });
+        });
+    }
+
     pub fn move_line_up(&mut self, _: &MoveLineUp, cx: &mut ViewContext<Self>) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let buffer = self.buffer.read(cx).snapshot(cx);
@@ -8460,6 +8772,10 @@ impl Editor {
                         edits.push((suffix_range, empty_str.clone()));
                     }
                 } else {
+                    log::info!(
+                        "not toggling comments: {} has no comment syntax",
+                        language.language_name()
+                    );
                     continue;
                 }
             }
@@ -10233,10 +10549,21 @@ impl Editor {
             let range = selection.range().sorted();
             let buffer_start_row = range.start.row;
 
+            // Query the syntax tree once per selection, rather than for every row.
+            let mut syntax_folds = display_map.syntax_foldable_ranges(
+                MultiBufferRow(range.start.row)..MultiBufferRow(range.end.row + 1),
+            );
+            syntax_folds.extend(display_map.enclosing_syntax_foldable_range(range.clone()));
+
             for row in (0..=range.end.row).rev() {
-                if let Some((foldable_range, fold_text)) =
-                    display_map.foldable_range(MultiBufferRow(row))
-                {
+                let foldable_range = display_map
+                    .crease_or_indent_foldable_range(MultiBufferRow(row))
+                    .or_else(|| {
+                        let fold = syntax_folds.iter().find(|fold| fold.start.row == row)?;
+                        (!display_map.is_line_folded(MultiBufferRow(row)))
+                            .then(|| (fold.clone(), display_map.fold_placeholder.clone()))
+                    });
+                if let Some((foldable_range, fold_text)) = foldable_range {
                     if foldable_range.end.row >= buffer_start_row {
                         fold_ranges.push((foldable_range, fold_text));
                         if row <= range.start.row {
@@ -10671,8 +10998,21 @@ impl Editor {
     }
 
     pub fn should_use_relative_line_numbers(&self, cx: &WindowContext) -> bool {
-        self.use_relative_line_numbers
-            .unwrap_or(EditorSettings::get_global(cx).relative_line_numbers)
+        self.line_numbers_mode(cx) != LineNumbers::Absolute
+    }
+
+    /// How this editor's line numbers are displayed, taking into account any override
+    /// from toggling relative line numbers.
+    pub fn line_numbers_mode(&self, cx: &WindowContext) -> LineNumbers {
+        let settings = EditorSettings::get_global(cx);
+        let is_relative = self.use_relative_line_numbers.unwrap_or(
+            settings.relative_line_numbers || settings.line_numbers != LineNumbers::Absolute,
+        );
+        match settings.line_numbers {
+            _ if !is_relative => LineNumbers::Absolute,
+            LineNumbers::Relative => LineNumbers::Relative,
+            LineNumbers::Absolute | LineNumbers::Hybrid => LineNumbers::Hybrid,
+        }
     }
 
     pub fn toggle_relative_line_numbers(
@@ -12626,6 +12966,7 @@ impl EditorSnapshot {
         &self,
         buffer_row: MultiBufferRow,
         row_contains_cursor: bool,
+        starts_syntax_fold: bool,
         editor: View<Editor>,
         cx: &mut WindowContext,
     ) -> Option<AnyElement> {
@@ -12654,7 +12995,8 @@ impl EditorSnapshot {
                 cx,
             ))
         } else if folded
-            || (self.starts_indent(buffer_row) && (row_contains_cursor || self.gutter_hovered))
+            || ((starts_syntax_fold || self.starts_indent(buffer_row))
+                && (row_contains_cursor || self.gutter_hovered))
         {
             Some(
                 Disclosure::new(("indent-fold-indicator", buffer_row.0), !folded)
@@ -13509,13 +13851,7 @@ impl RowRangeExt for Range<DisplayRow> {
 }
 
 fn hunk_status(hunk: &DiffHunk<MultiBufferRow>) -> DiffHunkStatus {
-    if hunk.diff_base_byte_range.is_empty() {
-        DiffHunkStatus::Added
-    } else if hunk.associated_range.is_empty() {
-        DiffHunkStatus::Removed
-    } else {
-        DiffHunkStatus::Modified
-    }
+    hunk.status()
 }
 
 /// If select range has more than one line, we
@@ -18,7 +18,9 @@ pub struct EditorSettings {
     pub scroll_beyond_last_line: ScrollBeyondLastLine,
     pub vertical_scroll_margin: f32,
     pub scroll_sensitivity: f32,
+    pub smooth_scrolling: bool,
     pub relative_line_numbers: bool,
+    pub line_numbers: LineNumbers,
     pub seed_search_query_from_cursor: SeedQuerySetting,
     pub use_smartcase_search: bool,
     pub multi_cursor_modifier: MultiCursorModifier,
@@ -27,12 +29,15 @@ pub struct EditorSettings {
     pub middle_click_paste: bool,
     #[serde(default)]
     pub double_click_in_multibuffer: DoubleClickInMultibuffer,
+    #[serde(default)]
+    pub column_selection_short_lines: ColumnSelectionShortLines,
     pub search_wrap: bool,
     #[serde(default)]
     pub search: SearchSettings,
     pub auto_signature_help: bool,
     pub show_signature_help_after_edits: bool,
     pub jupyter: Jupyter,
+    pub minimap: Minimap,
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
@@ -60,6 +65,21 @@ pub enum SeedQuerySetting {
     Never,
 }
 
+/// How line numbers are displayed in the gutter.
+///
+/// Default: absolute
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LineNumbers {
+    /// Show each line's number in the buffer.
+    #[default]
+    Absolute,
+    /// Show each line's distance from the cursor, and 0 on the cursor's line.
+    Relative,
+    /// Show each line's distance from the cursor, and the absolute number on the cursor's line.
+    Hybrid,
+}
+
 /// What to do when multibuffer is double clicked in some of its excerpts (parts of singleton buffers).
 #[derive(Default, Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -72,6 +92,18 @@ pub enum DoubleClickInMultibuffer {
     Open,
 }
 
+/// What to do with lines that end before the left edge of a column selection.
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnSelectionShortLines {
+    /// Leave the line out of the selection.
+    #[default]
+    Skip,
+    /// Place a cursor at the end of the line, and pad the line with spaces up to the
+    /// selection's left edge when text is typed there.
+    Pad,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Jupyter {
     /// Whether the Jupyter feature is enabled.
@@ -89,6 +121,22 @@ pub struct JupyterContent {
     pub enabled: Option<bool>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct Minimap {
+    /// Whether to compute an overview of the buffer for the scrollbar.
+    ///
+    /// Default: false
+    pub enabled: bool,
+}
+
+#[derive(Default, Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct MinimapContent {
+    /// Whether to compute an overview of the buffer for the scrollbar.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct Toolbar {
     pub breadcrumbs: bool,
@@ -156,6 +204,28 @@ pub enum ScrollBeyondLastLine {
 
     /// The editor will scroll beyond the last line by the same number of lines as vertical_scroll_margin.
     VerticalScrollMargin,
+
+    /// The editor will scroll beyond the last line by the given number of lines.
+    Lines(u32),
+}
+
+impl ScrollBeyondLastLine {
+    /// The furthest row that can be scrolled to the top of an editor that shows `height_in_lines`
+    /// rows of a buffer whose last row is `max_row`.
+    pub fn max_scroll_top(
+        self,
+        max_row: f32,
+        height_in_lines: f32,
+        vertical_scroll_margin: f32,
+    ) -> f32 {
+        let rows_beyond_last_line = match self {
+            ScrollBeyondLastLine::OnePage => return max_row,
+            ScrollBeyondLastLine::Off => 0.,
+            ScrollBeyondLastLine::VerticalScrollMargin => vertical_scroll_margin,
+            ScrollBeyondLastLine::Lines(lines) => lines as f32,
+        };
+        (max_row - height_in_lines + 1. + rows_beyond_last_line).max(0.)
+    }
 }
 
 /// Default options for buffer and project search items.
@@ -226,10 +296,25 @@ pub struct EditorSettingsContent {
     ///
     /// Default: 1.0
     pub scroll_sensitivity: Option<f32>,
+    /// Whether to animate scrolling by a page, rather than jumping to the new position.
+    ///
+    /// Default: false
+    pub smooth_scrolling: Option<bool>,
     /// Whether the line numbers on editors gutter are relative or not.
+    /// Superseded by `line_numbers`: true is the same as `"line_numbers": "hybrid"`,
+    /// and is ignored when `line_numbers` is already relative or hybrid.
     ///
     /// Default: false
     pub relative_line_numbers: Option<bool>,
+    /// How line numbers are displayed in the gutter: absolutely, relative to the
+    /// cursor, or relative to the cursor with the cursor's line numbered absolutely.
+    ///
+    /// When `relative_line_numbers` is true, "absolute" is shown as "hybrid". Toggling
+    /// relative line numbers in an editor overrides both settings for that editor, and
+    /// `gutter.line_numbers` set to false hides line numbers whatever their mode.
+    ///
+    /// Default: absolute
+    pub line_numbers: Option<LineNumbers>,
     /// When to populate a new search's query based on the text under the cursor.
     ///
     /// Default: always
@@ -261,6 +346,10 @@ pub struct EditorSettingsContent {
     ///
     /// Default: select
     pub double_click_in_multibuffer: Option<DoubleClickInMultibuffer>,
+    /// What to do with lines that end before the left edge of a column selection.
+    ///
+    /// Default: skip
+    pub column_selection_short_lines: Option<ColumnSelectionShortLines>,
     /// Whether the editor search results will loop
     ///
     /// Default: true
@@ -283,6 +372,9 @@ pub struct EditorSettingsContent {
 
     /// Jupyter REPL settings.
     pub jupyter: Option<JupyterContent>,
+
+    /// Minimap related settings
+    pub minimap: Option<MinimapContent>,
 }
 
 // Toolbar related settings
@@ -335,7 +427,8 @@ pub struct ScrollbarContent {
 /// Gutter related settings
 #[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct GutterContent {
-    /// Whether to show line numbers in the gutter.
+    /// Whether to show line numbers in the gutter. How they're numbered is set by the
+    /// top-level `line_numbers` setting.
     ///
     /// Default: true
     pub line_numbers: Option<bool>,
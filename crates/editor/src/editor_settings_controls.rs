@@ -135,6 +135,8 @@ impl EditableSettingControl for BufferFontSizeControl {
         _cx: &AppContext,
     ) {
         settings.buffer_font_size = Some(value.into());
+        // Otherwise a size chosen by zooming would hide the new one.
+        settings.zoomed_buffer_font_size = None;
     }
 }
 
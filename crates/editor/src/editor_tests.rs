@@ -1,5 +1,6 @@
 use super::*;
 use crate::{
+    editor_settings::MinimapContent,
     scroll::scroll_amount::ScrollAmount,
     test::{
         assert_text_with_selections, build_editor, editor_hunks,
@@ -19,7 +20,7 @@ use language::{
         AllLanguageSettings, AllLanguageSettingsContent, LanguageSettingsContent, PrettierSettings,
     },
     BracketPairConfig,
-    Capability::ReadWrite,
+    Capability::{self, ReadWrite},
     FakeLspAdapter, IndentGuide, LanguageConfig, LanguageConfigOverride, LanguageMatcher,
     LanguageName, Override, ParsedMarkdown, Point,
 };
@@ -490,6 +491,118 @@ fn test_multiple_cursor_removal(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+fn test_columnar_selection(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("aaaaaa\nbb\ncccccc\ndddddd", cx);
+        build_editor(buffer, cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        // Each line gets a selection over its part of the rectangle
+        view.begin_selection(DisplayPoint::new(DisplayRow(0), 1), false, 1, cx);
+        view.end_selection(cx);
+        view.begin_columnar_selection(DisplayPoint::new(DisplayRow(3), 4), 4, false, cx);
+        view.end_selection(cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            [
+                DisplayPoint::new(DisplayRow(0), 1)..DisplayPoint::new(DisplayRow(0), 4),
+                DisplayPoint::new(DisplayRow(1), 1)..DisplayPoint::new(DisplayRow(1), 2),
+                DisplayPoint::new(DisplayRow(2), 1)..DisplayPoint::new(DisplayRow(2), 4),
+                DisplayPoint::new(DisplayRow(3), 1)..DisplayPoint::new(DisplayRow(3), 4),
+            ]
+        );
+
+        // Lines that end before the selection's left edge are skipped by default
+        view.begin_selection(DisplayPoint::new(DisplayRow(0), 4), false, 1, cx);
+        view.end_selection(cx);
+        view.begin_columnar_selection(DisplayPoint::new(DisplayRow(3), 4), 4, false, cx);
+        view.end_selection(cx);
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            [
+                DisplayPoint::new(DisplayRow(0), 4)..DisplayPoint::new(DisplayRow(0), 4),
+                DisplayPoint::new(DisplayRow(2), 4)..DisplayPoint::new(DisplayRow(2), 4),
+                DisplayPoint::new(DisplayRow(3), 4)..DisplayPoint::new(DisplayRow(3), 4),
+            ]
+        );
+
+        view.handle_input("X", cx);
+        assert_eq!(view.text(cx), "aaaaXaa\nbb\nccccXcc\nddddXdd");
+    });
+}
+
+#[gpui::test]
+fn test_columnar_selection_padding_short_lines(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.column_selection_short_lines = Some(ColumnSelectionShortLines::Pad);
+            });
+        });
+    });
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("aaaaaa\nbb\n\ndddddd", cx);
+        build_editor(buffer, cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.begin_selection(DisplayPoint::new(DisplayRow(0), 4), false, 1, cx);
+        view.end_selection(cx);
+
+        // While dragging, short lines get a cursor at their end
+        view.begin_columnar_selection(DisplayPoint::new(DisplayRow(3), 4), 4, false, cx);
+        assert_eq!(view.text(cx), "aaaaaa\nbb\n\ndddddd");
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            [
+                DisplayPoint::new(DisplayRow(0), 4)..DisplayPoint::new(DisplayRow(0), 4),
+                DisplayPoint::new(DisplayRow(1), 2)..DisplayPoint::new(DisplayRow(1), 2),
+                DisplayPoint::new(DisplayRow(2), 0)..DisplayPoint::new(DisplayRow(2), 0),
+                DisplayPoint::new(DisplayRow(3), 4)..DisplayPoint::new(DisplayRow(3), 4),
+            ]
+        );
+
+        // Ending the selection doesn't edit the buffer
+        view.end_selection(cx);
+        assert_eq!(view.text(cx), "aaaaaa\nbb\n\ndddddd");
+        assert!(!view.buffer.read(cx).is_dirty(cx));
+
+        // Typing pads the short lines, so text is inserted at the same column on every line
+        view.handle_input("X", cx);
+        assert_eq!(view.text(cx), "aaaaXaa\nbb  X\n    X\nddddXdd");
+        assert_eq!(
+            view.selections.display_ranges(cx),
+            [
+                DisplayPoint::new(DisplayRow(0), 5)..DisplayPoint::new(DisplayRow(0), 5),
+                DisplayPoint::new(DisplayRow(1), 5)..DisplayPoint::new(DisplayRow(1), 5),
+                DisplayPoint::new(DisplayRow(2), 5)..DisplayPoint::new(DisplayRow(2), 5),
+                DisplayPoint::new(DisplayRow(3), 5)..DisplayPoint::new(DisplayRow(3), 5),
+            ]
+        );
+
+        // Padding is undone along with the typed text
+        view.undo(&Undo, cx);
+        assert_eq!(view.text(cx), "aaaaaa\nbb\n\ndddddd");
+
+        // Moving the cursors discards the padding
+        view.begin_selection(DisplayPoint::new(DisplayRow(0), 4), false, 1, cx);
+        view.end_selection(cx);
+        view.begin_columnar_selection(DisplayPoint::new(DisplayRow(3), 4), 4, false, cx);
+        view.end_selection(cx);
+        view.move_right(&MoveRight, cx);
+        view.move_left(&MoveLeft, cx);
+        view.handle_input("X", cx);
+        assert_eq!(view.text(cx), "aaaaXaa\nbbX\nX\nddddXdd");
+    });
+}
+
 #[gpui::test]
 fn test_canceling_pending_selection(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -820,6 +933,33 @@ fn test_cancel(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_read_only_editor_rejects_edits(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let buffer = cx.new_model(|cx| {
+        let mut buffer = language::Buffer::local("one\ntwo\n", cx);
+        buffer.set_capability(Capability::ReadOnly, cx);
+        buffer
+    });
+    let view = cx.add_window(|cx| {
+        let buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+        build_editor(buffer, cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.change_selections(None, cx, |s| s.select_ranges([4..4]));
+        view.handle_input("x", cx);
+        view.insert("y", cx);
+        view.newline(&Newline, cx);
+        view.backspace(&Backspace, cx);
+        view.delete_line(&DeleteLine, cx);
+        assert_eq!(view.text(cx), "one\ntwo\n");
+        assert!(!view.is_dirty(cx));
+        assert!(!view.can_save(cx));
+    });
+}
+
 #[gpui::test]
 fn test_fold_action(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -912,6 +1052,63 @@ fn test_fold_action(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_fold_action_unindented_syntax_block(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_brackets_query(r#"("{" @open "}" @close)"#)
+        .unwrap(),
+    );
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // The body isn't indented, so the fold range comes from the syntax tree.
+    cx.set_state(indoc! {"
+        fn a() {ˇ
+        let x = 1;
+        let y = 2;
+        }
+        fn b() {}
+    "});
+    cx.executor().run_until_parked();
+
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor
+                .snapshot(cx)
+                .syntax_foldable_ranges(MultiBufferRow(0)..MultiBufferRow(5)),
+            vec![Point::new(0, 8)..Point::new(2, 10)]
+        );
+
+        editor.fold(&Fold, cx);
+        assert_eq!(editor.display_text(cx), "fn a() {⋯\n}\nfn b() {}\n");
+
+        editor.unfold_lines(&UnfoldLines, cx);
+        assert_eq!(
+            editor.display_text(cx),
+            editor.buffer.read(cx).read(cx).text()
+        );
+    });
+
+    // Folding from inside the block folds the innermost block enclosing the cursor.
+    cx.set_state(indoc! {"
+        fn a() {
+        let x = ˇ1;
+        let y = 2;
+        }
+        fn b() {}
+    "});
+    cx.update_editor(|editor, cx| {
+        editor.fold(&Fold, cx);
+        assert_eq!(editor.display_text(cx), "fn a() {⋯\n}\nfn b() {}\n");
+    });
+}
+
 #[gpui::test]
 fn test_fold_action_whitespace_sensitive_language(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -1590,6 +1787,68 @@ fn test_beginning_end_of_line_ignore_soft_wrap(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_move_up_down_with_soft_wrap(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let view = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abcdefghijklmnopqrstuvwxyz0123456789\nshort", cx);
+        build_editor(buffer, cx)
+    });
+
+    _ = view.update(cx, |view, cx| {
+        view.set_wrap_width(Some(140.0.into()), cx);
+        assert_eq!(
+            "abcdefghijklmnop\nqrstuvwxyz012345\n6789\nshort",
+            view.display_text(cx),
+        );
+
+        view.change_selections(None, cx, |s| {
+            s.select_display_ranges([
+                DisplayPoint::new(DisplayRow(0), 5)..DisplayPoint::new(DisplayRow(0), 5)
+            ]);
+        });
+
+        // Moving down stays within the wrapped buffer line, landing on the next visual row.
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            vec![DisplayPoint::new(DisplayRow(1), 5)..DisplayPoint::new(DisplayRow(1), 5)],
+            view.selections.display_ranges(cx)
+        );
+
+        // A shorter visual row clamps the column without forgetting the goal column.
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            vec![DisplayPoint::new(DisplayRow(2), 4)..DisplayPoint::new(DisplayRow(2), 4)],
+            view.selections.display_ranges(cx)
+        );
+
+        view.move_down(&MoveDown, cx);
+        assert_eq!(
+            vec![DisplayPoint::new(DisplayRow(3), 5)..DisplayPoint::new(DisplayRow(3), 5)],
+            view.selections.display_ranges(cx)
+        );
+
+        view.move_up(&MoveUp, cx);
+        assert_eq!(
+            vec![DisplayPoint::new(DisplayRow(2), 4)..DisplayPoint::new(DisplayRow(2), 4)],
+            view.selections.display_ranges(cx)
+        );
+
+        view.move_up(&MoveUp, cx);
+        assert_eq!(
+            vec![DisplayPoint::new(DisplayRow(1), 5)..DisplayPoint::new(DisplayRow(1), 5)],
+            view.selections.display_ranges(cx)
+        );
+
+        view.move_up(&MoveUp, cx);
+        assert_eq!(
+            vec![DisplayPoint::new(DisplayRow(0), 5)..DisplayPoint::new(DisplayRow(0), 5)],
+            view.selections.display_ranges(cx)
+        );
+    });
+}
+
 #[gpui::test]
 fn test_prev_next_word_boundary(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -2611,6 +2870,103 @@ async fn test_tab_with_mixed_whitespace(cx: &mut gpui::TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_auto_indent_on_newline(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4);
+    });
+
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_indents_query(r#"(_ "{" "}" @end) @indent"#)
+        .unwrap(),
+    );
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+    // New lines inherit the indentation of the previous line
+    cx.set_state(indoc! {"
+        fn a() {
+            b();ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.newline(&Newline, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            b();
+            ˇ
+        }
+    "});
+
+    // New lines after an opening brace are indented one more level
+    cx.set_state(indoc! {"
+        fn a() {ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.newline(&Newline, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            ˇ
+        }
+    "});
+
+    // Typing a closing brace as the first character on a line outdents it
+    cx.set_state(indoc! {"
+        fn a() {
+            if b {
+                c();
+                ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.handle_input("}", cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            if b {
+                c();
+            }ˇ
+        }
+    "});
+
+    // When disabled, new lines only keep the previous line's indentation
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.auto_indent = Some(false);
+            });
+        });
+    });
+    cx.set_state(indoc! {"
+        fn a() {ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.newline(&Newline, cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+        ˇ
+        }
+    "});
+
+    cx.set_state(indoc! {"
+        fn a() {
+            if b {
+                c();
+                ˇ
+        }
+    "});
+    cx.update_editor(|e, cx| e.handle_input("}", cx));
+    cx.assert_editor_state(indoc! {"
+        fn a() {
+            if b {
+                c();
+                }ˇ
+        }
+    "});
+}
+
 #[gpui::test]
 async fn test_indent_outdent(cx: &mut gpui::TestAppContext) {
     init_test(cx, |settings| {
@@ -2684,6 +3040,53 @@ async fn test_indent_outdent(cx: &mut gpui::TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_indent_with_detected_indentation(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4);
+        settings.defaults.detect_indentation = Some(true);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // A buffer indented with two spaces gets two-space indents, despite the tab size.
+    cx.set_state(indoc! {"
+        one {
+          two
+        ˇthree
+        }
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        one {
+          two
+          ˇthree
+        }
+    "});
+    cx.update_editor(|e, cx| e.tab_prev(&TabPrev, cx));
+    cx.assert_editor_state(indoc! {"
+        one {
+          two
+        ˇthree
+        }
+    "});
+
+    // A buffer indented with tabs gets tabs, even though hard tabs are disabled.
+    cx.set_state(indoc! {"
+        one {
+        \ttwo
+        ˇthree
+        }
+    "});
+    cx.update_editor(|e, cx| e.tab(&Tab, cx));
+    cx.assert_editor_state(indoc! {"
+        one {
+        \ttwo
+        \tˇthree
+        }
+    "});
+}
+
 #[gpui::test]
 async fn test_indent_outdent_with_hard_tabs(cx: &mut gpui::TestAppContext) {
     init_test(cx, |settings| {
@@ -3117,6 +3520,49 @@ fn test_join_lines_with_single_selection(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_join_lines_collapses_whitespace(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("aaa  \n    bbb\t\n\tccc\n   \nddd", cx);
+        let mut editor = build_editor(buffer.clone(), cx);
+        let buffer = buffer.read(cx).as_singleton().unwrap();
+
+        // Trailing and leading whitespace around the join point become a single space
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb\t\n\tccc\n   \nddd");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            &[Point::new(0, 3)..Point::new(0, 3)]
+        );
+
+        // Joining a selection collapses whitespace at every join point
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(0, 0)..Point::new(1, 1)])
+        });
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb ccc\n   \nddd");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            &[Point::new(0, 7)..Point::new(0, 7)]
+        );
+
+        // A line containing only whitespace is removed entirely
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 0)..Point::new(1, 0)])
+        });
+        editor.join_lines(&JoinLines, cx);
+        assert_eq!(buffer.read(cx).text(), "aaa bbb ccc\nddd");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            &[Point::new(1, 0)..Point::new(1, 0)]
+        );
+
+        editor
+    });
+}
+
 #[gpui::test]
 fn test_join_lines_with_multi_selection(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -3247,7 +3693,9 @@ async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
         Y
         Xˇ»
     "});
-    cx.update_editor(|e, cx| e.sort_lines_case_insensitive(&SortLinesCaseInsensitive, cx));
+    cx.update_editor(|e, cx| {
+        e.sort_lines_case_insensitive(&SortLinesCaseInsensitive::default(), cx)
+    });
     cx.assert_editor_state(indoc! {"
         «x
         X
@@ -3280,13 +3728,14 @@ async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
     // Since all methods calling manipulate_lines() are doing the exact same general thing (reordering lines)
 
     // Don't manipulate when cursor is on single line, but expand the selection
+    // (the sort actions themselves apply to the whole buffer without a selection)
     cx.set_state(indoc! {"
         ddˇdd
         ccc
         bb
         a
     "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.update_editor(|e, cx| e.manipulate_lines(cx, |lines| lines.sort()));
     cx.assert_editor_state(indoc! {"
         «ddddˇ»
         ccc
@@ -3303,7 +3752,7 @@ async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
         bb
         aaaaaˇ»
     "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive::default(), cx));
     cx.assert_editor_state(indoc! {"
         «aaaaa
         bb
@@ -3321,7 +3770,7 @@ async fn test_manipulate_lines_with_single_selection(cx: &mut TestAppContext) {
 
         ˇ»
     "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive::default(), cx));
     cx.assert_editor_state(indoc! {"
         «
 
@@ -3414,42 +3863,156 @@ async fn test_unique_lines_multi_selection(cx: &mut TestAppContext) {
         aaaaa
         aaaaaˇ»
 
-        aaa«aaˇ»
+        aaa«aaˇ»
+    "});
+    cx.update_editor(|e, cx| e.unique_lines_case_sensitive(&UniqueLinesCaseSensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «aaaaa
+        bbˇ»
+
+        «aaaaaˇ»
+    "});
+}
+
+#[gpui::test]
+async fn test_unique_lines_single_selection(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    cx.set_state(indoc! {"
+        «Aaa
+        aAa
+        Aaaˇ»
+    "});
+    cx.update_editor(|e, cx| e.unique_lines_case_sensitive(&UniqueLinesCaseSensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «Aaa
+        aAaˇ»
+    "});
+
+    cx.set_state(indoc! {"
+        «Aaa
+        aAa
+        aaAˇ»
+    "});
+    cx.update_editor(|e, cx| e.unique_lines_case_insensitive(&UniqueLinesCaseInsensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «Aaaˇ»
+    "});
+}
+
+#[gpui::test]
+async fn test_sort_and_unique_lines_without_selection(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Without a selection, the whole buffer is sorted and the trailing newline is kept
+    cx.set_state(indoc! {"
+        bˇ
+        A
+        a
+        B
+    "});
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        «A
+        B
+        a
+        bˇ»
+    "});
+
+    cx.set_state(indoc! {"
+        bˇ
+        A
+        a
+        B
+    "});
+    cx.update_editor(|e, cx| {
+        e.sort_lines_case_insensitive(&SortLinesCaseInsensitive::default(), cx)
+    });
+    cx.assert_editor_state(indoc! {"
+        «A
+        a
+        b
+        Bˇ»
+    "});
+
+    // Deduplicating keeps the first occurrence of each line
+    cx.set_state(indoc! {"
+        ˇb
+        a
+        b
+        A
+        a
+        c
+    "});
+    cx.update_editor(|e, cx| e.unique_lines_case_sensitive(&UniqueLinesCaseSensitive, cx));
+    cx.assert_editor_state(indoc! {"
+        «b
+        a
+        A
+        cˇ»
+    "});
+
+    cx.set_state(indoc! {"
+        ˇb
+        a
+        b
+        A
+        a
+        c
     "});
-    cx.update_editor(|e, cx| e.unique_lines_case_sensitive(&UniqueLinesCaseSensitive, cx));
+    cx.update_editor(|e, cx| e.unique_lines_case_insensitive(&UniqueLinesCaseInsensitive, cx));
     cx.assert_editor_state(indoc! {"
-        «aaaaa
-        bbˇ»
-
-        «aaaaaˇ»
+        «b
+        a
+        cˇ»
     "});
 }
 
 #[gpui::test]
-async fn test_unique_lines_single_selection(cx: &mut TestAppContext) {
+async fn test_sort_lines_descending(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
 
     let mut cx = EditorTestContext::new(cx).await;
 
     cx.set_state(indoc! {"
-        «Aaa
-        aAa
-        Aaaˇ»
+        «b
+        A
+        a
+        Bˇ»
+        z
     "});
-    cx.update_editor(|e, cx| e.unique_lines_case_sensitive(&UniqueLinesCaseSensitive, cx));
+    cx.update_editor(|e, cx| {
+        e.sort_lines_case_sensitive(&SortLinesCaseSensitive { descending: true }, cx)
+    });
     cx.assert_editor_state(indoc! {"
-        «Aaa
-        aAaˇ»
+        «b
+        a
+        B
+        Aˇ»
+        z
     "});
 
+    // Lines that only differ in case keep their relative order
     cx.set_state(indoc! {"
-        «Aaa
-        aAa
-        aaAˇ»
+        «b
+        A
+        a
+        Bˇ»
+        z
     "});
-    cx.update_editor(|e, cx| e.unique_lines_case_insensitive(&UniqueLinesCaseInsensitive, cx));
+    cx.update_editor(|e, cx| {
+        e.sort_lines_case_insensitive(&SortLinesCaseInsensitive { descending: true }, cx)
+    });
     cx.assert_editor_state(indoc! {"
-        «Aaaˇ»
+        «b
+        B
+        A
+        aˇ»
+        z
     "});
 }
 
@@ -3466,7 +4029,7 @@ async fn test_manipulate_lines_with_multi_selection(cx: &mut TestAppContext) {
         bb
         aaaˇ»aa
     "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive::default(), cx));
     cx.assert_editor_state(indoc! {"
         «aaaaa
         bb
@@ -3487,7 +4050,7 @@ async fn test_manipulate_lines_with_multi_selection(cx: &mut TestAppContext) {
         bb
         aaaˇ»aa
     "});
-    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive, cx));
+    cx.update_editor(|e, cx| e.sort_lines_case_sensitive(&SortLinesCaseSensitive::default(), cx));
     cx.assert_editor_state(indoc! {"
         «1
         2
@@ -3652,6 +4215,92 @@ async fn test_manipulate_text(cx: &mut TestAppContext) {
     cx.assert_editor_state(indoc! {"
         «HeLlO, wOrLD!ˇ»
     "});
+
+    // Each selection is transformed independently
+    cx.set_state(indoc! {"
+        «HELLOˇ» big «BEAUTIFUL
+        Worldˇ» and «Moreˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_lower_case(&ConvertToLowerCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «helloˇ» big «beautiful
+        worldˇ» and «moreˇ»
+    "});
+
+    cx.set_state(indoc! {"
+        «the quick brownˇ» fox, «HELLO worldˇ» and «once moreˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_title_case(&ConvertToTitleCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «The Quick Brownˇ» fox, «Hello Worldˇ» and «Once Moreˇ»
+    "});
+
+    cx.set_state(indoc! {"
+        «Helloˇ» wOrLd «ABCdefˇ»
+    "});
+    cx.update_editor(|e, cx| e.convert_to_opposite_case(&ConvertToOppositeCase, cx));
+    cx.assert_editor_state(indoc! {"
+        «hELLOˇ» wOrLd «abcDEFˇ»
+    "});
+}
+
+#[gpui::test]
+async fn test_encode_decode_text(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Round-trip through base64
+    cx.set_state(indoc! {"
+        «hello worldˇ»
+    "});
+    cx.update_editor(|e, cx| e.encode_base64(&EncodeBase64, cx));
+    cx.assert_editor_state(indoc! {"
+        «aGVsbG8gd29ybGQ=ˇ»
+    "});
+    cx.update_editor(|e, cx| e.decode_base64(&DecodeBase64, cx));
+    cx.assert_editor_state(indoc! {"
+        «hello worldˇ»
+    "});
+
+    // Round-trip through hex, with multiple selections
+    cx.set_state(indoc! {"
+        «helloˇ» «worldˇ»
+    "});
+    cx.update_editor(|e, cx| e.encode_hex(&EncodeHex, cx));
+    cx.assert_editor_state(indoc! {"
+        «68656c6c6fˇ» «776f726c64ˇ»
+    "});
+    cx.update_editor(|e, cx| e.decode_hex(&DecodeHex, cx));
+    cx.assert_editor_state(indoc! {"
+        «helloˇ» «worldˇ»
+    "});
+
+    // Invalid input in any selection leaves all of the text unchanged
+    cx.set_state(indoc! {"
+        «aGk=ˇ» «not base64!ˇ»
+    "});
+    cx.update_editor(|e, cx| e.decode_base64(&DecodeBase64, cx));
+    cx.assert_editor_state(indoc! {"
+        «aGk=ˇ» «not base64!ˇ»
+    "});
+
+    cx.set_state(indoc! {"
+        «abcˇ»
+    "});
+    cx.update_editor(|e, cx| e.decode_hex(&DecodeHex, cx));
+    cx.assert_editor_state(indoc! {"
+        «abcˇ»
+    "});
+
+    // Decoded bytes that aren't valid UTF-8 are rejected
+    cx.set_state(indoc! {"
+        «ffˇ»
+    "});
+    cx.update_editor(|e, cx| e.decode_hex(&DecodeHex, cx));
+    cx.assert_editor_state(indoc! {"
+        «ffˇ»
+    "});
 }
 
 #[gpui::test]
@@ -3757,6 +4406,32 @@ fn test_duplicate_line(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_duplicate_selection(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Without a selection, the line is duplicated and the cursor moves to the copy below.
+    cx.set_state("abc\ndeˇf\nghi");
+    cx.update_editor(|e, cx| e.duplicate_selection(&DuplicateSelection, cx));
+    cx.assert_editor_state("abc\ndef\ndeˇf\nghi");
+
+    // A selection is duplicated in place, and the copy becomes selected.
+    cx.set_state("a«bcˇ»d\nefg");
+    cx.update_editor(|e, cx| e.duplicate_selection(&DuplicateSelection, cx));
+    cx.assert_editor_state("abc«bcˇ»d\nefg");
+
+    cx.set_state("a«ˇbc\nde»f");
+    cx.update_editor(|e, cx| e.duplicate_selection(&DuplicateSelection, cx));
+    cx.assert_editor_state("abc\nde«ˇbc\nde»f");
+
+    // Each selection is duplicated independently, and cursors sharing a line only
+    // duplicate it once.
+    cx.set_state("«abˇ»c\ndˇeˇf\nghi");
+    cx.update_editor(|e, cx| e.duplicate_selection(&DuplicateSelection, cx));
+    cx.assert_editor_state("ab«abˇ»c\ndef\ndˇeˇf\nghi");
+}
+
 #[gpui::test]
 fn test_move_line_up_down(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -3856,6 +4531,36 @@ fn test_move_line_up_down(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_move_line_up_down_at_edges(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Moving the first line up or the last line down does nothing.
+    cx.set_state("abˇc\ndef");
+    cx.update_editor(|e, cx| e.move_line_up(&MoveLineUp, cx));
+    cx.assert_editor_state("abˇc\ndef");
+
+    cx.set_state("abc\ndeˇf");
+    cx.update_editor(|e, cx| e.move_line_down(&MoveLineDown, cx));
+    cx.assert_editor_state("abc\ndeˇf");
+
+    // A selection spanning several lines moves as a block, keeping its columns.
+    cx.set_state("a«bc\ndeˇ»f\nghi\njkl");
+    cx.update_editor(|e, cx| e.move_line_down(&MoveLineDown, cx));
+    cx.assert_editor_state("ghi\na«bc\ndeˇ»f\njkl");
+
+    // Cursors on separate lines move independently, and one stuck at the top
+    // doesn't prevent the others from moving.
+    cx.set_state("aˇbc\ndef\ngˇhi\njkl");
+    cx.update_editor(|e, cx| e.move_line_down(&MoveLineDown, cx));
+    cx.assert_editor_state("def\naˇbc\njkl\ngˇhi");
+    cx.update_editor(|e, cx| e.move_line_up(&MoveLineUp, cx));
+    cx.assert_editor_state("aˇbc\ndef\ngˇhi\njkl");
+    cx.update_editor(|e, cx| e.move_line_up(&MoveLineUp, cx));
+    cx.assert_editor_state("aˇbc\ngˇhi\ndef\njkl");
+}
+
 #[gpui::test]
 fn test_move_line_up_down_with_blocks(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -4599,6 +5304,37 @@ async fn test_select_next(cx: &mut gpui::TestAppContext) {
     cx.assert_editor_state("«abcˇ»\n«abcˇ» «abcˇ»\ndefabc\n«abcˇ»");
 }
 
+#[gpui::test]
+async fn test_select_next_wordwise_and_substring(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    // Starting from a caret selects the word under it, and only whole-word matches are added.
+    cx.set_state("ˇab xab ab");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abˇ» xab ab");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abˇ» xab «abˇ»");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abˇ» xab «abˇ»");
+
+    // Starting from an explicit selection matches the text anywhere, wrapping around.
+    cx.set_state("ab x«abˇ» ab");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("ab x«abˇ» «abˇ»");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abˇ» x«abˇ» «abˇ»");
+    cx.update_editor(|e, cx| e.select_next(&SelectNext::default(), cx))
+        .unwrap();
+    cx.assert_editor_state("«abˇ» x«abˇ» «abˇ»");
+}
+
 #[gpui::test]
 async fn test_select_all_matches(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -5320,6 +6056,73 @@ async fn test_always_treat_brackets_as_autoclosed_skip_over(cx: &mut gpui::TestA
     );
 }
 
+#[gpui::test]
+async fn test_autoclose_disabled_via_settings(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.use_autoclose = Some(false);
+    });
+
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let language = Arc::new(Language::new(
+        LanguageConfig {
+            brackets: BracketPairConfig {
+                pairs: vec![
+                    BracketPair {
+                        start: "(".to_string(),
+                        end: ")".to_string(),
+                        close: true,
+                        surround: true,
+                        newline: true,
+                    },
+                    BracketPair {
+                        start: "\"".to_string(),
+                        end: "\"".to_string(),
+                        close: true,
+                        surround: true,
+                        newline: false,
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Some(tree_sitter_rust::language()),
+    ));
+
+    cx.language_registry().add(language.clone());
+    cx.update_buffer(|buffer, cx| {
+        buffer.set_language(Some(language), cx);
+    });
+
+    // Opening characters are inserted on their own.
+    cx.set_state("a ˇ");
+    cx.update_editor(|view, cx| {
+        view.handle_input("(", cx);
+        view.handle_input("\"", cx);
+    });
+    cx.assert_editor_state("a (\"ˇ");
+
+    // Closing characters are inserted rather than typed over.
+    cx.set_state("a (ˇ)");
+    cx.update_editor(|view, cx| view.handle_input(")", cx));
+    cx.assert_editor_state("a ()ˇ)");
+
+    // Re-enabling the setting takes effect immediately.
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.use_autoclose = Some(true);
+            });
+        });
+    });
+    cx.set_state("a ˇ");
+    cx.update_editor(|view, cx| view.handle_input("(", cx));
+    cx.assert_editor_state("a (ˇ)");
+    cx.update_editor(|view, cx| view.handle_input(")", cx));
+    cx.assert_editor_state("a ()ˇ");
+}
+
 #[gpui::test]
 async fn test_autoclose_with_embedded_language(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -6194,6 +6997,58 @@ async fn test_snippets(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_whitespace_cleanup_during_save(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.remove_trailing_whitespace_on_save = Some(true);
+        settings.defaults.ensure_final_newline_on_save = Some(true);
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_file("/file.txt", Default::default()).await;
+
+    let project = Project::test(fs.clone(), ["/file.txt".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/file.txt", cx))
+        .await
+        .unwrap();
+
+    let buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer, cx));
+    let (editor, cx) = cx.add_window_view(|cx| build_editor(buffer, cx));
+    editor.update(cx, |editor, cx| {
+        editor.set_text("one  \ntwo\t\n\nthree   ", cx);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([
+                Point::new(0, 5)..Point::new(0, 5),
+                Point::new(1, 1)..Point::new(1, 4),
+            ])
+        });
+    });
+
+    let save = editor
+        .update(cx, |editor, cx| editor.save(true, project.clone(), cx))
+        .unwrap();
+    save.await;
+
+    // Trailing spaces and tabs are removed and a final newline is added, while selections
+    // inside the removed whitespace are clamped to the new line lengths.
+    editor.update(cx, |editor, cx| {
+        assert_eq!(editor.text(cx), "one\ntwo\n\nthree\n");
+        assert_eq!(
+            editor.selections.ranges::<Point>(cx),
+            [
+                Point::new(0, 3)..Point::new(0, 3),
+                Point::new(1, 1)..Point::new(1, 3),
+            ]
+        );
+    });
+    assert_eq!(
+        fs.load("/file.txt".as_ref()).await.unwrap(),
+        "one\ntwo\n\nthree\n"
+    );
+    assert!(!cx.read(|cx| editor.is_dirty(cx)));
+}
+
 #[gpui::test]
 async fn test_document_format_during_save(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -7935,28 +8790,112 @@ async fn test_no_duplicated_completion_requests(cx: &mut gpui::TestAppContext) {
         }
     });
 
-    cx.condition(|editor, _| editor.context_menu_visible())
-        .await;
-    cx.assert_editor_state(indoc! {"fn main() { let a = 2.ˇ; }"});
-    assert!(request.next().await.is_some());
-    assert_eq!(counter.load(atomic::Ordering::Acquire), 1);
+    cx.condition(|editor, _| editor.context_menu_visible())
+        .await;
+    cx.assert_editor_state(indoc! {"fn main() { let a = 2.ˇ; }"});
+    assert!(request.next().await.is_some());
+    assert_eq!(counter.load(atomic::Ordering::Acquire), 1);
+
+    cx.simulate_keystroke("S");
+    cx.simulate_keystroke("o");
+    cx.simulate_keystroke("m");
+    cx.condition(|editor, _| editor.context_menu_visible())
+        .await;
+    cx.assert_editor_state(indoc! {"fn main() { let a = 2.Somˇ; }"});
+    assert!(request.next().await.is_some());
+    assert!(request.next().await.is_some());
+    assert!(request.next().await.is_some());
+    request.close();
+    assert!(request.next().await.is_none());
+    assert_eq!(
+        counter.load(atomic::Ordering::Acquire),
+        4,
+        "With the completions menu open, only one LSP request should happen per input"
+    );
+}
+
+#[gpui::test]
+async fn test_incomplete_completions(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            completion_provider: Some(lsp::CompletionOptions {
+                trigger_characters: Some(vec![".".to_string()]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+
+    let trigger_kinds = Arc::new(Mutex::new(Vec::new()));
+    cx.lsp.handle_request::<lsp::request::Completion, _, _>({
+        let trigger_kinds = trigger_kinds.clone();
+        move |params, _| {
+            trigger_kinds
+                .lock()
+                .push(params.context.map(|context| context.trigger_kind));
+            async move {
+                Ok(Some(lsp::CompletionResponse::List(lsp::CompletionList {
+                    is_incomplete: true,
+                    items: ["apple", "apricot", "banana"]
+                        .into_iter()
+                        .map(|label| lsp::CompletionItem {
+                            label: label.into(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                })))
+            }
+        }
+    });
+
+    let menu_entries = |cx: &mut EditorLspTestContext| {
+        cx.update_editor(|editor, _| {
+            if let Some(ContextMenu::Completions(menu)) = editor.context_menu.read().as_ref() {
+                let mut entries = menu
+                    .matches
+                    .iter()
+                    .map(|m| m.string.clone())
+                    .collect::<Vec<_>>();
+                entries.sort();
+                entries
+            } else {
+                panic!("expected completion menu to be open");
+            }
+        })
+    };
 
-    cx.simulate_keystroke("S");
-    cx.simulate_keystroke("o");
-    cx.simulate_keystroke("m");
-    cx.condition(|editor, _| editor.context_menu_visible())
-        .await;
-    cx.assert_editor_state(indoc! {"fn main() { let a = 2.Somˇ; }"});
-    assert!(request.next().await.is_some());
-    assert!(request.next().await.is_some());
-    assert!(request.next().await.is_some());
-    request.close();
-    assert!(request.next().await.is_none());
+    cx.set_state("fn main() { oneˇ }");
+    cx.simulate_keystroke(".");
+    cx.executor().run_until_parked();
+    assert_eq!(menu_entries(&mut cx), ["apple", "apricot", "banana"]);
+
+    // Because the results were incomplete, typing asks the server again, and the
+    // new results are filtered by the typed prefix.
+    cx.simulate_keystrokes("a p");
+    cx.executor().run_until_parked();
+    assert_eq!(menu_entries(&mut cx), ["apple", "apricot"]);
     assert_eq!(
-        counter.load(atomic::Ordering::Acquire),
-        4,
-        "With the completions menu open, only one LSP request should happen per input"
+        trigger_kinds.lock().first(),
+        Some(&Some(lsp::CompletionTriggerKind::TRIGGER_CHARACTER))
     );
+    assert_eq!(
+        trigger_kinds.lock().last(),
+        Some(&Some(
+            lsp::CompletionTriggerKind::TRIGGER_FOR_INCOMPLETE_COMPLETIONS
+        ))
+    );
+
+    // Moving the cursor out of the completed word dismisses the menu and
+    // cancels any pending completion requests.
+    cx.update_editor(|editor, cx| editor.move_to_beginning(&MoveToBeginning, cx));
+    cx.update_editor(|editor, _| {
+        assert!(!editor.context_menu_visible());
+        assert!(editor.completion_tasks.is_empty());
+    });
 }
 
 #[gpui::test]
@@ -8080,6 +9019,61 @@ async fn test_toggle_comment(cx: &mut gpui::TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_toggle_comment_uses_language_comment_token(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+    let mut cx = EditorTestContext::new(cx).await;
+
+    let python = Arc::new(Language::new(
+        LanguageConfig {
+            name: "Python".into(),
+            line_comments: vec!["# ".into()],
+            ..Default::default()
+        },
+        None,
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(python), cx));
+
+    cx.set_state(indoc! {"
+        def a():
+            «b()
+            c()ˇ»
+    "});
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        def a():
+            # «b()
+            # c()ˇ»
+    "});
+
+    // Lines that are already commented are uncommented.
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        def a():
+            «b()
+            c()ˇ»
+    "});
+
+    // Without any comment syntax, toggling comments leaves the text alone.
+    let no_comments = Arc::new(Language::new(
+        LanguageConfig {
+            name: "No Comments".into(),
+            ..Default::default()
+        },
+        None,
+    ));
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(no_comments), cx));
+    cx.set_state(indoc! {"
+        «a
+        bˇ»
+    "});
+    cx.update_editor(|e, cx| e.toggle_comments(&ToggleComments::default(), cx));
+    cx.assert_editor_state(indoc! {"
+        «a
+        bˇ»
+    "});
+}
+
 #[gpui::test]
 async fn test_advance_downward_on_toggle_comment(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -12752,6 +13746,183 @@ fn indent_guide(buffer_id: BufferId, start_row: u32, end_row: u32, depth: u32) -
     }
 }
 
+#[gpui::test]
+fn test_smooth_scrolling(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple(&sample_text(100, 5, 'a'), cx);
+        build_editor(buffer, cx)
+    });
+    let scroll_top = |cx: &mut TestAppContext| {
+        editor
+            .update(cx, |editor, cx| editor.scroll_position(cx).y)
+            .unwrap()
+    };
+
+    // Without smooth scrolling, scrolling by a page jumps straight to the new position.
+    _ = editor.update(cx, |editor, cx| {
+        editor.set_visible_line_count(10., cx);
+        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+    });
+    assert_eq!(scroll_top(cx), 9.);
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.smooth_scrolling = Some(true);
+            });
+        });
+    });
+
+    // With smooth scrolling, the position approaches the new one on each frame.
+    _ = editor.update(cx, |editor, cx| {
+        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+    });
+    assert_eq!(scroll_top(cx), 9.);
+    let mut previous_scroll_top = 9.;
+    for _ in 1..scroll::SMOOTH_SCROLL_FRAMES {
+        cx.executor()
+            .advance_clock(scroll::SMOOTH_SCROLL_FRAME_INTERVAL);
+        let current_scroll_top = scroll_top(cx);
+        assert!(
+            previous_scroll_top < current_scroll_top && current_scroll_top < 18.,
+            "{current_scroll_top} should be between {previous_scroll_top} and 18"
+        );
+        previous_scroll_top = current_scroll_top;
+    }
+    cx.executor()
+        .advance_clock(scroll::SMOOTH_SCROLL_FRAME_INTERVAL);
+    assert_eq!(scroll_top(cx), 18.);
+
+    // Scrolling during an animation cancels it.
+    _ = editor.update(cx, |editor, cx| {
+        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+    });
+    cx.executor()
+        .advance_clock(scroll::SMOOTH_SCROLL_FRAME_INTERVAL);
+    _ = editor.update(cx, |editor, cx| {
+        editor.apply_scroll_delta(point(0., 1.), cx);
+    });
+    let scroll_top_after_input = scroll_top(cx);
+    cx.executor()
+        .advance_clock(scroll::SMOOTH_SCROLL_FRAME_INTERVAL * scroll::SMOOTH_SCROLL_FRAMES);
+    assert_eq!(scroll_top(cx), scroll_top_after_input);
+    assert!(scroll_top_after_input < 27.);
+
+    // Scrolling again during an animation continues from where the first one was heading.
+    let start = scroll_top(cx);
+    _ = editor.update(cx, |editor, cx| {
+        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+    });
+    cx.executor()
+        .advance_clock(scroll::SMOOTH_SCROLL_FRAME_INTERVAL);
+    _ = editor.update(cx, |editor, cx| {
+        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+    });
+    cx.executor()
+        .advance_clock(scroll::SMOOTH_SCROLL_FRAME_INTERVAL * scroll::SMOOTH_SCROLL_FRAMES);
+    assert!((scroll_top(cx) - (start + 18.)).abs() < 0.01);
+
+    // Setting the scroll anchor during an animation cancels it, too.
+    let start = scroll_top(cx);
+    _ = editor.update(cx, |editor, cx| {
+        editor.scroll_screen(&ScrollAmount::Page(1.), cx);
+    });
+    cx.executor()
+        .advance_clock(scroll::SMOOTH_SCROLL_FRAME_INTERVAL);
+    _ = editor.update(cx, |editor, cx| {
+        let anchor = editor.scroll_manager.anchor();
+        editor.set_scroll_anchor(anchor, cx);
+    });
+    let scroll_top_after_anchor = scroll_top(cx);
+    cx.executor()
+        .advance_clock(scroll::SMOOTH_SCROLL_FRAME_INTERVAL * scroll::SMOOTH_SCROLL_FRAMES);
+    assert_eq!(scroll_top(cx), scroll_top_after_anchor);
+    assert!(scroll_top_after_anchor < start + 9.);
+}
+
+#[test]
+fn test_max_scroll_top_for_scroll_beyond_last_line() {
+    // A 100-row buffer shown 20 rows at a time, with a vertical scroll margin of 3.
+    let max_scroll_top = |scroll_beyond_last_line: ScrollBeyondLastLine| {
+        scroll_beyond_last_line.max_scroll_top(99., 20., 3.)
+    };
+    assert_eq!(max_scroll_top(ScrollBeyondLastLine::Off), 80.);
+    assert_eq!(max_scroll_top(ScrollBeyondLastLine::OnePage), 99.);
+    assert_eq!(
+        max_scroll_top(ScrollBeyondLastLine::VerticalScrollMargin),
+        83.
+    );
+    assert_eq!(max_scroll_top(ScrollBeyondLastLine::Lines(10)), 90.);
+
+    // Buffers shorter than the editor can't be scrolled unless scrolling beyond the last line.
+    assert_eq!(ScrollBeyondLastLine::Off.max_scroll_top(4., 20., 3.), 0.);
+    assert_eq!(
+        ScrollBeyondLastLine::Lines(10).max_scroll_top(4., 20., 3.),
+        0.
+    );
+    assert_eq!(
+        ScrollBeyondLastLine::Lines(30).max_scroll_top(4., 20., 3.),
+        15.
+    );
+
+    assert_eq!(
+        serde_json::from_value::<ScrollBeyondLastLine>(json!({ "lines": 5 })).unwrap(),
+        ScrollBeyondLastLine::Lines(5)
+    );
+    assert_eq!(
+        serde_json::from_value::<ScrollBeyondLastLine>(json!("off")).unwrap(),
+        ScrollBeyondLastLine::Off
+    );
+}
+
+#[gpui::test]
+fn test_minimap_line_densities(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let buffer = MultiBuffer::build_simple("fn main() {\n    let x = 1;\n}\n", cx);
+    let editor = cx.add_window(|cx| build_editor(buffer, cx));
+
+    _ = editor.update(cx, |editor, cx| {
+        assert_eq!(editor.minimap_line_densities(cx), None);
+    });
+
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|settings, cx| {
+            settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                settings.minimap = Some(MinimapContent {
+                    enabled: Some(true),
+                });
+            });
+        });
+    });
+
+    _ = editor.update(cx, |editor, cx| {
+        assert_eq!(
+            editor.minimap_line_densities(cx).unwrap(),
+            [0.09, 0.07, 0.01, 0.0]
+        );
+
+        editor.edit(
+            [
+                (Point::new(0, 3)..Point::new(0, 7), "run"),
+                (Point::new(0, 11)..Point::new(0, 11), "\n    // comment"),
+                (Point::new(2, 0)..Point::new(2, 1), "};"),
+            ],
+            cx,
+        );
+        assert_eq!(
+            editor.text(cx),
+            "fn run() {\n    // comment\n    let x = 1;\n};\n"
+        );
+        assert_eq!(
+            editor.minimap_line_densities(cx).unwrap(),
+            [0.08, 0.09, 0.07, 0.02, 0.0]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_indent_guide_single_line(cx: &mut gpui::TestAppContext) {
     let (buffer_id, mut cx) = setup_indent_guides_editor(
@@ -12854,6 +14025,33 @@ async fn test_indent_guide_continues_on_empty_line(cx: &mut gpui::TestAppContext
     assert_indent_guides(0..5, vec![indent_guide(buffer_id, 1, 3, 0)], None, &mut cx);
 }
 
+#[gpui::test]
+async fn test_indent_guide_nested_continues_on_empty_line(cx: &mut gpui::TestAppContext) {
+    let (buffer_id, mut cx) = setup_indent_guides_editor(
+        &"
+        fn main() {
+            if a {
+                b();
+
+                c();
+            }
+        }"
+        .unindent(),
+        cx,
+    )
+    .await;
+
+    assert_indent_guides(
+        0..7,
+        vec![
+            indent_guide(buffer_id, 1, 5, 0),
+            indent_guide(buffer_id, 2, 4, 1),
+        ],
+        None,
+        &mut cx,
+    );
+}
+
 #[gpui::test]
 async fn test_indent_guide_complex(cx: &mut gpui::TestAppContext) {
     let (buffer_id, mut cx) = setup_indent_guides_editor(
@@ -13233,7 +14431,8 @@ fn test_crease_insertion_and_rendering(cx: &mut TestAppContext) {
 
             editor.insert_creases(Some(crease), cx);
             let snapshot = editor.snapshot(cx);
-            let _div = snapshot.render_fold_toggle(MultiBufferRow(1), false, cx.view().clone(), cx);
+            let _div =
+                snapshot.render_fold_toggle(MultiBufferRow(1), false, false, cx.view().clone(), cx);
             snapshot
         })
         .unwrap();
@@ -13490,6 +14689,99 @@ async fn test_goto_definition_with_find_all_references_fallback(cx: &mut gpui::T
     });
 }
 
+#[gpui::test]
+async fn test_goto_definition_in_other_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/a",
+        json!({
+            "main.rs": "mod other;\n\nfn main() {\n    other::helper();\n}\n",
+            "other.rs": "// Helpers\n\npub fn helper() {}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/a".as_ref()], cx).await;
+    let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+    let cx = &mut VisualTestContext::from_window(*workspace.deref(), cx);
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                definition_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let editor = workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path((worktree_id, "main.rs"), None, true, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap()
+        .downcast::<Editor>()
+        .unwrap();
+    cx.executor().start_waiting();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    fake_server.handle_request::<lsp::request::GotoDefinition, _, _>(|params, _| async move {
+        assert_eq!(
+            params.text_document_position_params.text_document.uri,
+            lsp::Url::from_file_path("/a/main.rs").unwrap(),
+        );
+        assert_eq!(
+            params.text_document_position_params.position,
+            lsp::Position::new(3, 11)
+        );
+        Ok(Some(lsp::GotoDefinitionResponse::Scalar(lsp::Location {
+            uri: lsp::Url::from_file_path("/a/other.rs").unwrap(),
+            range: lsp::Range::new(lsp::Position::new(2, 7), lsp::Position::new(2, 13)),
+        })))
+    });
+    editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(3, 11)..Point::new(3, 11)])
+        });
+    });
+    let navigated = editor
+        .update(cx, |editor, cx| {
+            editor.go_to_definition(&GoToDefinition, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(navigated, Navigated::Yes);
+    cx.run_until_parked();
+
+    // The definition's file is opened, with the definition selected.
+    let definition_editor = workspace
+        .update(cx, |workspace, cx| workspace.active_item_as::<Editor>(cx))
+        .unwrap()
+        .unwrap();
+    assert_ne!(definition_editor, editor);
+    definition_editor.update(cx, |definition_editor, cx| {
+        let buffer = definition_editor.buffer().read(cx).as_singleton().unwrap();
+        assert_eq!(
+            buffer.read(cx).file().unwrap().path().as_ref(),
+            Path::new("other.rs")
+        );
+        assert_eq!(
+            definition_editor.selections.ranges::<Point>(cx),
+            [Point::new(2, 7)..Point::new(2, 13)]
+        );
+    });
+}
+
 fn empty_range(row: usize, column: usize) -> Range<DisplayPoint> {
     let point = DisplayPoint::new(DisplayRow(row as u32), column as u32);
     point..point
@@ -4,8 +4,8 @@ use crate::{
         Block, BlockContext, BlockStyle, DisplaySnapshot, HighlightedChunk, ToDisplayPoint,
     },
     editor_settings::{
-        CurrentLineHighlight, DoubleClickInMultibuffer, MultiCursorModifier, ScrollBeyondLastLine,
-        ShowScrollbar,
+        CurrentLineHighlight, DoubleClickInMultibuffer, LineNumbers, MultiCursorModifier,
+        ScrollBeyondLastLine, ShowScrollbar,
     },
     git::{
         blame::{CommitDetails, GitBlame},
@@ -27,7 +27,7 @@ use crate::{
     ToPoint, CURSORS_VISIBLE_FOR, MAX_LINE_LEN,
 };
 use client::ParticipantIndex;
-use collections::{BTreeMap, HashMap};
+use collections::{BTreeMap, HashMap, HashSet};
 use git::{blame::BlameEntry, diff::DiffHunkStatus, Oid};
 use gpui::Subscription;
 use gpui::{
@@ -204,6 +204,10 @@ impl EditorElement {
         register_action(view, cx, Editor::convert_to_upper_camel_case);
         register_action(view, cx, Editor::convert_to_lower_camel_case);
         register_action(view, cx, Editor::convert_to_opposite_case);
+        register_action(view, cx, Editor::encode_base64);
+        register_action(view, cx, Editor::decode_base64);
+        register_action(view, cx, Editor::encode_hex);
+        register_action(view, cx, Editor::decode_hex);
         register_action(view, cx, Editor::delete_to_previous_word_start);
         register_action(view, cx, Editor::delete_to_previous_subword_start);
         register_action(view, cx, Editor::delete_to_next_word_end);
@@ -213,6 +217,7 @@ impl EditorElement {
         register_action(view, cx, Editor::cut_to_end_of_line);
         register_action(view, cx, Editor::duplicate_line_up);
         register_action(view, cx, Editor::duplicate_line_down);
+        register_action(view, cx, Editor::duplicate_selection);
         register_action(view, cx, Editor::move_line_up);
         register_action(view, cx, Editor::move_line_down);
         register_action(view, cx, Editor::transpose);
@@ -1166,6 +1171,7 @@ impl EditorElement {
             ScrollBeyondLastLine::OnePage => rows_per_page,
             ScrollBeyondLastLine::Off => 1.0,
             ScrollBeyondLastLine::VerticalScrollMargin => 1.0 + settings.vertical_scroll_margin,
+            ScrollBeyondLastLine::Lines(lines) => 1.0 + lines as f32,
         };
         let total_rows =
             (snapshot.max_point().row().as_f32() + scroll_beyond_last_line).max(rows_per_page);
@@ -1764,13 +1770,22 @@ impl EditorElement {
         });
         let font_size = self.style.text.font_size.to_pixels(cx.rem_size());
 
-        let is_relative = editor.should_use_relative_line_numbers(cx);
-        let relative_to = if is_relative {
-            Some(newest_selection_head.row())
-        } else {
+        let line_numbers_mode = editor.line_numbers_mode(cx);
+        let relative_to = if line_numbers_mode == LineNumbers::Absolute {
             None
+        } else {
+            Some(newest_selection_head.row())
         };
-        let relative_rows = self.calculate_relative_line_numbers(snapshot, &rows, relative_to);
+        let mut relative_rows = self.calculate_relative_line_numbers(snapshot, &rows, relative_to);
+        if let (Some(relative_to), LineNumbers::Relative) = (relative_to, line_numbers_mode) {
+            // The number is shown on the first display row of the cursor's line, which may be
+            // above the cursor when the line is soft-wrapped.
+            let buffer_row = DisplayPoint::new(relative_to, 0).to_point(snapshot).row;
+            let cursor_line_row = Point::new(buffer_row, 0).to_display_point(snapshot).row();
+            if rows.contains(&cursor_line_row) {
+                relative_rows.insert(cursor_line_row, 0);
+            }
+        }
         let mut line_number = String::new();
         buffer_rows
             .into_iter()
@@ -1818,6 +1833,22 @@ impl EditorElement {
             && snapshot.mode == EditorMode::Full
             && self.editor.read(cx).is_singleton(cx);
         if include_fold_statuses {
+            let buffer_rows = buffer_rows.into_iter().collect::<Vec<_>>();
+            // Find the rows starting syntax folds with a single query for all the visible rows.
+            let syntax_fold_rows = buffer_rows
+                .iter()
+                .flatten()
+                .copied()
+                .minmax()
+                .into_option()
+                .map(|(first_row, last_row)| {
+                    snapshot
+                        .syntax_foldable_ranges(first_row..last_row.next_row())
+                        .into_iter()
+                        .map(|range| range.start.row)
+                        .collect::<HashSet<_>>()
+                })
+                .unwrap_or_default();
             buffer_rows
                 .into_iter()
                 .enumerate()
@@ -1828,6 +1859,7 @@ impl EditorElement {
                         snapshot.render_fold_toggle(
                             multibuffer_row,
                             active,
+                            syntax_fold_rows.contains(&multibuffer_row.0),
                             self.editor.clone(),
                             cx,
                         )
@@ -4610,15 +4642,18 @@ impl LineWithInvisibles {
         whitespace_setting: ShowWhitespaceSetting,
         cx: &mut WindowContext,
     ) {
-        let extract_whitespace_info = |invisible: &Invisible| {
-            let (token_offset, token_end_offset, invisible_symbol) = match invisible {
+        for ix in visible_invisible_indices(
+            &self.invisibles,
+            self.len,
+            row,
+            whitespace_setting,
+            selection_ranges,
+        ) {
+            let (token_offset, invisible_symbol) = match self.invisibles[ix] {
                 Invisible::Tab {
-                    line_start_offset,
-                    line_end_offset,
-                } => (*line_start_offset, *line_end_offset, &layout.tab_invisible),
-                Invisible::Whitespace { line_offset } => {
-                    (*line_offset, line_offset + 1, &layout.space_invisible)
-                }
+                    line_start_offset, ..
+                } => (line_start_offset, &layout.tab_invisible),
+                Invisible::Whitespace { line_offset } => (line_offset, &layout.space_invisible),
             };
 
             let x_offset = self.x_for_index(token_offset);
@@ -4629,73 +4664,7 @@ impl LineWithInvisibles {
                     x_offset + invisible_offset - layout.position_map.scroll_pixel_position.x,
                     line_y,
                 );
-
-            (
-                [token_offset, token_end_offset],
-                Box::new(move |cx: &mut WindowContext| {
-                    invisible_symbol.paint(origin, line_height, cx).log_err();
-                }),
-            )
-        };
-
-        let invisible_iter = self.invisibles.iter().map(extract_whitespace_info);
-        match whitespace_setting {
-            ShowWhitespaceSetting::None => (),
-            ShowWhitespaceSetting::All => invisible_iter.for_each(|(_, paint)| paint(cx)),
-            ShowWhitespaceSetting::Selection => invisible_iter.for_each(|([start, _], paint)| {
-                let invisible_point = DisplayPoint::new(row, start as u32);
-                if !selection_ranges
-                    .iter()
-                    .any(|region| region.start <= invisible_point && invisible_point < region.end)
-                {
-                    return;
-                }
-
-                paint(cx);
-            }),
-
-            // For a whitespace to be on a boundary, any of the following conditions need to be met:
-            // - It is a tab
-            // - It is adjacent to an edge (start or end)
-            // - It is adjacent to a whitespace (left or right)
-            ShowWhitespaceSetting::Boundary => {
-                // We'll need to keep track of the last invisible we've seen and then check if we are adjacent to it for some of
-                // the above cases.
-                // Note: We zip in the original `invisibles` to check for tab equality
-                let mut last_seen: Option<(bool, usize, Box<dyn Fn(&mut WindowContext)>)> = None;
-                for (([start, end], paint), invisible) in
-                    invisible_iter.zip_eq(self.invisibles.iter())
-                {
-                    let should_render = match (&last_seen, invisible) {
-                        (_, Invisible::Tab { .. }) => true,
-                        (Some((_, last_end, _)), _) => *last_end == start,
-                        _ => false,
-                    };
-
-                    if should_render || start == 0 || end == self.len {
-                        paint(cx);
-
-                        // Since we are scanning from the left, we will skip over the first available whitespace that is part
-                        // of a boundary between non-whitespace segments, so we correct by manually redrawing it if needed.
-                        if let Some((should_render_last, last_end, paint_last)) = last_seen {
-                            // Note that we need to make sure that the last one is actually adjacent
-                            if !should_render_last && last_end == start {
-                                paint_last(cx);
-                            }
-                        }
-                    }
-
-                    // Manually render anything within a selection
-                    let invisible_point = DisplayPoint::new(row, start as u32);
-                    if selection_ranges.iter().any(|region| {
-                        region.start <= invisible_point && invisible_point < region.end
-                    }) {
-                        paint(cx);
-                    }
-
-                    last_seen = Some((should_render, end, paint));
-                }
-            }
+            invisible_symbol.paint(origin, line_height, cx).log_err();
         }
     }
 
@@ -4784,6 +4753,83 @@ impl LineWithInvisibles {
     }
 }
 
+/// Returns the indices of the `invisibles` on a line of length `line_len` that should be drawn
+/// according to the given setting.
+fn visible_invisible_indices(
+    invisibles: &[Invisible],
+    line_len: usize,
+    row: DisplayRow,
+    whitespace_setting: ShowWhitespaceSetting,
+    selection_ranges: &[Range<DisplayPoint>],
+) -> Vec<usize> {
+    let is_selected = |start: usize| {
+        let invisible_point = DisplayPoint::new(row, start as u32);
+        selection_ranges
+            .iter()
+            .any(|region| region.start <= invisible_point && invisible_point < region.end)
+    };
+
+    match whitespace_setting {
+        ShowWhitespaceSetting::None => Vec::new(),
+        ShowWhitespaceSetting::All => (0..invisibles.len()).collect(),
+        ShowWhitespaceSetting::Selection => (0..invisibles.len())
+            .filter(|ix| is_selected(invisibles[*ix].range().start))
+            .collect(),
+
+        // For a whitespace to be on a boundary, any of the following conditions need to be met:
+        // - It is a tab
+        // - It is adjacent to an edge (start or end)
+        // - It is adjacent to a whitespace (left or right)
+        ShowWhitespaceSetting::Boundary => {
+            let mut indices = Vec::new();
+            // Whether the previous invisible was on a boundary, and where it ended.
+            let mut last_seen: Option<(bool, usize)> = None;
+            for (ix, invisible) in invisibles.iter().enumerate() {
+                let Range { start, end } = invisible.range();
+                let is_adjacent_to_last =
+                    last_seen.map_or(false, |(_, last_end)| last_end == start);
+                let on_boundary = matches!(invisible, Invisible::Tab { .. }) || is_adjacent_to_last;
+
+                if on_boundary || start == 0 || end == line_len {
+                    // Since we are scanning from the left, we will skip over the first available
+                    // whitespace that is part of a boundary between non-whitespace segments, so we
+                    // correct by including it if needed.
+                    if let Some((false, _)) = last_seen.filter(|_| is_adjacent_to_last) {
+                        indices.push(ix - 1);
+                    }
+                    indices.push(ix);
+                } else if is_selected(start) {
+                    indices.push(ix);
+                }
+
+                last_seen = Some((on_boundary, end));
+            }
+            indices.dedup();
+            indices
+        }
+
+        // Only whitespace running up to the end of the line is drawn, along with any whitespace
+        // within a selection.
+        ShowWhitespaceSetting::Trailing => {
+            let mut trailing_start = line_len;
+            for invisible in invisibles.iter().rev() {
+                let range = invisible.range();
+                if range.end != trailing_start {
+                    break;
+                }
+                trailing_start = range.start;
+            }
+
+            (0..invisibles.len())
+                .filter(|ix| {
+                    let start = invisibles[*ix].range().start;
+                    start >= trailing_start || is_selected(start)
+                })
+                .collect()
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Invisible {
     /// A tab character
@@ -4800,6 +4846,18 @@ enum Invisible {
     },
 }
 
+impl Invisible {
+    fn range(&self) -> Range<usize> {
+        match *self {
+            Invisible::Tab {
+                line_start_offset,
+                line_end_offset,
+            } => line_start_offset..line_end_offset,
+            Invisible::Whitespace { line_offset } => line_offset..line_offset + 1,
+        }
+    }
+}
+
 impl EditorElement {
     /// Returns the rem size to use when rendering the [`EditorElement`].
     ///
@@ -5046,14 +5104,11 @@ impl Element for EditorElement {
                         (max_row - height_in_lines + 1.).max(0.)
                     } else {
                         let settings = EditorSettings::get_global(cx);
-                        match settings.scroll_beyond_last_line {
-                            ScrollBeyondLastLine::OnePage => max_row,
-                            ScrollBeyondLastLine::Off => (max_row - height_in_lines + 1.).max(0.),
-                            ScrollBeyondLastLine::VerticalScrollMargin => {
-                                (max_row - height_in_lines + 1. + settings.vertical_scroll_margin)
-                                    .max(0.)
-                            }
-                        }
+                        settings.scroll_beyond_last_line.max_scroll_top(
+                            max_row,
+                            height_in_lines,
+                            settings.vertical_scroll_margin,
+                        )
                     };
 
                     let mut autoscroll_request = None;
@@ -6330,6 +6385,7 @@ mod tests {
     use gpui::{TestAppContext, VisualTestContext};
     use language::language_settings;
     use log::info;
+    use settings::SettingsStore;
     use std::num::NonZeroU32;
     use ui::Context;
     use util::test::sample_text;
@@ -6411,6 +6467,85 @@ mod tests {
         assert_eq!(relative_rows[&DisplayRow(2)], 3);
     }
 
+    #[gpui::test]
+    fn test_line_numbers_modes(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+        let window = cx.add_window(|cx| {
+            let buffer = MultiBuffer::build_simple(&sample_text(6, 6, 'a'), cx);
+            Editor::new(EditorMode::Full, buffer, None, true, cx)
+        });
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|cx| editor.read(cx).style().unwrap().clone());
+        let element = EditorElement::new(&editor, style);
+
+        let displayed_line_numbers = |mode: LineNumbers, cx: &mut TestAppContext| {
+            cx.update(|cx| {
+                SettingsStore::update_global(cx, |store, cx| {
+                    store.update_user_settings::<EditorSettings>(cx, |settings| {
+                        settings.line_numbers = Some(mode);
+                    });
+                });
+            });
+            let snapshot = window.update(cx, |editor, cx| editor.snapshot(cx)).unwrap();
+            cx.update_window(*window, |_, cx| {
+                element
+                    .layout_line_numbers(
+                        DisplayRow(0)..DisplayRow(6),
+                        (0..6).map(MultiBufferRow).map(Some),
+                        &Default::default(),
+                        Some(DisplayPoint::new(DisplayRow(3), 0)),
+                        &snapshot,
+                        cx,
+                    )
+                    .into_iter()
+                    .map(|line| line.unwrap().text.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap()
+        };
+
+        assert_eq!(
+            displayed_line_numbers(LineNumbers::Absolute, cx),
+            ["1", "2", "3", "4", "5", "6"]
+        );
+        assert_eq!(
+            displayed_line_numbers(LineNumbers::Relative, cx),
+            ["3", "2", "1", "0", "1", "2"]
+        );
+        assert_eq!(
+            displayed_line_numbers(LineNumbers::Hybrid, cx),
+            ["3", "2", "1", "4", "1", "2"]
+        );
+
+        // `relative_line_numbers` only turns absolute numbers into hybrid ones.
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.relative_line_numbers = Some(true);
+                });
+            });
+        });
+        assert_eq!(
+            displayed_line_numbers(LineNumbers::Absolute, cx),
+            ["3", "2", "1", "4", "1", "2"]
+        );
+        assert_eq!(
+            displayed_line_numbers(LineNumbers::Relative, cx),
+            ["3", "2", "1", "0", "1", "2"]
+        );
+
+        // Toggling relative line numbers off overrides the setting.
+        window
+            .update(cx, |editor, cx| {
+                editor.set_relative_line_number(Some(false), cx)
+            })
+            .unwrap();
+        assert_eq!(
+            displayed_line_numbers(LineNumbers::Relative, cx),
+            ["1", "2", "3", "4", "5", "6"]
+        );
+    }
+
     #[gpui::test]
     async fn test_vim_visual_selections(cx: &mut TestAppContext) {
         init_test(cx, |_| {});
@@ -6656,6 +6791,55 @@ mod tests {
         assert_eq!(expected_invisibles, actual_invisibles);
     }
 
+    #[test]
+    fn test_visible_invisible_indices() {
+        // "→   a·b··c·", with a tab expanded to four columns.
+        let invisibles = [
+            Invisible::Tab {
+                line_start_offset: 0,
+                line_end_offset: 4,
+            },
+            Invisible::Whitespace { line_offset: 5 },
+            Invisible::Whitespace { line_offset: 7 },
+            Invisible::Whitespace { line_offset: 8 },
+            Invisible::Whitespace { line_offset: 10 },
+        ];
+        let line_len = 11;
+        let row = DisplayRow(0);
+        let selection = [DisplayPoint::new(row, 5)..DisplayPoint::new(row, 6)];
+        let indices = |setting, selections: &[Range<DisplayPoint>]| {
+            visible_invisible_indices(&invisibles, line_len, row, setting, selections)
+        };
+
+        assert_eq!(
+            indices(ShowWhitespaceSetting::None, &selection),
+            Vec::<usize>::new()
+        );
+        assert_eq!(indices(ShowWhitespaceSetting::All, &[]), [0, 1, 2, 3, 4]);
+        assert_eq!(
+            indices(ShowWhitespaceSetting::Selection, &[]),
+            Vec::<usize>::new()
+        );
+        assert_eq!(indices(ShowWhitespaceSetting::Selection, &selection), [1]);
+        assert_eq!(indices(ShowWhitespaceSetting::Boundary, &[]), [0, 2, 3, 4]);
+        assert_eq!(
+            indices(ShowWhitespaceSetting::Boundary, &selection),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(indices(ShowWhitespaceSetting::Trailing, &[]), [4]);
+        assert_eq!(indices(ShowWhitespaceSetting::Trailing, &selection), [1, 4]);
+
+        // A line with nothing but whitespace is entirely trailing.
+        let invisibles = [
+            Invisible::Whitespace { line_offset: 0 },
+            Invisible::Whitespace { line_offset: 1 },
+        ];
+        assert_eq!(
+            visible_invisible_indices(&invisibles, 2, row, ShowWhitespaceSetting::Trailing, &[]),
+            [0, 1]
+        );
+    }
+
     #[gpui::test]
     fn test_invisibles_dont_appear_in_certain_editors(cx: &mut TestAppContext) {
         init_test(cx, |s| {
@@ -6678,6 +6862,54 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    fn test_soft_wrap_at_preferred_line_length(cx: &mut TestAppContext) {
+        init_test(cx, |s| {
+            s.defaults.soft_wrap = Some(language_settings::SoftWrap::PreferredLineLength);
+            s.defaults.preferred_line_length = Some(10);
+        });
+
+        let text = "abcdefghijklmnopqrstuvwxyz0123456789\nshort";
+        let window = cx.add_window(|cx| {
+            let buffer = MultiBuffer::build_simple(text, cx);
+            Editor::new(EditorMode::Full, buffer, None, true, cx)
+        });
+        let cx = &mut VisualTestContext::from_window(*window, cx);
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|cx| editor.read(cx).style().unwrap().clone());
+        cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style)
+        });
+        cx.run_until_parked();
+
+        // The long line wraps at the column even though the editor is wider, while the
+        // buffer itself is left untouched.
+        let display_text = editor.update(cx, |editor, cx| editor.display_text(cx));
+        let display_lines = display_text.lines().collect::<Vec<_>>();
+        assert!(display_lines.len() > 2, "{display_text:?}");
+        assert!(
+            display_lines.iter().all(|line| line.len() <= 10),
+            "{display_text:?}"
+        );
+        assert_eq!(display_text.replace('\n', ""), text.replace('\n', ""));
+        assert_eq!(editor.update(cx, |editor, cx| editor.text(cx)), text);
+
+        // Editing at the start of a wrapped row lands at the matching buffer offset.
+        let wrap_offset = display_lines[0].len();
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.select_display_ranges([
+                    DisplayPoint::new(DisplayRow(1), 0)..DisplayPoint::new(DisplayRow(1), 0)
+                ]);
+            });
+            editor.insert("|", cx);
+        });
+        assert_eq!(
+            editor.update(cx, |editor, cx| editor.text(cx)),
+            format!("{}|{}", &text[..wrap_offset], &text[wrap_offset..])
+        );
+    }
+
     #[gpui::test]
     fn test_wrapped_invisibles_drawing(cx: &mut TestAppContext) {
         let tab_size = 4;
@@ -17,6 +17,7 @@ use gpui::{
 };
 use language::{
     proto::serialize_anchor as serialize_text_anchor, Bias, Buffer, CharKind, Point, SelectionGoal,
+    SerializedUndoHistory,
 };
 use multi_buffer::AnchorRangeExt;
 use project::{
@@ -49,6 +50,12 @@ use workspace::{
 
 pub const MAX_TAB_TITLE_LEN: usize = 24;
 
+/// The number of recent transactions whose undo history is persisted for each editor.
+const MAX_SERIALIZED_UNDO_TRANSACTIONS: usize = 20;
+/// Undo history isn't persisted for buffers larger than this, as serializing it requires
+/// reconstructing the buffer's text before each transaction.
+const MAX_SERIALIZED_UNDO_BUFFER_LEN: usize = 128 * 1024;
+
 impl FollowableItem for Editor {
     fn remote_id(&self) -> Option<ViewId> {
         self.remote_id
@@ -704,6 +711,9 @@ impl Item for Editor {
     }
 
     fn can_save(&self, cx: &AppContext) -> bool {
+        if self.read_only(cx) {
+            return false;
+        }
         let buffer = &self.buffer().read(cx);
         if let Some(buffer) = buffer.as_singleton() {
             buffer.read(cx).project_path(cx).is_some()
@@ -1050,9 +1060,26 @@ impl SerializableItem for Editor {
             _ => return Task::ready(Err(anyhow!("No path or contents found for buffer"))),
         };
 
+        let undo_history = DB
+            .get_undo_history(item_id, workspace_id)
+            .log_err()
+            .flatten()
+            .and_then(|undo_history| {
+                serde_json::from_str::<SerializedUndoHistory>(&undo_history).log_err()
+            });
+
         cx.spawn(|pane, mut cx| async move {
             let buffer = buffer_task.await?;
 
+            if let Some(undo_history) = undo_history {
+                buffer.update(&mut cx, |buffer, cx| {
+                    // Don't clobber the history of a buffer that's already being edited elsewhere.
+                    if buffer.peek_undo_stack().is_none() {
+                        buffer.restore_undo_history(&undo_history, cx);
+                    }
+                })?;
+            }
+
             pane.update(&mut cx, |_, cx| {
                 cx.new_view(|cx| {
                     let mut editor = Editor::for_buffer(buffer, Some(project), cx);
@@ -1094,6 +1121,16 @@ impl SerializableItem for Editor {
         let mtime = buffer.read(cx).saved_mtime();
 
         let snapshot = buffer.read(cx).snapshot();
+        // Only the versions are captured here. Reconstructing the text at each of them happens
+        // on the background executor, from the snapshot.
+        let undo_history_versions = (path.is_some()
+            && snapshot.len() <= MAX_SERIALIZED_UNDO_BUFFER_LEN)
+            .then(|| {
+                buffer
+                    .read(cx)
+                    .undo_history_versions(MAX_SERIALIZED_UNDO_TRANSACTIONS)
+            })
+            .unwrap_or_default();
 
         Some(cx.spawn(|_this, cx| async move {
             cx.background_executor()
@@ -1115,7 +1152,16 @@ impl SerializableItem for Editor {
 
                     DB.save_serialized_editor(item_id, workspace_id, editor)
                         .await
-                        .context("failed to save serialized editor")
+                        .context("failed to save serialized editor")?;
+
+                    let undo_history =
+                        Some(snapshot.serialize_undo_history(&undo_history_versions))
+                            .filter(|undo_history| !undo_history.transactions.is_empty())
+                            .map(|undo_history| serde_json::to_string(&undo_history))
+                            .transpose()?;
+                    DB.save_undo_history(item_id, workspace_id, undo_history)
+                        .await
+                        .context("failed to save undo history")
                 })
                 .await
                 .context("failed to save contents of buffer")?;
@@ -1550,11 +1596,12 @@ mod tests {
     use super::*;
     use gpui::{AppContext, VisualTestContext};
     use language::{LanguageMatcher, TestFile};
-    use project::FakeFs;
+    use project::{FakeFs, Fs as _};
     use std::{
         path::{Path, PathBuf},
         time::SystemTime,
     };
+    use workspace::AppState;
 
     #[gpui::test]
     fn test_path_for_file(cx: &mut AppContext) {
@@ -1743,4 +1790,101 @@ mod tests {
             });
         }
     }
+
+    #[gpui::test]
+    async fn test_unsaved_buffer_recovered_after_crash(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/dir", serde_json::json!({})).await;
+        let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+        let item_id = 4321 as ItemId;
+
+        // Edits to an untitled buffer are serialized as they happen. Dropping the workspace
+        // without closing the editor stands in for a crash.
+        {
+            let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+            let app_state = cx.update(AppState::test);
+            let (workspace, cx) = cx.add_window_view(|cx| {
+                Workspace::new(Some(workspace_id), project.clone(), app_state, cx)
+            });
+            let editor = cx.new_view(|cx| {
+                let buffer =
+                    project.update(cx, |project, cx| project.create_local_buffer("", None, cx));
+                Editor::for_buffer(buffer, Some(project.clone()), cx)
+            });
+            editor.update(cx, |editor, cx| editor.set_text("unsaved work", cx));
+            workspace
+                .update(cx, |workspace, cx| {
+                    editor.update(cx, |editor, cx| {
+                        SerializableItem::serialize(editor, workspace, item_id, false, cx)
+                    })
+                })
+                .unwrap()
+                .await
+                .unwrap();
+        }
+
+        let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+        let app_state = cx.update(AppState::test);
+        let (workspace, cx) = cx.add_window_view(|cx| {
+            Workspace::new(Some(workspace_id), project.clone(), app_state, cx)
+        });
+        let recovered = deserialize_editor(
+            item_id,
+            workspace_id,
+            workspace.clone(),
+            project.clone(),
+            cx,
+        )
+        .await;
+        recovered.update(cx, |editor, cx| {
+            assert_eq!(editor.text(cx), "unsaved work");
+            assert!(editor.is_dirty(cx));
+        });
+
+        // Once the buffer is saved to a file, its contents are no longer stored.
+        let buffer = recovered.update(cx, |editor, cx| {
+            editor.buffer().read(cx).as_singleton().unwrap()
+        });
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        project
+            .update(cx, |project, cx| {
+                project.save_buffer_as(
+                    buffer,
+                    ProjectPath {
+                        worktree_id,
+                        path: Path::new("notes.txt").into(),
+                    },
+                    cx,
+                )
+            })
+            .await
+            .unwrap();
+        workspace
+            .update(cx, |workspace, cx| {
+                recovered.update(cx, |editor, cx| {
+                    SerializableItem::serialize(editor, workspace, item_id, false, cx)
+                })
+            })
+            .unwrap()
+            .await
+            .unwrap();
+
+        let serialized_editor = DB
+            .get_serialized_editor(item_id, workspace_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serialized_editor.path,
+            Some(PathBuf::from("/dir/notes.txt"))
+        );
+        assert_eq!(serialized_editor.contents, None);
+        assert_eq!(
+            fs.load(Path::new("/dir/notes.txt")).await.unwrap(),
+            "unsaved work"
+        );
+    }
 }
@@ -0,0 +1,100 @@
+use std::ops::Range;
+
+use multi_buffer::{MultiBufferRow, MultiBufferSnapshot};
+use settings::Settings;
+use text::{Point, Subscription};
+use ui::ViewContext;
+
+use crate::{Editor, EditorSettings};
+
+/// The number of columns a line must fill with non-whitespace characters to have a density of 1.
+const MINIMAP_COLUMNS: f32 = 100.0;
+
+/// A downsampled representation of a buffer for drawing an overview alongside the scrollbar.
+///
+/// Densities are only recomputed for the lines touched by edits since the last sync.
+pub struct MinimapState {
+    buffer_subscription: Subscription,
+    snapshot: MultiBufferSnapshot,
+    line_densities: Vec<f32>,
+}
+
+impl MinimapState {
+    pub fn new(buffer_subscription: Subscription, snapshot: MultiBufferSnapshot) -> Self {
+        let line_densities = (0..=snapshot.max_buffer_row().0)
+            .map(|row| line_density(&snapshot, row))
+            .collect();
+        Self {
+            buffer_subscription,
+            snapshot,
+            line_densities,
+        }
+    }
+
+    /// Brings the densities up to date with the given snapshot of the buffer.
+    pub fn sync(&mut self, snapshot: MultiBufferSnapshot) {
+        let edits = self.buffer_subscription.consume().into_inner();
+        let mut row_edits: Vec<(Range<u32>, Range<u32>)> = Vec::new();
+        for edit in edits {
+            let old_rows = self.snapshot.offset_to_point(edit.old.start).row
+                ..self.snapshot.offset_to_point(edit.old.end).row + 1;
+            let new_rows = snapshot.offset_to_point(edit.new.start).row
+                ..snapshot.offset_to_point(edit.new.end).row + 1;
+            // Edits on the same line must be applied as one splice.
+            if let Some((last_old_rows, last_new_rows)) = row_edits.last_mut() {
+                if old_rows.start < last_old_rows.end {
+                    last_old_rows.end = last_old_rows.end.max(old_rows.end);
+                    last_new_rows.end = last_new_rows.end.max(new_rows.end);
+                    continue;
+                }
+            }
+            row_edits.push((old_rows, new_rows));
+        }
+
+        for (old_rows, new_rows) in row_edits.into_iter().rev() {
+            self.line_densities.splice(
+                old_rows.start as usize..old_rows.end as usize,
+                new_rows.map(|row| line_density(&snapshot, row)),
+            );
+        }
+        self.snapshot = snapshot;
+    }
+
+    /// The fraction of each line occupied by non-whitespace characters, between 0 and 1.
+    pub fn line_densities(&self) -> &[f32] {
+        &self.line_densities
+    }
+}
+
+fn line_density(snapshot: &MultiBufferSnapshot, row: u32) -> f32 {
+    let line_len = snapshot.line_len(MultiBufferRow(row));
+    let filled_columns = snapshot
+        .text_for_range(Point::new(row, 0)..Point::new(row, line_len))
+        .flat_map(str::chars)
+        .filter(|c| !c.is_whitespace())
+        .count();
+    (filled_columns as f32 / MINIMAP_COLUMNS).min(1.)
+}
+
+impl Editor {
+    /// Returns the density of each line in the buffer, or `None` if the minimap is disabled.
+    pub fn minimap_line_densities(&mut self, cx: &mut ViewContext<Self>) -> Option<&[f32]> {
+        if !EditorSettings::get_global(cx).minimap.enabled {
+            self.minimap = None;
+            return None;
+        }
+
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let minimap = match self.minimap.take() {
+            Some(mut minimap) => {
+                minimap.sync(snapshot);
+                minimap
+            }
+            None => {
+                let subscription = self.buffer.update(cx, |buffer, _| buffer.subscribe());
+                MinimapState::new(subscription, snapshot)
+            }
+        };
+        Some(self.minimap.insert(minimap).line_densities())
+    }
+}
@@ -88,6 +88,7 @@ define_connection!(
     //   language: Option<String>,
     //   mtime_seconds: Option<i64>,
     //   mtime_nanos: Option<i32>,
+    //   undo_history: Option<String>, // JSON encoded SerializedUndoHistory
     // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
@@ -136,6 +137,9 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN mtime_seconds INTEGER DEFAULT NULL;
             ALTER TABLE editors ADD COLUMN mtime_nanos INTEGER DEFAULT NULL;
         ),
+        sql! (
+            ALTER TABLE editors ADD COLUMN undo_history TEXT DEFAULT NULL;
+        ),
         ];
 );
 
@@ -190,6 +194,26 @@ impl EditorDb {
         }
     }
 
+    query! {
+        pub fn get_undo_history(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<String>> {
+            SELECT undo_history
+            FROM editors
+            WHERE item_id = ? AND workspace_id = ? AND undo_history IS NOT NULL
+        }
+    }
+
+    query! {
+        pub async fn save_undo_history(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            undo_history: Option<String>
+        ) -> Result<()> {
+            UPDATE OR IGNORE editors
+            SET undo_history = ?3
+            WHERE item_id = ?1 AND workspace_id = ?2
+        }
+    }
+
     pub async fn delete_unloaded_items(
         &self,
         workspace: WorkspaceId,
@@ -297,4 +321,31 @@ mod tests {
             .unwrap();
         assert_eq!(have, serialized_editor);
     }
+
+    #[gpui::test]
+    async fn test_save_and_get_undo_history() {
+        let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+
+        // Undo history is only stored alongside an existing editor
+        DB.save_undo_history(1234, workspace_id, Some("{}".to_owned()))
+            .await
+            .unwrap();
+        assert_eq!(DB.get_undo_history(1234, workspace_id).unwrap(), None);
+
+        DB.save_serialized_editor(1234, workspace_id, SerializedEditor::default())
+            .await
+            .unwrap();
+        DB.save_undo_history(1234, workspace_id, Some("{}".to_owned()))
+            .await
+            .unwrap();
+        assert_eq!(
+            DB.get_undo_history(1234, workspace_id).unwrap().as_deref(),
+            Some("{}")
+        );
+
+        DB.save_undo_history(1234, workspace_id, None)
+            .await
+            .unwrap();
+        assert_eq!(DB.get_undo_history(1234, workspace_id).unwrap(), None);
+    }
 }
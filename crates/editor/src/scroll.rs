@@ -24,6 +24,8 @@ use workspace::{ItemId, WorkspaceId};
 
 pub const SCROLL_EVENT_SEPARATION: Duration = Duration::from_millis(28);
 const SCROLLBAR_SHOW_INTERVAL: Duration = Duration::from_secs(1);
+pub(crate) const SMOOTH_SCROLL_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+pub(crate) const SMOOTH_SCROLL_FRAMES: u32 = 10;
 
 #[derive(Default)]
 pub struct ScrollbarAutoHide(pub bool);
@@ -139,6 +141,13 @@ pub struct ScrollManager {
     dragging_scrollbar: bool,
     visible_line_count: Option<f32>,
     forbid_vertical_scroll: bool,
+    scroll_animation: Option<ScrollAnimation>,
+}
+
+/// A scroll in progress towards `target`, driven by `_task`.
+struct ScrollAnimation {
+    target: gpui::Point<f32>,
+    _task: Task<()>,
 }
 
 impl ScrollManager {
@@ -154,6 +163,7 @@ impl ScrollManager {
             last_autoscroll: None,
             visible_line_count: None,
             forbid_vertical_scroll: false,
+            scroll_animation: None,
         }
     }
 
@@ -201,25 +211,16 @@ impl ScrollManager {
             )
         } else {
             let scroll_top = scroll_position.y;
-            let scroll_top = match EditorSettings::get_global(cx).scroll_beyond_last_line {
-                ScrollBeyondLastLine::OnePage => scroll_top,
-                ScrollBeyondLastLine::Off => {
-                    if let Some(height_in_lines) = self.visible_line_count {
-                        let max_row = map.max_point().row().0 as f32;
-                        scroll_top.min(max_row - height_in_lines + 1.).max(0.)
-                    } else {
-                        scroll_top
-                    }
-                }
-                ScrollBeyondLastLine::VerticalScrollMargin => {
-                    if let Some(height_in_lines) = self.visible_line_count {
-                        let max_row = map.max_point().row().0 as f32;
-                        scroll_top
-                            .min(max_row - height_in_lines + 1. + self.vertical_scroll_margin)
-                            .max(0.)
-                    } else {
-                        scroll_top
-                    }
+            let scroll_beyond_last_line = EditorSettings::get_global(cx).scroll_beyond_last_line;
+            let scroll_top = match (scroll_beyond_last_line, self.visible_line_count) {
+                (ScrollBeyondLastLine::OnePage, _) | (_, None) => scroll_top,
+                (scroll_beyond_last_line, Some(height_in_lines)) => {
+                    let max_row = map.max_point().row().0 as f32;
+                    scroll_top.min(scroll_beyond_last_line.max_scroll_top(
+                        max_row,
+                        height_in_lines,
+                        self.vertical_scroll_margin,
+                    ))
                 }
             };
 
@@ -398,6 +399,61 @@ impl Editor {
         self.set_scroll_position_internal(scroll_position, true, false, cx);
     }
 
+    /// Scrolls to the given position, animating the change when `smooth_scrolling` is enabled.
+    /// Any other scroll cancels the animation.
+    ///
+    /// The animation starts from the current scroll position. Its length is added to the
+    /// remaining distance of any animation that is still in progress, so that repeated scrolls
+    /// aren't cut short.
+    pub fn animate_scroll_position(
+        &mut self,
+        scroll_position: gpui::Point<f32>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if !EditorSettings::get_global(cx).smooth_scrolling {
+            self.set_scroll_position(scroll_position, cx);
+            return;
+        }
+        if self.scroll_manager.forbid_vertical_scroll {
+            return;
+        }
+
+        let start = self.scroll_position(cx);
+        let scroll_position = match &self.scroll_manager.scroll_animation {
+            Some(animation) => animation.target + (scroll_position - start),
+            None => scroll_position,
+        };
+        let task = cx.spawn(|editor, mut cx| async move {
+            for frame in 1..=SMOOTH_SCROLL_FRAMES {
+                cx.background_executor()
+                    .timer(SMOOTH_SCROLL_FRAME_INTERVAL)
+                    .await;
+                let position = if frame == SMOOTH_SCROLL_FRAMES {
+                    scroll_position
+                } else {
+                    // Ease out, so the scroll starts quickly and settles on the target.
+                    let progress = 1. - (1. - frame as f32 / SMOOTH_SCROLL_FRAMES as f32).powi(3);
+                    start + (scroll_position - start) * progress
+                };
+                let updated = editor.update(&mut cx, |editor, cx| {
+                    // Setting the position cancels the animation, so keep it while doing that.
+                    let animation = editor.scroll_manager.scroll_animation.take();
+                    editor.set_scroll_position_internal(position, true, false, cx);
+                    if frame < SMOOTH_SCROLL_FRAMES {
+                        editor.scroll_manager.scroll_animation = animation;
+                    }
+                });
+                if updated.is_err() {
+                    return;
+                }
+            }
+        });
+        self.scroll_manager.scroll_animation = Some(ScrollAnimation {
+            target: scroll_position,
+            _task: task,
+        });
+    }
+
     pub(crate) fn set_scroll_position_internal(
         &mut self,
         scroll_position: gpui::Point<f32>,
@@ -418,6 +474,7 @@ impl Editor {
         cx: &mut ViewContext<Self>,
     ) {
         hide_hover(self, cx);
+        self.scroll_manager.scroll_animation = None;
         let workspace_id = self.workspace.as_ref().and_then(|workspace| workspace.1);
 
         self.scroll_manager.set_scroll_position(
@@ -439,6 +496,7 @@ impl Editor {
 
     pub fn set_scroll_anchor(&mut self, scroll_anchor: ScrollAnchor, cx: &mut ViewContext<Self>) {
         hide_hover(self, cx);
+        self.scroll_manager.scroll_animation = None;
         let workspace_id = self.workspace.as_ref().and_then(|workspace| workspace.1);
         let top_row = scroll_anchor
             .anchor
@@ -454,6 +512,7 @@ impl Editor {
         cx: &mut ViewContext<Self>,
     ) {
         hide_hover(self, cx);
+        self.scroll_manager.scroll_animation = None;
         let workspace_id = self.workspace.as_ref().and_then(|workspace| workspace.1);
         let snapshot = &self.buffer().read(cx).snapshot(cx);
         if !scroll_anchor.anchor.is_valid(snapshot) {
@@ -480,7 +539,7 @@ impl Editor {
             return;
         };
         let new_pos = cur_position + point(0., amount.lines(visible_line_count));
-        self.set_scroll_position(new_pos, cx);
+        self.animate_scroll_position(new_pos, cx);
     }
 
     /// Returns an ordering. The newest selection is:
@@ -26,11 +26,11 @@ use std::{
     time::{Duration, SystemTime},
 };
 use tempfile::{NamedTempFile, TempDir};
-use text::LineEnding;
+use text::{Encoding, LineEnding};
 use util::ResultExt;
 
 #[cfg(any(test, feature = "test-support"))]
-use collections::{btree_map, BTreeMap};
+use collections::{btree_map, BTreeMap, HashSet};
 #[cfg(any(test, feature = "test-support"))]
 use git::repository::{FakeGitRepositoryState, GitFileStatus};
 #[cfg(any(test, feature = "test-support"))]
@@ -94,11 +94,24 @@ pub trait Fs: Send + Sync {
         Ok(String::from_utf8(self.load_bytes(path).await?)?)
     }
     async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>>;
+    /// Loads a file that may not be UTF-8, returning its decoded text and detected encoding.
+    async fn load_with_encoding(&self, path: &Path) -> Result<(String, Encoding)> {
+        Encoding::detect_and_decode(self.load_bytes(path).await?)
+    }
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
     async fn is_dir(&self, path: &Path) -> bool;
+    /// Whether the current user lacks permission to write to the file at the given path.
+    async fn is_read_only(&self, path: &Path) -> bool;
     async fn metadata(&self, path: &Path) -> Result<Option<Metadata>>;
     async fn read_link(&self, path: &Path) -> Result<PathBuf>;
     async fn read_dir(
@@ -135,6 +148,11 @@ impl dyn Fs {
         GlobalFs::global(cx).0.clone()
     }
 
+    /// Returns the global [`Fs`], if one has been set.
+    pub fn try_global(cx: &AppContext) -> Option<Arc<Self>> {
+        cx.try_global::<GlobalFs>().map(|fs| fs.0.clone())
+    }
+
     /// Sets the global [`Fs`].
     pub fn set_global(fs: Arc<Self>, cx: &mut AppContext) {
         cx.set_global(GlobalFs(fs));
@@ -165,6 +183,19 @@ pub struct RemoveOptions {
     pub ignore_if_not_exists: bool,
 }
 
+/// Returned when moving a file to the trash fails because the system has no trash, as
+/// opposed to the trash rejecting that particular file.
+#[derive(Debug)]
+pub struct TrashUnavailable;
+
+impl std::fmt::Display for TrashUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the system trash is unavailable")
+    }
+}
+
+impl std::error::Error for TrashUnavailable {}
+
 #[derive(Copy, Clone, Debug)]
 pub struct Metadata {
     pub inode: u64,
@@ -338,6 +369,7 @@ impl Fs for RealFs {
         let file = File::open(path)?;
         match trash::trash_file(&file.as_fd()).await {
             Ok(_) => Ok(()),
+            Err(ashpd::Error::PortalNotFound(_)) => Err(TrashUnavailable.into()),
             Err(err) => Err(anyhow::Error::new(err)),
         }
     }
@@ -447,6 +479,24 @@ impl Fs for RealFs {
         Ok(())
     }
 
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()> {
+        if encoding == Encoding::Utf8 {
+            return self.save(path, text, line_ending).await;
+        }
+        let content = encoding.encode(&chunks(text, line_ending).collect::<String>())?;
+        if let Some(path) = path.parent() {
+            self.create_dir(path).await?;
+        }
+        smol::fs::write(path, content).await?;
+        Ok(())
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         Ok(smol::fs::canonicalize(path).await?)
     }
@@ -463,6 +513,34 @@ impl Fs for RealFs {
             .map_or(false, |metadata| metadata.is_dir())
     }
 
+    #[cfg(unix)]
+    async fn is_read_only(&self, path: &Path) -> bool {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // Unlike the permission bits, this accounts for the file's owner and group, so that
+        // e.g. a root-owned file with mode 0644 is read-only for other users.
+        smol::unblock(move || {
+            if unsafe { libc::access(path.as_ptr(), libc::W_OK) } == 0 {
+                return false;
+            }
+            matches!(
+                io::Error::last_os_error().raw_os_error(),
+                Some(libc::EACCES | libc::EPERM | libc::EROFS)
+            )
+        })
+        .await
+    }
+
+    #[cfg(windows)]
+    async fn is_read_only(&self, path: &Path) -> bool {
+        smol::fs::metadata(path)
+            .await
+            .map_or(false, |metadata| metadata.permissions().readonly())
+    }
+
     async fn metadata(&self, path: &Path) -> Result<Option<Metadata>> {
         let symlink_metadata = match smol::fs::symlink_metadata(path).await {
             Ok(metadata) => metadata,
@@ -792,6 +870,22 @@ struct FakeFsState {
     buffered_events: Vec<PathEvent>,
     metadata_call_count: usize,
     read_dir_call_count: usize,
+    read_only_paths: HashSet<PathBuf>,
+    trash: FakeTrash,
+    trashed_paths: Vec<PathBuf>,
+}
+
+/// How [`FakeFs`] responds to requests to move files to the trash.
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FakeTrash {
+    /// Trashed paths are removed and recorded in [`FakeFs::trashed_paths`].
+    #[default]
+    Available,
+    /// Trashing fails with [`TrashUnavailable`].
+    Unavailable,
+    /// Trashing fails with an error other than [`TrashUnavailable`].
+    Failing,
 }
 
 #[cfg(any(test, feature = "test-support"))]
@@ -945,6 +1039,9 @@ impl FakeFs {
                 events_paused: false,
                 read_dir_call_count: 0,
                 metadata_call_count: 0,
+                read_only_paths: Default::default(),
+                trash: Default::default(),
+                trashed_paths: Vec::new(),
             }),
         })
     }
@@ -954,6 +1051,34 @@ impl FakeFs {
         state.next_mtime = next_mtime;
     }
 
+    pub fn set_trash(&self, trash: FakeTrash) {
+        self.state.lock().trash = trash;
+    }
+
+    /// The paths that have been moved to the trash, in the order they were trashed.
+    pub fn trashed_paths(&self) -> Vec<PathBuf> {
+        self.state.lock().trashed_paths.clone()
+    }
+
+    fn check_trash(&self, path: &Path) -> Result<()> {
+        match self.state.lock().trash {
+            FakeTrash::Available => Ok(()),
+            FakeTrash::Unavailable => Err(TrashUnavailable.into()),
+            FakeTrash::Failing => Err(anyhow!("failed to move {path:?} to the trash")),
+        }
+    }
+
+    /// Makes the file at the given path report itself as read-only.
+    pub fn set_read_only(&self, path: impl AsRef<Path>, read_only: bool) {
+        let path = normalize_path(path.as_ref());
+        let mut state = self.state.lock();
+        if read_only {
+            state.read_only_paths.insert(path);
+        } else {
+            state.read_only_paths.remove(&path);
+        }
+    }
+
     pub async fn touch_path(&self, path: impl AsRef<Path>) {
         let mut state = self.state.lock();
         let path = path.as_ref();
@@ -1617,6 +1742,20 @@ impl Fs for FakeFs {
         Ok(())
     }
 
+    async fn trash_file(&self, path: &Path, options: RemoveOptions) -> Result<()> {
+        self.check_trash(path)?;
+        self.remove_file(path, options).await?;
+        self.state.lock().trashed_paths.push(normalize_path(path));
+        Ok(())
+    }
+
+    async fn trash_dir(&self, path: &Path, options: RemoveOptions) -> Result<()> {
+        self.check_trash(path)?;
+        self.remove_dir(path, options).await?;
+        self.state.lock().trashed_paths.push(normalize_path(path));
+        Ok(())
+    }
+
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read>> {
         let bytes = self.load_internal(path).await?;
         Ok(Box::new(io::Cursor::new(bytes)))
@@ -1649,6 +1788,23 @@ impl Fs for FakeFs {
         Ok(())
     }
 
+    async fn save_with_encoding(
+        &self,
+        path: &Path,
+        text: &Rope,
+        line_ending: LineEnding,
+        encoding: Encoding,
+    ) -> Result<()> {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        let content = encoding.encode(&chunks(text, line_ending).collect::<String>())?;
+        if let Some(path) = path.parent() {
+            self.create_dir(path).await?;
+        }
+        self.write_file_internal(path, content)?;
+        Ok(())
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         let path = normalize_path(path);
         self.simulate_random_delay().await;
@@ -1677,6 +1833,12 @@ impl Fs for FakeFs {
             .is_ok_and(|metadata| metadata.is_some_and(|metadata| metadata.is_dir))
     }
 
+    async fn is_read_only(&self, path: &Path) -> bool {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        self.state.lock().read_only_paths.contains(&path)
+    }
+
     async fn metadata(&self, path: &Path) -> Result<Option<Metadata>> {
         self.simulate_random_delay().await;
         let path = normalize_path(path);
@@ -427,6 +427,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_match_candidates_stops_when_cancelled() {
+        let paths = ["a/one", "a/two", "a/three", "a/four"]
+            .into_iter()
+            .map(|path| Arc::from(PathBuf::from(path)))
+            .collect::<Vec<Arc<Path>>>();
+        let candidates = paths.iter().map(|path| PathMatchCandidate {
+            is_dir: false,
+            char_bag: CharBag::from(&*path.to_string_lossy()),
+            path,
+        });
+
+        let query = ['a'];
+        let mut matcher = Matcher::new(&query, &query, CharBag::from(&query[..]), false, 100);
+        let cancel_flag = AtomicBool::new(false);
+        let mut results = Vec::new();
+
+        // Cancel the search once the third candidate is reached.
+        matcher.match_candidates(
+            &[],
+            &[],
+            candidates.enumerate().map(|(ix, candidate)| {
+                if ix == 2 {
+                    cancel_flag.store(true, atomic::Ordering::Relaxed);
+                }
+                candidate
+            }),
+            &mut results,
+            &cancel_flag,
+            |candidate, score| PathMatch {
+                score,
+                worktree_id: 0,
+                positions: Vec::new(),
+                path: Arc::from(candidate.path),
+                path_prefix: "".into(),
+                distance_to_relative_ancestor: usize::MAX,
+                is_dir: false,
+            },
+        );
+
+        let mut matched_paths = results
+            .iter()
+            .map(|result| result.path.as_ref())
+            .collect::<Vec<_>>();
+        matched_paths.sort();
+        assert_eq!(matched_paths, [Path::new("a/one"), Path::new("a/two")]);
+    }
+
     fn match_single_path_query<'a>(
         query: &str,
         smart_case: bool,
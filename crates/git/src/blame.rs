@@ -3,6 +3,7 @@ use crate::{parse_git_remote_url, BuildCommitPermalinkParams, GitHostingProvider
 use anyhow::{anyhow, Context, Result};
 use collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::io::Write;
 use std::process::{Command, Stdio};
 use std::sync::Arc;
@@ -69,6 +70,24 @@ impl Blame {
             remote_url,
         })
     }
+
+    /// Returns the blame entry covering the given zero-based row, or `None` if
+    /// the row hasn't been committed yet.
+    pub fn entry_for_row(&self, row: u32) -> Option<&BlameEntry> {
+        let ix = self
+            .entries
+            .binary_search_by(|entry| {
+                if entry.range.end <= row {
+                    Ordering::Less
+                } else if entry.range.start > row {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .ok()?;
+        self.entries.get(ix)
+    }
 }
 
 const GIT_BLAME_NO_COMMIT_ERROR: &str = "fatal: no such ref: HEAD";
@@ -326,10 +345,13 @@ fn parse_git_blame(output: &str) -> Result<Vec<BlameEntry>> {
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
 
     use super::parse_git_blame;
-    use super::BlameEntry;
+    use super::{Blame, BlameEntry};
+    use crate::GitHostingProviderRegistry;
+    use text::Rope;
 
     fn read_test_data(filename: &str) -> String {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -389,4 +411,59 @@ mod tests {
         let entries = parse_git_blame(&output).unwrap();
         assert_eq_golden(&entries, "blame_incremental_complex");
     }
+
+    #[test]
+    fn test_blame_for_path_in_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("file.txt"), "one\ntwo\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::new(
+            "Joe Schmoe",
+            "joe.schmoe@example.com",
+            &git2::Time::new(1709741400, 60),
+        )
+        .unwrap();
+        let sha = repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Joe's cool commit",
+                &tree,
+                &[],
+            )
+            .unwrap();
+
+        // The buffer has an unsaved line between the two committed ones.
+        let blame = Blame::for_path(
+            Path::new("git"),
+            dir.path(),
+            Path::new("file.txt"),
+            &Rope::from("one\nthree\ntwo\n"),
+            None,
+            Arc::new(GitHostingProviderRegistry::new()),
+        )
+        .unwrap();
+
+        let entry = blame.entry_for_row(2).unwrap();
+        assert_eq!(entry.sha.to_string(), sha.to_string());
+        assert_eq!(entry.range, 2..3);
+        assert_eq!(entry.original_line_number, 2);
+        assert_eq!(entry.author.as_deref(), Some("Joe Schmoe"));
+        assert_eq!(
+            entry.author_mail.as_deref(),
+            Some("<joe.schmoe@example.com>")
+        );
+        assert_eq!(entry.author_time, Some(1709741400));
+        assert_eq!(entry.author_tz.as_deref(), Some("+0100"));
+        assert_eq!(entry.summary.as_deref(), Some("Joe's cool commit"));
+
+        assert_eq!(blame.entry_for_row(0).unwrap().range, 0..1);
+        assert_eq!(blame.entry_for_row(1), None);
+        assert_eq!(blame.entry_for_row(3), None);
+    }
 }
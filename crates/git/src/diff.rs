@@ -30,6 +30,19 @@ pub struct DiffHunk<T> {
     pub diff_base_byte_range: Range<usize>,
 }
 
+impl<T: PartialOrd> DiffHunk<T> {
+    /// Whether the hunk's lines were added, removed or modified relative to the diff base.
+    pub fn status(&self) -> DiffHunkStatus {
+        if self.diff_base_byte_range.is_empty() {
+            DiffHunkStatus::Added
+        } else if self.associated_range.is_empty() {
+            DiffHunkStatus::Removed
+        } else {
+            DiffHunkStatus::Modified
+        }
+    }
+}
+
 impl sum_tree::Item for DiffHunk<Anchor> {
     type Summary = DiffHunkSummary;
 
@@ -14,6 +14,7 @@ pub use crate::hosting_provider::*;
 pub mod blame;
 pub mod commit;
 pub mod diff;
+pub mod log;
 pub mod repository;
 pub mod status;
 
@@ -0,0 +1,132 @@
+use crate::Oid;
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use time::OffsetDateTime;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+/// Separates the entries of the `git log` output.
+const RECORD_SEPARATOR: char = '\x1e';
+/// Separates the fields of a single `git log` entry.
+const FIELD_SEPARATOR: char = '\0';
+
+/// A commit that touched a file, as listed by `git log --follow`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    pub sha: Oid,
+    pub author: String,
+    pub author_mail: String,
+    /// Author timestamp, normalized to Unix Epoch format.
+    pub author_time: i64,
+    pub summary: String,
+    /// Path of the file in this commit, which differs from the current one if the file was renamed since.
+    pub path: PathBuf,
+}
+
+impl LogEntry {
+    pub fn author_date_time(&self) -> Result<OffsetDateTime> {
+        Ok(OffsetDateTime::from_unix_timestamp(self.author_time)?)
+    }
+}
+
+/// Returns the commits that touched the file at `path`, newest first, following renames.
+pub fn file_history(
+    git_binary: &Path,
+    working_directory: &Path,
+    path: &Path,
+) -> Result<Vec<LogEntry>> {
+    let mut command = Command::new(git_binary);
+
+    command
+        .current_dir(working_directory)
+        .arg("log")
+        .arg("-z")
+        .arg("--follow")
+        .arg("--name-only")
+        .arg("--format=%x1e%H%x00%an%x00%ae%x00%at%x00%s")
+        .arg("--")
+        .arg(path.as_os_str());
+
+    #[cfg(windows)]
+    command.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+
+    let output = command
+        .output()
+        .map_err(|e| anyhow!("Failed to start git log process: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("git log process failed: {}", stderr.trim()));
+    }
+
+    parse_git_log(&String::from_utf8_lossy(&output.stdout))
+}
+
+// parse_git_log parses the output of `git log -z --follow --name-only` with a format
+// in which each entry starts with a record separator and all fields, including the
+// path (which may itself contain newlines), are terminated by NUL bytes:
+//
+//     \x1e<sha>\0<author>\0<author-mail>\0<author-time>\0<summary>\0\n<path>\0
+fn parse_git_log(output: &str) -> Result<Vec<LogEntry>> {
+    output
+        .split(RECORD_SEPARATOR)
+        .filter(|record| !record.is_empty())
+        .map(|record| {
+            let mut fields = record.split(FIELD_SEPARATOR);
+            let mut next_field = |name: &str| {
+                fields
+                    .next()
+                    .with_context(|| format!("missing {name} in git log entry {record:?}"))
+            };
+            Ok(LogEntry {
+                sha: Oid::from_str(next_field("sha")?)?,
+                author: next_field("author")?.to_string(),
+                author_mail: next_field("author mail")?.to_string(),
+                author_time: next_field("author time")?
+                    .parse()
+                    .context("failed to parse author time")?,
+                summary: next_field("summary")?.to_string(),
+                path: PathBuf::from(
+                    next_field("path").map_or("", |path| path.strip_prefix('\n').unwrap_or(path)),
+                ),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_git_log() {
+        let output = "\x1e2b8f6d5e0c1c9b0ef4f58bb56a4bd1c55c43a7e4\0Jane Doe\0jane@example.com\01717000000\0Rename lib to main\0\nsrc/main.rs\0\x1e9a1c2e3d4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c\0John Roe\0john@example.com\01716000000\0Initial commit\0\nsrc/lib\nold.rs\0";
+        assert_eq!(
+            parse_git_log(output).unwrap(),
+            [
+                LogEntry {
+                    sha: Oid::from_str("2b8f6d5e0c1c9b0ef4f58bb56a4bd1c55c43a7e4").unwrap(),
+                    author: "Jane Doe".to_string(),
+                    author_mail: "jane@example.com".to_string(),
+                    author_time: 1717000000,
+                    summary: "Rename lib to main".to_string(),
+                    path: PathBuf::from("src/main.rs"),
+                },
+                LogEntry {
+                    sha: Oid::from_str("9a1c2e3d4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c").unwrap(),
+                    author: "John Roe".to_string(),
+                    author_mail: "john@example.com".to_string(),
+                    author_time: 1716000000,
+                    summary: "Initial commit".to_string(),
+                    path: PathBuf::from("src/lib\nold.rs"),
+                },
+            ]
+        );
+        assert_eq!(parse_git_log("").unwrap(), Vec::new());
+        assert!(parse_git_log("\x1enot-a-sha\0Jane Doe\n").is_err());
+    }
+}
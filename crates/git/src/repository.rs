@@ -1,5 +1,5 @@
 use crate::GitHostingProviderRegistry;
-use crate::{blame::Blame, status::GitStatus};
+use crate::{blame::Blame, log::LogEntry, status::GitStatus};
 use anyhow::{Context, Result};
 use collections::HashMap;
 use git2::BranchType;
@@ -29,6 +29,9 @@ pub trait GitRepository: Send + Sync {
     /// Note that for symlink entries, this will return the contents of the symlink, not the target.
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String>;
 
+    /// Loads the contents of a file as of the given revision, e.g. `HEAD~1` or a commit SHA.
+    fn load_revision_text(&self, revision: &str, relative_file_path: &Path) -> Result<String>;
+
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> Option<String>;
     fn branch_name(&self) -> Option<String>;
@@ -43,6 +46,9 @@ pub trait GitRepository: Send + Sync {
     fn create_branch(&self, _: &str) -> Result<()>;
 
     fn blame(&self, path: &Path, content: Rope) -> Result<crate::blame::Blame>;
+
+    /// Returns the commits that touched the file at the given path, newest first.
+    fn file_history(&self, path: &Path) -> Result<Vec<LogEntry>>;
 }
 
 impl std::fmt::Debug for dyn GitRepository {
@@ -105,6 +111,30 @@ impl GitRepository for RealGitRepository {
         None
     }
 
+    fn load_revision_text(&self, revision: &str, relative_file_path: &Path) -> Result<String> {
+        check_path_to_repo_path_errors(relative_file_path)?;
+
+        let repo = self.repository.lock();
+        let commit = repo
+            .revparse_single(revision)
+            .with_context(|| format!("unknown revision {revision:?}"))?
+            .peel_to_commit()
+            .with_context(|| format!("revision {revision:?} does not point to a commit"))?;
+        let entry = commit
+            .tree()?
+            .get_path(relative_file_path)
+            .with_context(|| {
+                format!("path {relative_file_path:?} does not exist in revision {revision:?}")
+            })?;
+        let content = entry
+            .to_object(&repo)?
+            .peel_to_blob()
+            .with_context(|| format!("path {relative_file_path:?} is not a file"))?
+            .content()
+            .to_owned();
+        Ok(String::from_utf8(content)?)
+    }
+
     fn remote_url(&self, name: &str) -> Option<String> {
         let repo = self.repository.lock();
         let remote = repo.find_remote(name).ok()?;
@@ -199,6 +229,17 @@ impl GitRepository for RealGitRepository {
             self.hosting_provider_registry.clone(),
         )
     }
+
+    fn file_history(&self, path: &Path) -> Result<Vec<LogEntry>> {
+        let working_directory = self
+            .repository
+            .lock()
+            .workdir()
+            .with_context(|| format!("failed to get git working directory for file {:?}", path))?
+            .to_path_buf();
+
+        crate::log::file_history(&self.git_binary_path, &working_directory, path)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -209,7 +250,9 @@ pub struct FakeGitRepository {
 #[derive(Debug, Clone, Default)]
 pub struct FakeGitRepositoryState {
     pub index_contents: HashMap<PathBuf, String>,
+    pub revision_contents: HashMap<(String, PathBuf), String>,
     pub blames: HashMap<PathBuf, Blame>,
+    pub file_histories: HashMap<PathBuf, Vec<LogEntry>>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub branch_name: Option<String>,
 }
@@ -228,6 +271,15 @@ impl GitRepository for FakeGitRepository {
         state.index_contents.get(path).cloned()
     }
 
+    fn load_revision_text(&self, revision: &str, path: &Path) -> Result<String> {
+        let state = self.state.lock();
+        state
+            .revision_contents
+            .get(&(revision.to_string(), path.to_path_buf()))
+            .with_context(|| format!("path {path:?} does not exist in revision {revision:?}"))
+            .cloned()
+    }
+
     fn remote_url(&self, _name: &str) -> Option<String> {
         None
     }
@@ -287,6 +339,11 @@ impl GitRepository for FakeGitRepository {
             .with_context(|| format!("failed to get blame for {:?}", path))
             .cloned()
     }
+
+    fn file_history(&self, path: &Path) -> Result<Vec<LogEntry>> {
+        let state = self.state.lock();
+        Ok(state.file_histories.get(path).cloned().unwrap_or_default())
+    }
 }
 
 fn check_path_to_repo_path_errors(relative_file_path: &Path) -> Result<()> {
@@ -110,6 +110,18 @@ impl CursorPosition {
         text.push(')');
     }
 
+    /// The text shown in the status bar, or `None` when there is no active editor.
+    pub(crate) fn text(&self, cx: &AppContext) -> Option<String> {
+        let position = self.position?;
+        let mut text = format!(
+            "{}{FILE_ROW_COLUMN_DELIMITER}{}",
+            position.row + 1,
+            position.column + 1
+        );
+        self.write_position(&mut text, cx);
+        Some(text)
+    }
+
     #[cfg(test)]
     pub(crate) fn selection_stats(&self) -> &SelectionStats {
         &self.selected_count
@@ -118,14 +130,7 @@ impl CursorPosition {
 
 impl Render for CursorPosition {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        div().when_some(self.position, |el, position| {
-            let mut text = format!(
-                "{}{FILE_ROW_COLUMN_DELIMITER}{}",
-                position.row + 1,
-                position.column + 1
-            );
-            self.write_position(&mut text, cx);
-
+        div().when_some(self.text(cx), |el, text| {
             el.child(
                 Button::new("go-to-line-column", text)
                     .label_size(LabelSize::Small)
@@ -1,7 +1,7 @@
 pub mod cursor_position;
 
 use cursor_position::LineIndicatorFormat;
-use editor::{scroll::Autoscroll, Editor};
+use editor::{scroll::Autoscroll, Editor, MultiBufferSnapshot};
 use gpui::{
     div, prelude::*, AnyWindowHandle, AppContext, DismissEvent, EventEmitter, FocusHandle,
     FocusableView, Render, SharedString, Styled, Subscription, View, ViewContext, VisualContext,
@@ -116,7 +116,7 @@ impl GoToLine {
         if let Some(point) = self.point_from_query(cx) {
             self.active_editor.update(cx, |active_editor, cx| {
                 let snapshot = active_editor.snapshot(cx).display_snapshot;
-                let point = snapshot.buffer_snapshot.clip_point(point, Bias::Left);
+                let point = clamp_point(point, &snapshot.buffer_snapshot);
                 let anchor = snapshot.buffer_snapshot.anchor_before(point);
                 active_editor.clear_row_highlights::<GoToLineRowHighlights>();
                 active_editor.highlight_rows::<GoToLineRowHighlights>(
@@ -158,7 +158,15 @@ impl GoToLine {
         if let Some(point) = self.point_from_query(cx) {
             self.active_editor.update(cx, |editor, cx| {
                 let snapshot = editor.snapshot(cx).display_snapshot;
-                let point = snapshot.buffer_snapshot.clip_point(point, Bias::Left);
+                let max_row = snapshot.buffer_snapshot.max_point().row;
+                if point.row > max_row {
+                    log::info!(
+                        "line {} is past the end of the buffer, going to line {} instead",
+                        point.row + 1,
+                        max_row + 1
+                    );
+                }
+                let point = clamp_point(point, &snapshot.buffer_snapshot);
                 editor.change_selections(Some(Autoscroll::center()), cx, |s| {
                     s.select_ranges([point..point])
                 });
@@ -172,6 +180,12 @@ impl GoToLine {
     }
 }
 
+/// Clamps rows past the end of the buffer to its last line, keeping the requested column.
+fn clamp_point(point: Point, snapshot: &MultiBufferSnapshot) -> Point {
+    let point = Point::new(point.row.min(snapshot.max_point().row), point.column);
+    snapshot.clip_point(point, Bias::Left)
+}
+
 impl Render for GoToLine {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let mut help_text = self.current_text.clone();
@@ -228,7 +242,7 @@ mod tests {
     use project::{FakeFs, Project};
     use serde_json::json;
     use std::sync::Arc;
-    use workspace::{AppState, Workspace};
+    use workspace::{item::ItemHandle, AppState, StatusItemView, Workspace};
 
     #[gpui::test]
     async fn test_go_to_line_view_row_highlights(cx: &mut TestAppContext) {
@@ -337,6 +351,53 @@ mod tests {
         assert_single_caret_at_row(&editor, expected_highlighted_row, cx);
     }
 
+    #[gpui::test]
+    async fn test_go_to_line_and_column(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "a.rs": "one\ntwo\nthree"
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let worktree_id = workspace.update(cx, |workspace, cx| {
+            workspace.project().update(cx, |project, cx| {
+                project.worktrees(cx).next().unwrap().read(cx).id()
+            })
+        });
+        let editor = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, "a.rs"), None, true, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+
+        let go_to = |query: &str, cx: &mut VisualTestContext| {
+            let go_to_line_view = open_go_to_line_view(&workspace, cx);
+            cx.simulate_input(query);
+            cx.dispatch_action(menu::Confirm);
+            drop(go_to_line_view);
+            editor.update(cx, |editor, cx| {
+                editor.selections.newest::<Point>(cx).head()
+            })
+        };
+
+        assert_eq!(go_to("2", cx), Point::new(1, 0));
+        assert_eq!(go_to("3:2", cx), Point::new(2, 1));
+        // Columns past the end of the line are clipped to it.
+        assert_eq!(go_to("2:100", cx), Point::new(1, 3));
+        // Lines past the end of the buffer go to the last line, keeping the column.
+        assert_eq!(go_to("100:2", cx), Point::new(2, 1));
+        assert_eq!(go_to("100", cx), Point::new(2, 0));
+    }
+
     #[gpui::test]
     async fn test_unicode_characters_selection(cx: &mut TestAppContext) {
         init_test(cx);
@@ -414,6 +475,124 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_custom_status_item(cx: &mut TestAppContext) {
+        struct CursorRow {
+            text: Option<String>,
+            _observe_active_editor: Option<Subscription>,
+        }
+
+        impl CursorRow {
+            fn update_text(&mut self, editor: View<Editor>, cx: &mut ViewContext<Self>) {
+                let head = editor.read(cx).selections.newest::<Point>(cx).head();
+                self.text = Some(format!("Row {}", head.row + 1));
+                cx.notify();
+            }
+        }
+
+        impl Render for CursorRow {
+            fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+                div().when_some(self.text.clone(), |el, text| el.child(Label::new(text)))
+            }
+        }
+
+        impl StatusItemView for CursorRow {
+            fn set_active_pane_item(
+                &mut self,
+                active_pane_item: Option<&dyn ItemHandle>,
+                cx: &mut ViewContext<Self>,
+            ) {
+                if let Some(editor) = active_pane_item.and_then(|item| item.act_as::<Editor>(cx)) {
+                    self._observe_active_editor = Some(cx.observe(&editor, Self::update_text));
+                    self.update_text(editor, cx);
+                } else {
+                    self.text = None;
+                    self._observe_active_editor = None;
+                }
+            }
+        }
+
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({
+                "a.rs": "one\ntwo\nthree\n"
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        workspace.update(cx, |workspace, cx| {
+            let cursor_position = cx.new_view(|_| CursorPosition::new(workspace));
+            let cursor_row = cx.new_view(|_| CursorRow {
+                text: None,
+                _observe_active_editor: None,
+            });
+            workspace.status_bar().update(cx, |status_bar, cx| {
+                status_bar.add_left_item(cursor_row, cx);
+                status_bar.add_right_item(cursor_position, cx);
+            });
+        });
+
+        let status_texts = |cx: &mut VisualTestContext| {
+            workspace.update(cx, |workspace, cx| {
+                let status_bar = workspace.status_bar().read(cx);
+                let cursor_row = status_bar
+                    .item_of_type::<CursorRow>()
+                    .expect("missing custom status item")
+                    .read(cx)
+                    .text
+                    .clone();
+                let cursor_position = status_bar
+                    .item_of_type::<CursorPosition>()
+                    .expect("missing cursor position item")
+                    .read(cx)
+                    .text(cx);
+                (cursor_row, cursor_position)
+            })
+        };
+        assert_eq!(status_texts(cx), (None, None));
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(
+                workspace
+                    .status_bar()
+                    .read(cx)
+                    .position_of_item::<CursorRow>(),
+                Some(0)
+            );
+        });
+
+        let worktree_id = workspace.update(cx, |workspace, cx| {
+            workspace.project().update(cx, |project, cx| {
+                project.worktrees(cx).next().unwrap().read(cx).id()
+            })
+        });
+        let editor = workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path((worktree_id, "a.rs"), None, true, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+        assert_eq!(
+            status_texts(cx),
+            (Some("Row 1".to_string()), Some("1:1".to_string()))
+        );
+
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::new(1, 2)..Point::new(1, 2)])
+            });
+        });
+        assert_eq!(
+            status_texts(cx),
+            (Some("Row 2".to_string()), Some("2:3".to_string()))
+        );
+    }
+
     fn open_go_to_line_view(
         workspace: &View<Workspace>,
         cx: &mut VisualTestContext,
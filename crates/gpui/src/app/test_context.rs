@@ -277,6 +277,19 @@ impl TestAppContext {
         self.test_platform.has_pending_prompt()
     }
 
+    /// Simulates credentials for the given URL being stored in the system keychain.
+    pub fn simulate_keychain_credentials(&self, url: &str, username: &str, password: &[u8]) {
+        self.test_platform
+            .keychain
+            .borrow_mut()
+            .insert(url.to_string(), (username.to_string(), password.to_vec()));
+    }
+
+    /// Returns how many times credentials were read from the system keychain during this test.
+    pub fn keychain_read_count(&self) -> usize {
+        self.test_platform.keychain_reads.get()
+    }
+
     /// All the urls that have been opened with cx.open_url() during this test.
     pub fn opened_url(&self) -> Option<String> {
         self.test_platform.opened_url.borrow().clone()
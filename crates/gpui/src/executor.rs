@@ -1,8 +1,11 @@
 use crate::{AppContext, PlatformDispatcher};
+use collections::HashMap;
 use futures::channel::mpsc;
+use parking_lot::Mutex;
 use smol::prelude::*;
 use std::{
     fmt::Debug,
+    hash::Hash,
     marker::PhantomData,
     mem,
     num::NonZeroUsize,
@@ -15,7 +18,7 @@ use std::{
     task::{Context, Poll},
     time::{Duration, Instant},
 };
-use util::TryFutureExt;
+use util::{post_inc, TryFutureExt};
 use waker_fn::waker_fn;
 
 #[cfg(any(test, feature = "test-support"))]
@@ -27,6 +30,7 @@ use rand::rngs::StdRng;
 pub struct BackgroundExecutor {
     #[doc(hidden)]
     pub dispatcher: Arc<dyn PlatformDispatcher>,
+    num_cpus_hint: Arc<AtomicUsize>,
 }
 
 /// A pointer to the executor that is currently running,
@@ -126,7 +130,10 @@ type AnyFuture<R> = Pin<Box<dyn 'static + Send + Future<Output = R>>>;
 impl BackgroundExecutor {
     #[doc(hidden)]
     pub fn new(dispatcher: Arc<dyn PlatformDispatcher>) -> Self {
-        Self { dispatcher }
+        Self {
+            dispatcher,
+            num_cpus_hint: Arc::new(AtomicUsize::new(0)),
+        }
     }
 
     /// Enqueues the given future to be run to completion on a background thread.
@@ -405,9 +412,28 @@ impl BackgroundExecutor {
         self.dispatcher.as_test().unwrap().rng()
     }
 
-    /// How many CPUs are available to the dispatcher.
+    /// How many CPUs are available to the dispatcher, or the hint set with
+    /// [`Self::set_num_cpus_hint`] when it's lower.
     pub fn num_cpus(&self) -> usize {
-        num_cpus::get()
+        match self.num_cpus_hint.load(SeqCst) {
+            0 => num_cpus::get(),
+            num_cpus_hint => num_cpus_hint.min(num_cpus::get()),
+        }
+    }
+
+    /// Sets a hint for how many CPUs [`Self::num_cpus`] reports. Jobs that split their work
+    /// across that many workers, like worktree scans and fuzzy matching, use fewer workers as a
+    /// result, but the dispatcher still runs tasks on all of its threads. The hint is clamped
+    /// between one and the available parallelism, and `None` removes it.
+    pub fn set_num_cpus_hint(&self, num_cpus_hint: Option<usize>) {
+        let num_cpus_hint = num_cpus_hint.map_or(0, |num_cpus_hint| {
+            let clamped = num_cpus_hint.clamp(1, num_cpus::get());
+            if clamped != num_cpus_hint {
+                log::warn!("clamped CPU count hint {num_cpus_hint} to {clamped}");
+            }
+            clamped
+        });
+        self.num_cpus_hint.store(num_cpus_hint, SeqCst);
     }
 
     /// Whether we're on the main thread.
@@ -509,3 +535,131 @@ impl<'a> Drop for Scope<'a> {
         self.executor.block(self.rx.next());
     }
 }
+
+/// Debounces background work by key.
+///
+/// Scheduling a task for a key cancels any task still waiting on that key, so only the last task
+/// in a burst runs. Tasks scheduled for different keys don't affect one another.
+pub struct Debouncer<K> {
+    executor: BackgroundExecutor,
+    /// The tasks that haven't finished yet, along with the id they were scheduled with.
+    pending: Arc<Mutex<HashMap<K, (usize, Task<()>)>>>,
+    next_task_id: usize,
+}
+
+impl<K: Clone + Eq + Hash + Send + 'static> Debouncer<K> {
+    /// Creates a debouncer that runs its tasks on the given executor.
+    pub fn new(executor: BackgroundExecutor) -> Self {
+        Self {
+            executor,
+            pending: Arc::default(),
+            next_task_id: 0,
+        }
+    }
+
+    /// Runs `task` once `delay` has elapsed, unless another task is scheduled for the same key
+    /// before then.
+    pub fn schedule<F>(&mut self, key: K, delay: Duration, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let task_id = post_inc(&mut self.next_task_id);
+        let timer = self.executor.timer(delay);
+        // Hold the lock while spawning, so that the task can't finish before it's recorded.
+        let mut pending = self.pending.lock();
+        let task = self.executor.spawn({
+            let pending = self.pending.clone();
+            let key = key.clone();
+            async move {
+                timer.await;
+                task.await;
+
+                // Forget the finished task, unless a newer one has taken its place already.
+                let mut pending = pending.lock();
+                if pending.get(&key).map_or(false, |(id, _)| *id == task_id) {
+                    if let Some((_, task)) = pending.remove(&key) {
+                        task.detach();
+                    }
+                }
+            }
+        });
+        pending.insert(key, (task_id, task));
+    }
+
+    /// Cancels the task scheduled for the given key, if any.
+    pub fn cancel(&mut self, key: &K) {
+        self.pending.lock().remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{self as gpui, TestAppContext};
+
+    #[gpui::test]
+    fn test_debouncer_runs_last_task_in_burst(cx: &mut TestAppContext) {
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let mut debouncer = Debouncer::new(cx.executor());
+
+        for ix in 0..3 {
+            let runs = runs.clone();
+            debouncer.schedule("save", Duration::from_millis(100), async move {
+                runs.lock().push(ix);
+            });
+            cx.executor().advance_clock(Duration::from_millis(50));
+        }
+        assert_eq!(*runs.lock(), []);
+
+        cx.executor().advance_clock(Duration::from_millis(50));
+        assert_eq!(*runs.lock(), [2]);
+        assert!(
+            debouncer.pending.lock().is_empty(),
+            "finished tasks are forgotten"
+        );
+    }
+
+    #[gpui::test]
+    fn test_debouncer_keys_are_independent(cx: &mut TestAppContext) {
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let mut debouncer = Debouncer::new(cx.executor());
+
+        for key in ["save", "reload"] {
+            let runs = runs.clone();
+            debouncer.schedule(key, Duration::from_millis(100), async move {
+                runs.lock().push(key);
+            });
+        }
+        let runs_for_cancelled = runs.clone();
+        debouncer.schedule("search", Duration::from_millis(100), async move {
+            runs_for_cancelled.lock().push("search");
+        });
+        debouncer.cancel(&"search");
+
+        cx.executor().advance_clock(Duration::from_millis(100));
+        let mut runs = runs.lock().clone();
+        runs.sort_unstable();
+        assert_eq!(runs, ["reload", "save"]);
+    }
+
+    #[gpui::test]
+    fn test_num_cpus_hint(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let available = num_cpus::get();
+        assert_eq!(executor.num_cpus(), available);
+
+        executor.set_num_cpus_hint(Some(0));
+        assert_eq!(executor.num_cpus(), 1);
+        assert_eq!(
+            cx.executor().num_cpus(),
+            1,
+            "the hint is shared between clones"
+        );
+
+        executor.set_num_cpus_hint(Some(available + 1));
+        assert_eq!(executor.num_cpus(), available);
+
+        executor.set_num_cpus_hint(None);
+        assert_eq!(executor.num_cpus(), available);
+    }
+}
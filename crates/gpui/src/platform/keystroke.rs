@@ -393,3 +393,56 @@ impl Modifiers {
             && (other.function || !self.function)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keystroke() {
+        assert_eq!(
+            Keystroke::parse("cmd-shift-p").unwrap(),
+            Keystroke {
+                modifiers: Modifiers {
+                    platform: true,
+                    shift: true,
+                    ..Default::default()
+                },
+                key: "p".into(),
+                ime_key: None,
+            }
+        );
+        assert_eq!(
+            Keystroke::parse("ctrl-alt-fn-f1").unwrap(),
+            Keystroke {
+                modifiers: Modifiers {
+                    control: true,
+                    alt: true,
+                    function: true,
+                    ..Default::default()
+                },
+                key: "f1".into(),
+                ime_key: None,
+            }
+        );
+        assert_eq!(
+            Keystroke::parse("super-a").unwrap(),
+            Keystroke::parse("cmd-a").unwrap()
+        );
+
+        // A trailing dash binds the `-` key itself.
+        assert_eq!(Keystroke::parse("ctrl--").unwrap().key, "-");
+
+        // A lone modifier is treated as the key.
+        assert_eq!(
+            Keystroke::parse("shift").unwrap(),
+            Keystroke {
+                modifiers: Modifiers::default(),
+                key: "shift".into(),
+                ime_key: None,
+            }
+        );
+
+        assert!(Keystroke::parse("ctrl-x-y").is_err());
+    }
+}
@@ -672,7 +672,10 @@ mod lenient_font_attributes {
 
 #[cfg(test)]
 mod tests {
-    use crate::{font, px, FontRun, GlyphId, MacTextSystem, PlatformTextSystem};
+    use crate::{
+        font, px, Font, FontFeatures, FontRun, GlyphId, MacTextSystem, PlatformTextSystem,
+    };
+    use std::sync::Arc;
 
     #[test]
     fn test_layout_line_bom_char() {
@@ -698,4 +701,45 @@ mod tests {
                                                                  // There's no glyph for \u{feff}
         assert_eq!(layout.runs[0].glyphs[1].id, GlyphId(69u32)); // b
     }
+
+    #[test]
+    fn test_calt_feature_toggles_ligatures() {
+        let fonts = MacTextSystem::new();
+        fonts
+            .add_fonts(vec![std::fs::read(
+                "../../assets/fonts/plex-mono/ZedPlexMono-Regular.ttf",
+            )
+            .unwrap()
+            .into()])
+            .unwrap();
+        let with_ligatures = font("Zed Plex Mono");
+        let without_ligatures = Font {
+            features: FontFeatures(Arc::new(vec![("calt".into(), 0)])),
+            ..font("Zed Plex Mono")
+        };
+
+        let glyphs = |font: &Font, text: &str| {
+            let font_id = fonts.font_id(font).unwrap();
+            let run = FontRun {
+                font_id,
+                len: text.len(),
+            };
+            fonts
+                .layout_line(text, px(16.), &[run])
+                .runs
+                .iter()
+                .flat_map(|run| run.glyphs.iter().map(|glyph| glyph.id))
+                .collect::<Vec<_>>()
+        };
+
+        // With `calt` off, "->" is shaped as its separate characters.
+        let separate = [
+            glyphs(&without_ligatures, "-"),
+            glyphs(&without_ligatures, ">"),
+        ]
+        .concat();
+        assert_eq!(glyphs(&without_ligatures, "->"), separate);
+        // Zed Plex Mono's contextual alternates join them into an arrow by default.
+        assert_ne!(glyphs(&with_ligatures, "->"), separate);
+    }
 }
@@ -4,11 +4,11 @@ use crate::{
     WindowParams,
 };
 use anyhow::Result;
-use collections::VecDeque;
+use collections::{HashMap, VecDeque};
 use futures::channel::oneshot;
 use parking_lot::Mutex;
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     path::{Path, PathBuf},
     rc::{Rc, Weak},
     sync::Arc,
@@ -32,6 +32,10 @@ pub(crate) struct TestPlatform {
     current_primary_item: Mutex<Option<ClipboardItem>>,
     pub(crate) prompts: RefCell<TestPrompts>,
     pub opened_url: RefCell<Option<String>>,
+    /// Credentials put in the keychain by the test, keyed by URL. The app's own writes aren't
+    /// recorded, so that tests only see the credentials they ask for.
+    pub(crate) keychain: RefCell<HashMap<String, (String, Vec<u8>)>>,
+    pub(crate) keychain_reads: Cell<usize>,
     pub text_system: Arc<dyn PlatformTextSystem>,
     #[cfg(target_os = "windows")]
     bitmap_factory: std::mem::ManuallyDrop<IWICImagingFactory>,
@@ -80,6 +84,8 @@ impl TestPlatform {
             current_primary_item: Mutex::new(None),
             weak: weak.clone(),
             opened_url: Default::default(),
+            keychain: Default::default(),
+            keychain_reads: Default::default(),
             #[cfg(target_os = "windows")]
             bitmap_factory,
             text_system,
@@ -307,11 +313,13 @@ impl Platform for TestPlatform {
         Task::ready(Ok(()))
     }
 
-    fn read_credentials(&self, _url: &str) -> Task<Result<Option<(String, Vec<u8>)>>> {
-        Task::ready(Ok(None))
+    fn read_credentials(&self, url: &str) -> Task<Result<Option<(String, Vec<u8>)>>> {
+        self.keychain_reads.set(self.keychain_reads.get() + 1);
+        Task::ready(Ok(self.keychain.borrow().get(url).cloned()))
     }
 
-    fn delete_credentials(&self, _url: &str) -> Task<Result<()>> {
+    fn delete_credentials(&self, url: &str) -> Task<Result<()>> {
+        self.keychain.borrow_mut().remove(url);
         Task::ready(Ok(()))
     }
 
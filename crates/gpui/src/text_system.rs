@@ -28,6 +28,7 @@ use std::{
     cmp,
     fmt::{Debug, Display, Formatter},
     hash::{Hash, Hasher},
+    iter,
     ops::{Deref, DerefMut, Range},
     sync::Arc,
 };
@@ -157,6 +158,100 @@ impl TextSystem {
         );
     }
 
+    /// Finds the font that displays the given character: the font itself if it has a glyph for
+    /// it, otherwise the first of its fallbacks that does.
+    pub fn font_id_for_char(&self, font: &Font, ch: char) -> Option<FontId> {
+        let font_id = self.font_id(font).ok()?;
+        if self.has_glyph(font_id, ch) {
+            return Some(font_id);
+        }
+        self.fallback_font_ids(font)
+            .into_iter()
+            .find(|&font_id| self.has_glyph(font_id, ch))
+    }
+
+    fn has_glyph(&self, font_id: FontId, ch: char) -> bool {
+        self.platform_text_system
+            .glyph_for_char(font_id, ch)
+            .is_some()
+    }
+
+    /// Resolves the fonts of the given font's fallback families, in order.
+    fn fallback_font_ids(&self, font: &Font) -> SmallVec<[FontId; 4]> {
+        let Some(fallbacks) = font.fallbacks.as_ref() else {
+            return SmallVec::new();
+        };
+        fallbacks
+            .fallback_list()
+            .iter()
+            .filter_map(|family| {
+                let fallback = Font {
+                    family: family.clone().into(),
+                    fallbacks: None,
+                    ..font.clone()
+                };
+                self.font_id(&fallback).ok()
+            })
+            .collect()
+    }
+
+    /// Appends runs for the given range of text in the given font, merging them with the previous
+    /// run when they use the same font.
+    ///
+    /// The macOS and Windows text systems apply a font's fallbacks when shaping, but on other
+    /// platforms the text is split so that characters the font lacks use a fallback instead.
+    fn push_font_runs(
+        &self,
+        font_runs: &mut Vec<FontRun>,
+        font: &Font,
+        text: &str,
+        range: Range<usize>,
+    ) {
+        let mut push_run = |font_id: FontId, len: usize| {
+            if let Some(last_run) = font_runs.last_mut() {
+                if last_run.font_id == font_id {
+                    last_run.len += len;
+                    return;
+                }
+            }
+            font_runs.push(FontRun { len, font_id });
+        };
+
+        let font_id = self.resolve_font(font);
+        let has_fallbacks = font
+            .fallbacks
+            .as_ref()
+            .map_or(false, |fallbacks| !fallbacks.fallback_list().is_empty());
+        let text = text
+            .get(range.clone())
+            .filter(|_| has_fallbacks && !cfg!(any(target_os = "macos", target_os = "windows")));
+        let Some(text) = text else {
+            push_run(font_id, range.len());
+            return;
+        };
+
+        // Most runs are entirely covered by their font, in which case the fallbacks aren't needed.
+        let Some((missing_ix, _)) = text
+            .char_indices()
+            .find(|&(_, ch)| !self.has_glyph(font_id, ch))
+        else {
+            push_run(font_id, text.len());
+            return;
+        };
+        if missing_ix > 0 {
+            push_run(font_id, missing_ix);
+        }
+
+        let fallback_font_ids = self.fallback_font_ids(font);
+        for ch in text[missing_ix..].chars() {
+            let char_font_id = iter::once(font_id)
+                .chain(fallback_font_ids.iter().copied())
+                .find(|&font_id| self.has_glyph(font_id, ch))
+                .unwrap_or(font_id);
+            push_run(char_font_id, ch.len_utf8());
+        }
+    }
+
     /// Get the bounding box for the given font and font size.
     /// A font's bounding box is the smallest rectangle that could enclose all glyphs
     /// in the font. superimposed over one another.
@@ -384,7 +479,6 @@ impl WindowTextSystem {
         let mut process_line = |line_text: SharedString| {
             let line_end = line_start + line_text.len();
 
-            let mut last_font: Option<Font> = None;
             let mut decoration_runs = SmallVec::<[DecorationRun; 32]>::new();
             let mut run_start = line_start;
             while run_start < line_end {
@@ -394,15 +488,9 @@ impl WindowTextSystem {
 
                 let run_len_within_line = cmp::min(line_end, run_start + run.len) - run_start;
 
-                if last_font == Some(run.font.clone()) {
-                    font_runs.last_mut().unwrap().len += run_len_within_line;
-                } else {
-                    last_font = Some(run.font.clone());
-                    font_runs.push(FontRun {
-                        len: run_len_within_line,
-                        font_id: self.resolve_font(&run.font),
-                    });
-                }
+                let range_in_line =
+                    run_start - line_start..run_start - line_start + run_len_within_line;
+                self.push_font_runs(&mut font_runs, &run.font, &line_text, range_in_line);
 
                 if decoration_runs.last().map_or(false, |last_run| {
                     last_run.color == run.color
@@ -490,18 +578,11 @@ impl WindowTextSystem {
         runs: &[TextRun],
     ) -> Result<Arc<LineLayout>> {
         let mut font_runs = self.font_runs_pool.lock().pop().unwrap_or_default();
+        let mut run_start = 0;
         for run in runs.iter() {
-            let font_id = self.resolve_font(&run.font);
-            if let Some(last_run) = font_runs.last_mut() {
-                if last_run.font_id == font_id {
-                    last_run.len += run.len;
-                    continue;
-                }
-            }
-            font_runs.push(FontRun {
-                len: run.len,
-                font_id,
-            });
+            let run_end = run_start + run.len;
+            self.push_font_runs(&mut font_runs, &run.font, text, run_start..run_end);
+            run_start = run_end;
         }
 
         let layout = self
@@ -792,3 +873,74 @@ impl FontMetrics {
         (self.bounding_box / self.units_per_em as f32 * font_size.0).map(px)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TestAppContext, TestDispatcher};
+    use rand::prelude::*;
+
+    #[test]
+    fn test_font_id_for_char_uses_fallbacks() {
+        let dispatcher = TestDispatcher::new(StdRng::seed_from_u64(0));
+        let cx = TestAppContext::new(dispatcher, None);
+        let text_system = cx.text_system();
+        text_system
+            .add_fonts(vec![
+                std::fs::read("../../assets/fonts/plex-mono/ZedPlexMono-Regular.ttf")
+                    .unwrap()
+                    .into(),
+                std::fs::read("../../assets/fonts/plex-sans/ZedPlexSans-Regular.ttf")
+                    .unwrap()
+                    .into(),
+            ])
+            .unwrap();
+
+        // Zed Plex Mono has no Greek letters, but Zed Plex Sans does.
+        let mono = font("Zed Plex Mono");
+        assert_eq!(text_system.font_id_for_char(&mono, 'Ω'), None);
+
+        let mono_with_fallback = Font {
+            fallbacks: Some(FontFallbacks::from_fonts(vec!["Zed Plex Sans".into()])),
+            ..mono
+        };
+        let mono_id = text_system.font_id(&mono_with_fallback).unwrap();
+        let sans_id = text_system.font_id(&font("Zed Plex Sans")).unwrap();
+        assert_eq!(
+            text_system.font_id_for_char(&mono_with_fallback, 'a'),
+            Some(mono_id)
+        );
+        assert_eq!(
+            text_system.font_id_for_char(&mono_with_fallback, 'Ω'),
+            Some(sans_id)
+        );
+
+        #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+        {
+            let text_system = WindowTextSystem::new(text_system.clone());
+            let text = "abΩc";
+            let layout = text_system
+                .layout_line(
+                    text,
+                    px(16.),
+                    &[TextRun {
+                        len: text.len(),
+                        font: mono_with_fallback,
+                        color: Default::default(),
+                        background_color: None,
+                        underline: None,
+                        strikethrough: None,
+                    }],
+                )
+                .unwrap();
+            assert_eq!(
+                layout
+                    .runs
+                    .iter()
+                    .map(|run| run.font_id)
+                    .collect::<Vec<_>>(),
+                [mono_id, sans_id, mono_id]
+            );
+        }
+    }
+}
@@ -53,9 +53,9 @@ use text::operation_queue::OperationQueue;
 use text::*;
 pub use text::{
     Anchor, Bias, Buffer as TextBuffer, BufferId, BufferSnapshot as TextBufferSnapshot, Edit,
-    OffsetRangeExt, OffsetUtf16, Patch, Point, PointUtf16, Rope, Selection, SelectionGoal,
-    Subscription, TextDimension, TextSummary, ToOffset, ToOffsetUtf16, ToPoint, ToPointUtf16,
-    Transaction, TransactionId, Unclipped,
+    Encoding, OffsetRangeExt, OffsetUtf16, Patch, Point, PointUtf16, Rope, Selection,
+    SelectionGoal, SerializedUndoHistory, Subscription, TextDimension, TextSummary, ToOffset,
+    ToOffsetUtf16, ToPoint, ToPointUtf16, Transaction, TransactionId, Unclipped,
 };
 use theme::SyntaxTheme;
 #[cfg(any(test, feature = "test-support"))]
@@ -114,11 +114,15 @@ pub struct Buffer {
     completion_triggers_timestamp: clock::Lamport,
     deferred_ops: OperationQueue<Operation>,
     capability: Capability,
+    encoding: Encoding,
     has_conflict: bool,
     diff_base_version: usize,
     /// Memoize calls to has_changes_since(saved_version).
     /// The contents of a cell are (self.version, has_changes) at the time of a last call.
     has_unsaved_edits: Cell<(clock::Global, bool)>,
+    /// Memoizes the indentation detected from the buffer's contents, shared with its snapshots.
+    /// The contents are (version, detected indent size) at the time of the last detection.
+    detected_indent_size: Arc<Mutex<Option<(clock::Global, Option<IndentSize>)>>>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -138,6 +142,7 @@ pub struct BufferSnapshot {
     remote_selections: TreeMap<ReplicaId, SelectionSet>,
     language: Option<Arc<Language>>,
     non_text_state_update_count: usize,
+    detected_indent_size: Arc<Mutex<Option<(clock::Global, Option<IndentSize>)>>>,
 }
 
 /// The kind and amount of indentation in a particular line. For now,
@@ -387,8 +392,8 @@ pub trait LocalFile: File {
     /// Returns the absolute path of this file
     fn abs_path(&self, cx: &AppContext) -> PathBuf;
 
-    /// Loads the file's contents from disk.
-    fn load(&self, cx: &AppContext) -> Task<Result<String>>;
+    /// Loads the file's contents from disk, along with the [Encoding] they were decoded from.
+    fn load(&self, cx: &AppContext) -> Task<Result<(String, Encoding)>>;
 
     /// Returns true if the file should not be shared with collaborators.
     fn is_private(&self, _: &AppContext) -> bool {
@@ -711,6 +716,21 @@ impl Buffer {
         self.capability == Capability::ReadOnly
     }
 
+    /// Returns the [Encoding] of the file backing this buffer, which is used when saving it.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Assign the encoding to use when saving this buffer.
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Assign the line ending to use when saving this buffer.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.text.set_line_ending(line_ending);
+    }
+
     /// Builds a [Buffer] with the given underlying [TextBuffer], diff base, [File] and [Capability].
     pub fn build(
         buffer: TextBuffer,
@@ -739,6 +759,7 @@ impl Buffer {
             git_diff: git::diff::BufferDiff::new(),
             file,
             capability,
+            encoding: Encoding::default(),
             syntax_map: Mutex::new(SyntaxMap::new()),
             parsing_in_background: false,
             non_text_state_update_count: 0,
@@ -754,6 +775,7 @@ impl Buffer {
             completion_triggers_timestamp: Default::default(),
             deferred_ops: OperationQueue::new(),
             has_conflict: false,
+            detected_indent_size: Default::default(),
         }
     }
 
@@ -774,6 +796,7 @@ impl Buffer {
             diagnostics: self.diagnostics.clone(),
             language: self.language.clone(),
             non_text_state_update_count: self.non_text_state_update_count,
+            detected_indent_size: self.detected_indent_size.clone(),
         }
     }
 
@@ -868,7 +891,7 @@ impl Buffer {
                 return Ok(());
             };
 
-            let new_text = new_text.await?;
+            let (new_text, new_encoding) = new_text.await?;
             let diff = this
                 .update(&mut cx, |this, cx| this.diff(new_text.clone(), cx))?
                 .await;
@@ -878,6 +901,7 @@ impl Buffer {
                     this.apply_diff(diff, cx);
                     tx.send(this.finalize_last_transaction().cloned()).ok();
                     this.has_conflict = false;
+                    this.encoding = new_encoding;
                     this.did_reload(this.version(), this.line_ending(), new_mtime, cx);
                 } else {
                     if !diff.edits.is_empty()
@@ -1494,6 +1518,38 @@ impl Buffer {
             })
     }
 
+    /// Spawns a background task that computes the line-based diff hunks between the buffer's
+    /// text and the contents of its file on disk. If the file has been deleted, every line is
+    /// reported as added.
+    pub fn diff_against_disk(
+        &self,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<git::diff::DiffHunk<u32>>>> {
+        let load = match self.file.as_ref() {
+            Some(file) if file.is_deleted() => None,
+            Some(file) => match file.as_local() {
+                Some(file) => Some(file.load(cx)),
+                None => return Task::ready(Err(anyhow!("buffer is not backed by a local file"))),
+            },
+            None => return Task::ready(Err(anyhow!("buffer has no file"))),
+        };
+        let snapshot = self.text.snapshot();
+        cx.background_executor().spawn(async move {
+            let mut disk_text = match load {
+                Some(load) => load.await?.0,
+                None => String::new(),
+            };
+            LineEnding::normalize(&mut disk_text);
+
+            let mut diff = git::diff::BufferDiff::new();
+            diff.update(&Rope::from(disk_text.as_str()), &snapshot)
+                .await;
+            Ok(diff
+                .hunks_intersecting_range(Anchor::MIN..Anchor::MAX, &snapshot)
+                .collect())
+        })
+    }
+
     /// Spawns a background task that searches the buffer for any whitespace
     /// at the ends of a lines, and returns a `Diff` that removes that whitespace.
     pub fn remove_trailing_whitespace(&self, cx: &AppContext) -> Task<Diff> {
@@ -1515,9 +1571,13 @@ impl Buffer {
     }
 
     /// Ensures that the buffer ends with a single newline character, and
-    /// no other whitespace.
+    /// no other whitespace. Empty buffers are left empty.
     pub fn ensure_final_newline(&mut self, cx: &mut ModelContext<Self>) {
         let len = self.len();
+        if len == 0 {
+            return;
+        }
+
         let mut offset = len;
         for chunk in self.as_rope().reversed_chunks_in_range(0..len) {
             let non_whitespace_len = chunk
@@ -1765,6 +1825,45 @@ impl Buffer {
         self.edit([(0..self.len(), text)], None, cx)
     }
 
+    /// Rebuilds the buffer's undo history from a [`SerializedUndoHistory`], so that the recorded
+    /// transactions can be undone as if they had been performed on this buffer.
+    ///
+    /// The history is only restored if it ends at the buffer's current text, in which case the
+    /// text is left untouched. Returns whether the history was restored.
+    pub fn restore_undo_history(
+        &mut self,
+        history: &SerializedUndoHistory,
+        cx: &mut ModelContext<Self>,
+    ) -> bool {
+        if history.transactions.is_empty()
+            || history.final_text().as_deref() != Some(self.text().as_str())
+        {
+            return false;
+        }
+
+        let was_dirty = self.is_dirty();
+        self.finalize_last_transaction();
+
+        // Rewind to the base text without making that change undoable.
+        self.start_transaction();
+        self.set_text(history.base_text.as_str(), cx);
+        if let Some(transaction_id) = self.end_transaction(cx) {
+            self.forget_transaction(transaction_id);
+        }
+
+        for (range, new_text) in &history.transactions {
+            self.start_transaction();
+            self.edit([(range.clone(), new_text.as_str())], None, cx);
+            self.end_transaction(cx);
+            self.finalize_last_transaction();
+        }
+
+        if !was_dirty {
+            self.did_reload(self.version(), self.line_ending(), self.saved_mtime, cx);
+        }
+        true
+    }
+
     /// Applies the given edits to the buffer. Each edit is specified as a range of text to
     /// delete, and a string of text to insert at that location.
     ///
@@ -1824,12 +1923,16 @@ impl Buffer {
                 .into_iter()
                 .enumerate()
                 .zip(&edit_operation.as_edit().unwrap().new_text)
-                .map(|((ix, (range, _)), new_text)| {
+                .filter_map(|((ix, (range, _)), new_text)| {
                     let new_text_length = new_text.len();
                     let old_start = range.start.to_point(&before_edit);
                     let new_start = (delta + range.start as isize) as usize;
                     delta += new_text_length as isize - (range.end as isize - range.start as isize);
 
+                    if !before_edit.settings_at(range.start, cx).auto_indent {
+                        return None;
+                    }
+
                     let mut range_of_insertion_to_indent = 0..new_text_length;
                     let mut first_line_is_new = false;
                     let mut original_indent_column = None;
@@ -1866,21 +1969,23 @@ impl Buffer {
                         }
                     }
 
-                    AutoindentRequestEntry {
+                    Some(AutoindentRequestEntry {
                         first_line_is_new,
                         original_indent_column,
                         indent_size: before_edit.language_indent_size_at(range.start, cx),
                         range: self.anchor_before(new_start + range_of_insertion_to_indent.start)
                             ..self.anchor_after(new_start + range_of_insertion_to_indent.end),
-                    }
+                    })
                 })
-                .collect();
-
-            self.autoindent_requests.push(Arc::new(AutoindentRequest {
-                before_edit,
-                entries,
-                is_block_mode: matches!(mode, AutoindentMode::Block { .. }),
-            }));
+                .collect::<Vec<_>>();
+
+            if !entries.is_empty() {
+                self.autoindent_requests.push(Arc::new(AutoindentRequest {
+                    before_edit,
+                    entries,
+                    is_block_mode: matches!(mode, AutoindentMode::Block { .. }),
+                }));
+            }
         }
 
         self.end_transaction(cx);
@@ -2319,6 +2424,11 @@ impl BufferSnapshot {
     /// and language preferences.
     pub fn language_indent_size_at<T: ToOffset>(&self, position: T, cx: &AppContext) -> IndentSize {
         let settings = language_settings(self.language_at(position), self.file(), cx);
+        if settings.detect_indentation {
+            if let Some(indent_size) = self.memoized_detect_indent_size() {
+                return indent_size;
+            }
+        }
         if settings.hard_tabs {
             IndentSize::tab()
         } else {
@@ -2326,6 +2436,67 @@ impl BufferSnapshot {
         }
     }
 
+    /// Like [`Self::detect_indent_size`], but only scans the buffer once per version.
+    fn memoized_detect_indent_size(&self) -> Option<IndentSize> {
+        let mut detected_indent_size = self.detected_indent_size.lock();
+        if let Some((version, indent_size)) = detected_indent_size.as_ref() {
+            if version == self.version() {
+                return *indent_size;
+            }
+        }
+        let indent_size = self.detect_indent_size();
+        *detected_indent_size = Some((self.version().clone(), indent_size));
+        indent_size
+    }
+
+    /// Guesses the unit of indentation used by this buffer from the leading whitespace of
+    /// its first lines, returning `None` if none of them are indented.
+    ///
+    /// Tabs win if more lines are indented with tabs than with spaces. Otherwise, the
+    /// unit is the most common increase in indentation between consecutive lines.
+    pub fn detect_indent_size(&self) -> Option<IndentSize> {
+        const MAX_ROWS: u32 = 1000;
+
+        let mut tab_indented_lines = 0;
+        let mut space_indented_lines = 0;
+        let mut space_deltas = BTreeMap::<u32, usize>::new();
+        let mut previous_spaces = 0;
+        for row in 0..=self.max_point().row.min(MAX_ROWS) {
+            if self.is_line_blank(row) {
+                continue;
+            }
+            let indent = self.indent_size_for_line(row);
+            match indent.kind {
+                _ if indent.len == 0 => previous_spaces = 0,
+                IndentKind::Tab => {
+                    tab_indented_lines += 1;
+                    previous_spaces = 0;
+                }
+                IndentKind::Space => {
+                    space_indented_lines += 1;
+                    if indent.len > previous_spaces {
+                        *space_deltas
+                            .entry(indent.len - previous_spaces)
+                            .or_default() += 1;
+                    }
+                    previous_spaces = indent.len;
+                }
+            }
+        }
+
+        if tab_indented_lines > space_indented_lines {
+            Some(IndentSize::tab())
+        } else {
+            space_deltas
+                .into_iter()
+                .filter(|(delta, _)| *delta <= 8)
+                // Prefer the smaller delta on ties, so that 2-space files aren't mistaken for
+                // 4-space files when nesting happens to be deep.
+                .max_by_key(|(delta, count)| (*count, std::cmp::Reverse(*delta)))
+                .map(|(delta, _)| IndentSize::spaces(delta))
+        }
+    }
+
     /// Retrieve the suggested indent size for all of the given rows. The unit of indentation
     /// is passed in as `single_indent_size`.
     pub fn suggested_indents(
@@ -3171,6 +3342,62 @@ impl BufferSnapshot {
         result
     }
 
+    /// Returns the foldable ranges intersecting the given range, based on the multi-line bracket
+    /// pairs in the language's brackets query.
+    ///
+    /// Each range spans from the end of the line containing the opening bracket to the end of
+    /// the line preceding the closing bracket, so that both brackets remain visible when folded.
+    /// When several pairs open on the same line, only the outermost one is returned.
+    pub fn foldable_ranges<T: ToOffset>(&self, range: Range<T>) -> Vec<Range<Point>> {
+        let mut ranges = self
+            .bracket_ranges(range)
+            .filter_map(|(open, close)| {
+                let start_row = self.offset_to_point(open.end).row;
+                let close_row = self.offset_to_point(close.start).row;
+                if close_row < start_row + 2 {
+                    return None;
+                }
+
+                let end_row = close_row - 1;
+                Some(
+                    Point::new(start_row, self.line_len(start_row))
+                        ..Point::new(end_row, self.line_len(end_row)),
+                )
+            })
+            .collect::<Vec<_>>();
+        ranges.sort_unstable_by_key(|range| (range.start, Reverse(range.end)));
+        ranges.dedup_by_key(|range| range.start.row);
+        ranges
+    }
+
+    /// Returns the range of the bracket paired with the bracket adjacent to `offset`, or None if
+    /// there is no bracket adjacent to `offset` or it is unbalanced.
+    ///
+    /// A bracket starting at `offset` takes precedence over one ending at `offset`.
+    pub fn matching_bracket<T: ToOffset>(&self, offset: T) -> Option<Range<usize>> {
+        let offset = offset.to_offset(self);
+
+        let mut result = None;
+        for (open, close) in self.bracket_ranges(offset..offset) {
+            // Tree-sitter inserts zero-width nodes for missing brackets, which have no partner.
+            if open.is_empty() || close.is_empty() {
+                continue;
+            }
+
+            if open.start == offset {
+                return Some(close);
+            } else if close.start == offset {
+                return Some(open);
+            } else if open.end == offset {
+                result = Some(close);
+            } else if close.end == offset {
+                result = Some(open);
+            }
+        }
+
+        result
+    }
+
     /// Returns anchor ranges for any matches of the redaction query.
     /// The buffer can be associated with multiple languages, and the redaction query associated with each
     /// will be run on the relevant section of the buffer.
@@ -3812,6 +4039,7 @@ impl Clone for BufferSnapshot {
             diagnostics: self.diagnostics.clone(),
             language: self.language.clone(),
             non_text_state_update_count: self.non_text_state_update_count,
+            detected_indent_size: self.detected_indent_size.clone(),
         }
     }
 }
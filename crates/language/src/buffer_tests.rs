@@ -15,6 +15,7 @@ use regex::RegexBuilder;
 use settings::SettingsStore;
 use std::{
     env,
+    num::NonZeroU32,
     ops::Range,
     sync::LazyLock,
     time::{Duration, Instant},
@@ -23,7 +24,11 @@ use text::network::Network;
 use text::{BufferId, LineEnding, LineIndent};
 use text::{Point, ToPoint};
 use unindent::Unindent as _;
-use util::{assert_set_eq, post_inc, test::marked_text_ranges, RandomCharIter};
+use util::{
+    assert_set_eq, post_inc,
+    test::{marked_text_offsets, marked_text_ranges},
+    RandomCharIter,
+};
 
 pub static TRAILING_WHITESPACE_REGEX: LazyLock<regex::Regex> = LazyLock::new(|| {
     RegexBuilder::new(r"[ \t]+$")
@@ -344,6 +349,45 @@ fn test_edit_events(cx: &mut gpui::AppContext) {
     );
 }
 
+#[gpui::test]
+fn test_restore_undo_history(cx: &mut gpui::AppContext) {
+    let history = cx
+        .new_model(|cx| {
+            let mut buffer = Buffer::local("one two", cx);
+            buffer.set_group_interval(Duration::from_secs(0));
+            buffer.edit([(3..3, " and a half")], None, cx);
+            buffer.edit([(0..3, "ONE")], None, cx);
+            buffer
+        })
+        .read_with(cx, |buffer, _| {
+            buffer.serialize_undo_history(&buffer.undo_history_versions(10))
+        });
+
+    // The history is replayed onto a buffer whose text matches where it left off.
+    let buffer = cx.new_model(|cx| Buffer::local("ONE and a half two", cx));
+    buffer.update(cx, |buffer, cx| {
+        assert!(buffer.restore_undo_history(&history, cx));
+        assert_eq!(buffer.text(), "ONE and a half two");
+        assert!(!buffer.is_dirty());
+
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "one and a half two");
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "one two");
+        assert_eq!(buffer.undo(cx), None);
+        buffer.redo(cx);
+        assert_eq!(buffer.text(), "one and a half two");
+    });
+
+    // The history is discarded when the text has changed since it was recorded.
+    let buffer = cx.new_model(|cx| Buffer::local("ONE and a half three", cx));
+    buffer.update(cx, |buffer, cx| {
+        assert!(!buffer.restore_undo_history(&history, cx));
+        assert_eq!(buffer.text(), "ONE and a half three");
+        assert_eq!(buffer.undo(cx), None);
+    });
+}
+
 #[gpui::test]
 async fn test_apply_diff(cx: &mut TestAppContext) {
     let text = "a\nbb\nccc\ndddd\neeeee\nffffff\n";
@@ -367,6 +411,28 @@ async fn test_apply_diff(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_ensure_final_newline(cx: &mut AppContext) {
+    for (text, expected) in [
+        ("", ""),
+        ("one", "one\n"),
+        ("one\n", "one\n"),
+        ("one\n\n\n", "one\n"),
+        ("one\ntwo  \n \t\n", "one\ntwo\n"),
+        ("  \n\n", "\n"),
+    ] {
+        let buffer = cx.new_model(|cx| Buffer::local(text, cx));
+        buffer.update(cx, |buffer, cx| {
+            let version = buffer.version();
+            buffer.ensure_final_newline(cx);
+            assert_eq!(buffer.text(), expected, "for {text:?}");
+            if text == expected {
+                assert_eq!(buffer.version(), version, "{text:?} should not be edited");
+            }
+        });
+    }
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_normalize_whitespace(cx: &mut gpui::TestAppContext) {
     let text = [
@@ -1056,6 +1122,82 @@ fn test_enclosing_bracket_ranges_where_brackets_are_not_outermost_children(cx: &
     );
 }
 
+#[gpui::test]
+fn test_foldable_ranges(cx: &mut AppContext) {
+    let text = r#"
+        fn a() {
+            let x = 1;
+            if x > 0 {
+                b(|| {
+                    c();
+                });
+            }
+        }
+        struct S { f: u8 }
+        impl S {
+        }
+    "#
+    .unindent();
+    let buffer =
+        cx.new_model(|cx| Buffer::local(text.clone(), cx).with_language(Arc::new(rust_lang()), cx));
+    let snapshot = buffer.read(cx).snapshot();
+
+    assert_eq!(
+        snapshot.foldable_ranges(0..text.len()),
+        &[
+            Point::new(0, 8)..Point::new(6, 5),
+            Point::new(2, 14)..Point::new(5, 11),
+            Point::new(3, 14)..Point::new(4, 16),
+        ]
+    );
+
+    // Pairs without any lines between the brackets aren't foldable.
+    assert_eq!(
+        snapshot.foldable_ranges(Point::new(9, 0)..Point::new(10, 1)),
+        &[] as &[Range<Point>]
+    );
+}
+
+#[gpui::test]
+fn test_matching_bracket(cx: &mut AppContext) {
+    let language = Arc::new(
+        rust_lang()
+            .with_brackets_query(
+                r#"
+                ("(" @open ")" @close)
+                ("[" @open "]" @close)
+                ("{" @open "}" @close)
+                "#,
+            )
+            .unwrap(),
+    );
+    let matching_bracket = |text: &str, cx: &mut AppContext| {
+        let (text, offsets) = marked_text_offsets(text);
+        let buffer = cx.new_model(|cx| Buffer::local(text, cx).with_language(language.clone(), cx));
+        let snapshot = buffer.read(cx).snapshot();
+        snapshot.matching_bracket(offsets[0])
+    };
+
+    // Nested pairs, with the cursor before or after either bracket.
+    assert_eq!(matching_bracket("fn a() ˇ{ b([c]) }", cx), Some(16..17));
+    assert_eq!(matching_bracket("fn a() { b(ˇ[c]) }", cx), Some(13..14));
+    assert_eq!(matching_bracket("fn a() { b([c]ˇ) }", cx), Some(10..11));
+    assert_eq!(matching_bracket("fn a() { b([c])ˇ }", cx), Some(10..11));
+    assert_eq!(matching_bracket("fn a() { b([c]) }ˇ", cx), Some(7..8));
+
+    // A bracket starting at the cursor wins over one ending there.
+    assert_eq!(matching_bracket("fn a() { b(c)ˇ(d) }", cx), Some(15..16));
+
+    // Brackets are only matched when adjacent to the cursor.
+    assert_eq!(matching_bracket("fn a() { bˇ(c) }", cx), Some(12..13));
+    assert_eq!(matching_bracket("fn a() { b(ˇc) }", cx), Some(12..13));
+    assert_eq!(matching_bracket("fn a() { ˇb(c) }", cx), None);
+
+    // Mismatched and unbalanced brackets.
+    assert_eq!(matching_bracket("fn a() { b(ˇc]; }", cx), None);
+    assert_eq!(matching_bracket("fn a() { b(ˇc; }", cx), None);
+}
+
 #[gpui::test]
 fn test_range_for_syntax_ancestor(cx: &mut AppContext) {
     cx.new_model(|cx| {
@@ -1134,6 +1276,70 @@ fn test_autoindent_with_soft_tabs(cx: &mut AppContext) {
     });
 }
 
+#[gpui::test]
+fn test_detect_indent_size(cx: &mut AppContext) {
+    let detect = |text: &str, cx: &mut AppContext| {
+        cx.new_model(|cx| Buffer::local(text, cx))
+            .read(cx)
+            .snapshot()
+            .detect_indent_size()
+    };
+
+    let two_spaces = r#"
+        fn main() {
+          let x = 1;
+          if x > 0 {
+            println!("{x}");
+
+            for i in 0..x {
+              dbg!(i);
+            }
+          }
+        }
+    "#
+    .unindent();
+    assert_eq!(detect(&two_spaces, cx), Some(IndentSize::spaces(2)));
+
+    let four_spaces =
+        "class A:\n    def f(self):\n        pass\n\n    def g(self):\n        pass\n";
+    assert_eq!(detect(four_spaces, cx), Some(IndentSize::spaces(4)));
+
+    let tabs = "int main() {\n\tif (x) {\n\t\treturn 1;\n\t}\n  // aligned comment\n}\n";
+    assert_eq!(detect(tabs, cx), Some(IndentSize::tab()));
+
+    assert_eq!(detect("no\nindentation\nhere\n", cx), None);
+}
+
+#[gpui::test]
+fn test_language_indent_size_with_detect_indentation(cx: &mut AppContext) {
+    init_settings(cx, |settings| {
+        settings.defaults.tab_size = NonZeroU32::new(4);
+        settings.defaults.detect_indentation = Some(true);
+    });
+
+    let buffer = cx.new_model(|cx| Buffer::local("a {\n\tb\n}\n", cx));
+    assert_eq!(
+        buffer.read(cx).snapshot().language_indent_size_at(0, cx),
+        IndentSize::tab()
+    );
+
+    // The detected indentation is remembered, but follows edits.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..buffer.len(), "a {\n  b\n}\n")], None, cx)
+    });
+    assert_eq!(
+        buffer.read(cx).snapshot().language_indent_size_at(0, cx),
+        IndentSize::spaces(2)
+    );
+
+    // Fall back to the configured indentation when there is nothing to detect.
+    let buffer = cx.new_model(|cx| Buffer::local("a\nb\n", cx));
+    assert_eq!(
+        buffer.read(cx).snapshot().language_indent_size_at(0, cx),
+        IndentSize::spaces(4)
+    );
+}
+
 #[gpui::test]
 fn test_autoindent_with_hard_tabs(cx: &mut AppContext) {
     init_settings(cx, |settings| {
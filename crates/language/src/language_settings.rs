@@ -1,6 +1,6 @@
 //! Provides `language`-related settings.
 
-use crate::{File, Language, LanguageName, LanguageServerName};
+use crate::{File, Language, LanguageName, LanguageServerName, LineEnding};
 use anyhow::Result;
 use collections::{HashMap, HashSet};
 use core::slice;
@@ -65,6 +65,9 @@ pub struct LanguageSettings {
     /// Whether to indent lines using tab characters, as opposed to multiple
     /// spaces.
     pub hard_tabs: bool,
+    /// Whether to infer the indentation of a buffer from its contents, taking
+    /// precedence over `hard_tabs` and `tab_size` when inserting indentation.
+    pub detect_indentation: bool,
     /// How to soft-wrap long lines of text.
     pub soft_wrap: SoftWrap,
     /// The column at which to soft-wrap lines, for buffers where soft-wrap
@@ -87,8 +90,13 @@ pub struct LanguageSettings {
     /// Whether or not to ensure there's a single newline at the end of a buffer
     /// when saving it.
     pub ensure_final_newline_on_save: bool,
+    /// Which line ending to use when saving a buffer.
+    pub line_ending: LineEndingSetting,
     /// How to perform a buffer format.
     pub formatter: SelectedFormatter,
+    /// How long an external formatter may run before it's stopped and the buffer is
+    /// left unformatted.
+    pub external_formatter_timeout_ms: u64,
     /// Zed's Prettier integration settings.
     pub prettier: PrettierSettings,
     /// Whether to use language servers to provide code intelligence.
@@ -107,6 +115,9 @@ pub struct LanguageSettings {
     pub show_whitespaces: ShowWhitespaceSetting,
     /// Whether to start a new line with a comment when a previous line is a comment as well.
     pub extend_comment_on_newline: bool,
+    /// Whether to adjust indentation based on the language's syntax as you type, such as
+    /// indenting after an opening bracket and outdenting a closing one.
+    pub auto_indent: bool,
     /// Inlay hint related settings.
     pub inlay_hints: InlayHintSettings,
     /// Whether to automatically close brackets.
@@ -225,6 +236,12 @@ pub struct LanguageSettingsContent {
     /// Default: false
     #[serde(default)]
     pub hard_tabs: Option<bool>,
+    /// Whether to infer the indentation of a buffer from its contents, taking
+    /// precedence over `hard_tabs` and `tab_size` when inserting indentation.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub detect_indentation: Option<bool>,
     /// How to soft-wrap long lines of text.
     ///
     /// Default: none
@@ -269,11 +286,22 @@ pub struct LanguageSettingsContent {
     /// Default: true
     #[serde(default)]
     pub ensure_final_newline_on_save: Option<bool>,
+    /// Which line ending to use when saving a buffer.
+    ///
+    /// Default: detect
+    #[serde(default)]
+    pub line_ending: Option<LineEndingSetting>,
     /// How to perform a buffer format.
     ///
     /// Default: auto
     #[serde(default)]
     pub formatter: Option<SelectedFormatter>,
+    /// How long an external formatter may run, in milliseconds, before it's stopped
+    /// and the buffer is left unformatted.
+    ///
+    /// Default: 10000
+    #[serde(default)]
+    pub external_formatter_timeout_ms: Option<u64>,
     /// Zed's Prettier integration settings.
     /// Allows to enable/disable formatting with Prettier
     /// and configure default Prettier, used when no project-level Prettier installation is found.
@@ -310,6 +338,13 @@ pub struct LanguageSettingsContent {
     /// Default: true
     #[serde(default)]
     pub extend_comment_on_newline: Option<bool>,
+    /// Whether to adjust indentation based on the language's syntax as you type, such as
+    /// indenting after an opening bracket and outdenting a closing one. When disabled,
+    /// new lines keep the indentation of the line before them.
+    ///
+    /// Default: true
+    #[serde(default)]
+    pub auto_indent: Option<bool>,
     /// Inlay hint related settings.
     #[serde(default)]
     pub inlay_hints: Option<InlayHintSettings>,
@@ -381,6 +416,30 @@ pub enum SoftWrap {
     Bounded,
 }
 
+/// Controls which line ending is used when saving a buffer.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEndingSetting {
+    /// Keep the line ending detected when the file was opened.
+    #[default]
+    Detect,
+    /// Always save with `\n` line endings.
+    Lf,
+    /// Always save with `\r\n` line endings.
+    Crlf,
+}
+
+impl LineEndingSetting {
+    /// Returns the line ending to save with, given the one detected in the file.
+    pub fn resolve(&self, detected: LineEnding) -> LineEnding {
+        match self {
+            LineEndingSetting::Detect => detected,
+            LineEndingSetting::Lf => LineEnding::Unix,
+            LineEndingSetting::Crlf => LineEnding::Windows,
+        }
+    }
+}
+
 /// Controls the behavior of formatting files when they are saved.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FormatOnSave {
@@ -512,6 +571,8 @@ pub enum ShowWhitespaceSetting {
     /// - It is adjacent to an edge (start or end)
     /// - It is adjacent to a whitespace (left or right)
     Boundary,
+    /// Draw only whitespace at the end of a line, along with any in the selected text.
+    Trailing,
 }
 
 /// Controls which formatter should be used when formatting code.
@@ -1028,6 +1089,7 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
 
     merge(&mut settings.tab_size, src.tab_size);
     merge(&mut settings.hard_tabs, src.hard_tabs);
+    merge(&mut settings.detect_indentation, src.detect_indentation);
     merge(&mut settings.soft_wrap, src.soft_wrap);
     merge(&mut settings.use_autoclose, src.use_autoclose);
     merge(&mut settings.use_auto_surround, src.use_auto_surround);
@@ -1050,6 +1112,10 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         src.preferred_line_length,
     );
     merge(&mut settings.formatter, src.formatter.clone());
+    merge(
+        &mut settings.external_formatter_timeout_ms,
+        src.external_formatter_timeout_ms,
+    );
     merge(&mut settings.prettier, src.prettier.clone());
     merge(&mut settings.format_on_save, src.format_on_save.clone());
     merge(
@@ -1060,6 +1126,7 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         &mut settings.ensure_final_newline_on_save,
         src.ensure_final_newline_on_save,
     );
+    merge(&mut settings.line_ending, src.line_ending);
     merge(
         &mut settings.enable_language_server,
         src.enable_language_server,
@@ -1074,6 +1141,7 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         &mut settings.extend_comment_on_newline,
         src.extend_comment_on_newline,
     );
+    merge(&mut settings.auto_indent, src.auto_indent);
     merge(&mut settings.inlay_hints, src.inlay_hints);
 }
 
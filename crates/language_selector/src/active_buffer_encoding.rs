@@ -0,0 +1,62 @@
+use editor::Editor;
+use gpui::{div, IntoElement, ParentElement, Render, Subscription, View, ViewContext};
+use language::Encoding;
+use ui::{FluentBuilder, Label, LabelCommon, LabelSize};
+use workspace::{item::ItemHandle, StatusItemView};
+
+/// Shows the encoding of the active buffer in the status bar when it isn't plain UTF-8.
+pub struct ActiveBufferEncoding {
+    active_encoding: Option<Encoding>,
+    _observe_active_editor: Option<Subscription>,
+}
+
+impl ActiveBufferEncoding {
+    pub fn new() -> Self {
+        Self {
+            active_encoding: None,
+            _observe_active_editor: None,
+        }
+    }
+
+    fn update_encoding(&mut self, editor: View<Editor>, cx: &mut ViewContext<Self>) {
+        self.active_encoding = editor
+            .read(cx)
+            .active_excerpt(cx)
+            .map(|(_, buffer, _)| buffer.read(cx).encoding())
+            .filter(|encoding| *encoding != Encoding::Utf8);
+
+        cx.notify();
+    }
+}
+
+impl Default for ActiveBufferEncoding {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Render for ActiveBufferEncoding {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        div().when_some(self.active_encoding, |el, encoding| {
+            el.child(Label::new(encoding.name()).size(LabelSize::Small))
+        })
+    }
+}
+
+impl StatusItemView for ActiveBufferEncoding {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Some(editor) = active_pane_item.and_then(|item| item.act_as::<Editor>(cx)) {
+            self._observe_active_editor = Some(cx.observe(&editor, Self::update_encoding));
+            self.update_encoding(editor, cx);
+        } else {
+            self.active_encoding = None;
+            self._observe_active_editor = None;
+        }
+
+        cx.notify();
+    }
+}
@@ -1,5 +1,7 @@
+mod active_buffer_encoding;
 mod active_buffer_language;
 
+pub use active_buffer_encoding::ActiveBufferEncoding;
 pub use active_buffer_language::ActiveBufferLanguage;
 use anyhow::anyhow;
 use editor::Editor;
@@ -1456,6 +1456,61 @@ mod tests {
         fake.receive_notification::<notification::Exit>().await;
     }
 
+    #[gpui::test]
+    async fn test_fake_hover(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            release_channel::init(SemanticVersion::default(), cx);
+        });
+        let (server, mut fake) = FakeLanguageServer::new(
+            LanguageServerId(0),
+            LanguageServerBinary {
+                path: "path/to/language-server".into(),
+                arguments: vec![],
+                env: None,
+            },
+            "the-lsp".to_string(),
+            ServerCapabilities {
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            cx.to_async(),
+        );
+
+        // The capabilities the server responds to `initialize` with are stored on the client.
+        let server = cx.update(|cx| server.initialize(None, cx)).await.unwrap();
+        assert_eq!(
+            server.capabilities().hover_provider,
+            Some(HoverProviderCapability::Simple(true))
+        );
+
+        fake.handle_request::<request::HoverRequest, _, _>(|params, _| async move {
+            assert_eq!(
+                params.text_document_position_params.position,
+                Position::new(1, 2)
+            );
+            Ok(Some(Hover {
+                contents: HoverContents::Scalar(MarkedString::String("fn main()".to_string())),
+                range: None,
+            }))
+        });
+        let hover = server
+            .request::<request::HoverRequest>(HoverParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(Url::from_str("file://a/b").unwrap()),
+                    Position::new(1, 2),
+                ),
+                work_done_progress_params: Default::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            hover.map(|hover| hover.contents),
+            Some(HoverContents::Scalar(MarkedString::String(
+                "fn main()".to_string()
+            )))
+        );
+    }
+
     #[gpui::test]
     fn test_deserialize_string_digit_id() {
         let json = r#"{"jsonrpc":"2.0","id":"2","method":"workspace/configuration","params":{"items":[{"scopeUri":"file:///Users/mph/Devel/personal/hello-scala/","section":"metals"}]}}"#;
@@ -8,12 +8,16 @@ use client::Client;
 use collections::{hash_map, HashMap, HashSet};
 use fs::Fs;
 use futures::{channel::oneshot, stream::FuturesUnordered, StreamExt};
-use git::blame::Blame;
+use git::{
+    blame::{Blame, BlameEntry},
+    log::LogEntry,
+};
 use gpui::{
     AppContext, AsyncAppContext, Context as _, EventEmitter, Model, ModelContext, Task, WeakModel,
 };
 use http_client::Url;
 use language::{
+    language_settings::language_settings,
     proto::{deserialize_line_ending, deserialize_version, serialize_version, split_operations},
     Buffer, Capability, Event as BufferEvent, File as _, Language, Operation,
 };
@@ -48,6 +52,7 @@ pub struct BufferStore {
     remote_buffer_listeners:
         HashMap<BufferId, Vec<oneshot::Sender<Result<Model<Buffer>, anyhow::Error>>>>,
     shared_buffers: HashMap<proto::PeerId, HashSet<BufferId>>,
+    blames_by_buffer_id: HashMap<BufferId, (clock::Global, Arc<Blame>)>,
 }
 
 enum OpenBuffer {
@@ -109,6 +114,7 @@ impl BufferStore {
             local_buffer_ids_by_entry_id: Default::default(),
             loading_buffers_by_path: Default::default(),
             shared_buffers: Default::default(),
+            blames_by_buffer_id: Default::default(),
         }
     }
 
@@ -245,6 +251,12 @@ impl BufferStore {
             })
             .collect::<Vec<_>>();
 
+        // Blames are stale once their repository changes, for example when HEAD moves.
+        for (buffer, _) in &current_buffers {
+            self.blames_by_buffer_id
+                .remove(&buffer.read(cx).remote_id());
+        }
+
         if future_buffers.len() + current_buffers.len() == 0 {
             return;
         }
@@ -320,17 +332,24 @@ impl BufferStore {
             let buffer_id = BufferId::from(reservation.entity_id().as_non_zero_u64());
             cx.spawn(move |_, mut cx| async move {
                 let loaded = load_file.await?;
+                let capability = if loaded.is_read_only {
+                    log::warn!(
+                        "{:?} is not writable, opening it read-only",
+                        loaded.file.path
+                    );
+                    Capability::ReadOnly
+                } else {
+                    Capability::ReadWrite
+                };
                 let text_buffer = cx
                     .background_executor()
                     .spawn(async move { text::Buffer::new(0, buffer_id, loaded.text) })
                     .await;
                 cx.insert_model(reservation, |_| {
-                    Buffer::build(
-                        text_buffer,
-                        loaded.diff_base,
-                        Some(loaded.file),
-                        Capability::ReadWrite,
-                    )
+                    let mut buffer =
+                        Buffer::build(text_buffer, loaded.diff_base, Some(loaded.file), capability);
+                    buffer.set_encoding(loaded.encoding);
+                    buffer
                 })
             })
         });
@@ -482,9 +501,16 @@ impl BufferStore {
         mut has_changed_file: bool,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<()>> {
+        let line_ending = buffer_handle.update(cx, |buffer, cx| {
+            let line_ending = language_settings(buffer.language(), buffer.file(), cx)
+                .line_ending
+                .resolve(buffer.line_ending());
+            buffer.set_line_ending(line_ending);
+            line_ending
+        });
         let buffer = buffer_handle.read(cx);
         let text = buffer.as_rope().clone();
-        let line_ending = buffer.line_ending();
+        let encoding = buffer.encoding();
         let version = buffer.version();
         let buffer_id = buffer.remote_id();
         if buffer.file().is_some_and(|file| !file.is_created()) {
@@ -492,7 +518,7 @@ impl BufferStore {
         }
 
         let save = worktree.update(cx, |worktree, cx| {
-            worktree.write_file(path.as_ref(), text, line_ending, cx)
+            worktree.write_file(path.as_ref(), text, line_ending, encoding, cx)
         });
 
         cx.spawn(move |this, mut cx| async move {
@@ -620,6 +646,81 @@ impl BufferStore {
         }
     }
 
+    /// Returns the commits that touched the buffer's file, newest first, following renames.
+    /// Files outside of git repositories have no history.
+    pub fn file_history(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<LogEntry>>> {
+        let buffer = buffer.read(cx);
+        let Some(file) = File::from_dyn(buffer.file()) else {
+            return Task::ready(Err(anyhow!("buffer has no file")));
+        };
+
+        match file.worktree.read(cx) {
+            Worktree::Local(worktree) => {
+                let worktree = worktree.snapshot();
+                let Some((repo_entry, local_repo_entry)) = worktree.repo_for_path(&file.path)
+                else {
+                    return Task::ready(Ok(Vec::new()));
+                };
+                let relative_path = match repo_entry.relativize(&worktree, &file.path) {
+                    Ok(relative_path) => relative_path,
+                    Err(error) => return Task::ready(Err(error)),
+                };
+                let repo = local_repo_entry.repo().clone();
+
+                cx.background_executor().spawn(async move {
+                    repo.file_history(&relative_path)
+                        .with_context(|| format!("Failed to get history of {:?}", relative_path.0))
+                })
+            }
+            Worktree::Remote(_) => Task::ready(Err(anyhow!(
+                "file history is not supported for remote projects"
+            ))),
+        }
+    }
+
+    /// Returns the blame entry for the given row of the buffer, or `None` if the buffer isn't
+    /// in a git repository or the row hasn't been committed yet. The blame of a local buffer is
+    /// cached until the buffer is edited or its repository changes.
+    pub fn blame_buffer_row(
+        &mut self,
+        buffer: &Model<Buffer>,
+        row: u32,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<BlameEntry>>> {
+        let buffer_id = buffer.read(cx).remote_id();
+        let version = buffer.read(cx).version();
+        // Only local worktrees report repository changes, so remote blames aren't cached.
+        let is_local = File::from_dyn(buffer.read(cx).file())
+            .map_or(false, |file| file.worktree.read(cx).is_local());
+        if let Some((blame_version, blame)) = self.blames_by_buffer_id.get(&buffer_id) {
+            if *blame_version == version {
+                return Task::ready(Ok(blame.entry_for_row(row).cloned()));
+            }
+        }
+
+        let blame = self.blame_buffer(buffer, None, cx);
+        cx.spawn(|this, mut cx| async move {
+            let blame = match blame.await {
+                Ok(blame) => Arc::new(blame),
+                Err(error) if error.downcast_ref::<NoRepositoryError>().is_some() => {
+                    return Ok(None);
+                }
+                Err(error) => return Err(error),
+            };
+            let entry = blame.entry_for_row(row).cloned();
+            if is_local {
+                this.update(&mut cx, |this, _| {
+                    this.blames_by_buffer_id.insert(buffer_id, (version, blame));
+                })?;
+            }
+            Ok(entry)
+        })
+    }
+
     fn add_buffer(&mut self, buffer: Model<Buffer>, cx: &mut ModelContext<Self>) -> Result<()> {
         let remote_id = buffer.read(cx).remote_id();
         let is_remote = buffer.read(cx).replica_id() != 0;
@@ -633,7 +734,8 @@ impl BufferStore {
         buffer.update(cx, move |_, cx| {
             cx.on_release(move |buffer, cx| {
                 handle
-                    .update(cx, |_, cx| {
+                    .update(cx, |this, cx| {
+                        this.blames_by_buffer_id.remove(&buffer.remote_id());
                         cx.emit(BufferStoreEvent::BufferDropped(buffer.remote_id()))
                     })
                     .ok();
@@ -35,7 +35,12 @@ use futures::{
     AsyncWriteExt, FutureExt, StreamExt,
 };
 
-use git::{blame::Blame, repository::GitRepository};
+use git::{
+    blame::{Blame, BlameEntry},
+    log::LogEntry,
+    repository::{GitRepository, RepoPath},
+    Oid,
+};
 use gpui::{
     AnyModel, AppContext, AsyncAppContext, BorrowAppContext, Context, EventEmitter, Model,
     ModelContext, SharedString, Task, WeakModel, WindowContext,
@@ -1855,6 +1860,106 @@ impl Project {
         })
     }
 
+    /// Returns the local git repository containing `path`, and the path relative to it.
+    fn local_repo_for_path(
+        &self,
+        path: &ProjectPath,
+        cx: &AppContext,
+    ) -> Result<(Arc<dyn GitRepository>, RepoPath)> {
+        let worktree = self
+            .worktree_for_id(path.worktree_id, cx)
+            .context("no such worktree")?;
+        let worktree = worktree
+            .read(cx)
+            .as_local()
+            .context("git history is only supported for local projects")?
+            .snapshot();
+        let (repo_entry, local_repo_entry) = worktree
+            .repo_for_path(&path.path)
+            .with_context(|| format!("{:?} is not in a git repository", path.path))?;
+        let relative_path = repo_entry
+            .relativize(&worktree, &path.path)
+            .context("failed to relativize path")?;
+        Ok((local_repo_entry.repo().clone(), relative_path))
+    }
+
+    /// Opens a read-only buffer with the contents of the file at `path` as of the given git revision.
+    pub fn open_buffer_at_revision(
+        &mut self,
+        path: ProjectPath,
+        revision: String,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Buffer>>> {
+        let repo_and_path = self.local_repo_for_path(&path, cx);
+        let languages = self.languages.clone();
+
+        cx.spawn(|project, mut cx| async move {
+            let (repo, relative_path) = repo_and_path?;
+            let text = cx
+                .background_executor()
+                .spawn({
+                    let revision = revision.clone();
+                    async move { repo.load_revision_text(&revision, &relative_path) }
+                })
+                .await?;
+            let language = languages.language_for_file_path(&path.path).await.ok();
+
+            project.update(&mut cx, |project, cx| {
+                let buffer = project.create_local_buffer(&text, language, cx);
+                buffer.update(cx, |buffer, cx| {
+                    buffer.set_capability(Capability::ReadOnly, cx)
+                });
+                buffer
+            })
+        })
+    }
+
+    /// Opens a read-only buffer with the contents of the file at `path` as of commit `sha`,
+    /// diffed against its contents in the commit's parent.
+    ///
+    /// `path_at_commit` and `path_at_parent` are the repository-relative paths of the file
+    /// in the commit and its parent, which differ from the current one if the file was renamed.
+    /// A file that doesn't exist in the parent is diffed against an empty base.
+    pub fn open_commit_diff(
+        &mut self,
+        path: ProjectPath,
+        sha: Oid,
+        path_at_commit: PathBuf,
+        path_at_parent: PathBuf,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Buffer>>> {
+        let repo = self.local_repo_for_path(&path, cx).map(|(repo, _)| repo);
+        let languages = self.languages.clone();
+
+        cx.spawn(|project, mut cx| async move {
+            let repo = repo?;
+            let (text, parent_text) = cx
+                .background_executor()
+                .spawn(async move {
+                    let text = repo.load_revision_text(&sha.to_string(), &path_at_commit)?;
+                    let parent_text = repo
+                        .load_revision_text(&format!("{sha}^"), &path_at_parent)
+                        .unwrap_or_default();
+                    anyhow::Ok((text, parent_text))
+                })
+                .await?;
+            let language = languages.language_for_file_path(&path.path).await.ok();
+
+            let buffer = project.update(&mut cx, |project, cx| {
+                project.create_local_buffer(&text, language, cx)
+            })?;
+            let diff = buffer.update(&mut cx, |buffer, cx| {
+                buffer.set_capability(Capability::ReadOnly, cx);
+                buffer.set_diff_base(Some(parent_text), cx);
+                buffer.git_diff_recalc(cx)
+            })?;
+            if let Some(diff) = diff {
+                diff.await;
+            }
+            Ok(buffer)
+        })
+    }
+
     pub fn open_path(
         &mut self,
         path: ProjectPath,
@@ -3075,13 +3180,21 @@ impl Project {
                 .flatten(),
             Formatter::External { command, arguments } => {
                 let buffer_abs_path = buffer_abs_path.as_ref().map(|path| path.as_path());
-                Self::format_via_external_command(buffer, buffer_abs_path, command, arguments, cx)
-                    .await
-                    .context(format!(
-                        "failed to format via external command {:?}",
-                        command
-                    ))?
-                    .map(FormatOperation::External)
+                let timeout = Duration::from_millis(settings.external_formatter_timeout_ms);
+                Self::format_via_external_command(
+                    buffer,
+                    buffer_abs_path,
+                    command,
+                    arguments,
+                    timeout,
+                    cx,
+                )
+                .await
+                .context(format!(
+                    "failed to format via external command {:?}",
+                    command
+                ))?
+                .map(FormatOperation::External)
             }
             Formatter::CodeActions(code_actions) => {
                 let code_actions = deserialize_code_actions(code_actions);
@@ -3109,6 +3222,7 @@ impl Project {
         buffer_abs_path: Option<&Path>,
         command: &str,
         arguments: &[String],
+        timeout: Duration,
         cx: &mut AsyncAppContext,
     ) -> Result<Option<Diff>> {
         let working_dir_path = buffer.update(cx, |buffer, cx| {
@@ -3143,6 +3257,7 @@ impl Project {
             .stdin(smol::process::Stdio::piped())
             .stdout(smol::process::Stdio::piped())
             .stderr(smol::process::Stdio::piped())
+            .kill_on_drop(true)
             .spawn()?;
 
         let stdin = child
@@ -3155,7 +3270,13 @@ impl Project {
         }
         stdin.flush().await?;
 
-        let output = child.output().await?;
+        let mut timer = cx.background_executor().timer(timeout).fuse();
+        let output = futures::select! {
+            output = child.output().fuse() => output?,
+            _ = timer => {
+                return Err(anyhow!("command timed out after {:?}", timeout));
+            }
+        };
         if !output.status.success() {
             return Err(anyhow!(
                 "command failed with exit code {:?}:\nstdout: {}\nstderr: {}",
@@ -3791,11 +3912,19 @@ impl Project {
 
             if expanded.is_absolute() {
                 let fs = self.fs.clone();
-                cx.background_executor().spawn(async move {
-                    let path = expanded.as_path();
-                    let exists = fs.is_file(path).await;
+                let buffer = buffer.clone();
+                cx.spawn(|this, mut cx| async move {
+                    if fs.is_file(&expanded).await {
+                        return Some(ResolvedPath::AbsPath(expanded));
+                    }
 
-                    exists.then(|| ResolvedPath::AbsPath(expanded))
+                    // Paths like `/src/main.rs` are often meant relative to a worktree root.
+                    let root_relative = expanded.strip_prefix("/").ok()?.to_path_buf();
+                    this.update(&mut cx, |this, cx| {
+                        this.resolve_path_in_worktrees(root_relative, &buffer, cx)
+                    })
+                    .ok()?
+                    .await
                 })
             } else {
                 self.resolve_path_in_worktrees(expanded, buffer, cx)
@@ -3810,25 +3939,61 @@ impl Project {
         }
     }
 
+    /// Resolves a relative path against the directory of the given buffer's file and then
+    /// against the root of each worktree.
+    ///
+    /// Paths starting with `./` or `../` are only resolved against the buffer's directory.
+    /// If no file exists at the literal path, extensionless paths are retried with the
+    /// buffer's own extension and then with [`COMMON_FILE_EXTENSIONS`].
     fn resolve_path_in_worktrees(
         &self,
         path: PathBuf,
         buffer: &Model<Buffer>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Option<ResolvedPath>> {
-        let mut candidates = vec![path.clone()];
+        let is_relative_to_buffer = matches!(
+            path.components().next(),
+            Some(Component::CurDir | Component::ParentDir)
+        );
+        let buffer_path = buffer.read(cx).file().map(|file| file.path().clone());
 
-        if let Some(file) = buffer.read(cx).file() {
-            if let Some(dir) = file.path().parent() {
-                let joined = dir.to_path_buf().join(path);
-                candidates.push(joined);
+        let mut candidates = Vec::new();
+        if let Some(dir) = buffer_path.as_ref().and_then(|path| path.parent()) {
+            candidates.push(dir.join(&path));
+        }
+        if !is_relative_to_buffer || candidates.is_empty() {
+            candidates.push(path.clone());
+        }
+
+        let is_path_like = is_relative_to_buffer || path.components().count() > 1;
+        if is_path_like && path.extension().is_none() {
+            let buffer_extension = buffer_path
+                .as_ref()
+                .and_then(|path| path.extension())
+                .and_then(|extension| extension.to_str());
+            let extensions = buffer_extension
+                .into_iter()
+                .chain(
+                    COMMON_FILE_EXTENSIONS
+                        .iter()
+                        .copied()
+                        .filter(|extension| Some(*extension) != buffer_extension),
+                )
+                .collect::<Vec<_>>();
+            let literal_candidates = candidates.clone();
+            for extension in extensions {
+                candidates.extend(
+                    literal_candidates
+                        .iter()
+                        .map(|candidate| candidate.with_extension(extension)),
+                );
             }
         }
 
         let worktrees = self.worktrees(cx).collect::<Vec<_>>();
         cx.spawn(|_, mut cx| async move {
-            for worktree in worktrees {
-                for candidate in candidates.iter() {
+            for candidate in candidates.iter() {
+                for worktree in worktrees.iter() {
                     let path = worktree
                         .update(&mut cx, |worktree, _| {
                             let root_entry_path = &worktree.root_entry()?.path;
@@ -3838,12 +4003,15 @@ impl Project {
                             let stripped =
                                 resolved.strip_prefix(root_entry_path).unwrap_or(&resolved);
 
-                            worktree.entry_for_path(stripped).map(|entry| {
-                                ResolvedPath::ProjectPath(ProjectPath {
-                                    worktree_id: worktree.id(),
-                                    path: entry.path.clone(),
+                            worktree
+                                .entry_for_path(stripped)
+                                .filter(|entry| entry.is_file())
+                                .map(|entry| {
+                                    ResolvedPath::ProjectPath(ProjectPath {
+                                        worktree_id: worktree.id(),
+                                        path: entry.path.clone(),
+                                    })
                                 })
-                            })
                         })
                         .ok()?;
 
@@ -4131,6 +4299,24 @@ impl Project {
         self.buffer_store.read(cx).blame_buffer(buffer, version, cx)
     }
 
+    pub fn blame_buffer_row(
+        &self,
+        buffer: &Model<Buffer>,
+        row: u32,
+        cx: &mut AppContext,
+    ) -> Task<Result<Option<BlameEntry>>> {
+        self.buffer_store
+            .update(cx, |store, cx| store.blame_buffer_row(buffer, row, cx))
+    }
+
+    pub fn file_history(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<LogEntry>>> {
+        self.buffer_store.read(cx).file_history(buffer, cx)
+    }
+
     // RPC message handlers
 
     async fn handle_unshare_project(
@@ -5294,6 +5480,11 @@ pub fn relativize_path(base: &Path, path: &Path) -> PathBuf {
     components.iter().map(|c| c.as_os_str()).collect()
 }
 
+/// Extensions tried, in order, when resolving an extensionless path that doesn't exist.
+const COMMON_FILE_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "py", "go", "c", "h", "cpp", "md", "json", "toml",
+];
+
 fn resolve_path(base: &Path, path: &Path) -> PathBuf {
     let mut result = base.to_path_buf();
     for component in path.components() {
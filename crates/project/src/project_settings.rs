@@ -43,6 +43,14 @@ pub struct ProjectSettings {
     /// Configuration for session-related features
     #[serde(default)]
     pub session: SessionSettings,
+
+    /// The maximum number of background workers used for scanning and searching
+    /// projects, for resource-constrained machines. Other background tasks still
+    /// run on all of the executor's threads. Read at startup, and clamped between
+    /// one and the available parallelism.
+    /// Default: null (use all available CPUs)
+    #[serde(default)]
+    pub max_background_threads: Option<usize>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
@@ -1,11 +1,15 @@
 use crate::{Event, *};
 use fs::FakeFs;
 use futures::{future, StreamExt};
+use git::{diff::DiffHunkStatus, Oid};
 use gpui::{AppContext, SemanticVersion, UpdateGlobal};
 use http_client::Url;
 use language::{
-    language_settings::{AllLanguageSettings, LanguageSettingsContent},
-    tree_sitter_rust, tree_sitter_typescript, Diagnostic, DiagnosticSet, FakeLspAdapter,
+    language_settings::{
+        AllLanguageSettings, FormatOnSave, Formatter, FormatterList, LanguageSettingsContent,
+        LineEndingSetting, SelectedFormatter,
+    },
+    tree_sitter_rust, tree_sitter_typescript, Diagnostic, DiagnosticSet, Encoding, FakeLspAdapter,
     LanguageConfig, LanguageMatcher, LanguageName, LineEnding, OffsetRangeExt, Point, ToPoint,
 };
 use lsp::{DiagnosticSeverity, NumberOrString};
@@ -1962,6 +1966,72 @@ async fn test_empty_diagnostic_ranges(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_diagnostics_with_utf16_ranges(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "let crab = \"🦀\"; undefined;\n" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    // The crab emoji is four bytes long in UTF-8, but two code units long in UTF-16,
+    // which LSP positions are expressed in.
+    fake_server.notify::<lsp::notification::PublishDiagnostics>(lsp::PublishDiagnosticsParams {
+        uri: lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+        version: None,
+        diagnostics: vec![
+            lsp::Diagnostic {
+                range: lsp::Range::new(lsp::Position::new(0, 12), lsp::Position::new(0, 14)),
+                severity: Some(DiagnosticSeverity::WARNING),
+                message: "crab".to_string(),
+                ..Default::default()
+            },
+            lsp::Diagnostic {
+                range: lsp::Range::new(lsp::Position::new(0, 17), lsp::Position::new(0, 26)),
+                severity: Some(DiagnosticSeverity::ERROR),
+                message: "undefined".to_string(),
+                ..Default::default()
+            },
+        ],
+    });
+    cx.executor().run_until_parked();
+
+    buffer.update(cx, |buffer, _| {
+        let snapshot = buffer.snapshot();
+        let diagnostics = snapshot
+            .diagnostics_in_range::<_, usize>(0..buffer.len(), false)
+            .map(|entry| {
+                (
+                    snapshot
+                        .text_for_range(entry.range.clone())
+                        .collect::<String>(),
+                    entry.range,
+                    entry.diagnostic.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            [
+                ("🦀".to_string(), 12..16, DiagnosticSeverity::WARNING),
+                ("undefined".to_string(), 19..28, DiagnosticSeverity::ERROR),
+            ]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_diagnostics_from_multiple_language_servers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -3218,6 +3288,70 @@ async fn test_buffer_identity_across_renames(cx: &mut gpui::TestAppContext) {
     buffer.update(cx, |buffer, _| assert!(!buffer.is_dirty()));
 }
 
+#[gpui::test]
+async fn test_open_buffer_after_rename_and_delete_entry(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.txt": "a-contents" }))
+        .await;
+
+    let project = Project::test(fs.clone(), [Path::new("/dir")], cx).await;
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer((worktree_id, "a.txt"), cx)
+        })
+        .await
+        .unwrap();
+    let entry_id = buffer.update(cx, |buffer, cx| {
+        File::from_dyn(buffer.file())
+            .unwrap()
+            .project_entry_id(cx)
+            .unwrap()
+    });
+
+    project
+        .update(cx, |project, cx| {
+            project.rename_entry(entry_id, Path::new("b.txt"), cx)
+        })
+        .await
+        .unwrap()
+        .to_included()
+        .unwrap();
+    cx.executor().run_until_parked();
+    buffer.update(cx, |buffer, _| {
+        let file = buffer.file().unwrap();
+        assert_eq!(file.path().as_ref(), Path::new("b.txt"));
+        assert!(!file.is_deleted());
+        assert!(!buffer.is_dirty());
+    });
+    assert!(!fs.is_file(Path::new("/dir/a.txt")).await);
+    assert!(fs.is_file(Path::new("/dir/b.txt")).await);
+
+    project
+        .update(cx, |project, cx| project.delete_entry(entry_id, false, cx))
+        .unwrap()
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    buffer.update(cx, |buffer, _| {
+        assert!(buffer.file().unwrap().is_deleted());
+        assert_eq!(buffer.text(), "a-contents");
+        assert!(buffer.is_dirty());
+    });
+    assert!(!fs.is_file(Path::new("/dir/b.txt")).await);
+
+    let result = project
+        .update(cx, |project, cx| {
+            project.rename_entry(entry_id, Path::new("c.txt"), cx)
+        })
+        .await;
+    assert!(result.is_err(), "renaming a deleted entry should fail");
+}
+
 #[gpui::test]
 async fn test_buffer_deduping(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -3264,6 +3398,207 @@ async fn test_buffer_deduping(cx: &mut gpui::TestAppContext) {
     assert_eq!(buffer_a_3.entity_id(), buffer_a_id);
 }
 
+#[gpui::test]
+async fn test_opening_read_only_file(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "locked": "can't touch this",
+            "unlocked": "edit me",
+        }),
+    )
+    .await;
+    fs.set_read_only("/dir/locked", true);
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let locked = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/locked", cx))
+        .await
+        .unwrap();
+    let unlocked = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/unlocked", cx))
+        .await
+        .unwrap();
+
+    locked.update(cx, |buffer, _| {
+        assert!(buffer.read_only());
+        assert_eq!(buffer.text(), "can't touch this");
+    });
+    unlocked.update(cx, |buffer, _| assert!(!buffer.read_only()));
+}
+
+#[gpui::test]
+async fn test_preserving_file_encodings(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({})).await;
+    fs.insert_file(
+        "/dir/utf16.txt",
+        [0xFF, 0xFE]
+            .into_iter()
+            .chain("héllo\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect(),
+    )
+    .await;
+    fs.insert_file("/dir/latin1.txt", b"caf\xe9\n".to_vec())
+        .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let utf16 = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/utf16.txt", cx))
+        .await
+        .unwrap();
+    let latin1 = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/latin1.txt", cx))
+        .await
+        .unwrap();
+
+    utf16.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "héllo\n");
+        assert_eq!(buffer.encoding(), Encoding::Utf16Le);
+        buffer.edit([(6..6, " wörld")], None, cx);
+    });
+    latin1.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "café\n");
+        assert_eq!(buffer.encoding(), Encoding::Latin1);
+        buffer.edit([(0..0, "à la ")], None, cx);
+    });
+
+    project
+        .update(cx, |project, cx| project.save_buffer(utf16.clone(), cx))
+        .await
+        .unwrap();
+    project
+        .update(cx, |project, cx| project.save_buffer(latin1.clone(), cx))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        fs.load_bytes("/dir/utf16.txt".as_ref()).await.unwrap(),
+        [0xFF, 0xFE]
+            .into_iter()
+            .chain("héllo wörld\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        fs.load_bytes("/dir/latin1.txt".as_ref()).await.unwrap(),
+        b"\xe0 la caf\xe9\n"
+    );
+
+    // Reloading after the file changes on disk picks up the encoding of the new contents.
+    fs.insert_file(
+        "/dir/utf16.txt",
+        [0xFE, 0xFF]
+            .into_iter()
+            .chain("héllo\n".encode_utf16().flat_map(u16::to_be_bytes))
+            .collect(),
+    )
+    .await;
+    cx.executor().run_until_parked();
+    utf16.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "héllo\n");
+        assert_eq!(buffer.encoding(), Encoding::Utf16Be);
+    });
+
+    // Binary files aren't decoded as ISO-8859-1.
+    fs.insert_file("/dir/image.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec())
+        .await;
+    assert!(project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/image.png", cx))
+        .await
+        .is_err());
+}
+
+#[gpui::test]
+async fn test_resolve_existing_file_path(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "README.md": "",
+            "src": {
+                "main.rs": "",
+                "util.rs": "",
+                "nested": {
+                    "mod.rs": "",
+                    "util.rs": "",
+                },
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/src/nested/mod.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    let resolve = |path: &'static str, cx: &mut gpui::TestAppContext| {
+        let task = project.update(cx, |project, cx| {
+            project.resolve_existing_file_path(path, &buffer, cx)
+        });
+        async move {
+            task.await.map(|resolved| match resolved {
+                ResolvedPath::ProjectPath(project_path) => {
+                    project_path.path.to_string_lossy().into_owned()
+                }
+                ResolvedPath::AbsPath(abs_path) => abs_path.to_string_lossy().into_owned(),
+            })
+        }
+    };
+
+    // Relative to the current file's directory first, then to the worktree root.
+    assert_eq!(
+        resolve("util.rs", cx).await.as_deref(),
+        Some("src/nested/util.rs")
+    );
+    assert_eq!(resolve("README.md", cx).await.as_deref(), Some("README.md"));
+    assert_eq!(
+        resolve("./util.rs", cx).await.as_deref(),
+        Some("src/nested/util.rs")
+    );
+    assert_eq!(
+        resolve("../main.rs", cx).await.as_deref(),
+        Some("src/main.rs")
+    );
+    assert_eq!(
+        resolve("src/main.rs", cx).await.as_deref(),
+        Some("src/main.rs")
+    );
+
+    // Absolute paths, and root-relative paths that don't exist on disk.
+    assert_eq!(
+        resolve("/dir/src/main.rs", cx).await.as_deref(),
+        Some("/dir/src/main.rs")
+    );
+    assert_eq!(
+        resolve("/src/main.rs", cx).await.as_deref(),
+        Some("src/main.rs")
+    );
+
+    // Extensions are appended when the literal path doesn't exist.
+    assert_eq!(resolve("../util", cx).await.as_deref(), Some("src/util.rs"));
+    assert_eq!(
+        resolve("src/main", cx).await.as_deref(),
+        Some("src/main.rs")
+    );
+
+    // Missing files and directories. Dot-relative paths ignore the worktree root.
+    assert_eq!(resolve("missing.rs", cx).await, None);
+    assert_eq!(resolve("../missing", cx).await, None);
+    assert_eq!(resolve("src", cx).await, None);
+    assert_eq!(resolve("./README.md", cx).await, None);
+}
+
 #[gpui::test]
 async fn test_buffer_is_dirty(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -3487,68 +3822,548 @@ async fn test_buffer_file_changes_on_disk(cx: &mut gpui::TestAppContext) {
 }
 
 #[gpui::test]
-async fn test_buffer_line_endings(cx: &mut gpui::TestAppContext) {
+async fn test_buffer_diff_against_disk(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "file1": "a\nb\nc\n",
-            "file2": "one\r\ntwo\r\nthree\r\n",
+            "the-file": "one\ntwo\nthree\nfour\nfive\n",
         }),
     )
     .await;
-
     let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
-    let buffer1 = project
-        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
-        .await
-        .unwrap();
-    let buffer2 = project
-        .update(cx, |p, cx| p.open_local_buffer("/dir/file2", cx))
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/the-file", cx))
         .await
         .unwrap();
 
-    buffer1.update(cx, |buffer, _| {
-        assert_eq!(buffer.text(), "a\nb\nc\n");
-        assert_eq!(buffer.line_ending(), LineEnding::Unix);
-    });
-    buffer2.update(cx, |buffer, _| {
-        assert_eq!(buffer.text(), "one\ntwo\nthree\n");
-        assert_eq!(buffer.line_ending(), LineEnding::Windows);
-    });
+    let hunks = |buffer: &Model<Buffer>, cx: &mut gpui::TestAppContext| {
+        let diff = buffer.update(cx, |buffer, cx| buffer.diff_against_disk(cx));
+        async move {
+            diff.await
+                .unwrap()
+                .into_iter()
+                .map(|hunk| (hunk.associated_range.clone(), hunk.status()))
+                .collect::<Vec<_>>()
+        }
+    };
 
-    // Change a file's line endings on disk from unix to windows. The buffer's
-    // state updates correctly.
-    fs.save(
-        "/dir/file1".as_ref(),
-        &"aaa\nb\nc\n".into(),
-        LineEnding::Windows,
-    )
-    .await
-    .unwrap();
-    cx.executor().run_until_parked();
-    buffer1.update(cx, |buffer, _| {
-        assert_eq!(buffer.text(), "aaa\nb\nc\n");
-        assert_eq!(buffer.line_ending(), LineEnding::Windows);
-    });
+    // An unmodified buffer has no hunks.
+    assert_eq!(hunks(&buffer, cx).await, []);
 
-    // Save a file with windows line endings. The file is written correctly.
-    buffer2.update(cx, |buffer, cx| {
-        buffer.set_text("one\ntwo\nthree\nfour\n", cx);
+    // Inserted lines are reported as added.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit(
+            [(Point::new(1, 0)..Point::new(1, 0), "one and a half\n")],
+            None,
+            cx,
+        );
     });
-    project
-        .update(cx, |project, cx| project.save_buffer(buffer2, cx))
-        .await
-        .unwrap();
-    assert_eq!(
-        fs.load("/dir/file2".as_ref()).await.unwrap(),
-        "one\r\ntwo\r\nthree\r\nfour\r\n",
-    );
-}
+    assert_eq!(hunks(&buffer, cx).await, [(1..2, DiffHunkStatus::Added)]);
 
-#[gpui::test]
+    // Deleted lines are reported as removed, and changed lines as modified.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit(
+            [
+                (Point::new(3, 0)..Point::new(4, 0), ""),
+                (Point::new(5, 0)..Point::new(5, 4), "FIVE"),
+            ],
+            None,
+            cx,
+        );
+    });
+    assert_eq!(
+        hunks(&buffer, cx).await,
+        [
+            (1..2, DiffHunkStatus::Added),
+            (3..3, DiffHunkStatus::Removed),
+            (4..5, DiffHunkStatus::Modified),
+        ]
+    );
+
+    // When the file is deleted, every line is reported as added.
+    fs.remove_file("/dir/the-file".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(hunks(&buffer, cx).await, [(0..5, DiffHunkStatus::Added)]);
+}
+
+#[gpui::test]
+async fn test_git_diff_against_repository(cx: &mut gpui::TestAppContext) {
+    use worktree::WorktreeModelHandle as _;
+
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let dir = temp_tree(json!({
+        "file.txt": "one\ntwo\nthree\n",
+    }));
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("file.txt")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("test", "test@zed.dev").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+        .unwrap();
+
+    let project = Project::test(Arc::new(RealFs::default()), [dir.path()], cx).await;
+    let worktree = project.update(cx, |project, cx| project.worktrees(cx).next().unwrap());
+    let buffer = project
+        .update(cx, |p, cx| {
+            p.open_local_buffer(dir.path().join("file.txt"), cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    let markers = |buffer: &Model<Buffer>, cx: &mut gpui::TestAppContext| {
+        buffer.read_with(cx, |buffer, _| {
+            buffer
+                .snapshot()
+                .git_diff_hunks_in_row_range(0..u32::MAX)
+                .map(|hunk| (hunk.associated_range.clone(), hunk.status()))
+                .collect::<Vec<_>>()
+        })
+    };
+    assert_eq!(markers(&buffer, cx), []);
+
+    // Changed lines of the tracked file are marked, and stay marked once saved.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit(
+            [
+                (Point::new(1, 0)..Point::new(1, 3), "TWO"),
+                (Point::new(3, 0)..Point::new(3, 0), "four\n"),
+            ],
+            None,
+            cx,
+        );
+    });
+    cx.executor().run_until_parked();
+    assert_eq!(
+        markers(&buffer, cx),
+        [
+            (1..2, DiffHunkStatus::Modified),
+            (3..4, DiffHunkStatus::Added),
+        ]
+    );
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        markers(&buffer, cx),
+        [
+            (1..2, DiffHunkStatus::Modified),
+            (3..4, DiffHunkStatus::Added),
+        ]
+    );
+
+    // Staging the file outside of Zed recomputes the markers against the new index.
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("file.txt")).unwrap();
+    index.write().unwrap();
+    worktree.flush_fs_events_in_root_git_repository(cx).await;
+    cx.executor().run_until_parked();
+    assert_eq!(markers(&buffer, cx), []);
+}
+
+#[gpui::test]
+async fn test_blame_buffer_row(cx: &mut gpui::TestAppContext) {
+    use git::blame::{Blame, BlameEntry};
+
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "file.txt": "one\ntwo\nthree\n",
+        }),
+    )
+    .await;
+    fs.insert_tree("/untracked", json!({ "file.txt": "one\n" }))
+        .await;
+    let blame = |entries: &[(&str, Range<u32>)]| Blame {
+        entries: entries
+            .iter()
+            .map(|(sha, range)| BlameEntry {
+                sha: sha.parse().unwrap(),
+                range: range.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    fs.set_blame_for_repo(
+        Path::new("/dir/.git"),
+        vec![(
+            Path::new("file.txt"),
+            blame(&[("1b1b1b", 0..1), ("3a3a3a", 2..3)]),
+        )],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref(), "/untracked".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/file.txt", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    let blamed_sha = |row: u32, buffer: &Model<Buffer>, cx: &mut gpui::TestAppContext| {
+        let entry = project.update(cx, |project, cx| project.blame_buffer_row(buffer, row, cx));
+        async move { entry.await.unwrap().map(|entry| entry.sha) }
+    };
+    let sha = |sha: &str| sha.parse::<Oid>().unwrap();
+    assert_eq!(blamed_sha(0, &buffer, cx).await, Some(sha("1b1b1b")));
+    assert_eq!(blamed_sha(1, &buffer, cx).await, None, "uncommitted line");
+
+    // Once HEAD moves, the cached blame is replaced even though the buffer didn't change.
+    fs.set_blame_for_repo(
+        Path::new("/dir/.git"),
+        vec![(Path::new("file.txt"), blame(&[("0d0d0d", 0..3)]))],
+    );
+    cx.executor().run_until_parked();
+    assert_eq!(blamed_sha(0, &buffer, cx).await, Some(sha("0d0d0d")));
+    assert_eq!(blamed_sha(1, &buffer, cx).await, Some(sha("0d0d0d")));
+
+    // Files outside of git repositories have no blame.
+    let untracked = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/untracked/file.txt", cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(blamed_sha(0, &untracked, cx).await, None);
+}
+
+#[gpui::test]
+async fn test_open_buffer_at_revision(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let dir = temp_tree(json!({
+        "file.txt": "one\n",
+    }));
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    let signature = git2::Signature::now("test", "test@zed.dev").unwrap();
+    let commit = |message: &str| {
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parents = repo
+            .head()
+            .ok()
+            .map(|head| head.peel_to_commit().unwrap())
+            .into_iter()
+            .collect::<Vec<_>>();
+        let parents = parents.iter().collect::<Vec<_>>();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    };
+    commit("first");
+    std::fs::write(dir.path().join("file.txt"), "one\ntwo\n").unwrap();
+    commit("second");
+
+    let project = Project::test(Arc::new(RealFs::default()), [dir.path()], cx).await;
+    cx.executor().run_until_parked();
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let project_path = ProjectPath {
+        worktree_id,
+        path: Path::new("file.txt").into(),
+    };
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer_at_revision(project_path.clone(), "HEAD~1".into(), cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "one\n");
+        assert!(buffer.read_only());
+    });
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer_at_revision(project_path.clone(), "HEAD".into(), cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| assert_eq!(buffer.text(), "one\ntwo\n"));
+
+    let error = project
+        .update(cx, |project, cx| {
+            project.open_buffer_at_revision(project_path.clone(), "no-such-rev".into(), cx)
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "unknown revision \"no-such-rev\"");
+
+    let project_path = ProjectPath {
+        worktree_id,
+        path: Path::new("missing.txt").into(),
+    };
+    let error = project
+        .update(cx, |project, cx| {
+            project.open_buffer_at_revision(project_path, "HEAD".into(), cx)
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "path \"missing.txt\" does not exist in revision \"HEAD\""
+    );
+}
+
+#[gpui::test]
+async fn test_open_commit_diff(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let dir = temp_tree(json!({
+        "old.txt": "one\n",
+    }));
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    let signature = git2::Signature::now("test", "test@zed.dev").unwrap();
+    let commit = |message: &str| {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parents = repo
+            .head()
+            .ok()
+            .map(|head| head.peel_to_commit().unwrap())
+            .into_iter()
+            .collect::<Vec<_>>();
+        let parents = parents.iter().collect::<Vec<_>>();
+        Oid::from_bytes(
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+            .as_bytes(),
+        )
+        .unwrap()
+    };
+    let first = commit("first");
+    std::fs::rename(dir.path().join("old.txt"), dir.path().join("new.txt")).unwrap();
+    std::fs::write(dir.path().join("new.txt"), "one\ntwo\n").unwrap();
+    let second = commit("second");
+
+    let project = Project::test(Arc::new(RealFs::default()), [dir.path()], cx).await;
+    cx.executor().run_until_parked();
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let project_path = ProjectPath {
+        worktree_id,
+        path: Path::new("new.txt").into(),
+    };
+    let hunks = |buffer: &Model<Buffer>, cx: &mut gpui::TestAppContext| {
+        buffer.read_with(cx, |buffer, _| {
+            buffer
+                .snapshot()
+                .git_diff_hunks_in_row_range(0..u32::MAX)
+                .map(|hunk| (hunk.associated_range.clone(), hunk.status()))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // The commit that renamed the file is diffed against the file's old path in the parent.
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_commit_diff(
+                project_path.clone(),
+                second,
+                "new.txt".into(),
+                "old.txt".into(),
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "one\ntwo\n");
+        assert!(buffer.read_only());
+    });
+    assert_eq!(hunks(&buffer, cx), [(1..2, DiffHunkStatus::Added)]);
+
+    // The root commit is diffed against an empty file.
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_commit_diff(
+                project_path.clone(),
+                first,
+                "old.txt".into(),
+                "old.txt".into(),
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| assert_eq!(buffer.text(), "one\n"));
+    assert_eq!(hunks(&buffer, cx), [(0..1, DiffHunkStatus::Added)]);
+}
+
+#[gpui::test]
+async fn test_buffer_line_endings(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "file1": "a\nb\nc\n",
+            "file2": "one\r\ntwo\r\nthree\r\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer1 = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+    let buffer2 = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file2", cx))
+        .await
+        .unwrap();
+
+    buffer1.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "a\nb\nc\n");
+        assert_eq!(buffer.line_ending(), LineEnding::Unix);
+    });
+    buffer2.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "one\ntwo\nthree\n");
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+    });
+
+    // Change a file's line endings on disk from unix to windows. The buffer's
+    // state updates correctly.
+    fs.save(
+        "/dir/file1".as_ref(),
+        &"aaa\nb\nc\n".into(),
+        LineEnding::Windows,
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    buffer1.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "aaa\nb\nc\n");
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+    });
+
+    // Save a file with windows line endings. The file is written correctly.
+    buffer2.update(cx, |buffer, cx| {
+        buffer.set_text("one\ntwo\nthree\nfour\n", cx);
+    });
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer2, cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load("/dir/file2".as_ref()).await.unwrap(),
+        "one\r\ntwo\r\nthree\r\nfour\r\n",
+    );
+}
+
+#[gpui::test]
+async fn test_preserving_line_endings_on_save(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "crlf": "one\r\ntwo\r\n",
+            "lf": "one\ntwo\n",
+            "mixed": "one\ntwo\r\nthree\r\nfour\n\r\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    for (path, expected_line_ending, saved_text) in [
+        ("/dir/crlf", LineEnding::Windows, "zero\r\none\r\ntwo\r\n"),
+        ("/dir/lf", LineEnding::Unix, "zero\none\ntwo\n"),
+        (
+            "/dir/mixed",
+            LineEnding::Windows,
+            "zero\r\none\r\ntwo\r\nthree\r\nfour\r\n\r\n",
+        ),
+    ] {
+        let buffer = project
+            .update(cx, |p, cx| p.open_local_buffer(path, cx))
+            .await
+            .unwrap();
+        buffer.update(cx, |buffer, cx| {
+            assert!(!buffer.text().contains('\r'));
+            assert_eq!(buffer.line_ending(), expected_line_ending, "{path}");
+            buffer.edit([(0..0, "zero\n")], None, cx);
+        });
+        project
+            .update(cx, |project, cx| project.save_buffer(buffer, cx))
+            .await
+            .unwrap();
+        assert_eq!(fs.load(path.as_ref()).await.unwrap(), saved_text);
+    }
+
+    // The line ending setting overrides the detected one.
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.line_ending = Some(LineEndingSetting::Lf);
+            });
+        })
+    });
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/crlf", cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..0, "minus one\n")], None, cx)
+    });
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.line_ending(), LineEnding::Unix)
+    });
+    assert_eq!(
+        fs.load("/dir/crlf".as_ref()).await.unwrap(),
+        "minus one\nzero\none\ntwo\n"
+    );
+}
+
+#[gpui::test]
 async fn test_grouped_diagnostics(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
@@ -4548,6 +5363,168 @@ async fn test_search_in_gitignored_dirs(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[cfg(not(windows))]
+#[gpui::test]
+async fn test_format_on_save_with_external_command(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    fn set_external_formatter(script: &str, cx: &mut gpui::TestAppContext) {
+        let formatter = Formatter::External {
+            command: "sh".into(),
+            arguments: vec!["-c".to_string(), script.to_string()].into(),
+        };
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                    settings.defaults.format_on_save = Some(FormatOnSave::On);
+                    settings.defaults.formatter = Some(SelectedFormatter::List(FormatterList(
+                        vec![formatter].into(),
+                    )));
+                });
+            });
+        });
+    }
+
+    let dir = temp_tree(json!({
+        "a.txt": "hello world\n",
+    }));
+    let project = Project::test(Arc::new(RealFs::default()), [dir.path()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer(dir.path().join("a.txt"), cx)
+        })
+        .await
+        .unwrap();
+
+    // The formatter's stdout replaces the buffer's contents.
+    set_external_formatter("tr a-z A-Z", cx);
+    project
+        .update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                true,
+                FormatTrigger::Save,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "HELLO WORLD\n"
+    );
+
+    // When the formatter exits with a non-zero status, its output is discarded.
+    set_external_formatter("tr A-Z a-z; exit 1", cx);
+    project
+        .update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                true,
+                FormatTrigger::Save,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "HELLO WORLD\n"
+    );
+
+    // A formatter that hangs is stopped once the configured timeout elapses.
+    set_external_formatter("sleep 600; tr A-Z a-z", cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |store, cx| {
+            store.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.external_formatter_timeout_ms = Some(1000);
+            });
+        });
+    });
+    let mut format = project.update(cx, |project, cx| {
+        project.format(
+            HashSet::from_iter([buffer.clone()]),
+            true,
+            FormatTrigger::Save,
+            cx,
+        )
+    });
+    let mut elapsed = Duration::ZERO;
+    loop {
+        if let Poll::Ready(result) = futures::poll!(&mut format) {
+            result.unwrap();
+            break;
+        }
+        assert!(
+            elapsed < Duration::from_secs(10),
+            "formatter wasn't stopped"
+        );
+        // Starting the formatter involves real IO, so let it progress before moving the clock.
+        std::thread::sleep(Duration::from_millis(10));
+        cx.executor().advance_clock(Duration::from_millis(100));
+        elapsed += Duration::from_millis(100);
+    }
+    assert!(elapsed >= Duration::from_secs(1));
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "HELLO WORLD\n"
+    );
+}
+
+#[gpui::test]
+async fn test_search_real_fs_skips_gitignored_files(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.executor().allow_parking();
+
+    let dir = temp_tree(json!({
+        ".gitignore": "ignored.txt\nbuild/\n",
+        "ignored.txt": "needle",
+        "build": {
+            "output.txt": "needle needle",
+        },
+        "src": {
+            "a.txt": "needle haystack needle",
+            "b.txt": "haystack",
+            "nested": {
+                "c.txt": "one needle",
+            },
+        },
+    }));
+    let root_name = dir
+        .path()
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    let project = Project::test(Arc::new(RealFs::default()), [dir.path()], cx).await;
+
+    let results = search(
+        &project,
+        SearchQuery::text(
+            "needle",
+            false,
+            true,
+            false,
+            Default::default(),
+            Default::default(),
+            None,
+        )
+        .unwrap(),
+        cx,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        results,
+        HashMap::from_iter([
+            (format!("{root_name}/src/a.txt"), vec![0..6, 16..22]),
+            (format!("{root_name}/src/nested/c.txt"), vec![4..10]),
+        ])
+    );
+    assert_eq!(results.values().map(Vec::len).sum::<usize>(), 3);
+}
+
 #[gpui::test]
 async fn test_search_ordering(cx: &mut gpui::TestAppContext) {
     init_test(cx);
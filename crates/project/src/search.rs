@@ -534,4 +534,77 @@ mod tests {
             }
         }
     }
+
+    #[gpui::test]
+    async fn test_search_buffer(cx: &mut gpui::TestAppContext) {
+        let buffer = cx.new_model(|cx| Buffer::local("One one ONE\nonerous\n", cx));
+        let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+
+        let text = |query: &str, whole_word: bool, case_sensitive: bool| {
+            SearchQuery::text(
+                query,
+                whole_word,
+                case_sensitive,
+                false,
+                Default::default(),
+                Default::default(),
+                None,
+            )
+            .unwrap()
+        };
+        let regex = |query: &str| {
+            SearchQuery::regex(
+                query,
+                false,
+                true,
+                false,
+                Default::default(),
+                Default::default(),
+                None,
+            )
+            .unwrap()
+        };
+
+        // Literal, case-sensitive.
+        assert_eq!(
+            text("one", false, true).search(&snapshot, None).await,
+            [4..7, 12..15]
+        );
+
+        // Literal, case-insensitive.
+        assert_eq!(
+            text("one", false, false).search(&snapshot, None).await,
+            [0..3, 4..7, 8..11, 12..15]
+        );
+
+        // Whole words only.
+        assert_eq!(
+            text("one", true, false).search(&snapshot, None).await,
+            [0..3, 4..7, 8..11]
+        );
+
+        // Regex.
+        assert_eq!(regex(r"o\w+s").search(&snapshot, None).await, [12..19]);
+
+        // Zero-width matches are reported as empty ranges, once per position,
+        // including the empty line after the trailing newline.
+        assert_eq!(
+            regex("^").search(&snapshot, None).await,
+            [0..0, 12..12, 20..20]
+        );
+
+        // Searching a subrange reports matches relative to its start.
+        assert_eq!(
+            text("one", false, true)
+                .search(&snapshot, Some(12..20))
+                .await,
+            [0..3]
+        );
+
+        // An empty query matches nothing.
+        assert!(text("", false, true)
+            .search(&snapshot, None)
+            .await
+            .is_empty());
+    }
 }
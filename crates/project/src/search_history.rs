@@ -110,6 +110,19 @@ impl SearchHistory {
         cursor.selection = Some(prev_index);
         Some(&self.history[prev_index])
     }
+
+    /// Returns the queries in the history, from oldest to newest.
+    pub fn queries(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Replaces the history with previously saved queries, ordered from oldest to newest.
+    pub fn restore(&mut self, mut queries: Vec<String>) {
+        if let Some(max_history_len) = self.max_history_len {
+            queries.drain(..queries.len().saturating_sub(max_history_len));
+        }
+        self.history = queries;
+    }
 }
 
 #[cfg(test)]
@@ -258,4 +271,19 @@ mod tests {
         assert_eq!(search_history.current(&cursor1), None);
         assert_eq!(search_history.current(&cursor2), None);
     }
+
+    #[test]
+    fn test_restore() {
+        let mut search_history = SearchHistory::new(Some(2), QueryInsertionBehavior::AlwaysInsert);
+        let mut cursor = SearchHistoryCursor::default();
+
+        search_history.restore(vec!["Rust".into(), "Python".into(), "Go".into()]);
+        assert_eq!(
+            search_history.queries(),
+            ["Python", "Go"],
+            "Only the newest queries that fit should be restored"
+        );
+        assert_eq!(search_history.previous(&mut cursor), Some("Go"));
+        assert_eq!(search_history.previous(&mut cursor), Some("Python"));
+    }
 }
@@ -167,6 +167,8 @@ impl Project {
                     hide: spawn_task.hide,
                     status: TaskStatus::Running,
                     completion_rx,
+                    cwd: local_path.clone(),
+                    diagnostics: Vec::new(),
                 });
 
                 env.extend(spawn_task.env);
@@ -210,6 +212,11 @@ impl Project {
             }
         };
 
+        let max_scroll_history_lines = if spawn_task.is_some() {
+            settings.max_task_scroll_history_lines
+        } else {
+            settings.max_scroll_history_lines
+        };
         let terminal = TerminalBuilder::new(
             local_path,
             spawn_task,
@@ -217,7 +224,7 @@ impl Project {
             env,
             Some(settings.blinking),
             settings.alternate_scroll,
-            settings.max_scroll_history_lines,
+            max_scroll_history_lines,
             window,
             completion_tx,
             cx,
@@ -157,6 +157,7 @@ actions!(
         UnfoldDirectory,
         FoldDirectory,
         SelectParent,
+        ToggleHideGitIgnore,
     ]
 );
 
@@ -279,10 +280,15 @@ impl ProjectPanel {
             .detach();
 
             let mut project_panel_settings = *ProjectPanelSettings::get_global(cx);
-            cx.observe_global::<SettingsStore>(move |_, cx| {
+            cx.observe_global::<SettingsStore>(move |this, cx| {
                 let new_settings = *ProjectPanelSettings::get_global(cx);
                 if project_panel_settings != new_settings {
+                    let hide_gitignore_changed =
+                        project_panel_settings.hide_gitignore != new_settings.hide_gitignore;
                     project_panel_settings = new_settings;
+                    if hide_gitignore_changed {
+                        this.update_visible_entries(None, cx);
+                    }
                     cx.notify();
                 }
             })
@@ -686,6 +692,15 @@ impl ProjectPanel {
         cx.notify();
     }
 
+    fn toggle_hide_gitignore(&mut self, _: &ToggleHideGitIgnore, cx: &mut ViewContext<Self>) {
+        let hide_gitignore = ProjectPanelSettings::get_global(cx).hide_gitignore;
+        settings::update_settings_file::<ProjectPanelSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings, _| settings.hide_gitignore = Some(!hide_gitignore),
+        );
+    }
+
     fn toggle_expanded(&mut self, entry_id: ProjectEntryId, cx: &mut ViewContext<Self>) {
         if let Some(worktree_id) = self.project.read(cx).worktree_id_for_entry(entry_id, cx) {
             if let Some(expanded_dir_ids) = self.expanded_dir_ids.get_mut(&worktree_id) {
@@ -1030,7 +1045,8 @@ impl ProjectPanel {
     }
 
     fn trash(&mut self, action: &Trash, cx: &mut ViewContext<Self>) {
-        self.remove(true, action.skip_prompt, cx);
+        let trash = !ProjectPanelSettings::get_global(cx).permanent_delete;
+        self.remove(trash, action.skip_prompt, cx);
     }
 
     fn delete(&mut self, action: &Delete, cx: &mut ViewContext<Self>) {
@@ -1105,10 +1121,38 @@ impl ProjectPanel {
                         return Result::<(), anyhow::Error>::Ok(());
                     }
                 }
-                for (entry_id, _) in file_paths {
+                for (entry_id, file_name) in file_paths {
+                    let result = this
+                        .update(&mut cx, |this, cx| {
+                            this.project
+                                .update(cx, |project, cx| project.delete_entry(entry_id, trash, cx))
+                                .ok_or_else(|| anyhow!("no such entry"))
+                        })??
+                        .await;
+                    let Err(error) = result else {
+                        continue;
+                    };
+                    if !trash {
+                        return Err(error);
+                    }
+
+                    // Never fall back to deleting permanently without asking.
+                    let answer = this.update(&mut cx, |_, cx| {
+                        cx.prompt(
+                            PromptLevel::Warning,
+                            &format!(
+                                "Could not move {file_name} to the trash. Delete it permanently?"
+                            ),
+                            Some(&format!("{error:#}")),
+                            &["Delete", "Cancel"],
+                        )
+                    })?;
+                    if answer.await != Ok(0) {
+                        return Ok(());
+                    }
                     this.update(&mut cx, |this, cx| {
                         this.project
-                            .update(cx, |project, cx| project.delete_entry(entry_id, trash, cx))
+                            .update(cx, |project, cx| project.delete_entry(entry_id, false, cx))
                             .ok_or_else(|| anyhow!("no such entry"))
                     })??
                     .await?;
@@ -1737,7 +1781,9 @@ impl ProjectPanel {
         new_selected_entry: Option<(WorktreeId, ProjectEntryId)>,
         cx: &mut ViewContext<Self>,
     ) {
-        let auto_collapse_dirs = ProjectPanelSettings::get_global(cx).auto_fold_dirs;
+        let settings = ProjectPanelSettings::get_global(cx);
+        let auto_collapse_dirs = settings.auto_fold_dirs;
+        let hide_gitignore = settings.hide_gitignore;
         let project = self.project.read(cx);
         self.last_worktree_root_id = project
             .visible_worktrees(cx)
@@ -1781,6 +1827,10 @@ impl ProjectPanel {
             let mut entry_iter = snapshot.entries(true, 0);
             let mut auto_folded_ancestors = vec![];
             while let Some(entry) = entry_iter.entry() {
+                if hide_gitignore && entry.is_ignored {
+                    entry_iter.advance_to_sibling();
+                    continue;
+                }
                 if auto_collapse_dirs && entry.kind.is_dir() {
                     auto_folded_ancestors.push(entry.id);
                     if !self.unfolded_dir_ids.contains(&entry.id) {
@@ -2676,6 +2726,7 @@ impl Render for ProjectPanel {
                 .on_action(cx.listener(Self::expand_selected_entry))
                 .on_action(cx.listener(Self::collapse_selected_entry))
                 .on_action(cx.listener(Self::collapse_all_entries))
+                .on_action(cx.listener(Self::toggle_hide_gitignore))
                 .on_action(cx.listener(Self::open))
                 .on_action(cx.listener(Self::open_permanent))
                 .on_action(cx.listener(Self::confirm))
@@ -2924,7 +2975,7 @@ mod tests {
     use collections::HashSet;
     use gpui::{Empty, TestAppContext, View, VisualTestContext, WindowHandle};
     use pretty_assertions::assert_eq;
-    use project::{FakeFs, WorktreeSettings};
+    use project::{FakeFs, FakeTrash, WorktreeSettings};
     use serde_json::json;
     use settings::SettingsStore;
     use std::path::{Path, PathBuf};
@@ -5048,6 +5099,91 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_hide_gitignored_entries(cx: &mut gpui::TestAppContext) {
+        init_test_with_editor(cx);
+
+        let fs = FakeFs::new(cx.background_executor.clone());
+        fs.insert_tree(
+            "/project_root",
+            json!({
+                ".git": {},
+                ".gitignore": "**/gitignored_dir",
+                "dir_1": {
+                    "file_1.py": "# File 1_1 contents",
+                    "gitignored_dir": {
+                        "file_a.py": "# File contents",
+                    },
+                },
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/project_root".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace.update(cx, ProjectPanel::new).unwrap();
+
+        toggle_expand_dir(&panel, "project_root/dir_1", cx);
+        assert_eq!(
+            find_project_entry(&panel, "project_root/dir_1/gitignored_dir/file_a.py", cx),
+            None,
+            "gitignored directories should not be loaded until expanded"
+        );
+        toggle_expand_dir(&panel, "project_root/dir_1/gitignored_dir", cx);
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..20, cx),
+            &[
+                "v project_root",
+                "    > .git",
+                "    v dir_1",
+                "        v gitignored_dir  <== selected",
+                "              file_a.py",
+                "          file_1.py",
+                "      .gitignore",
+            ]
+        );
+
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<ProjectPanelSettings>(cx, |project_panel_settings| {
+                    project_panel_settings.hide_gitignore = Some(true)
+                });
+            })
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..20, cx),
+            &[
+                "v project_root",
+                "    v dir_1",
+                "          file_1.py",
+                "      .gitignore",
+            ]
+        );
+
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<ProjectPanelSettings>(cx, |project_panel_settings| {
+                    project_panel_settings.hide_gitignore = Some(false)
+                });
+            })
+        });
+        cx.run_until_parked();
+        assert_eq!(
+            visible_entries_as_strings(&panel, 0..20, cx),
+            &[
+                "v project_root",
+                "    > .git",
+                "    v dir_1",
+                "        v gitignored_dir  <== selected",
+                "              file_a.py",
+                "          file_1.py",
+                "      .gitignore",
+            ]
+        );
+    }
+
     #[gpui::test]
     async fn test_explicit_reveal(cx: &mut gpui::TestAppContext) {
         init_test_with_editor(cx);
@@ -5442,6 +5578,70 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_trash_entries(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/src",
+            json!({
+                "a.txt": "",
+                "b.txt": "",
+                "c.txt": "",
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/src".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace.update(cx, ProjectPanel::new).unwrap();
+        let trash = |panel: &View<ProjectPanel>, cx: &mut VisualTestContext| {
+            panel.update(cx, |panel, cx| {
+                panel.trash(&Trash { skip_prompt: true }, cx)
+            });
+            cx.executor().run_until_parked();
+        };
+
+        select_path(&panel, "src/a.txt", cx);
+        trash(&panel, cx);
+        assert!(!cx.has_pending_prompt());
+        assert_eq!(fs.trashed_paths(), [PathBuf::from("/src/a.txt")]);
+
+        // When trashing fails, the entry is only deleted permanently after confirming.
+        fs.set_trash(FakeTrash::Failing);
+        select_path(&panel, "src/b.txt", cx);
+        trash(&panel, cx);
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(1);
+        cx.executor().run_until_parked();
+        assert!(fs.is_file(Path::new("/src/b.txt")).await);
+
+        trash(&panel, cx);
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(0);
+        cx.executor().run_until_parked();
+        assert!(!fs.is_file(Path::new("/src/b.txt")).await);
+        assert_eq!(fs.trashed_paths(), [PathBuf::from("/src/a.txt")]);
+
+        // With `permanent_delete`, the trash isn't used at all.
+        fs.set_trash(FakeTrash::Available);
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<ProjectPanelSettings>(cx, |settings| {
+                    settings.permanent_delete = Some(true);
+                });
+            });
+        });
+        select_path(&panel, "src/c.txt", cx);
+        trash(&panel, cx);
+        assert!(!cx.has_pending_prompt());
+        assert!(!fs.is_file(Path::new("/src/c.txt")).await);
+        assert_eq!(fs.trashed_paths(), [PathBuf::from("/src/a.txt")]);
+        assert_eq!(visible_entries_as_strings(&panel, 0..10, cx), &["v src"]);
+    }
+
     fn toggle_expand_dir(
         panel: &View<ProjectPanel>,
         path: impl AsRef<Path>,
@@ -21,6 +21,8 @@ pub struct ProjectPanelSettings {
     pub indent_size: f32,
     pub auto_reveal_entries: bool,
     pub auto_fold_dirs: bool,
+    pub hide_gitignore: bool,
+    pub permanent_delete: bool,
     pub scrollbar: ScrollbarSettings,
 }
 
@@ -94,6 +96,15 @@ pub struct ProjectPanelSettingsContent {
     ///
     /// Default: false
     pub auto_fold_dirs: Option<bool>,
+    /// Whether to hide gitignored entries in the project panel.
+    ///
+    /// Default: false
+    pub hide_gitignore: Option<bool>,
+    /// Whether trashing entries deletes them permanently
+    /// instead of moving them to the system trash.
+    ///
+    /// Default: false
+    pub permanent_delete: Option<bool>,
     /// Scrollbar-related settings
     pub scrollbar: Option<ScrollbarSettingsContent>,
 }
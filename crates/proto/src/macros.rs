@@ -8,6 +8,7 @@ macro_rules! messages {
                         sender_id,
                         original_sender_id: envelope.original_sender_id,
                         message_id: envelope.id,
+                        idempotency_key: envelope.idempotency_key,
                         payload,
                         received_at,
                     }))
@@ -31,6 +32,8 @@ macro_rules! messages {
                         id,
                         responding_to,
                         original_sender_id,
+                        logical_channel_id: None,
+                        idempotency_key: None,
                         payload: Some(envelope::Payload::$name(self)),
                     }
                 }
@@ -160,6 +160,7 @@ messages!(
     (ChannelMessageUpdate, Foreground),
     (ComputeEmbeddings, Background),
     (ComputeEmbeddingsResponse, Background),
+    (ConnectionMetadata, Foreground),
     (CopyProjectEntry, Foreground),
     (CreateBufferForPeer, Foreground),
     (CreateChannel, Foreground),
@@ -36,6 +36,7 @@ pub struct ProtoMessageHandlerSet {
     pub entity_id_extractors: HashMap<TypeId, fn(&dyn AnyTypedEnvelope) -> u64>,
     pub models_by_message_type: HashMap<TypeId, AnyWeakModel>,
     pub message_handlers: HashMap<TypeId, ProtoMessageHandler>,
+    pub message_handler_names: HashMap<TypeId, &'static str>,
 }
 
 pub type ProtoMessageHandler = Arc<
@@ -52,6 +53,7 @@ pub type ProtoMessageHandler = Arc<
 impl ProtoMessageHandlerSet {
     pub fn clear(&mut self) {
         self.message_handlers.clear();
+        self.message_handler_names.clear();
         self.models_by_message_type.clear();
         self.entities_by_type_and_remote_id.clear();
         self.entity_id_extractors.clear();
@@ -60,10 +62,13 @@ impl ProtoMessageHandlerSet {
     fn add_message_handler(
         &mut self,
         message_type_id: TypeId,
+        message_type_name: &'static str,
         model: gpui::AnyWeakModel,
         handler: ProtoMessageHandler,
     ) {
         self.models_by_message_type.insert(message_type_id, model);
+        self.message_handler_names
+            .insert(message_type_id, message_type_name);
         let prev_handler = self.message_handlers.insert(message_type_id, handler);
         if prev_handler.is_some() {
             panic!("registered handler for the same message twice");
@@ -73,6 +78,7 @@ impl ProtoMessageHandlerSet {
     fn add_entity_message_handler(
         &mut self,
         message_type_id: TypeId,
+        message_type_name: &'static str,
         model_type_id: TypeId,
         entity_id_extractor: fn(&dyn AnyTypedEnvelope) -> u64,
         handler: ProtoMessageHandler,
@@ -82,12 +88,25 @@ impl ProtoMessageHandlerSet {
             .or_insert(entity_id_extractor);
         self.entity_types_by_message_type
             .insert(message_type_id, model_type_id);
+        self.message_handler_names
+            .insert(message_type_id, message_type_name);
         let prev_handler = self.message_handlers.insert(message_type_id, handler);
         if prev_handler.is_some() {
             panic!("registered handler for the same message twice");
         }
     }
 
+    /// Returns the names of all message types that currently have a handler, sorted.
+    pub fn message_handler_names(&self) -> Vec<&'static str> {
+        let mut names = self
+            .message_handler_names
+            .values()
+            .copied()
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
     pub fn handle_message(
         this: &parking_lot::Mutex<Self>,
         message: Box<dyn AnyTypedEnvelope>,
@@ -174,6 +193,7 @@ impl AnyProtoClient {
     {
         self.0.message_handler_set().lock().add_message_handler(
             TypeId::of::<M>(),
+            M::NAME,
             model.into(),
             Arc::new(move |model, envelope, client, cx| {
                 let model = model.downcast::<E>().unwrap();
@@ -219,6 +239,7 @@ impl AnyProtoClient {
             .lock()
             .add_entity_message_handler(
                 message_type_id,
+                M::NAME,
                 model_type_id,
                 entity_id_extractor,
                 Arc::new(move |model, envelope, client, cx| {
@@ -265,6 +286,7 @@ impl AnyProtoClient {
             .lock()
             .add_entity_message_handler(
                 message_type_id,
+                M::NAME,
                 model_type_id,
                 entity_id_extractor,
                 Arc::new(move |model, envelope, _, cx| {
@@ -21,6 +21,8 @@ pub struct TypedEnvelope<T> {
     pub sender_id: PeerId,
     pub original_sender_id: Option<PeerId>,
     pub message_id: u32,
+    /// Identifies a request that may be retried, so it is only applied once.
+    pub idempotency_key: Option<String>,
     pub payload: T,
     pub received_at: Instant,
 }
@@ -11,6 +11,7 @@ macro_rules! messages {
                             id: original_sender.id
                         }),
                         message_id: envelope.id,
+                        idempotency_key: envelope.idempotency_key,
                         payload,
                         received_at,
                     }))
@@ -34,6 +35,8 @@ macro_rules! messages {
                         id,
                         responding_to,
                         original_sender_id,
+                        logical_channel_id: None,
+                        idempotency_key: None,
                         payload: Some(envelope::Payload::$name(self)),
                     }
                 }
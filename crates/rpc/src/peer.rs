@@ -1,14 +1,15 @@
 use super::{
     proto::{
-        self, AnyTypedEnvelope, EnvelopedMessage, MessageStream, PeerId, Receipt, RequestMessage,
-        TypedEnvelope,
+        self, AnyTypedEnvelope, BufferPool, EnvelopedMessage, MessageStream, PeerId, Receipt,
+        RequestMessage, TypedEnvelope,
     },
     Connection,
 };
 use anyhow::{anyhow, Context, Result};
-use collections::HashMap;
+use collections::{hash_map, HashMap, HashSet, VecDeque};
 use futures::{
     channel::{mpsc, oneshot},
+    future::Shared,
     stream::BoxStream,
     FutureExt, SinkExt, Stream, StreamExt, TryFutureExt,
 };
@@ -20,7 +21,7 @@ use std::{
     future::Future,
     sync::atomic::Ordering::SeqCst,
     sync::{
-        atomic::{self, AtomicU32},
+        atomic::{self, AtomicU32, AtomicUsize},
         Arc,
     },
     time::Duration,
@@ -62,25 +63,36 @@ pub struct Peer {
     epoch: AtomicU32,
     pub connections: RwLock<HashMap<ConnectionId, ConnectionState>>,
     next_connection_id: AtomicU32,
+    idempotency_keys: Mutex<IdempotencyKeys>,
+    max_outstanding_requests: AtomicUsize,
 }
 
+/// The most recently seen idempotency keys, oldest first. Keys are scoped to the
+/// sender that made the request, so one sender can't suppress another sender's requests
+/// by reusing its keys.
+#[derive(Default)]
+struct IdempotencyKeys {
+    keys: HashSet<(u64, String)>,
+    order: VecDeque<(u64, String)>,
+}
+
+const MAX_IDEMPOTENCY_KEYS: usize = 4096;
+
 #[derive(Clone, Serialize)]
 pub struct ConnectionState {
     #[serde(skip)]
     outgoing_tx: mpsc::UnboundedSender<proto::Message>,
     next_message_id: Arc<AtomicU32>,
-    #[allow(clippy::type_complexity)]
     #[serde(skip)]
-    response_channels: Arc<
-        Mutex<
-            Option<
-                HashMap<
-                    u32,
-                    oneshot::Sender<(proto::Envelope, std::time::Instant, oneshot::Sender<()>)>,
-                >,
-            >,
-        >,
-    >,
+    response_channels: Arc<Mutex<Option<HashMap<u32, ResponseSender>>>>,
+    /// Messages sent with [`Peer::send_acknowledged`] that are awaiting an ack. These don't
+    /// count as outstanding requests, since the receiver may never ack them.
+    #[serde(skip)]
+    pending_acks: Arc<Mutex<Option<HashMap<u32, ResponseSender>>>>,
+    /// Notified when a response arrives, for requests waiting until fewer requests are
+    /// outstanding.
+    #[serde(skip)]
+    request_slot_waiters: Arc<Mutex<Vec<oneshot::Sender<()>>>>,
     #[allow(clippy::type_complexity)]
     #[serde(skip)]
     stream_response_channels: Arc<
@@ -90,11 +102,167 @@ pub struct ConnectionState {
             >,
         >,
     >,
+    #[serde(skip)]
+    logical_channels: Arc<Mutex<HashMap<u32, LogicalChannel>>>,
+    /// The metadata the other side of the connection sent in its [`proto::Hello`].
+    #[serde(skip)]
+    remote_metadata: Arc<Mutex<HashMap<String, String>>>,
+    /// Resolves once the connection's IO future has finished or been dropped.
+    #[serde(skip)]
+    io_ended: Shared<oneshot::Receiver<()>>,
+}
+
+type ResponseSender = oneshot::Sender<(proto::Envelope, Instant, oneshot::Sender<()>)>;
+
+/// A request that hasn't been sent yet because its connection had too many outstanding
+/// requests.
+struct QueuedRequest {
+    envelope: proto::Envelope,
+    response_tx: ResponseSender,
+    slot_freed: oneshot::Receiver<()>,
+}
+
+enum SendRequest {
+    Sent(PendingResponse),
+    Queued(QueuedRequest),
+}
+
+/// Stops awaiting the response to a message when dropped, so that requests that are
+/// abandoned before they're answered, e.g. because they timed out, free their slot.
+struct PendingResponse {
+    message_id: u32,
+    channels: Arc<Mutex<Option<HashMap<u32, ResponseSender>>>>,
+    slot_waiters: Option<Arc<Mutex<Vec<oneshot::Sender<()>>>>>,
+}
+
+impl Drop for PendingResponse {
+    fn drop(&mut self) {
+        let removed = self
+            .channels
+            .lock()
+            .as_mut()
+            .and_then(|channels| channels.remove(&self.message_id))
+            .is_some();
+        if removed {
+            if let Some(slot_waiters) = &self.slot_waiters {
+                for waiter in slot_waiters.lock().drain(..) {
+                    let _ = waiter.send(());
+                }
+            }
+        }
+    }
+}
+
+impl ConnectionState {
+    /// Sends a request, or queues it if `max_outstanding` requests are already awaiting a
+    /// response. Requests that weren't queued before also wait behind already queued ones.
+    fn send_request(
+        &self,
+        mut envelope: proto::Envelope,
+        response_tx: ResponseSender,
+        max_outstanding: usize,
+        was_queued: bool,
+    ) -> Result<SendRequest> {
+        let mut response_channels = self.response_channels.lock();
+        let response_channels = response_channels
+            .as_mut()
+            .ok_or_else(|| anyhow!("connection was closed"))?;
+        let mut waiters = self.request_slot_waiters.lock();
+        if response_channels.len() >= max_outstanding || (!was_queued && !waiters.is_empty()) {
+            let (slot_freed_tx, slot_freed) = oneshot::channel();
+            waiters.push(slot_freed_tx);
+            return Ok(SendRequest::Queued(QueuedRequest {
+                envelope,
+                response_tx,
+                slot_freed,
+            }));
+        }
+        drop(waiters);
+
+        let message_id = self.next_message_id.fetch_add(1, SeqCst);
+        envelope.id = message_id;
+        response_channels.insert(message_id, response_tx);
+        self.outgoing_tx
+            .unbounded_send(proto::Message::Envelope(envelope))
+            .map_err(|_| anyhow!("connection was closed"))?;
+        Ok(SendRequest::Sent(PendingResponse {
+            message_id,
+            channels: self.response_channels.clone(),
+            slot_waiters: Some(self.request_slot_waiters.clone()),
+        }))
+    }
+
+    /// Sends a message whose response is awaited without counting against the limit of
+    /// outstanding requests.
+    fn send_with_ack(
+        &self,
+        mut envelope: proto::Envelope,
+        ack_tx: ResponseSender,
+    ) -> Result<PendingResponse> {
+        let mut pending_acks = self.pending_acks.lock();
+        let pending_acks = pending_acks
+            .as_mut()
+            .ok_or_else(|| anyhow!("connection was closed"))?;
+        let message_id = self.next_message_id.fetch_add(1, SeqCst);
+        envelope.id = message_id;
+        pending_acks.insert(message_id, ack_tx);
+        self.outgoing_tx
+            .unbounded_send(proto::Message::Envelope(envelope))
+            .map_err(|_| anyhow!("connection was closed"))?;
+        Ok(PendingResponse {
+            message_id,
+            channels: self.pending_acks.clone(),
+            slot_waiters: None,
+        })
+    }
+
+    /// Fails the connection's pending requests and stops routing messages to it.
+    fn close_channels(&self) {
+        self.response_channels.lock().take();
+        self.pending_acks.lock().take();
+        self.request_slot_waiters.lock().clear();
+        self.logical_channels.lock().clear();
+        if let Some(channels) = self.stream_response_channels.lock().take() {
+            for channel in channels.values() {
+                let _ = channel
+                    .unbounded_send((Err(anyhow!("connection closed")), oneshot::channel().0));
+            }
+        }
+    }
+}
+
+/// A point-in-time view of a [`Peer`]'s connections, intended to be attached to bug reports.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PeerDebugSnapshot {
+    pub epoch: u32,
+    pub connections: Vec<ConnectionDebugSnapshot>,
+    pub metrics: PeerDebugMetrics,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConnectionDebugSnapshot {
+    pub connection_id: ConnectionId,
+    /// Ids of requests sent on this connection that are still awaiting a response.
+    pub outstanding_request_ids: Vec<u32>,
+    /// Ids of streaming requests sent on this connection that haven't finished yet.
+    pub outstanding_stream_request_ids: Vec<u32>,
+    /// The number of messages sent on this connection so far.
+    pub sent_message_count: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PeerDebugMetrics {
+    pub connection_count: usize,
+    pub outstanding_request_count: usize,
+    pub outstanding_stream_request_count: usize,
+    pub sent_message_count: u64,
 }
 
 const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
 const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
 pub const RECEIVE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How many unconsumed messages a logical channel buffers before it's closed.
+pub const LOGICAL_CHANNEL_CAPACITY: usize = 256;
 
 impl Peer {
     pub fn new(epoch: u32) -> Arc<Self> {
@@ -102,6 +270,8 @@ impl Peer {
             epoch: AtomicU32::new(epoch),
             connections: Default::default(),
             next_connection_id: Default::default(),
+            idempotency_keys: Default::default(),
+            max_outstanding_requests: AtomicUsize::new(usize::MAX),
         })
     }
 
@@ -109,7 +279,6 @@ impl Peer {
         self.epoch.load(SeqCst)
     }
 
-    #[instrument(skip_all)]
     pub fn add_connection<F, Fut, Out>(
         self: &Arc<Self>,
         connection: Connection,
@@ -119,6 +288,28 @@ impl Peer {
         impl Future<Output = anyhow::Result<()>> + Send,
         BoxStream<'static, Box<dyn AnyTypedEnvelope>>,
     )
+    where
+        F: Send + Fn(Duration) -> Fut,
+        Fut: Send + Future<Output = Out>,
+        Out: Send,
+    {
+        self.add_connection_with_metadata(connection, Default::default(), create_timer)
+    }
+
+    /// Adds a connection whose first message is a [`proto::ConnectionMetadata`] carrying the given
+    /// metadata, which the other side can read with [`Peer::connection_metadata`]. Nothing is sent
+    /// if the metadata is empty.
+    #[instrument(skip_all)]
+    pub fn add_connection_with_metadata<F, Fut, Out>(
+        self: &Arc<Self>,
+        connection: Connection,
+        metadata: HashMap<String, String>,
+        create_timer: F,
+    ) -> (
+        ConnectionId,
+        impl Future<Output = anyhow::Result<()>> + Send,
+        BoxStream<'static, Box<dyn AnyTypedEnvelope>>,
+    )
     where
         F: Send + Fn(Duration) -> Fut,
         Fut: Send + Future<Output = Out>,
@@ -139,24 +330,49 @@ impl Peer {
             owner_id: self.epoch.load(SeqCst),
             id: self.next_connection_id.fetch_add(1, SeqCst),
         };
+        let (io_ended_tx, io_ended_rx) = oneshot::channel();
         let connection_state = ConnectionState {
             outgoing_tx,
             next_message_id: Default::default(),
             response_channels: Arc::new(Mutex::new(Some(Default::default()))),
+            pending_acks: Arc::new(Mutex::new(Some(Default::default()))),
             stream_response_channels: Arc::new(Mutex::new(Some(Default::default()))),
+            request_slot_waiters: Default::default(),
+            logical_channels: Default::default(),
+            remote_metadata: Default::default(),
+            io_ended: io_ended_rx.shared(),
         };
-        let mut writer = MessageStream::new(connection.tx);
-        let mut reader = MessageStream::new(connection.rx);
+        if !metadata.is_empty() {
+            let metadata = proto::ConnectionMetadata {
+                metadata: metadata.into_iter().collect(),
+            };
+            let message_id = connection_state.next_message_id.fetch_add(1, SeqCst);
+            let _ = connection_state
+                .outgoing_tx
+                .unbounded_send(proto::Message::Envelope(
+                    metadata.into_envelope(message_id, None, None),
+                ));
+        }
+        let buffer_pool = Arc::new(BufferPool::default());
+        let mut writer = MessageStream::with_buffer_pool(connection.tx, buffer_pool.clone());
+        let mut reader = MessageStream::with_buffer_pool(connection.rx, buffer_pool);
 
         let this = self.clone();
         let response_channels = connection_state.response_channels.clone();
+        let pending_acks = connection_state.pending_acks.clone();
         let stream_response_channels = connection_state.stream_response_channels.clone();
+        let request_slot_waiters = connection_state.request_slot_waiters.clone();
+        let logical_channels = connection_state.logical_channels.clone();
 
         let handle_io = async move {
             tracing::trace!(%connection_id, "handle io future: start");
 
+            let _io_ended = io_ended_tx;
             let _end_connection = util::defer(|| {
                 response_channels.lock().take();
+                pending_acks.lock().take();
+                request_slot_waiters.lock().clear();
+                logical_channels.lock().clear();
                 if let Some(channels) = stream_response_channels.lock().take() {
                     for channel in channels.values() {
                         let _ = channel.unbounded_send((
@@ -228,6 +444,14 @@ impl Peer {
                             receive_timeout.set(create_timer(RECEIVE_TIMEOUT).fuse());
                             if let (proto::Message::Envelope(incoming), received_at) = incoming {
                                 tracing::trace!(%connection_id, "incoming rpc message: processing");
+                                let Some((incoming, received_at)) = forward_to_logical_channel(
+                                    &logical_channels,
+                                    connection_id,
+                                    incoming,
+                                    received_at,
+                                ) else {
+                                    break;
+                                };
                                 futures::select_biased! {
                                     result = incoming_tx.send((incoming, received_at)).fuse() => match result {
                                         Ok(_) => {
@@ -256,14 +480,20 @@ impl Peer {
         };
 
         let response_channels = connection_state.response_channels.clone();
+        let pending_acks = connection_state.pending_acks.clone();
         let stream_response_channels = connection_state.stream_response_channels.clone();
+        let request_slot_waiters = connection_state.request_slot_waiters.clone();
+        let remote_metadata = connection_state.remote_metadata.clone();
         self.connections
             .write()
             .insert(connection_id, connection_state);
 
         let incoming_rx = incoming_rx.filter_map(move |(incoming, received_at)| {
             let response_channels = response_channels.clone();
+            let pending_acks = pending_acks.clone();
             let stream_response_channels = stream_response_channels.clone();
+            let request_slot_waiters = request_slot_waiters.clone();
+            let remote_metadata = remote_metadata.clone();
             async move {
                 let message_id = incoming.id;
                 tracing::trace!(?incoming, "incoming message future: start");
@@ -278,8 +508,18 @@ impl Peer {
                         responding_to,
                         "incoming response: received"
                     );
-                    let response_channel =
-                        response_channels.lock().as_mut()?.remove(&responding_to);
+                    let response_channel = {
+                        let mut response_channels = response_channels.lock();
+                        let response_channel = response_channels.as_mut()?.remove(&responding_to);
+                        if response_channel.is_some() {
+                            for waiter in request_slot_waiters.lock().drain(..) {
+                                let _ = waiter.send(());
+                            }
+                            response_channel
+                        } else {
+                            pending_acks.lock().as_mut()?.remove(&responding_to)
+                        }
+                    };
                     let stream_response_channel = stream_response_channels
                         .lock()
                         .as_ref()?
@@ -355,6 +595,13 @@ impl Peer {
                     None
                 } else {
                     tracing::trace!(%connection_id, message_id, "incoming message: received");
+                    // Connection metadata is for the peer itself, so it isn't handed to handlers.
+                    if let Some(proto::envelope::Payload::ConnectionMetadata(metadata)) =
+                        &incoming.payload
+                    {
+                        *remote_metadata.lock() = metadata.metadata.clone().into_iter().collect();
+                        return None;
+                    }
                     proto::build_typed_envelope(connection_id.into(), received_at, incoming)
                         .or_else(|| {
                             tracing::error!(
@@ -384,8 +631,21 @@ impl Peer {
         self.add_connection(connection, move |duration| executor.timer(duration))
     }
 
+    /// The metadata the other side of the given connection sent when connecting, if any.
+    pub fn connection_metadata(
+        &self,
+        connection_id: ConnectionId,
+    ) -> Option<HashMap<String, String>> {
+        let connections = self.connections.read();
+        let metadata = connections.get(&connection_id)?.remote_metadata.lock();
+        Some(metadata.clone())
+    }
+
+    /// Disconnects the given connection, failing its pending requests.
     pub fn disconnect(&self, connection_id: ConnectionId) {
-        self.connections.write().remove(&connection_id);
+        if let Some(connection) = self.connections.write().remove(&connection_id) {
+            connection.close_channels();
+        }
     }
 
     #[cfg(any(test, feature = "test-support"))]
@@ -395,7 +655,41 @@ impl Peer {
     }
 
     pub fn teardown(&self) {
-        self.connections.write().clear();
+        for (_, connection) in self.connections.write().drain() {
+            connection.close_channels();
+        }
+    }
+
+    /// Disconnects every connection, failing their pending requests, and resolves once
+    /// all of their IO futures have finished.
+    pub fn shutdown_all(&self) -> impl Future<Output = ()> {
+        let connections = self.connections.write().drain().collect::<Vec<_>>();
+        let io_ended = connections
+            .into_iter()
+            .map(|(_, connection)| {
+                connection.close_channels();
+                connection.io_ended.clone()
+            })
+            .collect::<Vec<_>>();
+        async move {
+            futures::future::join_all(io_ended).await;
+        }
+    }
+
+    /// Limits how many requests can await a response on each connection. Requests made beyond
+    /// the limit wait to be sent until an earlier one is answered. `None` removes the limit.
+    pub fn set_max_outstanding_requests(&self, max: Option<usize>) {
+        self.max_outstanding_requests
+            .store(max.map_or(usize::MAX, |max| max.max(1)), SeqCst);
+    }
+
+    /// The number of requests sent on the given connection that are still awaiting a response.
+    pub fn outstanding_requests(&self, connection_id: ConnectionId) -> usize {
+        self.connections
+            .read()
+            .get(&connection_id)
+            .and_then(|connection| Some(connection.response_channels.lock().as_ref()?.len()))
+            .unwrap_or(0)
     }
 
     /// Make a request and wait for a response.
@@ -416,6 +710,66 @@ impl Peer {
         self.request_internal(None, receiver_id, request)
     }
 
+    /// Sends a [`proto::Ping`] and measures the round-trip time until it is acknowledged.
+    ///
+    /// The time is told by `now`, so that callers can measure it on their executor's clock.
+    pub fn ping(
+        &self,
+        receiver_id: ConnectionId,
+        now: impl Fn() -> Instant,
+    ) -> impl Future<Output = Result<Duration>> {
+        let sent_at = now();
+        let response = self.request(receiver_id, proto::Ping {});
+        async move {
+            response.await?;
+            Ok(now().saturating_duration_since(sent_at))
+        }
+    }
+
+    /// Make a request tagged with an idempotency key, so that a receiver using
+    /// [`Peer::record_idempotency_key`] applies it at most once, even if it is retried,
+    /// including after reconnecting.
+    pub fn request_idempotent<T: RequestMessage>(
+        &self,
+        receiver_id: ConnectionId,
+        request: T,
+        idempotency_key: impl Into<String>,
+    ) -> impl Future<Output = Result<T::Response>> {
+        let mut envelope = request.into_envelope(0, None, None);
+        envelope.idempotency_key = Some(idempotency_key.into());
+        let response = self.request_dynamic(receiver_id, envelope, T::NAME);
+        async move {
+            let (response, _) = response.await?;
+            T::Response::from_envelope(response)
+                .ok_or_else(|| anyhow!("received response of the wrong type"))
+        }
+    }
+
+    /// Records the idempotency key of a received request, returning false if the same
+    /// sender has already made a request with that key and it shouldn't be applied
+    /// again. Requests without a key are always applied.
+    ///
+    /// `sender` identifies who sent the request, such as their user id. It must stay the
+    /// same when they reconnect, as requests are retried on the new connection.
+    pub fn record_idempotency_key<T>(&self, sender: u64, envelope: &TypedEnvelope<T>) -> bool {
+        let Some(key) = envelope.idempotency_key.as_ref() else {
+            return true;
+        };
+
+        let entry = (sender, key.clone());
+        let mut seen = self.idempotency_keys.lock();
+        if !seen.keys.insert(entry.clone()) {
+            return false;
+        }
+        seen.order.push_back(entry);
+        if seen.order.len() > MAX_IDEMPOTENCY_KEYS {
+            if let Some(oldest) = seen.order.pop_front() {
+                seen.keys.remove(&oldest);
+            }
+        }
+        true
+    }
+
     pub fn forward_request<T: RequestMessage>(
         &self,
         sender_id: ConnectionId,
@@ -440,6 +794,7 @@ impl Peer {
                 message_id: response.id,
                 sender_id: receiver_id.into(),
                 original_sender_id: response.original_sender_id,
+                idempotency_key: None,
                 payload: T::Response::from_envelope(response)
                     .ok_or_else(|| anyhow!("received response of the wrong type"))?,
                 received_at,
@@ -455,26 +810,38 @@ impl Peer {
     pub fn request_dynamic(
         &self,
         receiver_id: ConnectionId,
-        mut envelope: proto::Envelope,
+        envelope: proto::Envelope,
         type_name: &'static str,
     ) -> impl Future<Output = Result<(proto::Envelope, Instant)>> {
         let (tx, rx) = oneshot::channel();
+        let max_outstanding = self.max_outstanding_requests.load(SeqCst);
         let send = self.connection_state(receiver_id).and_then(|connection| {
-            envelope.id = connection.next_message_id.fetch_add(1, SeqCst);
-            connection
-                .response_channels
-                .lock()
-                .as_mut()
-                .ok_or_else(|| anyhow!("connection was closed"))?
-                .insert(envelope.id, tx);
-            connection
-                .outgoing_tx
-                .unbounded_send(proto::Message::Envelope(envelope))
-                .map_err(|_| anyhow!("connection was closed"))?;
-            Ok(())
+            let sent = connection.send_request(envelope, tx, max_outstanding, false)?;
+            Ok((connection, sent))
         });
         async move {
-            send?;
+            let (connection, mut sent) = send?;
+            let _pending_response = loop {
+                match sent {
+                    SendRequest::Sent(pending_response) => break pending_response,
+                    SendRequest::Queued(QueuedRequest {
+                        envelope,
+                        response_tx,
+                        slot_freed,
+                    }) => {
+                        // The waiter is dropped if the connection closes, which the next
+                        // attempt reports as an error.
+                        slot_freed.await.ok();
+                        sent = connection.send_request(
+                            envelope,
+                            response_tx,
+                            max_outstanding,
+                            true,
+                        )?;
+                    }
+                }
+            };
+            drop(connection);
             let (response, received_at, _barrier) =
                 rx.await.map_err(|_| anyhow!("connection was closed"))?;
             if let Some(proto::envelope::Payload::Error(error)) = &response.payload {
@@ -553,6 +920,109 @@ impl Peer {
         Ok(())
     }
 
+    /// Sends a message that isn't a request, resolving once the receiver acknowledges it with
+    /// [`Peer::ack`].
+    pub fn send_acknowledged<T: EnvelopedMessage>(
+        &self,
+        receiver_id: ConnectionId,
+        message: T,
+    ) -> impl Future<Output = Result<()>> {
+        let (tx, rx) = oneshot::channel();
+        let send = self.connection_state(receiver_id).and_then(|connection| {
+            connection.send_with_ack(message.into_envelope(0, None, None), tx)
+        });
+        async move {
+            let _pending_ack = send?;
+            let (response, _, _barrier) = rx.await.map_err(|_| anyhow!("connection was closed"))?;
+            if let Some(proto::envelope::Payload::Error(error)) = &response.payload {
+                return Err(RpcError::from_proto(error, T::NAME));
+            }
+            Ok(())
+        }
+    }
+
+    /// Acknowledges a message sent with [`Peer::send_acknowledged`] by responding to it with an
+    /// empty [`proto::Ack`].
+    pub fn ack<T: EnvelopedMessage>(&self, envelope: &TypedEnvelope<T>) -> Result<()> {
+        let connection = self.connection_state(envelope.sender_id.into())?;
+        let message_id = connection
+            .next_message_id
+            .fetch_add(1, atomic::Ordering::SeqCst);
+        connection
+            .outgoing_tx
+            .unbounded_send(proto::Message::Envelope(proto::Ack {}.into_envelope(
+                message_id,
+                Some(envelope.message_id),
+                None,
+            )))?;
+        Ok(())
+    }
+
+    /// Sends a message on a logical channel of the given connection. The receiver delivers it
+    /// to the stream returned by [`Peer::open_logical_channel`] if that channel is open, drops it
+    /// if the channel fell behind, and delivers it with its regular incoming messages otherwise.
+    pub fn send_on_logical_channel<T: EnvelopedMessage>(
+        &self,
+        receiver_id: ConnectionId,
+        channel_id: u32,
+        message: T,
+    ) -> Result<()> {
+        let connection = self.connection_state(receiver_id)?;
+        let message_id = connection
+            .next_message_id
+            .fetch_add(1, atomic::Ordering::SeqCst);
+        let mut envelope = message.into_envelope(message_id, None, None);
+        envelope.logical_channel_id = Some(channel_id);
+        connection
+            .outgoing_tx
+            .unbounded_send(proto::Message::Envelope(envelope))?;
+        Ok(())
+    }
+
+    /// Starts receiving the messages sent on a logical channel of the given connection
+    /// separately from the connection's other incoming messages.
+    ///
+    /// Up to [`LOGICAL_CHANNEL_CAPACITY`] messages on the channel are queued until they're
+    /// consumed, so a slow consumer of one channel doesn't hold up the others. When a consumer
+    /// falls further behind than that, the returned stream ends and the channel's later messages
+    /// are dropped until it's closed or opened again.
+    pub fn open_logical_channel(
+        &self,
+        connection_id: ConnectionId,
+        channel_id: u32,
+    ) -> Result<BoxStream<'static, Box<dyn AnyTypedEnvelope>>> {
+        let connection = self.connection_state(connection_id)?;
+        let (tx, rx) = mpsc::channel(LOGICAL_CHANNEL_CAPACITY);
+        match connection.logical_channels.lock().entry(channel_id) {
+            hash_map::Entry::Occupied(entry) if matches!(entry.get(), LogicalChannel::Open(_)) => {
+                Err(anyhow!("logical channel {channel_id} is already open"))?
+            }
+            hash_map::Entry::Occupied(mut entry) => {
+                entry.insert(LogicalChannel::Open(tx));
+            }
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(LogicalChannel::Open(tx));
+            }
+        }
+
+        Ok(rx
+            .filter_map(move |(envelope, received_at)| {
+                future::ready(proto::build_typed_envelope(
+                    connection_id.into(),
+                    received_at,
+                    envelope,
+                ))
+            })
+            .boxed())
+    }
+
+    /// Stops routing messages on the given logical channel to its own stream.
+    pub fn close_logical_channel(&self, connection_id: ConnectionId, channel_id: u32) {
+        if let Ok(connection) = self.connection_state(connection_id) {
+            connection.logical_channels.lock().remove(&channel_id);
+        }
+    }
+
     pub fn send_dynamic(&self, receiver_id: ConnectionId, message: proto::Envelope) -> Result<()> {
         let connection = self.connection_state(receiver_id)?;
         connection
@@ -660,6 +1130,62 @@ impl Peer {
         Ok(())
     }
 
+    pub fn debug_snapshot(&self) -> PeerDebugSnapshot {
+        let mut connections = self
+            .connections
+            .read()
+            .iter()
+            .map(|(connection_id, state)| {
+                let outstanding_ids = |ids: Option<Vec<u32>>| {
+                    let mut ids = ids.unwrap_or_default();
+                    ids.sort_unstable();
+                    ids
+                };
+                ConnectionDebugSnapshot {
+                    connection_id: *connection_id,
+                    outstanding_request_ids: outstanding_ids(
+                        state
+                            .response_channels
+                            .lock()
+                            .as_ref()
+                            .map(|channels| channels.keys().copied().collect()),
+                    ),
+                    outstanding_stream_request_ids: outstanding_ids(
+                        state
+                            .stream_response_channels
+                            .lock()
+                            .as_ref()
+                            .map(|channels| channels.keys().copied().collect()),
+                    ),
+                    sent_message_count: state.next_message_id.load(SeqCst),
+                }
+            })
+            .collect::<Vec<_>>();
+        connections.sort_unstable_by_key(|connection| connection.connection_id);
+
+        let metrics = PeerDebugMetrics {
+            connection_count: connections.len(),
+            outstanding_request_count: connections
+                .iter()
+                .map(|connection| connection.outstanding_request_ids.len())
+                .sum(),
+            outstanding_stream_request_count: connections
+                .iter()
+                .map(|connection| connection.outstanding_stream_request_ids.len())
+                .sum(),
+            sent_message_count: connections
+                .iter()
+                .map(|connection| connection.sent_message_count as u64)
+                .sum(),
+        };
+
+        PeerDebugSnapshot {
+            epoch: self.epoch(),
+            connections,
+            metrics,
+        }
+    }
+
     fn connection_state(&self, connection_id: ConnectionId) -> Result<ConnectionState> {
         let connections = self.connections.read();
         let connection = connections
@@ -680,6 +1206,55 @@ impl Serialize for Peer {
     }
 }
 
+enum LogicalChannel {
+    Open(mpsc::Sender<(proto::Envelope, Instant)>),
+    /// The consumer fell too far behind, so the channel's messages are dropped.
+    Lagged,
+}
+
+/// Hands off a message on a logical channel without waiting, so that a slow consumer of one
+/// channel doesn't stall the whole connection. A consumer that falls too far behind has its
+/// stream ended, and the channel's later messages are dropped rather than delivered out of
+/// context. Returns the message if it doesn't belong to a logical channel that was opened.
+fn forward_to_logical_channel(
+    logical_channels: &Mutex<HashMap<u32, LogicalChannel>>,
+    connection_id: ConnectionId,
+    incoming: proto::Envelope,
+    received_at: Instant,
+) -> Option<(proto::Envelope, Instant)> {
+    let channel_id = incoming
+        .logical_channel_id
+        .filter(|_| incoming.responding_to.is_none());
+    let mut logical_channels = logical_channels.lock();
+    let Some(hash_map::Entry::Occupied(mut channel)) =
+        channel_id.map(|channel_id| logical_channels.entry(channel_id))
+    else {
+        return Some((incoming, received_at));
+    };
+    let LogicalChannel::Open(tx) = channel.get_mut() else {
+        tracing::debug!(
+            %connection_id,
+            channel_id = channel.key(),
+            "incoming rpc message: logical channel lagged, dropping message"
+        );
+        return None;
+    };
+    if let Err(error) = tx.try_send((incoming, received_at)) {
+        if error.is_full() {
+            tracing::warn!(
+                %connection_id,
+                channel_id = channel.key(),
+                "incoming rpc message: logical channel full, dropping its messages"
+            );
+            channel.insert(LogicalChannel::Lagged);
+        } else {
+            tracing::trace!(%connection_id, "incoming rpc message: logical channel closed");
+            channel.remove();
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -790,6 +1365,666 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    async fn test_debug_snapshot(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, _client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (_, io_task2, _server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+
+        let snapshot = client.debug_snapshot();
+        assert_eq!(snapshot.connections.len(), 1);
+        assert_eq!(
+            snapshot.connections[0].connection_id,
+            client_to_server_conn_id
+        );
+        assert!(snapshot.connections[0].outstanding_request_ids.is_empty());
+
+        // The server never responds, so the request stays outstanding.
+        let _response = client.request(client_to_server_conn_id, proto::Ping {});
+        executor.run_until_parked();
+
+        let snapshot = client.debug_snapshot();
+        assert_eq!(snapshot.metrics.connection_count, 1);
+        assert_eq!(snapshot.metrics.outstanding_request_count, 1);
+        assert_eq!(
+            snapshot.connections[0].connection_id,
+            client_to_server_conn_id
+        );
+        assert_eq!(snapshot.connections[0].outstanding_request_ids.len(), 1);
+        assert!(serde_json::to_string(&snapshot).is_ok());
+
+        let server_snapshot = server.debug_snapshot();
+        assert_eq!(server_snapshot.metrics.outstanding_request_count, 0);
+    }
+
+    #[gpui::test]
+    async fn test_max_outstanding_requests(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+        client.set_max_outstanding_requests(Some(1));
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, mut client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (_, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+        executor
+            .spawn(async move { while client_incoming.next().await.is_some() {} })
+            .detach();
+
+        let first_response =
+            executor.spawn(client.request(client_to_server_conn_id, proto::Ping {}));
+        let second_response =
+            executor.spawn(client.request(client_to_server_conn_id, proto::Ping {}));
+        executor.run_until_parked();
+
+        // Only the first request is sent until it has been answered.
+        let first_request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Ping>>()
+            .unwrap();
+        executor.run_until_parked();
+        assert!(server_incoming.next().now_or_never().is_none());
+        assert_eq!(client.outstanding_requests(client_to_server_conn_id), 1);
+
+        server
+            .respond(first_request.receipt(), proto::Ack {})
+            .unwrap();
+        first_response.await.unwrap();
+
+        let second_request = server_incoming
+            .next()
+            .await
+            .unwrap()
+            .into_any()
+            .downcast::<TypedEnvelope<proto::Ping>>()
+            .unwrap();
+        assert_eq!(client.outstanding_requests(client_to_server_conn_id), 1);
+        server
+            .respond(second_request.receipt(), proto::Ack {})
+            .unwrap();
+        second_response.await.unwrap();
+        assert_eq!(client.outstanding_requests(client_to_server_conn_id), 0);
+    }
+
+    #[gpui::test]
+    async fn test_abandoned_requests_free_their_slots(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+        client.set_max_outstanding_requests(Some(1));
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, mut client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (_, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+        executor
+            .spawn(async move { while client_incoming.next().await.is_some() {} })
+            .detach();
+        executor
+            .spawn({
+                let server = server.clone();
+                async move {
+                    // Pings are answered, and acknowledged messages never are.
+                    while let Some(envelope) = server_incoming.next().await {
+                        if let Ok(ping) =
+                            envelope.into_any().downcast::<TypedEnvelope<proto::Ping>>()
+                        {
+                            server.respond(ping.receipt(), proto::Ack {}).unwrap();
+                        }
+                    }
+                }
+            })
+            .detach();
+
+        // Messages awaiting an ack don't count as outstanding requests.
+        let unacked = client.send_acknowledged(
+            client_to_server_conn_id,
+            proto::UnshareProject { project_id: 1 },
+        );
+        executor.run_until_parked();
+        assert_eq!(client.outstanding_requests(client_to_server_conn_id), 0);
+        client
+            .request(client_to_server_conn_id, proto::Ping {})
+            .await
+            .unwrap();
+
+        // A request that is dropped before its response arrives frees its slot.
+        let abandoned = client.request(client_to_server_conn_id, proto::Ping {});
+        assert_eq!(client.outstanding_requests(client_to_server_conn_id), 1);
+        drop(abandoned);
+        assert_eq!(client.outstanding_requests(client_to_server_conn_id), 0);
+        client
+            .request(client_to_server_conn_id, proto::Ping {})
+            .await
+            .unwrap();
+        drop(unacked);
+    }
+
+    #[gpui::test]
+    async fn test_connection_metadata(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let metadata = HashMap::from_iter([
+            ("version".to_string(), "1.2.3".to_string()),
+            ("session_id".to_string(), "abc".to_string()),
+        ]);
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, _client_incoming) = client
+            .add_connection_with_metadata(client_to_server_conn, metadata.clone(), {
+                let executor = executor.clone();
+                move |duration| executor.timer(duration)
+            });
+        let (server_to_client_conn_id, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+
+        // The metadata arrives in the first message after connecting, which the peer consumes
+        // rather than passing on, so it can't be mistaken for the server's hello.
+        client
+            .send(client_to_server_conn_id, proto::Ping {})
+            .unwrap();
+        let first_message = server_incoming.next().await.unwrap().into_any();
+        assert!(first_message.is::<TypedEnvelope<proto::Ping>>());
+        assert_eq!(
+            server.connection_metadata(server_to_client_conn_id),
+            Some(metadata)
+        );
+        assert_eq!(
+            client.connection_metadata(client_to_server_conn_id),
+            Some(Default::default())
+        );
+    }
+
+    #[gpui::test]
+    async fn test_ping(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, _client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (_, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+
+        executor
+            .spawn({
+                let server = server.clone();
+                let executor = executor.clone();
+                async move {
+                    while let Some(envelope) = server_incoming.next().await {
+                        let envelope = envelope
+                            .into_any()
+                            .downcast::<TypedEnvelope<proto::Ping>>()
+                            .unwrap();
+                        // Hold the response back for a while on the fake clock.
+                        executor.timer(Duration::from_millis(30)).await;
+                        server.respond(envelope.receipt(), proto::Ack {}).unwrap();
+                    }
+                }
+            })
+            .detach();
+
+        let now = {
+            let executor = executor.clone();
+            move || executor.now()
+        };
+        let ping = executor.spawn(client.ping(client_to_server_conn_id, now.clone()));
+        executor.run_until_parked();
+        executor.advance_clock(Duration::from_millis(30));
+        let latency = ping.await.unwrap();
+        assert_eq!(latency, Duration::from_millis(30));
+        assert_eq!(client.debug_snapshot().metrics.outstanding_request_count, 0);
+
+        // Pings fail once the connection is gone.
+        client.disconnect(client_to_server_conn_id);
+        assert!(client.ping(client_to_server_conn_id, now).await.is_err());
+    }
+
+    #[gpui::test]
+    async fn test_acknowledged_messages(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (_, io_task1, mut client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (server_to_client_conn_id, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+        executor
+            .spawn(async move { while server_incoming.next().await.is_some() {} })
+            .detach();
+
+        let mut first_ack = server
+            .send_acknowledged(
+                server_to_client_conn_id,
+                proto::UnshareProject { project_id: 1 },
+            )
+            .boxed();
+        let second_ack = server.send_acknowledged(
+            server_to_client_conn_id,
+            proto::UnshareProject { project_id: 2 },
+        );
+
+        let mut messages = Vec::new();
+        for _ in 0..2 {
+            messages.push(
+                client_incoming
+                    .next()
+                    .await
+                    .unwrap()
+                    .into_any()
+                    .downcast::<TypedEnvelope<proto::UnshareProject>>()
+                    .unwrap(),
+            );
+        }
+        assert_eq!(messages[1].payload.project_id, 2);
+
+        // Only the acknowledged message's future resolves.
+        client.ack(&messages[1]).unwrap();
+        second_ack.await.unwrap();
+        executor.run_until_parked();
+        assert!((&mut first_ack).now_or_never().is_none());
+
+        client.ack(&messages[0]).unwrap();
+        first_ack.await.unwrap();
+    }
+
+    #[gpui::test]
+    async fn test_shutdown_all(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let mut connection_ids = Vec::new();
+        let mut server_incomings = Vec::new();
+        for _ in 0..2 {
+            let (client_to_server_conn, server_to_client_conn, _kill) =
+                Connection::in_memory(executor.clone());
+            let (connection_id, io_task1, _client_incoming) =
+                client.add_test_connection(client_to_server_conn, executor.clone());
+            let (_, io_task2, server_incoming) =
+                server.add_test_connection(server_to_client_conn, executor.clone());
+            executor.spawn(io_task1).detach();
+            executor.spawn(io_task2).detach();
+            connection_ids.push(connection_id);
+            server_incomings.push(server_incoming);
+        }
+        assert_eq!(client.debug_snapshot().metrics.connection_count, 2);
+
+        // The server never responds, so this request is still pending at shutdown.
+        let pending_request = executor.spawn(client.request(connection_ids[0], proto::Ping {}));
+        executor.run_until_parked();
+        assert_eq!(client.debug_snapshot().metrics.outstanding_request_count, 1);
+
+        client.shutdown_all().await;
+        assert_eq!(client.debug_snapshot().metrics.connection_count, 0);
+        assert_eq!(
+            pending_request.await.unwrap_err().to_string(),
+            "connection was closed"
+        );
+        for connection_id in connection_ids {
+            assert!(client.send(connection_id, proto::Ping {}).is_err());
+        }
+    }
+
+    #[gpui::test]
+    async fn test_idempotent_requests(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, _client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (_, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+
+        let applied_count = Arc::new(AtomicU32::new(0));
+        executor
+            .spawn({
+                let server = server.clone();
+                let applied_count = applied_count.clone();
+                async move {
+                    while let Some(envelope) = server_incoming.next().await {
+                        let envelope = envelope
+                            .into_any()
+                            .downcast::<TypedEnvelope<proto::Ping>>()
+                            .unwrap();
+                        if server.record_idempotency_key(1, &envelope) {
+                            applied_count.fetch_add(1, SeqCst);
+                        }
+                        server.respond(envelope.receipt(), proto::Ack {}).unwrap();
+                    }
+                }
+            })
+            .detach();
+
+        // A retried request is acknowledged, but only applied once.
+        client
+            .request_idempotent(client_to_server_conn_id, proto::Ping {}, "a")
+            .await
+            .unwrap();
+        client
+            .request_idempotent(client_to_server_conn_id, proto::Ping {}, "a")
+            .await
+            .unwrap();
+        assert_eq!(applied_count.load(SeqCst), 1);
+
+        // Requests with a different key, or without one, are applied.
+        client
+            .request_idempotent(client_to_server_conn_id, proto::Ping {}, "b")
+            .await
+            .unwrap();
+        client
+            .request(client_to_server_conn_id, proto::Ping {})
+            .await
+            .unwrap();
+        client
+            .request(client_to_server_conn_id, proto::Ping {})
+            .await
+            .unwrap();
+        assert_eq!(applied_count.load(SeqCst), 4);
+    }
+
+    #[gpui::test]
+    async fn test_idempotency_keys_are_scoped_to_sender(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client_a = Peer::new(0);
+        let client_b = Peer::new(0);
+
+        let (client_a_conn, server_a_conn, _kill_a) = Connection::in_memory(executor.clone());
+        let (client_b_conn, server_b_conn, _kill_b) = Connection::in_memory(executor.clone());
+        let (client_a_conn_id, io_task1, _client_a_incoming) =
+            client_a.add_test_connection(client_a_conn, executor.clone());
+        let (client_b_conn_id, io_task2, _client_b_incoming) =
+            client_b.add_test_connection(client_b_conn, executor.clone());
+        let (_, io_task3, server_a_incoming) =
+            server.add_test_connection(server_a_conn, executor.clone());
+        let (_, io_task4, server_b_incoming) =
+            server.add_test_connection(server_b_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+        executor.spawn(io_task3).detach();
+        executor.spawn(io_task4).detach();
+
+        let applied_count = Arc::new(AtomicU32::new(0));
+        for (sender, mut server_incoming) in [(1, server_a_incoming), (2, server_b_incoming)] {
+            executor
+                .spawn({
+                    let server = server.clone();
+                    let applied_count = applied_count.clone();
+                    async move {
+                        while let Some(envelope) = server_incoming.next().await {
+                            let envelope = envelope
+                                .into_any()
+                                .downcast::<TypedEnvelope<proto::Ping>>()
+                                .unwrap();
+                            if server.record_idempotency_key(sender, &envelope) {
+                                applied_count.fetch_add(1, SeqCst);
+                            }
+                            server.respond(envelope.receipt(), proto::Ack {}).unwrap();
+                        }
+                    }
+                })
+                .detach();
+        }
+
+        // Another sender using the same key doesn't suppress the request.
+        client_a
+            .request_idempotent(client_a_conn_id, proto::Ping {}, "a")
+            .await
+            .unwrap();
+        client_b
+            .request_idempotent(client_b_conn_id, proto::Ping {}, "a")
+            .await
+            .unwrap();
+        assert_eq!(applied_count.load(SeqCst), 2);
+
+        // Each sender's own retries are still only applied once.
+        client_a
+            .request_idempotent(client_a_conn_id, proto::Ping {}, "a")
+            .await
+            .unwrap();
+        client_b
+            .request_idempotent(client_b_conn_id, proto::Ping {}, "a")
+            .await
+            .unwrap();
+        assert_eq!(applied_count.load(SeqCst), 2);
+    }
+
+    #[gpui::test]
+    async fn test_idempotency_keys_survive_reconnects(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+        let applied_count = Arc::new(AtomicU32::new(0));
+
+        for _ in 0..2 {
+            let (client_to_server_conn, server_to_client_conn, _kill) =
+                Connection::in_memory(executor.clone());
+            let (client_to_server_conn_id, io_task1, _client_incoming) =
+                client.add_test_connection(client_to_server_conn, executor.clone());
+            let (server_to_client_conn_id, io_task2, mut server_incoming) =
+                server.add_test_connection(server_to_client_conn, executor.clone());
+            executor.spawn(io_task1).detach();
+            executor.spawn(io_task2).detach();
+
+            executor
+                .spawn({
+                    let server = server.clone();
+                    let applied_count = applied_count.clone();
+                    async move {
+                        while let Some(envelope) = server_incoming.next().await {
+                            let envelope = envelope
+                                .into_any()
+                                .downcast::<TypedEnvelope<proto::Ping>>()
+                                .unwrap();
+                            // The same sender on every connection, like a user reconnecting.
+                            if server.record_idempotency_key(1, &envelope) {
+                                applied_count.fetch_add(1, SeqCst);
+                            }
+                            server.respond(envelope.receipt(), proto::Ack {}).unwrap();
+                        }
+                    }
+                })
+                .detach();
+
+            // The request is retried on a new connection after the first one drops.
+            client
+                .request_idempotent(client_to_server_conn_id, proto::Ping {}, "a")
+                .await
+                .unwrap();
+            client.disconnect(client_to_server_conn_id);
+            server.disconnect(server_to_client_conn_id);
+        }
+        assert_eq!(applied_count.load(SeqCst), 1);
+    }
+
+    #[gpui::test]
+    async fn test_logical_channels(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, _client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (server_to_client_conn_id, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+
+        let mut channel_a = server
+            .open_logical_channel(server_to_client_conn_id, 1)
+            .unwrap();
+        let mut channel_b = server
+            .open_logical_channel(server_to_client_conn_id, 2)
+            .unwrap();
+        assert!(server
+            .open_logical_channel(server_to_client_conn_id, 1)
+            .is_err());
+
+        // Nobody reads from channel A, but that doesn't hold up channel B.
+        for id in 0..10 {
+            client
+                .send_on_logical_channel(client_to_server_conn_id, 1, proto::Test { id })
+                .unwrap();
+        }
+        client
+            .send_on_logical_channel(client_to_server_conn_id, 2, proto::Test { id: 100 })
+            .unwrap();
+        let message = channel_b.next().await.unwrap();
+        assert_eq!(
+            message
+                .into_any()
+                .downcast::<TypedEnvelope<proto::Test>>()
+                .unwrap()
+                .payload,
+            proto::Test { id: 100 }
+        );
+
+        for id in 0..10 {
+            let message = channel_a.next().await.unwrap();
+            assert_eq!(
+                message
+                    .into_any()
+                    .downcast::<TypedEnvelope<proto::Test>>()
+                    .unwrap()
+                    .payload,
+                proto::Test { id }
+            );
+        }
+
+        // Messages on channels that aren't open are delivered as usual.
+        server.close_logical_channel(server_to_client_conn_id, 2);
+        client
+            .send_on_logical_channel(client_to_server_conn_id, 2, proto::Test { id: 200 })
+            .unwrap();
+        let message = server_incoming.next().await.unwrap();
+        assert_eq!(
+            message
+                .into_any()
+                .downcast::<TypedEnvelope<proto::Test>>()
+                .unwrap()
+                .payload,
+            proto::Test { id: 200 }
+        );
+    }
+
+    #[gpui::test]
+    async fn test_logical_channel_overflow(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let server = Peer::new(0);
+        let client = Peer::new(0);
+
+        let (client_to_server_conn, server_to_client_conn, _kill) =
+            Connection::in_memory(executor.clone());
+        let (client_to_server_conn_id, io_task1, _client_incoming) =
+            client.add_test_connection(client_to_server_conn, executor.clone());
+        let (server_to_client_conn_id, io_task2, mut server_incoming) =
+            server.add_test_connection(server_to_client_conn, executor.clone());
+        executor.spawn(io_task1).detach();
+        executor.spawn(io_task2).detach();
+
+        let mut channel = server
+            .open_logical_channel(server_to_client_conn_id, 1)
+            .unwrap();
+
+        // Once the channel's queue is full, the next message closes it instead of growing it.
+        let sent = LOGICAL_CHANNEL_CAPACITY as u64 + 2;
+        for id in 0..sent {
+            client
+                .send_on_logical_channel(client_to_server_conn_id, 1, proto::Test { id })
+                .unwrap();
+        }
+        executor.run_until_parked();
+
+        let mut received = Vec::new();
+        while let Some(message) = channel.next().await {
+            received.push(
+                message
+                    .into_any()
+                    .downcast::<TypedEnvelope<proto::Test>>()
+                    .unwrap()
+                    .payload
+                    .id,
+            );
+        }
+        assert_eq!(received, (0..sent - 1).collect::<Vec<_>>());
+
+        // Later messages on the lagged channel are dropped instead of being delivered as usual.
+        client
+            .send_on_logical_channel(client_to_server_conn_id, 1, proto::Test { id: 1000 })
+            .unwrap();
+        client
+            .send(client_to_server_conn_id, proto::Test { id: 1001 })
+            .unwrap();
+        let message = server_incoming.next().await.unwrap();
+        assert_eq!(
+            message
+                .into_any()
+                .downcast::<TypedEnvelope<proto::Test>>()
+                .unwrap()
+                .payload,
+            proto::Test { id: 1001 }
+        );
+
+        // Opening the channel again starts delivering its messages again.
+        let mut channel = server
+            .open_logical_channel(server_to_client_conn_id, 1)
+            .unwrap();
+        client
+            .send_on_logical_channel(client_to_server_conn_id, 1, proto::Test { id: 1002 })
+            .unwrap();
+        let message = channel.next().await.unwrap();
+        assert_eq!(
+            message
+                .into_any()
+                .downcast::<TypedEnvelope<proto::Test>>()
+                .unwrap()
+                .payload,
+            proto::Test { id: 1002 }
+        );
+    }
+
     #[gpui::test(iterations = 50)]
     async fn test_order_of_response_and_incoming(cx: &mut TestAppContext) {
         let executor = cx.executor();
@@ -1036,6 +2271,72 @@ mod tests {
             .is_err());
     }
 
+    #[gpui::test(iterations = 50)]
+    async fn test_disconnect_during_request(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let (client_conn, mut server_conn, _kill) = Connection::in_memory(executor.clone());
+
+        let client = Peer::new(0);
+        let (connection_id, io_handler, mut incoming) =
+            client.add_test_connection(client_conn, executor.clone());
+        executor.spawn(io_handler).detach();
+        executor
+            .spawn(async move { incoming.next().await })
+            .detach();
+
+        let response = executor.spawn(client.request(connection_id, proto::Ping {}));
+        let _request = server_conn.rx.next().await.unwrap().unwrap();
+
+        client.disconnect(connection_id);
+        assert_eq!(
+            response.await.unwrap_err().to_string(),
+            "connection was closed"
+        );
+        assert_eq!(
+            client
+                .request(connection_id, proto::Ping {})
+                .await
+                .unwrap_err()
+                .to_string(),
+            "no such connection: 0/0"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_keepalive_and_receive_timeout(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let (client_conn, mut server_conn, _kill) = Connection::in_memory(executor.clone());
+
+        let client = Peer::new(0);
+        let (connection_id, io_handler, _incoming) =
+            client.add_test_connection(client_conn, executor.clone());
+        let io_handler = executor.spawn(io_handler);
+
+        // The connection's timers run on the executor's fake clock.
+        executor.advance_clock(KEEPALIVE_INTERVAL);
+        assert!(matches!(
+            server_conn.rx.next().await,
+            Some(Ok(WebSocketMessage::Ping(_)))
+        ));
+
+        // Any message from the other side restarts the receive timeout.
+        server_conn
+            .tx
+            .send(WebSocketMessage::Pong(Default::default()))
+            .await
+            .unwrap();
+        executor.run_until_parked();
+        executor.advance_clock(RECEIVE_TIMEOUT - KEEPALIVE_INTERVAL);
+        assert!(client.connections.read().contains_key(&connection_id));
+
+        executor.advance_clock(KEEPALIVE_INTERVAL);
+        assert_eq!(
+            io_handler.await.unwrap_err().to_string(),
+            "delay between messages too long"
+        );
+        assert!(!client.connections.read().contains_key(&connection_id));
+    }
+
     #[gpui::test(iterations = 50)]
     async fn test_io_error(cx: &mut TestAppContext) {
         let executor = cx.executor();
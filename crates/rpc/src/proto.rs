@@ -3,18 +3,68 @@
 use anyhow::anyhow;
 use async_tungstenite::tungstenite::Message as WebSocketMessage;
 use futures::{SinkExt as _, StreamExt as _};
+use parking_lot::Mutex;
 pub use proto::{Message as _, *};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering::SeqCst},
+    Arc,
+};
 use std::time::Instant;
 use std::{fmt::Debug, io};
 
 const KIB: usize = 1024;
 const MIB: usize = KIB * 1024;
 const MAX_BUFFER_LEN: usize = MIB;
+const MAX_POOLED_BUFFERS: usize = 16;
 
 /// A stream of protobuf messages.
 pub struct MessageStream<S> {
     stream: S,
     encoding_buffer: Vec<u8>,
+    buffer_pool: Arc<BufferPool>,
+}
+
+/// Recycles the byte buffers of received frames as the buffers of outgoing ones, so
+/// the reading and writing halves of a connection don't allocate for every message.
+#[derive(Default)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    hit_count: AtomicUsize,
+    miss_count: AtomicUsize,
+}
+
+impl BufferPool {
+    fn take(&self) -> Vec<u8> {
+        if let Some(buffer) = self.buffers.lock().pop() {
+            self.hit_count.fetch_add(1, SeqCst);
+            buffer
+        } else {
+            self.miss_count.fetch_add(1, SeqCst);
+            Vec::new()
+        }
+    }
+
+    fn put(&self, mut buffer: Vec<u8>) {
+        if buffer.capacity() > MAX_BUFFER_LEN {
+            return;
+        }
+
+        let mut buffers = self.buffers.lock();
+        if buffers.len() < MAX_POOLED_BUFFERS {
+            buffer.clear();
+            buffers.push(buffer);
+        }
+    }
+
+    /// The number of buffers that were reused rather than allocated.
+    pub fn hit_count(&self) -> usize {
+        self.hit_count.load(SeqCst)
+    }
+
+    /// The number of buffers that had to be allocated because the pool was empty.
+    pub fn miss_count(&self) -> usize {
+        self.miss_count.load(SeqCst)
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -27,9 +77,14 @@ pub enum Message {
 
 impl<S> MessageStream<S> {
     pub fn new(stream: S) -> Self {
+        Self::with_buffer_pool(stream, Default::default())
+    }
+
+    pub fn with_buffer_pool(stream: S, buffer_pool: Arc<BufferPool>) -> Self {
         Self {
             stream,
             encoding_buffer: Vec::new(),
+            buffer_pool,
         }
     }
 
@@ -55,9 +110,13 @@ where
                 message
                     .encode(&mut self.encoding_buffer)
                     .map_err(io::Error::from)?;
-                let buffer =
-                    zstd::stream::encode_all(self.encoding_buffer.as_slice(), COMPRESSION_LEVEL)
-                        .unwrap();
+                let mut buffer = self.buffer_pool.take();
+                zstd::stream::copy_encode(
+                    self.encoding_buffer.as_slice(),
+                    &mut buffer,
+                    COMPRESSION_LEVEL,
+                )
+                .unwrap();
 
                 self.encoding_buffer.clear();
                 self.encoding_buffer.shrink_to(MAX_BUFFER_LEN);
@@ -89,6 +148,7 @@ where
             match bytes? {
                 WebSocketMessage::Binary(bytes) => {
                     zstd::stream::copy_decode(bytes.as_slice(), &mut self.encoding_buffer).unwrap();
+                    self.buffer_pool.put(bytes);
                     let envelope = Envelope::decode(self.encoding_buffer.as_slice())
                         .map_err(io::Error::from)?;
 
@@ -141,4 +201,39 @@ mod tests {
         stream.read().await.unwrap();
         assert!(stream.encoding_buffer.capacity() <= MAX_BUFFER_LEN);
     }
+
+    #[gpui::test]
+    async fn test_buffer_pool_reuse() {
+        let buffer_pool = Arc::new(BufferPool::default());
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let mut sink =
+            MessageStream::with_buffer_pool(tx.sink_map_err(|_| anyhow!("")), buffer_pool.clone());
+        let mut stream = MessageStream::with_buffer_pool(rx.map(anyhow::Ok), buffer_pool.clone());
+
+        for i in 0..100 {
+            sink.write(Message::Envelope(Envelope {
+                id: i,
+                payload: Some(envelope::Payload::UpdateWorktree(UpdateWorktree {
+                    root_name: format!("worktree-{i}"),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+            let (Message::Envelope(envelope), _) = stream.read().await.unwrap() else {
+                panic!("expected an envelope");
+            };
+            assert_eq!(envelope.id, i);
+        }
+
+        // Only the first message allocates; the rest reuse the buffer it was received in.
+        assert_eq!(buffer_pool.miss_count(), 1);
+        assert_eq!(buffer_pool.hit_count(), 99);
+
+        // Oversized buffers aren't kept around.
+        buffer_pool.put(Vec::with_capacity(MAX_BUFFER_LEN + 1));
+        assert_eq!(buffer_pool.buffers.lock().len(), 1);
+    }
 }
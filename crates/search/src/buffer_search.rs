@@ -14,8 +14,9 @@ use editor::{
 use futures::channel::oneshot;
 use gpui::{
     actions, div, impl_actions, Action, AppContext, ClickEvent, EventEmitter, FocusableView, Hsla,
-    InteractiveElement as _, IntoElement, KeyContext, ParentElement as _, Render, ScrollHandle,
-    Styled, Subscription, Task, TextStyle, View, ViewContext, VisualContext as _, WindowContext,
+    InteractiveElement as _, IntoElement, KeyContext, Model, ModelContext, ParentElement as _,
+    Render, ScrollHandle, Styled, Subscription, Task, TextStyle, View, ViewContext,
+    VisualContext as _, WindowContext,
 };
 use project::{
     search::SearchQuery,
@@ -23,7 +24,7 @@ use project::{
 };
 use serde::Deserialize;
 use settings::Settings;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use theme::ThemeSettings;
 
 use ui::{h_flex, prelude::*, IconButton, IconName, Tooltip, BASE_REM_SIZE_IN_PX};
@@ -31,7 +32,8 @@ use util::ResultExt;
 use workspace::{
     item::ItemHandle,
     searchable::{Direction, SearchEvent, SearchableItemHandle, WeakSearchableItemHandle},
-    ToolbarItemEvent, ToolbarItemLocation, ToolbarItemView, Workspace,
+    SearchHistoryKind, ToolbarItemEvent, ToolbarItemLocation, ToolbarItemView, Workspace,
+    WorkspaceId, WORKSPACE_DB,
 };
 
 pub use registrar::DivRegistrar;
@@ -40,6 +42,7 @@ use registrar::{ForDeployed, ForDismissed, SearchActionsRegistrar, WithResults};
 const MIN_INPUT_WIDTH_REMS: f32 = 10.;
 const MAX_INPUT_WIDTH_REMS: f32 = 30.;
 const MAX_BUFFER_SEARCH_HISTORY_SIZE: usize = 50;
+const SEARCH_HISTORY_SAVE_DEBOUNCE: Duration = Duration::from_secs(1);
 
 #[derive(PartialEq, Clone, Deserialize)]
 pub struct Deploy {
@@ -74,6 +77,79 @@ pub fn init(cx: &mut AppContext) {
         .detach();
 }
 
+/// The buffer search history of a workspace, shared by the search bars of all of its panes.
+///
+/// It is restored from the workspace database when created, and saved back shortly after
+/// each search.
+pub struct BufferSearchHistory {
+    history: SearchHistory,
+    workspace_id: Option<WorkspaceId>,
+    _restore: Option<Task<()>>,
+    save: Option<Task<()>>,
+}
+
+impl BufferSearchHistory {
+    pub fn new(workspace_id: Option<WorkspaceId>, cx: &mut ModelContext<Self>) -> Self {
+        let restore = workspace_id.map(|workspace_id| {
+            cx.spawn(|this, mut cx| async move {
+                let queries = cx
+                    .background_executor()
+                    .spawn(async move {
+                        WORKSPACE_DB
+                            .search_history_for_workspace(workspace_id, SearchHistoryKind::Buffer)
+                    })
+                    .await
+                    .log_err();
+                if let Some(queries) = queries {
+                    this.update(&mut cx, |this, _| {
+                        // Keep any searches made while the history was being read.
+                        let mut restored = queries;
+                        restored.extend(this.history.queries().iter().cloned());
+                        this.history.restore(restored);
+                    })
+                    .ok();
+                }
+            })
+        });
+
+        Self {
+            history: SearchHistory::new(
+                Some(MAX_BUFFER_SEARCH_HISTORY_SIZE),
+                project::search_history::QueryInsertionBehavior::ReplacePreviousIfContains,
+            ),
+            workspace_id,
+            _restore: restore,
+            save: None,
+        }
+    }
+
+    fn add(
+        &mut self,
+        cursor: &mut SearchHistoryCursor,
+        query: String,
+        cx: &mut ModelContext<Self>,
+    ) {
+        self.history.add(cursor, query);
+
+        // Queries are added as they are typed, so wait for a pause before saving them.
+        if let Some(workspace_id) = self.workspace_id {
+            self.save = Some(cx.spawn(|this, mut cx| async move {
+                cx.background_executor()
+                    .timer(SEARCH_HISTORY_SAVE_DEBOUNCE)
+                    .await;
+                let Ok(queries) = this.update(&mut cx, |this, _| this.history.queries().to_vec())
+                else {
+                    return;
+                };
+                WORKSPACE_DB
+                    .save_search_history(workspace_id, SearchHistoryKind::Buffer, queries)
+                    .await
+                    .log_err();
+            }));
+        }
+    }
+}
+
 pub struct BufferSearchBar {
     query_editor: View<Editor>,
     query_editor_focused: bool,
@@ -89,7 +165,7 @@ pub struct BufferSearchBar {
     default_options: SearchOptions,
     query_contains_error: bool,
     dismissed: bool,
-    search_history: SearchHistory,
+    search_history: Model<BufferSearchHistory>,
     search_history_cursor: SearchHistoryCursor,
     replace_enabled: bool,
     selection_search_enabled: bool,
@@ -521,10 +597,7 @@ impl BufferSearchBar {
             pending_search: None,
             query_contains_error: false,
             dismissed: true,
-            search_history: SearchHistory::new(
-                Some(MAX_BUFFER_SEARCH_HISTORY_SIZE),
-                project::search_history::QueryInsertionBehavior::ReplacePreviousIfContains,
-            ),
+            search_history: cx.new_model(|cx| BufferSearchHistory::new(None, cx)),
             search_history_cursor: Default::default(),
             active_search: None,
             replace_enabled: false,
@@ -535,6 +608,17 @@ impl BufferSearchBar {
         }
     }
 
+    /// Returns the search history, which may be shared with the search bars of other panes.
+    pub fn search_history(&self) -> &Model<BufferSearchHistory> {
+        &self.search_history
+    }
+
+    /// Replaces this search bar's history with one shared with other search bars.
+    pub fn set_search_history(&mut self, search_history: Model<BufferSearchHistory>) {
+        self.search_history = search_history;
+        self.search_history_cursor.reset();
+    }
+
     pub fn is_dismissed(&self) -> bool {
         self.dismissed
     }
@@ -1000,8 +1084,9 @@ impl BufferSearchBar {
                                 .insert(active_searchable_item.downgrade(), matches);
 
                             this.update_match_index(cx);
-                            this.search_history
-                                .add(&mut this.search_history_cursor, query_text);
+                            this.search_history.update(cx, |search_history, cx| {
+                                search_history.add(&mut this.search_history_cursor, query_text, cx)
+                            });
                             if !this.dismissed {
                                 let matches = this
                                     .searchable_items_with_matches
@@ -1067,11 +1152,12 @@ impl BufferSearchBar {
     }
 
     fn next_history_query(&mut self, _: &NextHistoryQuery, cx: &mut ViewContext<Self>) {
-        if let Some(new_query) = self
-            .search_history
-            .next(&mut self.search_history_cursor)
-            .map(str::to_string)
-        {
+        if let Some(new_query) = self.search_history.update(cx, |search_history, _| {
+            search_history
+                .history
+                .next(&mut self.search_history_cursor)
+                .map(str::to_string)
+        }) {
             drop(self.search(&new_query, Some(self.search_options), cx));
         } else {
             self.search_history_cursor.reset();
@@ -1083,7 +1169,9 @@ impl BufferSearchBar {
         if self.query(cx).is_empty() {
             if let Some(new_query) = self
                 .search_history
-                .current(&mut self.search_history_cursor)
+                .read(cx)
+                .history
+                .current(&self.search_history_cursor)
                 .map(str::to_string)
             {
                 drop(self.search(&new_query, Some(self.search_options), cx));
@@ -1091,11 +1179,12 @@ impl BufferSearchBar {
             }
         }
 
-        if let Some(new_query) = self
-            .search_history
-            .previous(&mut self.search_history_cursor)
-            .map(str::to_string)
-        {
+        if let Some(new_query) = self.search_history.update(cx, |search_history, _| {
+            search_history
+                .history
+                .previous(&mut self.search_history_cursor)
+                .map(str::to_string)
+        }) {
             drop(self.search(&new_query, Some(self.search_options), cx));
         }
     }
@@ -1927,6 +2016,53 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_shared_search_query_history(cx: &mut TestAppContext) {
+        init_globals(cx);
+        let buffer = cx.new_model(|cx| Buffer::local("one two three", cx));
+        let cx = cx.add_empty_window();
+        let editor = cx.new_view(|cx| Editor::for_buffer(buffer.clone(), None, cx));
+
+        let search_bar = cx.new_view(|cx| {
+            let mut search_bar = BufferSearchBar::new(cx);
+            search_bar.set_active_pane_item(Some(&editor), cx);
+            search_bar.show(cx);
+            search_bar
+        });
+        let other_search_bar = cx.new_view(|cx| {
+            let mut other_search_bar = BufferSearchBar::new(cx);
+            other_search_bar.set_search_history(search_bar.read(cx).search_history().clone());
+            other_search_bar.set_active_pane_item(Some(&editor), cx);
+            other_search_bar.show(cx);
+            other_search_bar
+        });
+
+        search_bar
+            .update(cx, |search_bar, cx| search_bar.search("one", None, cx))
+            .await
+            .unwrap();
+        other_search_bar
+            .update(cx, |search_bar, cx| search_bar.search("two", None, cx))
+            .await
+            .unwrap();
+
+        // Each search bar sees the queries made in the other.
+        search_bar.update(cx, |search_bar, cx| {
+            drop(search_bar.search("", None, cx));
+            search_bar.search_history_cursor.reset();
+            search_bar.previous_history_query(&PreviousHistoryQuery, cx);
+        });
+        search_bar.update(cx, |search_bar, cx| {
+            assert_eq!(search_bar.query(cx), "two");
+        });
+        other_search_bar.update(cx, |search_bar, cx| {
+            search_bar.previous_history_query(&PreviousHistoryQuery, cx);
+        });
+        other_search_bar.update(cx, |search_bar, cx| {
+            assert_eq!(search_bar.query(cx), "one");
+        });
+    }
+
     #[gpui::test]
     async fn test_replace_simple(cx: &mut TestAppContext) {
         let (editor, search_bar, cx) = init_test(cx);
@@ -41,8 +41,8 @@ use util::paths::PathMatcher;
 use workspace::{
     item::{BreadcrumbText, Item, ItemEvent, ItemHandle},
     searchable::{Direction, SearchableItem, SearchableItemHandle},
-    DeploySearch, ItemNavHistory, NewSearch, ToolbarItemEvent, ToolbarItemLocation,
-    ToolbarItemView, Workspace, WorkspaceId,
+    DeploySearch, ItemNavHistory, NewSearch, SearchHistoryKind, ToolbarItemEvent,
+    ToolbarItemLocation, ToolbarItemView, Workspace, WorkspaceId, WORKSPACE_DB,
 };
 
 const MIN_INPUT_WIDTH_REMS: f32 = 15.;
@@ -60,7 +60,11 @@ impl Global for ActiveSettings {}
 
 pub fn init(cx: &mut AppContext) {
     cx.set_global(ActiveSettings::default());
-    cx.observe_new_views(|workspace: &mut Workspace, _cx| {
+    cx.observe_new_views(|workspace: &mut Workspace, cx| {
+        if let Some(workspace_id) = workspace.database_id() {
+            restore_search_history(workspace_id, workspace.project(), cx);
+        }
+
         register_workspace_action(workspace, move |search_bar, _: &FocusSearch, cx| {
             search_bar.focus_search(cx);
         });
@@ -118,6 +122,31 @@ pub fn init(cx: &mut AppContext) {
     .detach();
 }
 
+/// Restores the project search history saved for the given workspace, unless the project has
+/// already been searched.
+fn restore_search_history(
+    workspace_id: WorkspaceId,
+    project: &Model<Project>,
+    cx: &mut AppContext,
+) {
+    let project = project.downgrade();
+    cx.spawn(|mut cx| async move {
+        let queries = cx
+            .background_executor()
+            .spawn(async move {
+                WORKSPACE_DB.search_history_for_workspace(workspace_id, SearchHistoryKind::Project)
+            })
+            .await?;
+        project.update(&mut cx, |project, _| {
+            let search_history = project.search_history_mut(SearchInputKind::Query);
+            if search_history.queries().is_empty() {
+                search_history.restore(queries);
+            }
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
 fn is_contains_uppercase(str: &str) -> bool {
     str.chars().any(|c| c.is_uppercase())
 }
@@ -926,9 +955,35 @@ impl ProjectSearchView {
     fn search(&mut self, cx: &mut ViewContext<Self>) {
         if let Some(query) = self.build_search_query(cx) {
             self.model.update(cx, |model, cx| model.search(query, cx));
+            self.save_search_history(cx);
         }
     }
 
+    fn save_search_history(&self, cx: &mut ViewContext<Self>) {
+        let Some(workspace_id) = self
+            .workspace
+            .upgrade()
+            .and_then(|workspace| workspace.read(cx).database_id())
+        else {
+            return;
+        };
+        let queries = self
+            .model
+            .read(cx)
+            .project
+            .read(cx)
+            .search_history(SearchInputKind::Query)
+            .queries()
+            .to_vec();
+        cx.background_executor()
+            .spawn(WORKSPACE_DB.save_search_history(
+                workspace_id,
+                SearchHistoryKind::Project,
+                queries,
+            ))
+            .detach_and_log_err(cx);
+    }
+
     pub fn search_query_text(&self, cx: &WindowContext) -> String {
         self.query_editor.read(cx).text(cx)
     }
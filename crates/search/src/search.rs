@@ -1,5 +1,5 @@
 use bitflags::bitflags;
-pub use buffer_search::BufferSearchBar;
+pub use buffer_search::{BufferSearchBar, BufferSearchHistory};
 use editor::SearchSettings;
 use gpui::{actions, Action, AppContext, IntoElement};
 use project::search::SearchQuery;
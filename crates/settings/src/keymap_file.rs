@@ -110,9 +110,15 @@ impl KeymapFile {
                         )
                     })
                     .log_err()
-                    .map(|action| KeyBinding::load(&keystroke, action, context.as_deref()))
+                    .and_then(|action| {
+                        KeyBinding::load(&keystroke, action, context.as_deref())
+                            .with_context(|| {
+                                format!("invalid keystroke {keystroke} or context {context:?}")
+                            })
+                            .log_err()
+                    })
                 })
-                .collect::<Result<Vec<_>>>()?;
+                .collect::<Vec<_>>();
 
             cx.bind_keys(bindings);
         }
@@ -1,14 +1,48 @@
 use std::{
     cell::RefCell,
     ffi::{CStr, CString},
+    fmt,
     marker::PhantomData,
     path::Path,
     ptr,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use libsqlite3_sys::*;
 
+/// An error reported by a call into SQLite.
+#[derive(Debug)]
+pub struct SqliteError {
+    /// The extended result code of the failed call.
+    pub code: i32,
+    pub message: Option<String>,
+}
+
+impl SqliteError {
+    /// Whether the error means that the database file is corrupt, or isn't a database at all.
+    pub fn is_corruption(&self) -> bool {
+        matches!(self.code & 0xff, SQLITE_CORRUPT | SQLITE_NOTADB)
+    }
+
+    /// Whether the error is transient contention, because the database is busy or a table is
+    /// locked by another connection.
+    pub fn is_busy(&self) -> bool {
+        matches!(self.code & 0xff, SQLITE_BUSY | SQLITE_LOCKED)
+    }
+}
+
+impl fmt::Display for SqliteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Sqlite call failed with code {} and message: {:?}",
+            self.code as isize, self.message
+        )
+    }
+}
+
+impl std::error::Error for SqliteError {}
+
 pub struct Connection {
     pub(crate) sqlite3: *mut sqlite3,
     persistent: bool,
@@ -68,6 +102,11 @@ impl Connection {
         *self.write.borrow()
     }
 
+    /// Whether an explicit transaction or savepoint is open on this connection.
+    pub fn in_transaction(&self) -> bool {
+        unsafe { sqlite3_get_autocommit(self.sqlite3) == 0 }
+    }
+
     pub fn backup_main(&self, destination: &Connection) -> Result<()> {
         unsafe {
             let backup = sqlite3_backup_init(
@@ -199,11 +238,7 @@ impl Connection {
                 )
             };
 
-            Err(anyhow!(
-                "Sqlite call failed with code {} and message: {:?}",
-                code as isize,
-                message
-            ))
+            Err(SqliteError { code, message }.into())
         }
     }
 
@@ -1,12 +1,16 @@
 use std::ffi::{c_int, CStr, CString};
 use std::marker::PhantomData;
-use std::{ptr, slice, str};
+use std::time::Duration;
+use std::{ptr, slice, str, thread};
 
 use anyhow::{anyhow, bail, Context, Result};
 use libsqlite3_sys::*;
 
 use crate::bindable::{Bind, Column};
-use crate::connection::Connection;
+use crate::connection::{Connection, SqliteError};
+
+const BUSY_RETRY_LIMIT: usize = 5;
+const BUSY_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(5);
 
 pub struct Statement<'a> {
     raw_statements: Vec<*mut sqlite3_stmt>,
@@ -264,6 +268,24 @@ impl<'a> Statement<'a> {
     }
 
     fn step(&mut self) -> Result<StepResult> {
+        // Writes can contend with other connections to the same database file, so retry them
+        // rather than surfacing the contention as a failure. Only a statement that runs as its own
+        // transaction can be retried on its own: inside an explicit transaction the contention is
+        // only resolved by retrying the whole transaction, and a statement that already returned
+        // rows would return them again.
+        if self.connection.can_write() && !self.connection.in_transaction() && !self.in_progress() {
+            retry_on_busy(|| self.step_once())
+        } else {
+            self.step_once()
+        }
+    }
+
+    /// Whether the current statement has been stepped, but hasn't finished or been reset since.
+    fn in_progress(&self) -> bool {
+        unsafe { sqlite3_stmt_busy(self.current_statement()) != 0 }
+    }
+
+    fn step_once(&mut self) -> Result<StepResult> {
         unsafe {
             match sqlite3_step(self.current_statement()) {
                 SQLITE_ROW => Ok(StepResult::Row),
@@ -387,13 +409,41 @@ impl<'a> Drop for Statement<'a> {
     }
 }
 
+/// Calls `f`, retrying with exponential backoff for as long as it fails because the database is
+/// busy or locked, up to a fixed number of retries. Any other error is returned immediately.
+fn retry_on_busy<T>(mut f: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut delay = BUSY_RETRY_INITIAL_DELAY;
+    for _ in 0..BUSY_RETRY_LIMIT {
+        match f() {
+            Err(error) if is_busy(&error) => {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    f()
+}
+
+fn is_busy(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<SqliteError>()
+            .map_or(false, SqliteError::is_busy)
+    })
+}
+
 #[cfg(test)]
 mod test {
+    use std::{thread, time::Duration};
+
+    use anyhow::Result;
     use indoc::indoc;
+    use libsqlite3_sys::{SQLITE_BUSY, SQLITE_CONSTRAINT};
 
     use crate::{
-        connection::Connection,
-        statement::{Statement, StepResult},
+        connection::{Connection, SqliteError},
+        statement::{retry_on_busy, Statement, StepResult},
     };
 
     #[test]
@@ -488,4 +538,78 @@ mod test {
             Some(text_to_insert.to_string())
         );
     }
+
+    #[test]
+    fn retry_on_busy_retries_until_success() {
+        let mut attempts = 0;
+        let result = retry_on_busy(|| {
+            attempts += 1;
+            if attempts < 3 {
+                Err(SqliteError {
+                    code: SQLITE_BUSY,
+                    message: Some("database is locked".into()),
+                }
+                .into())
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn busy_write_is_retried_until_the_other_connection_commits() {
+        let path = std::env::temp_dir().join(format!("sqlez-busy-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_string_lossy().to_string();
+        let holder = Connection::open_file(&path);
+        holder
+            .exec("PRAGMA journal_mode=WAL; CREATE TABLE test(col INTEGER);")
+            .unwrap()()
+        .unwrap();
+        let writer = Connection::open_file(&path);
+
+        // Another connection holding the write lock makes the write fail with SQLITE_BUSY,
+        // until it commits.
+        holder.exec("BEGIN IMMEDIATE;").unwrap()().unwrap();
+        let holder = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            holder.exec("COMMIT;").unwrap()().unwrap();
+            holder
+        });
+        writer.exec("INSERT INTO test(col) VALUES (1);").unwrap()().unwrap();
+        let holder = holder.join().unwrap();
+
+        // Inside an explicit transaction, the statement isn't retried on its own.
+        holder.exec("BEGIN IMMEDIATE;").unwrap()().unwrap();
+        writer.exec("BEGIN;").unwrap()().unwrap();
+        let error = writer.exec("INSERT INTO test(col) VALUES (2);").unwrap()().unwrap_err();
+        assert!(error.downcast_ref::<SqliteError>().unwrap().is_busy());
+        writer.exec("ROLLBACK;").unwrap()().unwrap();
+        holder.exec("COMMIT;").unwrap()().unwrap();
+
+        assert_eq!(
+            writer.select::<i32>("SELECT col FROM test").unwrap()().unwrap(),
+            [1]
+        );
+
+        drop((holder, writer));
+        for suffix in ["", "-wal", "-shm"] {
+            std::fs::remove_file(format!("{path}{suffix}")).ok();
+        }
+    }
+
+    #[test]
+    fn retry_on_busy_propagates_other_errors() {
+        let mut attempts = 0;
+        let result: Result<()> = retry_on_busy(|| {
+            attempts += 1;
+            Err(SqliteError {
+                code: SQLITE_CONSTRAINT,
+                message: None,
+            }
+            .into())
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
 }
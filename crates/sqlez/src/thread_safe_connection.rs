@@ -27,6 +27,15 @@ static QUEUES: LazyLock<RwLock<HashMap<Arc<str>, WriteQueue>>> = LazyLock::new(D
 /// Thread safe connection to a given database file or in memory db. This can be cloned, shared, static,
 /// whatever. It derefs to a synchronous connection by thread that is read only. A write capable connection
 /// may be accessed by passing a callback to the `write` function which will queue the callback
+///
+/// SQLite connections are opened with `SQLITE_OPEN_NOMUTEX`, so a single [`Connection`] must never be
+/// used from two threads at once. This type upholds that by:
+/// - Lazily opening one read only connection per thread the first time it is dereferenced on that thread.
+/// - Funneling every write for a given uri through one write queue, so writes are applied one at a time
+///   and in the order they were queued, regardless of which thread queued them.
+///
+/// Reads on one thread may therefore observe writes queued on another thread only once the future
+/// returned by `write` has resolved.
 pub struct ThreadSafeConnection<M: Migrator + 'static = ()> {
     uri: Arc<str>,
     persistent: bool,
@@ -35,6 +44,8 @@ pub struct ThreadSafeConnection<M: Migrator + 'static = ()> {
     _migrator: PhantomData<*mut M>,
 }
 
+// Safety: the connections are only ever handed out to the thread that opened them (see `Deref`),
+// and the migrator is a marker type that is never instantiated.
 unsafe impl<M: Migrator> Send for ThreadSafeConnection<M> {}
 unsafe impl<M: Migrator> Sync for ThreadSafeConnection<M> {}
 
@@ -2,6 +2,7 @@
 #![deny(missing_docs)]
 
 pub mod static_source;
+mod task_output;
 mod task_template;
 mod vscode_format;
 
@@ -13,6 +14,7 @@ use std::borrow::Cow;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+pub use task_output::{parse_task_output, TaskDiagnostic, TaskDiagnosticSeverity};
 pub use task_template::{HideStrategy, RevealStrategy, TaskTemplate, TaskTemplates};
 pub use vscode_format::VsCodeTaskFile;
 
@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+
+use util::paths::PathWithPosition;
+
+/// How severe a diagnostic reported in a task's output is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskDiagnosticSeverity {
+    /// An error, e.g. one that failed the build.
+    Error,
+    /// A warning.
+    Warning,
+    /// A note or help message, attached to another diagnostic.
+    Note,
+}
+
+/// A diagnostic found in a task's output, e.g. a compiler error, pointing at a location that can be navigated to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskDiagnostic {
+    /// Path of the file the diagnostic points at, resolved against the task's working directory.
+    pub path: PathBuf,
+    /// 1-based row of the diagnostic.
+    pub row: u32,
+    /// 1-based column of the diagnostic, if the output specified one.
+    pub column: Option<u32>,
+    /// Severity of the diagnostic.
+    pub severity: TaskDiagnosticSeverity,
+    /// Message of the diagnostic.
+    pub message: String,
+}
+
+/// Parses the diagnostics out of the output of a task that ran in `cwd`.
+///
+/// Two formats are recognized:
+/// * rustc's, where a `error[E0308]: message` line is followed by a `--> path:row:column` line;
+/// * gcc-style single lines, like `path:row:column: error: message`.
+pub fn parse_task_output(output: &str, cwd: &Path) -> Vec<TaskDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut rustc_header = None;
+    for line in output.lines() {
+        if let Some(header) = parse_rustc_header(line) {
+            rustc_header = Some(header);
+        } else if line.trim().is_empty() {
+            // A header without a location, like `error: aborting due to 1 previous error`,
+            // must not be attributed to a location printed later.
+            rustc_header = None;
+        } else if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            if let Some((severity, message)) = rustc_header.take() {
+                diagnostics.extend(diagnostic_at(location, severity, message, cwd));
+            }
+        } else if let Some(diagnostic) = parse_gcc_line(line, cwd) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+fn parse_severity(severity: &str) -> Option<TaskDiagnosticSeverity> {
+    match severity {
+        "error" | "fatal error" => Some(TaskDiagnosticSeverity::Error),
+        "warning" => Some(TaskDiagnosticSeverity::Warning),
+        "note" | "help" => Some(TaskDiagnosticSeverity::Note),
+        _ => None,
+    }
+}
+
+/// Parses a rustc diagnostic header, like `error[E0308]: mismatched types`.
+fn parse_rustc_header(line: &str) -> Option<(TaskDiagnosticSeverity, &str)> {
+    let (severity, message) = line.split_once(": ")?;
+    let severity = match severity.split_once('[') {
+        Some((severity, code)) if code.ends_with(']') => severity,
+        Some(_) => return None,
+        None => severity,
+    };
+    Some((parse_severity(severity)?, message))
+}
+
+/// Parses a gcc-style diagnostic, like `src/main.c:3:5: error: expected ';'`.
+fn parse_gcc_line(line: &str, cwd: &Path) -> Option<TaskDiagnostic> {
+    ["fatal error", "error", "warning", "note"]
+        .into_iter()
+        .find_map(|severity| {
+            let (location, message) = line.split_once(&format!(": {severity}: "))?;
+            diagnostic_at(location, parse_severity(severity)?, message, cwd)
+        })
+}
+
+fn diagnostic_at(
+    location: &str,
+    severity: TaskDiagnosticSeverity,
+    message: &str,
+    cwd: &Path,
+) -> Option<TaskDiagnostic> {
+    let location = PathWithPosition::parse_str(location);
+    Some(TaskDiagnostic {
+        path: cwd.join(location.path),
+        row: location.row?,
+        column: location.column,
+        severity,
+        message: message.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rustc_output() {
+        let output = r#"   Compiling example v0.1.0 (/work/example)
+warning: unused variable: `x`
+ --> src/lib.rs:2:9
+  |
+2 |     let x = 1;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+  |
+  = note: `#[warn(unused_variables)]` on by default
+
+error[E0308]: mismatched types
+  --> src/main.rs:4:18
+   |
+4  |     let y: u32 = "two";
+   |            ---   ^^^^^ expected `u32`, found `&str`
+
+error: aborting due to 1 previous error
+
+  --> src/unrelated.rs:1:1
+"#;
+        assert_eq!(
+            parse_task_output(output, Path::new("/work/example")),
+            [
+                TaskDiagnostic {
+                    path: PathBuf::from("/work/example/src/lib.rs"),
+                    row: 2,
+                    column: Some(9),
+                    severity: TaskDiagnosticSeverity::Warning,
+                    message: "unused variable: `x`".to_string(),
+                },
+                TaskDiagnostic {
+                    path: PathBuf::from("/work/example/src/main.rs"),
+                    row: 4,
+                    column: Some(18),
+                    severity: TaskDiagnosticSeverity::Error,
+                    message: "mismatched types".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_gcc_output() {
+        let output = r#"main.c: In function 'main':
+main.c:3:5: error: expected ';' before 'return'
+    3 |     return 0
+      |     ^~~~~~
+/usr/include/stdio.h:12: warning: ignoring attribute
+make: *** [Makefile:2: main] Error 1
+"#;
+        assert_eq!(
+            parse_task_output(output, Path::new("/work/c")),
+            [
+                TaskDiagnostic {
+                    path: PathBuf::from("/work/c/main.c"),
+                    row: 3,
+                    column: Some(5),
+                    severity: TaskDiagnosticSeverity::Error,
+                    message: "expected ';' before 'return'".to_string(),
+                },
+                TaskDiagnostic {
+                    path: PathBuf::from("/usr/include/stdio.h"),
+                    row: 12,
+                    column: None,
+                    severity: TaskDiagnosticSeverity::Warning,
+                    message: "ignoring attribute".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -39,7 +39,7 @@ use pty_info::PtyProcessInfo;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
 use smol::channel::{Receiver, Sender};
-use task::{HideStrategy, Shell, TaskId};
+use task::{parse_task_output, HideStrategy, Shell, TaskDiagnostic, TaskId};
 use terminal_settings::{AlternateScroll, TerminalBlink, TerminalSettings};
 use theme::{ActiveTheme, Theme};
 use util::truncate_and_trailoff;
@@ -354,17 +354,21 @@ impl TerminalBuilder {
         alacritty_terminal::tty::setup_env();
 
         let scrolling_history = if task.is_some() {
-            // Tasks like `cargo build --all` may produce a lot of output, ergo allow maximum scrolling.
+            // Tasks like `cargo build --all` may produce a lot of output, ergo allow maximum scrolling by default.
             // After the task finishes, we do not allow appending to that terminal, so small tasks output should not
             // cause excessive memory usage over time.
-            MAX_SCROLL_HISTORY_LINES
+            max_scroll_history_lines
+                .unwrap_or(MAX_SCROLL_HISTORY_LINES)
+                .min(MAX_SCROLL_HISTORY_LINES)
         } else {
             max_scroll_history_lines
                 .unwrap_or(DEFAULT_SCROLL_HISTORY_LINES)
                 .min(MAX_SCROLL_HISTORY_LINES)
         };
         let config = Config {
-            scrolling_history,
+            // Task terminals keep a spare line of history, to tell output that overflowed the
+            // history apart from output that exactly filled it.
+            scrolling_history: scrolling_history + task.is_some() as usize,
             ..Config::default()
         };
 
@@ -447,6 +451,7 @@ impl TerminalBuilder {
             hovered_word: false,
             url_regex,
             word_regex,
+            scrolling_history,
         };
 
         Ok(TerminalBuilder {
@@ -601,6 +606,7 @@ pub struct Terminal {
     url_regex: RegexSearch,
     word_regex: RegexSearch,
     task: Option<TaskState>,
+    scrolling_history: usize,
 }
 
 pub struct TaskState {
@@ -611,6 +617,10 @@ pub struct TaskState {
     pub status: TaskStatus,
     pub completion_rx: Receiver<()>,
     pub hide: HideStrategy,
+    /// Local directory the task runs in, which relative paths in its output are resolved against.
+    pub cwd: Option<PathBuf>,
+    /// Diagnostics parsed out of the task's output once it has finished.
+    pub diagnostics: Vec<TaskDiagnostic>,
 }
 
 /// A status of the current terminal tab's task.
@@ -1562,11 +1572,19 @@ impl Terminal {
         };
 
         let (finished_successfully, task_line, command_line) = task_summary(task, error_code);
+        let mut term = self.term.lock();
+        if let Some(cwd) = &task.cwd {
+            task.diagnostics = parse_task_output(&task_output(&term), cwd);
+        }
+        let truncation_line = truncate_task_history(&mut term, self.scrolling_history);
+        let mut summary_lines = vec![task_line.as_str(), command_line.as_str()];
+        summary_lines.extend(truncation_line.as_deref());
         // SAFETY: the invocation happens on non `TaskStatus::Running` tasks, once,
         // after either `AlacTermEvent::Exit` or `AlacTermEvent::ChildExit` events that are spawned
         // when Zed task finishes and no more output is made.
         // After the task summary is output once, no more text is appended to the terminal.
-        unsafe { append_text_to_term(&mut self.term.lock(), &[&task_line, &command_line]) };
+        unsafe { append_text_to_term(&mut term, &summary_lines) };
+        drop(term);
         match task.hide {
             HideStrategy::Never => {}
             HideStrategy::Always => {
@@ -1600,6 +1618,28 @@ fn task_summary(task: &TaskState, error_code: Option<i32>) -> (bool, String, Str
     (success, task_line, command_line)
 }
 
+/// Returns all text in the terminal's grid, including its scrollback history,
+/// with soft-wrapped lines joined back together.
+fn task_output(term: &Term<ZedListener>) -> String {
+    term.bounds_to_string(
+        AlacPoint::new(term.topmost_line(), Column(0)),
+        AlacPoint::new(term.bottommost_line(), term.last_column()),
+    )
+}
+
+/// Returns a line noting that the oldest output lines were dropped, if the task's output
+/// overflowed its scrollback history, in which case the spare line of history kept by
+/// task terminals is dropped too, to fit the history back within its limit.
+fn truncate_task_history(term: &mut Term<ZedListener>, scrolling_history: usize) -> Option<String> {
+    if term.grid().history_size() <= scrolling_history {
+        return None;
+    }
+    term.grid_mut().update_history(scrolling_history);
+    Some(format!(
+        "{TASK_DELIMITER}Output truncated to the last {scrolling_history} lines of history"
+    ))
+}
+
 /// Appends a stringified task summary to the terminal, after its output.
 ///
 /// SAFETY: This function should only be called after terminal's PTY is no longer alive.
@@ -1786,8 +1826,245 @@ mod tests {
     use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
 
     use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+        append_text_to_term, content_index_for_mouse, rgb_for_index, task_summary,
+        truncate_task_history, IndexedCell, TaskState, TaskStatus, TerminalContent, TerminalSize,
+        ZedListener,
     };
+    use alacritty_terminal::{grid::Dimensions, term::Config, Term};
+    use collections::HashMap;
+    use futures::channel::mpsc::unbounded;
+    use gpui::{SemanticVersion, TestAppContext};
+    use settings::{Settings as _, SettingsStore};
+    use std::path::PathBuf;
+    use task::{HideStrategy, Shell, TaskDiagnostic, TaskDiagnosticSeverity, TaskId};
+
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_task_output_through_pty(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            release_channel::init(SemanticVersion::default(), cx);
+            crate::TerminalSettings::register(cx);
+        });
+        let window = cx.add_empty_window().handle();
+
+        let (completion_tx, completion_rx) = smol::channel::bounded(1);
+        let task = TaskState {
+            id: TaskId("echo".to_string()),
+            full_label: "echo hello".to_string(),
+            label: "echo hello".to_string(),
+            command_label: "echo hello".to_string(),
+            status: TaskStatus::Running,
+            completion_rx: completion_rx.clone(),
+            hide: HideStrategy::Never,
+            cwd: None,
+            diagnostics: Vec::new(),
+        };
+        let terminal = cx.new_model(|cx| {
+            crate::TerminalBuilder::new(
+                None,
+                Some(task),
+                Shell::WithArguments {
+                    program: "echo".to_string(),
+                    args: vec!["hello".to_string()],
+                },
+                HashMap::default(),
+                None,
+                crate::terminal_settings::AlternateScroll::On,
+                None,
+                window,
+                completion_tx,
+                cx,
+            )
+            .unwrap()
+            .subscribe(cx)
+        });
+
+        completion_rx.recv().await.unwrap();
+        terminal.read_with(cx, |terminal, _| {
+            assert_eq!(
+                terminal.task().unwrap().status,
+                TaskStatus::Completed { success: true }
+            );
+            assert_eq!(
+                terminal.last_n_non_empty_lines(3),
+                [
+                    "hello",
+                    "⏵ Task `echo hello` finished successfully",
+                    "⏵ Command: 'echo hello'",
+                ]
+            );
+        });
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_task_diagnostics_through_pty(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            release_channel::init(SemanticVersion::default(), cx);
+            crate::TerminalSettings::register(cx);
+        });
+        let window = cx.add_empty_window().handle();
+
+        let (completion_tx, completion_rx) = smol::channel::bounded(1);
+        let task = TaskState {
+            id: TaskId("make".to_string()),
+            full_label: "make".to_string(),
+            label: "make".to_string(),
+            command_label: "make".to_string(),
+            status: TaskStatus::Running,
+            completion_rx: completion_rx.clone(),
+            hide: HideStrategy::Never,
+            cwd: Some(PathBuf::from("/work/c")),
+            diagnostics: Vec::new(),
+        };
+        let terminal = cx.new_model(|cx| {
+            crate::TerminalBuilder::new(
+                None,
+                Some(task),
+                Shell::WithArguments {
+                    program: "echo".to_string(),
+                    args: vec!["main.c:3:5: error: expected ';' before 'return'".to_string()],
+                },
+                HashMap::default(),
+                None,
+                crate::terminal_settings::AlternateScroll::On,
+                None,
+                window,
+                completion_tx,
+                cx,
+            )
+            .unwrap()
+            .subscribe(cx)
+        });
+
+        completion_rx.recv().await.unwrap();
+        terminal.read_with(cx, |terminal, _| {
+            assert_eq!(
+                terminal.task().unwrap().diagnostics,
+                [TaskDiagnostic {
+                    path: PathBuf::from("/work/c/main.c"),
+                    row: 3,
+                    column: Some(5),
+                    severity: TaskDiagnosticSeverity::Error,
+                    message: "expected ';' before 'return'".to_string(),
+                }]
+            );
+        });
+    }
+
+    #[test]
+    fn test_task_scroll_history_truncation() {
+        let output = (0..20).map(|i| format!("line {i}")).collect::<Vec<_>>();
+        let output_lines = output.iter().map(String::as_str).collect::<Vec<_>>();
+
+        // Task terminals keep a spare line of history beyond the limit.
+        let task_term = |scrolling_history: usize| {
+            let (events_tx, _events_rx) = unbounded();
+            let config = Config {
+                scrolling_history: scrolling_history + 1,
+                ..Config::default()
+            };
+            Term::new(config, &TerminalSize::default(), ZedListener(events_tx))
+        };
+
+        let mut term = task_term(5);
+        assert_eq!(truncate_task_history(&mut term, 5), None);
+
+        // SAFETY: there's no PTY attached to this terminal.
+        unsafe { append_text_to_term(&mut term, &output_lines) };
+        let marker = truncate_task_history(&mut term, 5).unwrap();
+        assert_eq!(marker, "⏵ Output truncated to the last 5 lines of history");
+        assert_eq!(term.grid().history_size(), 5);
+
+        let lines = non_empty_lines(&term);
+        let retained = term.screen_lines() + 5;
+        assert!(lines.len() < retained);
+        assert_eq!(lines, output[output.len() - lines.len()..]);
+
+        // SAFETY: there's no PTY attached to this terminal.
+        unsafe { append_text_to_term(&mut term, &[&marker]) };
+        let lines = non_empty_lines(&term);
+        assert_eq!(lines.last(), Some(&marker));
+        assert!(lines.len() < retained);
+
+        // Without any history, every line scrolled off the screen is dropped.
+        let mut term = task_term(0);
+        // SAFETY: there's no PTY attached to this terminal.
+        unsafe { append_text_to_term(&mut term, &output_lines) };
+        assert_eq!(
+            truncate_task_history(&mut term, 0).as_deref(),
+            Some("⏵ Output truncated to the last 0 lines of history")
+        );
+        assert_eq!(term.grid().history_size(), 0);
+
+        // Output that fits in the history isn't reported as truncated.
+        let mut term = task_term(100);
+        // SAFETY: there's no PTY attached to this terminal.
+        unsafe { append_text_to_term(&mut term, &output_lines) };
+        let history_size = term.grid().history_size();
+        assert_eq!(truncate_task_history(&mut term, 100), None);
+        assert_eq!(term.grid().history_size(), history_size);
+        assert_eq!(non_empty_lines(&term), output);
+    }
+
+    fn non_empty_lines(term: &Term<ZedListener>) -> Vec<String> {
+        let mut lines = Vec::new();
+        for line in term.topmost_line().0..=term.bottommost_line().0 {
+            let mut line_buffer = String::new();
+            for cell in &term.grid()[Line(line)] {
+                line_buffer.push(cell.c);
+            }
+            let line = line_buffer.trim_end();
+            if !line.is_empty() {
+                lines.push(line.to_string());
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn test_task_exit_status() {
+        let mut task = TaskState {
+            id: TaskId("test".to_string()),
+            full_label: "cargo test".to_string(),
+            label: "cargo test".to_string(),
+            command_label: "cargo test --workspace".to_string(),
+            status: TaskStatus::Running,
+            completion_rx: smol::channel::unbounded().1,
+            hide: HideStrategy::Never,
+            cwd: None,
+            diagnostics: Vec::new(),
+        };
+
+        let (success, task_line, command_line) = task_summary(&task, Some(0));
+        assert!(success);
+        assert_eq!(task_line, "⏵ Task `cargo test` finished successfully");
+        assert_eq!(command_line, "⏵ Command: 'cargo test --workspace'");
+
+        let (success, task_line, _) = task_summary(&task, Some(101));
+        assert!(!success);
+        assert_eq!(
+            task_line,
+            "⏵ Task `cargo test` finished with non-zero error code: 101"
+        );
+
+        // A task killed before reporting its exit code has an unknown status.
+        let (success, task_line, _) = task_summary(&task, None);
+        assert!(!success);
+        assert_eq!(task_line, "⏵ Task `cargo test` finished");
+        task.status.register_terminal_exit();
+        assert_eq!(task.status, TaskStatus::Unknown);
+
+        task.status = TaskStatus::Running;
+        task.status.register_task_exit(101);
+        assert_eq!(task.status, TaskStatus::Completed { success: false });
+    }
 
     #[test]
     fn test_rgb_for_index() {
@@ -42,6 +42,7 @@ pub struct TerminalSettings {
     pub default_height: Pixels,
     pub detect_venv: VenvSettings,
     pub max_scroll_history_lines: Option<usize>,
+    pub max_task_scroll_history_lines: Option<usize>,
     pub toolbar: Toolbar,
 }
 
@@ -175,6 +176,13 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: 10_000
     pub max_scroll_history_lines: Option<usize>,
+    /// The maximum number of lines of task output to keep in the scrollback history.
+    /// Once the limit is reached, the oldest lines are dropped and a note about the truncation
+    /// is printed after the task finishes.
+    /// Maximum allowed value is 100_000, all values above that will be treated as 100_000.
+    ///
+    /// Default: 100_000
+    pub max_task_scroll_history_lines: Option<usize>,
     /// Toolbar related settings
     pub toolbar: Option<ToolbarContent>,
 }
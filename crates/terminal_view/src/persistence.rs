@@ -38,6 +38,14 @@ define_connection! {
         )];
 }
 
+/// A terminal persisted as an item of a workspace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerializedTerminal {
+    pub workspace_id: WorkspaceId,
+    pub item_id: ItemId,
+    pub cwd: PathBuf,
+}
+
 impl TerminalDb {
     query! {
        pub async fn update_workspace_id(
@@ -63,13 +71,50 @@ impl TerminalDb {
     }
 
     query! {
-        pub fn get_working_directory(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<PathBuf>> {
+        fn get_working_directory(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<PathBuf>> {
             SELECT working_directory
             FROM terminals
             WHERE item_id = ? AND workspace_id = ?
         }
     }
 
+    query! {
+        fn get_working_directories(workspace_id: WorkspaceId) -> Result<Vec<(ItemId, PathBuf)>> {
+            SELECT item_id, working_directory
+            FROM terminals
+            WHERE workspace_id = ?
+            ORDER BY item_id
+        }
+    }
+
+    /// Returns the terminal persisted for the given item, if there is one.
+    pub fn get_terminal(
+        &self,
+        item_id: ItemId,
+        workspace_id: WorkspaceId,
+    ) -> Result<Option<SerializedTerminal>> {
+        Ok(self
+            .get_working_directory(item_id, workspace_id)?
+            .map(|cwd| SerializedTerminal {
+                workspace_id,
+                item_id,
+                cwd,
+            }))
+    }
+
+    /// Returns the terminals persisted for the given workspace.
+    pub fn get_terminals(&self, workspace_id: WorkspaceId) -> Result<Vec<SerializedTerminal>> {
+        Ok(self
+            .get_working_directories(workspace_id)?
+            .into_iter()
+            .map(|(item_id, cwd)| SerializedTerminal {
+                workspace_id,
+                item_id,
+                cwd,
+            })
+            .collect())
+    }
+
     pub async fn delete_unloaded_items(
         &self,
         workspace: WorkspaceId,
@@ -96,3 +141,66 @@ impl TerminalDb {
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    async fn test_save_and_get_terminals() {
+        let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+        assert_eq!(TERMINAL_DB.get_terminals(workspace_id).unwrap(), []);
+
+        TERMINAL_DB
+            .save_working_directory(1, workspace_id, PathBuf::from("/tmp/one"))
+            .await
+            .unwrap();
+        TERMINAL_DB
+            .save_working_directory(2, workspace_id, PathBuf::from("/tmp/two"))
+            .await
+            .unwrap();
+        // Saving a terminal again replaces its working directory.
+        TERMINAL_DB
+            .save_working_directory(1, workspace_id, PathBuf::from("/tmp/three"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            TERMINAL_DB.get_terminals(workspace_id).unwrap(),
+            [
+                SerializedTerminal {
+                    workspace_id,
+                    item_id: 1,
+                    cwd: PathBuf::from("/tmp/three"),
+                },
+                SerializedTerminal {
+                    workspace_id,
+                    item_id: 2,
+                    cwd: PathBuf::from("/tmp/two"),
+                },
+            ]
+        );
+        assert_eq!(
+            TERMINAL_DB.get_terminal(2, workspace_id).unwrap(),
+            Some(SerializedTerminal {
+                workspace_id,
+                item_id: 2,
+                cwd: PathBuf::from("/tmp/two"),
+            })
+        );
+
+        TERMINAL_DB
+            .delete_unloaded_items(workspace_id, vec![2])
+            .await
+            .unwrap();
+        assert_eq!(
+            TERMINAL_DB.get_terminals(workspace_id).unwrap(),
+            [SerializedTerminal {
+                workspace_id,
+                item_id: 2,
+                cwd: PathBuf::from("/tmp/two"),
+            }]
+        );
+        assert_eq!(TERMINAL_DB.get_terminal(1, workspace_id).unwrap(), None);
+    }
+}
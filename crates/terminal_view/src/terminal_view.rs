@@ -6,14 +6,15 @@ use collections::HashSet;
 use editor::{actions::SelectAll, scroll::Autoscroll, Editor};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
-    anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent, EventEmitter,
-    FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton,
-    MouseDownEvent, Pixels, Render, ScrollWheelEvent, Styled, Subscription, Task, View,
-    VisualContext, WeakView,
+    anchored, deferred, div, impl_actions, AnyElement, AppContext, AsyncAppContext, DismissEvent,
+    EventEmitter, FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model,
+    MouseButton, MouseDownEvent, Pixels, Render, ScrollWheelEvent, Styled, Subscription, Task,
+    View, VisualContext, WeakView,
 };
 use language::Bias;
-use persistence::TERMINAL_DB;
+use persistence::{SerializedTerminal, TERMINAL_DB};
 use project::{search::SearchQuery, terminals::TerminalKind, Fs, Metadata, Project};
+use task::TaskDiagnosticSeverity;
 use terminal::{
     alacritty_terminal::{
         index::Point,
@@ -598,6 +599,102 @@ impl TerminalView {
         dispatch_context
     }
 
+    /// Opens the location of the `ix`-th diagnostic found in the finished task's output.
+    fn open_task_diagnostic(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(diagnostic) = self
+            .terminal
+            .read(cx)
+            .task()
+            .and_then(|task| task.diagnostics.get(ix))
+            .cloned()
+        else {
+            return;
+        };
+        let Some(open) = self
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_abs_path(diagnostic.path.clone(), true, cx)
+            })
+            .log_err()
+        else {
+            return;
+        };
+
+        cx.spawn(|_, mut cx| async move {
+            let item = open.await?;
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update(&mut cx, |editor, cx| {
+                    let snapshot = editor.snapshot(cx).display_snapshot;
+                    let point = snapshot.buffer_snapshot.clip_point(
+                        language::Point::new(
+                            diagnostic.row.saturating_sub(1),
+                            diagnostic.column.unwrap_or(1).saturating_sub(1),
+                        ),
+                        Bias::Left,
+                    );
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([point..point])
+                    });
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_task_diagnostics(&self, cx: &mut ViewContext<Self>) -> Option<AnyElement> {
+        let task = self.terminal.read(cx).task()?;
+        if task.status == TaskStatus::Running || task.diagnostics.is_empty() {
+            return None;
+        }
+
+        let rows = task
+            .diagnostics
+            .iter()
+            .enumerate()
+            .map(|(ix, diagnostic)| {
+                let (icon, color) = match diagnostic.severity {
+                    TaskDiagnosticSeverity::Error => (IconName::XCircle, Color::Error),
+                    TaskDiagnosticSeverity::Warning => {
+                        (IconName::ExclamationTriangle, Color::Warning)
+                    }
+                    TaskDiagnosticSeverity::Note => (IconName::Indicator, Color::Muted),
+                };
+                let mut location = format!("{}:{}", diagnostic.path.display(), diagnostic.row);
+                if let Some(column) = diagnostic.column {
+                    location.push_str(&format!(":{column}"));
+                }
+                h_flex()
+                    .id(("task-diagnostic", ix))
+                    .gap_2()
+                    .px_2()
+                    .cursor_pointer()
+                    .hover(|style| style.bg(cx.theme().colors().element_hover))
+                    .child(Icon::new(icon).size(IconSize::Small).color(color))
+                    .child(Label::new(diagnostic.message.clone()).size(LabelSize::Small))
+                    .child(
+                        Label::new(location)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .on_click(cx.listener(move |this, _, cx| this.open_task_diagnostic(ix, cx)))
+            })
+            .collect::<Vec<_>>();
+
+        Some(
+            v_flex()
+                .id("task-diagnostics")
+                .flex_none()
+                .max_h(rems(10.))
+                .overflow_y_scroll()
+                .py_1()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .children(rows)
+                .into_any_element(),
+        )
+    }
+
     fn set_terminal(&mut self, terminal: Model<Terminal>, cx: &mut ViewContext<'_, TerminalView>) {
         self._terminal_subscriptions =
             subscribe_for_terminal_events(&terminal, self.workspace.clone(), cx);
@@ -909,7 +1006,9 @@ impl Render for TerminalView {
 
         let focused = self.focus_handle.is_focused(cx);
 
-        div()
+        let task_diagnostics = self.render_task_diagnostics(cx);
+
+        v_flex()
             .size_full()
             .relative()
             .track_focus(&self.focus_handle)
@@ -939,7 +1038,7 @@ impl Render for TerminalView {
             )
             .child(
                 // TODO: Oddly this wrapper div is needed for TerminalElement to not steal events from the context menu
-                div().size_full().child(TerminalElement::new(
+                div().w_full().flex_1().child(TerminalElement::new(
                     terminal_handle,
                     terminal_view_handle,
                     self.workspace.clone(),
@@ -950,6 +1049,7 @@ impl Render for TerminalView {
                     self.block_below_cursor.clone(),
                 )),
             )
+            .children(task_diagnostics)
             .children(self.context_menu.as_ref().map(|(menu, position, _)| {
                 deferred(
                     anchored()
@@ -1152,25 +1252,11 @@ impl SerializableItem for TerminalView {
     ) -> Task<anyhow::Result<View<Self>>> {
         let window = cx.window_handle();
         cx.spawn(|pane, mut cx| async move {
-            let cwd = cx
-                .update(|cx| {
-                    let from_db = TERMINAL_DB
-                        .get_working_directory(item_id, workspace_id)
-                        .log_err()
-                        .flatten();
-                    if from_db
-                        .as_ref()
-                        .is_some_and(|from_db| !from_db.as_os_str().is_empty())
-                    {
-                        from_db
-                    } else {
-                        workspace
-                            .upgrade()
-                            .and_then(|workspace| default_working_directory(workspace.read(cx), cx))
-                    }
-                })
-                .ok()
+            let persisted = TERMINAL_DB
+                .get_terminal(item_id, workspace_id)
+                .log_err()
                 .flatten();
+            let cwd = restored_working_directory(persisted, &project, &workspace, &mut cx).await?;
 
             let terminal = project.update(&mut cx, |project, cx| {
                 project.create_terminal(TerminalKind::Shell(cwd), window, cx)
@@ -1318,6 +1404,33 @@ pub fn default_working_directory(workspace: &Workspace, cx: &AppContext) -> Opti
         }
     }
 }
+
+/// The directory to reopen a persisted terminal in. That's the directory it was persisted with,
+/// unless it has been removed since, in which case the workspace's default directory is used.
+async fn restored_working_directory(
+    persisted: Option<SerializedTerminal>,
+    project: &Model<Project>,
+    workspace: &WeakView<Workspace>,
+    cx: &mut AsyncAppContext,
+) -> anyhow::Result<Option<PathBuf>> {
+    let persisted = persisted
+        .map(|terminal| terminal.cwd)
+        .filter(|cwd| !cwd.as_os_str().is_empty());
+    let (fs, is_local) =
+        project.read_with(cx, |project, _| (project.fs().clone(), project.is_local()))?;
+    if let Some(cwd) = persisted {
+        // Remote directories can't be checked here, so they're kept.
+        if !is_local || fs.is_dir(&cwd).await {
+            return Ok(Some(cwd));
+        }
+    }
+    cx.update(|cx| {
+        workspace
+            .upgrade()
+            .and_then(|workspace| default_working_directory(workspace.read(cx), cx))
+    })
+}
+
 ///Gets the first project's home directory, or the home directory
 fn first_project_directory(workspace: &Workspace, cx: &AppContext) -> Option<PathBuf> {
     let worktree = workspace.worktrees(cx).next()?.read(cx);
@@ -1441,6 +1554,47 @@ mod tests {
         });
     }
 
+    // Persisted working directory that has been removed since -> worktree_folder
+    #[gpui::test]
+    async fn restored_working_directory_was_removed(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        let fs = project.read_with(cx, |project, _| project.fs().clone());
+        fs.as_fake()
+            .insert_tree("/root", serde_json::json!({ "dir": {} }))
+            .await;
+        project
+            .update(cx, |project, cx| {
+                project.find_or_create_worktree("/root", true, cx)
+            })
+            .await
+            .unwrap();
+        cx.run_until_parked();
+
+        let workspace = workspace.downgrade();
+        let mut async_cx = cx.to_async();
+        let persisted = |cwd: &str| {
+            Some(SerializedTerminal {
+                workspace_id: WorkspaceId::default(),
+                item_id: 1,
+                cwd: PathBuf::from(cwd),
+            })
+        };
+        let res =
+            restored_working_directory(persisted("/root/dir"), &project, &workspace, &mut async_cx)
+                .await
+                .unwrap();
+        assert_eq!(res, Some(PathBuf::from("/root/dir")));
+        let res = restored_working_directory(
+            persisted("/root/removed"),
+            &project,
+            &workspace,
+            &mut async_cx,
+        )
+        .await
+        .unwrap();
+        assert_eq!(res, Some(PathBuf::from("/root")));
+    }
+
     /// Creates a worktree with 1 file: /root.txt
     pub async fn init_test(cx: &mut TestAppContext) -> (Model<Project>, View<Workspace>) {
         let params = cx.update(AppState::test);
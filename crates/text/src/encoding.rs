@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Context as _, Result};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// The number of leading bytes inspected when guessing whether a file without a byte order
+/// mark is UTF-16.
+const UTF16_SNIFF_LEN: usize = 4096;
+
+/// The character encoding of a file on disk.
+///
+/// Buffers are always UTF-8 in memory. The encoding is remembered so that the file can be
+/// written back in the same form it was read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 preceded by a byte order mark.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, used as a fallback for text files that aren't valid in any other encoding.
+    Latin1,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 with BOM",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    /// Guesses the encoding of the given bytes and decodes them.
+    ///
+    /// Byte order marks take precedence. Otherwise, text where every other byte is zero is
+    /// assumed to be UTF-16, valid UTF-8 is assumed to be UTF-8, and anything else is decoded
+    /// as ISO-8859-1.
+    ///
+    /// Fails if the bytes follow a UTF-8 byte order mark but aren't valid UTF-8, or if they
+    /// contain control characters that don't occur in text, since decoding those as ISO-8859-1
+    /// would silently turn a binary file into garbage.
+    pub fn detect_and_decode(bytes: Vec<u8>) -> Result<(String, Self)> {
+        if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+            let text = String::from_utf8(rest.to_vec())
+                .context("invalid UTF-8 after a UTF-8 byte order mark")?;
+            return Ok((text, Self::Utf8Bom));
+        }
+        if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+            return Ok((decode_utf16(rest, u16::from_le_bytes), Self::Utf16Le));
+        }
+        if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+            return Ok((decode_utf16(rest, u16::from_be_bytes), Self::Utf16Be));
+        }
+
+        // ASCII text encoded as UTF-16 is also valid UTF-8, so check for it first.
+        if let Some(encoding) = sniff_utf16(&bytes) {
+            let from_bytes = match encoding {
+                Self::Utf16Le => u16::from_le_bytes,
+                _ => u16::from_be_bytes,
+            };
+            return Ok((decode_utf16(&bytes, from_bytes), encoding));
+        }
+
+        let bytes = match String::from_utf8(bytes) {
+            Ok(text) => return Ok((text, Self::Utf8)),
+            Err(error) => error.into_bytes(),
+        };
+
+        if bytes.iter().copied().any(is_binary_control) {
+            return Err(anyhow!(
+                "file contains binary data that can't be decoded as text"
+            ));
+        }
+        Ok((
+            bytes.iter().map(|&byte| byte as char).collect(),
+            Self::Latin1,
+        ))
+    }
+
+    /// Encodes the given text, including a byte order mark for encodings that use one.
+    ///
+    /// Fails if the text contains characters that this encoding can't represent.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Utf8Bom => {
+                let mut bytes = UTF8_BOM.to_vec();
+                bytes.extend_from_slice(text.as_bytes());
+                Ok(bytes)
+            }
+            Encoding::Utf16Le => Ok(UTF16_LE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect()),
+            Encoding::Utf16Be => Ok(UTF16_BE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect()),
+            Encoding::Latin1 => text
+                .chars()
+                .map(|char| {
+                    u8::try_from(char)
+                        .map_err(|_| anyhow!("{char:?} can't be represented in {}", self.name()))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns whether the byte is an ASCII control character that doesn't appear in text files.
+/// Tabs, line breaks, form feeds and escapes are allowed.
+fn is_binary_control(byte: u8) -> bool {
+    matches!(byte, 0x00..=0x08 | 0x0E..=0x1A | 0x1C..=0x1F)
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|char| char.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Detects UTF-16 without a byte order mark by looking for text where most characters are
+/// ASCII, so that one byte of each pair is zero.
+fn sniff_utf16(bytes: &[u8]) -> Option<Encoding> {
+    let sample = &bytes[..bytes.len().min(UTF16_SNIFF_LEN)];
+    if sample.len() < 2 || sample.len() % 2 != 0 {
+        return None;
+    }
+
+    let pairs = sample.len() / 2;
+    let (even_zeros, odd_zeros) = sample.chunks_exact(2).fold((0, 0), |(even, odd), pair| {
+        (
+            even + (pair[0] == 0) as usize,
+            odd + (pair[1] == 0) as usize,
+        )
+    });
+    if odd_zeros * 10 >= pairs * 7 && even_zeros * 10 < pairs {
+        Some(Encoding::Utf16Le)
+    } else if even_zeros * 10 >= pairs * 7 && odd_zeros * 10 < pairs {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
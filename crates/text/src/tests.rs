@@ -176,6 +176,13 @@ fn test_line_endings() {
         LineEnding::detect(&"abcd\r\n".repeat(1000)),
         LineEnding::Windows
     );
+    assert_eq!(
+        LineEnding::detect("a\nb\r\nc\r\nd\n\r\n"),
+        LineEnding::Windows
+    );
+    assert_eq!(LineEnding::detect("a\r\nb\nc\nd\r\ne\n"), LineEnding::Unix);
+    assert_eq!(LineEnding::detect("a\r\nb\n"), LineEnding::Windows);
+    assert_eq!(LineEnding::detect("a\nb\r\n"), LineEnding::Unix);
 
     let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "one\r\ntwo\rthree".into());
     assert_eq!(buffer.text(), "one\ntwo\nthree");
@@ -189,6 +196,32 @@ fn test_line_endings() {
     buffer.check_invariants();
 }
 
+#[test]
+fn test_encodings() {
+    for (bytes, text, encoding) in [
+        (b"abc".to_vec(), "abc", Encoding::Utf8),
+        ("é".as_bytes().to_vec(), "é", Encoding::Utf8),
+        (b"\xEF\xBB\xBFabc".to_vec(), "abc", Encoding::Utf8Bom),
+        (b"\xFF\xFEa\0b\0".to_vec(), "ab", Encoding::Utf16Le),
+        (b"\xFE\xFF\0a\0b".to_vec(), "ab", Encoding::Utf16Be),
+        (b"a\0b\0c\0\n\0".to_vec(), "abc\n", Encoding::Utf16Le),
+        (b"\0a\0b\0c\0\n".to_vec(), "abc\n", Encoding::Utf16Be),
+        (b"caf\xe9".to_vec(), "café", Encoding::Latin1),
+    ] {
+        assert_eq!(
+            Encoding::detect_and_decode(bytes.clone()).unwrap(),
+            (text.to_string(), encoding)
+        );
+        if !matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be) || bytes[0] >= 0xFE {
+            assert_eq!(encoding.encode(text).unwrap(), bytes);
+        }
+    }
+
+    assert!(Encoding::Latin1.encode("🍐").is_err());
+    assert!(Encoding::detect_and_decode(b"\xEF\xBB\xBFcaf\xe9".to_vec()).is_err());
+    assert!(Encoding::detect_and_decode(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec()).is_err());
+}
+
 #[test]
 fn test_line_len() {
     let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "".into());
@@ -537,6 +570,35 @@ fn test_undo_redo() {
     assert_eq!(buffer.text(), "1234");
 }
 
+#[test]
+fn test_serialize_undo_history() {
+    let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "1234".into());
+    buffer.set_group_interval(Duration::from_secs(0));
+
+    buffer.edit([(1..1, "abx")]);
+    buffer.edit([(3..4, "yzef")]);
+    buffer.edit([(3..5, "cd")]);
+    assert_eq!(buffer.text(), "1abcdef234");
+
+    let history = buffer.serialize_undo_history(&buffer.undo_history_versions(usize::MAX));
+    assert_eq!(history.base_text, "1234");
+    assert_eq!(history.transactions.len(), 3);
+    assert_eq!(history.final_text().as_deref(), Some("1abcdef234"));
+
+    // Only the most recent transactions are kept, starting from the text preceding them.
+    let history = buffer.serialize_undo_history(&buffer.undo_history_versions(2));
+    assert_eq!(history.base_text, "1abx234");
+    assert_eq!(
+        history.transactions,
+        [(3..4, "yzef".to_string()), (3..5, "cd".to_string())]
+    );
+
+    buffer.undo();
+    let history = buffer.serialize_undo_history(&buffer.undo_history_versions(usize::MAX));
+    assert_eq!(history.transactions.len(), 2);
+    assert_eq!(history.final_text().as_deref(), Some("1abyzef234"));
+}
+
 #[test]
 fn test_history() {
     let mut now = Instant::now();
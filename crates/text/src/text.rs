@@ -1,4 +1,5 @@
 mod anchor;
+mod encoding;
 pub mod locator;
 #[cfg(any(test, feature = "test-support"))]
 pub mod network;
@@ -8,12 +9,14 @@ mod selection;
 pub mod subscription;
 #[cfg(test)]
 mod tests;
+mod undo_history;
 mod undo_map;
 
 pub use anchor::*;
 use anyhow::{anyhow, Context as _, Result};
 pub use clock::ReplicaId;
 use collections::{HashMap, HashSet};
+pub use encoding::Encoding;
 use locator::Locator;
 use operation_queue::OperationQueue;
 pub use patch::Patch;
@@ -37,6 +40,7 @@ use std::{
 pub use subscription::*;
 pub use sum_tree::Bias;
 use sum_tree::{FilterCursor, SumTree, TreeMap};
+pub use undo_history::SerializedUndoHistory;
 use undo_map::UndoMap;
 use util::ResultExt;
 
@@ -47,6 +51,9 @@ static LINE_SEPARATORS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"\r\n|\r|\u{2028}|\u{2029}").expect("Failed to create LINE_SEPARATORS_REGEX")
 });
 
+/// The number of leading bytes inspected when detecting the line ending of a text.
+const LINE_ENDING_DETECTION_LEN: usize = 64 * 1024;
+
 pub type TransactionId = clock::Lamport;
 
 pub struct Buffer {
@@ -1305,6 +1312,16 @@ impl Buffer {
         self.history.redo_stack.last()
     }
 
+    /// Returns the versions preceding the last `max_transactions` transactions on the undo stack,
+    /// oldest first, to be passed to [`BufferSnapshot::serialize_undo_history`].
+    pub fn undo_history_versions(&self, max_transactions: usize) -> Vec<clock::Global> {
+        let undo_stack = &self.history.undo_stack;
+        undo_stack[undo_stack.len().saturating_sub(max_transactions)..]
+            .iter()
+            .map(|entry| entry.transaction.start.clone())
+            .collect()
+    }
+
     pub fn start_transaction(&mut self) -> Option<TransactionId> {
         self.start_transaction_at(Instant::now())
     }
@@ -1778,6 +1795,20 @@ impl BufferSnapshot {
         &self.visible_text
     }
 
+    /// Captures the transactions performed since each of the given versions, which come from
+    /// [`Buffer::undo_history_versions`], in a form that can be persisted and replayed onto a
+    /// buffer with the same text.
+    ///
+    /// This reconstructs the text at every version, so it's best run on a background thread.
+    pub fn serialize_undo_history(&self, versions: &[clock::Global]) -> SerializedUndoHistory {
+        let mut texts = versions
+            .iter()
+            .map(|version| self.rope_for_version(version).to_string())
+            .collect::<Vec<_>>();
+        texts.push(self.text());
+        SerializedUndoHistory::from_texts(&texts)
+    }
+
     pub fn rope_for_version(&self, version: &clock::Global) -> Rope {
         let mut rope = Rope::new();
 
@@ -3011,20 +3042,34 @@ impl LineEnding {
         }
     }
 
+    /// Detects the line ending used by the given text.
+    ///
+    /// When the text mixes line endings, the one used by the majority of lines wins, with ties
+    /// going to the one that appears first. Text without any newlines gets the platform default.
     pub fn detect(text: &str) -> Self {
-        let mut max_ix = cmp::min(text.len(), 1000);
+        let mut max_ix = cmp::min(text.len(), LINE_ENDING_DETECTION_LEN);
         while !text.is_char_boundary(max_ix) {
             max_ix -= 1;
         }
 
-        if let Some(ix) = text[..max_ix].find(['\n']) {
-            if ix > 0 && text.as_bytes()[ix - 1] == b'\r' {
+        let mut first = None;
+        let mut windows_count = 0;
+        let mut unix_count = 0;
+        for (ix, _) in text[..max_ix].match_indices('\n') {
+            let line_ending = if ix > 0 && text.as_bytes()[ix - 1] == b'\r' {
+                windows_count += 1;
                 Self::Windows
             } else {
+                unix_count += 1;
                 Self::Unix
-            }
-        } else {
-            Self::default()
+            };
+            first.get_or_insert(line_ending);
+        }
+
+        match windows_count.cmp(&unix_count) {
+            cmp::Ordering::Greater => Self::Windows,
+            cmp::Ordering::Less => Self::Unix,
+            cmp::Ordering::Equal => first.unwrap_or_default(),
         }
     }
 
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A self-contained copy of the most recent transactions on a buffer's undo stack.
+///
+/// Unlike the buffer's own history, this doesn't reference any replica or lamport clock, so it
+/// can be persisted and later replayed onto a fresh buffer whose text is [`Self::final_text`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializedUndoHistory {
+    /// The text of the buffer before the oldest recorded transaction.
+    pub base_text: String,
+    /// One edit per transaction, oldest first. Each edit's range is a byte range into the text
+    /// obtained by applying all of the preceding edits to `base_text`.
+    pub transactions: Vec<(Range<usize>, String)>,
+}
+
+impl SerializedUndoHistory {
+    /// Builds a history from the text of the buffer before each transaction, oldest first,
+    /// followed by the buffer's current text.
+    pub fn from_texts(texts: &[String]) -> Self {
+        let Some((base_text, _)) = texts.split_first() else {
+            return Self::default();
+        };
+
+        Self {
+            base_text: base_text.clone(),
+            transactions: texts
+                .windows(2)
+                .map(|pair| {
+                    let (old_text, new_text) = (&pair[0], &pair[1]);
+                    let range = changed_range(old_text, new_text);
+                    let new_end = range.end + new_text.len() - old_text.len();
+                    (range.clone(), new_text[range.start..new_end].to_string())
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the text produced by applying every recorded transaction to the base text, or
+    /// `None` if any of the transactions don't fit the text they are applied to.
+    pub fn final_text(&self) -> Option<String> {
+        let mut text = self.base_text.clone();
+        for (range, new_text) in &self.transactions {
+            if range.start > range.end
+                || range.end > text.len()
+                || !text.is_char_boundary(range.start)
+                || !text.is_char_boundary(range.end)
+            {
+                return None;
+            }
+            text.replace_range(range.clone(), new_text);
+        }
+        Some(text)
+    }
+}
+
+/// Returns the range of `old_text` that differs from `new_text`, after trimming their longest
+/// common prefix and suffix.
+fn changed_range(old_text: &str, new_text: &str) -> Range<usize> {
+    let prefix_len = old_text
+        .char_indices()
+        .zip(new_text.chars())
+        .find(|((_, old_char), new_char)| old_char != new_char)
+        .map_or(old_text.len().min(new_text.len()), |((ix, _), _)| ix);
+
+    let max_suffix_len = old_text.len().min(new_text.len()) - prefix_len;
+    let suffix_len = old_text
+        .char_indices()
+        .rev()
+        .zip(new_text.chars().rev())
+        .take_while(|((ix, old_char), new_char)| {
+            old_char == new_char && old_text.len() - ix <= max_suffix_len
+        })
+        .last()
+        .map_or(0, |((ix, _), _)| old_text.len() - ix);
+
+    prefix_len..old_text.len() - suffix_len
+}
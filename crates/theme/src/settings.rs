@@ -2,6 +2,7 @@ use crate::one_themes::one_dark;
 use crate::{Appearance, SyntaxTheme, Theme, ThemeRegistry, ThemeStyleContent};
 use anyhow::Result;
 use derive_more::{Deref, DerefMut};
+use fs::Fs;
 use gpui::{
     px, AppContext, Font, FontFallbacks, FontFeatures, FontStyle, FontWeight, Global, Pixels,
     Subscription, ViewContext, WindowContext,
@@ -14,11 +15,15 @@ use schemars::{
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use settings::{add_references_to_properties, Settings, SettingsJsonSchemaParams, SettingsSources};
+use settings::{
+    add_references_to_properties, update_settings_file, Settings, SettingsJsonSchemaParams,
+    SettingsSources,
+};
 use std::sync::Arc;
 use util::ResultExt as _;
 
 const MIN_FONT_SIZE: Pixels = px(6.0);
+const MAX_FONT_SIZE: Pixels = px(100.0);
 const MIN_LINE_HEIGHT: f32 = 1.0;
 
 #[derive(
@@ -249,7 +254,7 @@ pub struct ThemeSettingsContent {
     #[serde(default)]
     #[schemars(default = "default_font_fallbacks")]
     pub ui_font_fallbacks: Option<Vec<String>>,
-    /// The OpenType features to enable for text in the UI.
+    /// The OpenType features to enable for text in the UI. Not supported on Linux yet.
     #[serde(default)]
     #[schemars(default = "default_font_features")]
     pub ui_font_features: Option<FontFeatures>,
@@ -266,13 +271,17 @@ pub struct ThemeSettingsContent {
     /// The default font size for rendering in text buffers.
     #[serde(default)]
     pub buffer_font_size: Option<f32>,
+    /// The font size chosen with the actions that change the buffer font size. It takes
+    /// precedence over `buffer_font_size`, and is removed when the font size is reset.
+    #[serde(default)]
+    pub zoomed_buffer_font_size: Option<f32>,
     /// The weight of the editor font in CSS units from 100 to 900.
     #[serde(default)]
     pub buffer_font_weight: Option<f32>,
     /// The buffer's line height.
     #[serde(default)]
     pub buffer_line_height: Option<BufferLineHeight>,
-    /// The OpenType features to enable for rendering in text buffers.
+    /// The OpenType features to enable for rendering in text buffers. Not supported on Linux yet.
     #[serde(default)]
     #[schemars(default = "default_font_features")]
     pub buffer_font_features: Option<FontFeatures>,
@@ -464,11 +473,34 @@ pub fn adjust_buffer_font_size(cx: &mut AppContext, f: fn(&mut Pixels)) {
         .map_or(buffer_font_size, |adjusted_size| adjusted_size.0);
 
     f(&mut adjusted_size);
-    adjusted_size = adjusted_size.max(MIN_FONT_SIZE);
+    adjusted_size = adjusted_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
     cx.set_global(AdjustedBufferFontSize(adjusted_size));
     cx.refresh();
 }
 
+/// Adjusts the buffer font size like [`adjust_buffer_font_size`], and saves the new size as the
+/// `zoomed_buffer_font_size` setting so that it's kept after restarting.
+pub fn adjust_and_persist_buffer_font_size(
+    fs: Arc<dyn Fs>,
+    cx: &mut AppContext,
+    f: fn(&mut Pixels),
+) {
+    adjust_buffer_font_size(cx, f);
+    let size = get_buffer_font_size(cx);
+    update_settings_file::<ThemeSettings>(fs, cx, move |settings, _| {
+        settings.zoomed_buffer_font_size = Some(size.0);
+    });
+}
+
+/// Resets the buffer font size to the configured `buffer_font_size`, removing the
+/// `zoomed_buffer_font_size` setting.
+pub fn reset_and_persist_buffer_font_size(fs: Arc<dyn Fs>, cx: &mut AppContext) {
+    reset_buffer_font_size(cx);
+    update_settings_file::<ThemeSettings>(fs, cx, |settings, _| {
+        settings.zoomed_buffer_font_size = None;
+    });
+}
+
 pub fn has_adjusted_buffer_font_size(cx: &mut AppContext) -> bool {
     cx.has_global::<AdjustedBufferFontSize>()
 }
@@ -613,6 +645,10 @@ impl settings::Settings for ThemeSettings {
                 &mut this.buffer_font_size,
                 value.buffer_font_size.map(Into::into),
             );
+            merge(
+                &mut this.buffer_font_size,
+                value.zoomed_buffer_font_size.map(Into::into),
+            );
             merge(&mut this.buffer_line_height, value.buffer_line_height);
 
             // Clamp the `unnecessary_code_fade` to ensure text can't disappear entirely.
@@ -666,3 +702,86 @@ fn merge<T: Copy>(target: &mut T, value: Option<T>) {
         *target = value;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_persisted_buffer_font_size(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.create_dir(paths::settings_file().parent().unwrap())
+            .await
+            .unwrap();
+        let user_settings = r#"{ "buffer_font_size": 20 }"#;
+        fs.insert_file(paths::settings_file(), user_settings.into())
+            .await;
+        cx.update(|cx| {
+            let mut settings_store = SettingsStore::test(cx);
+            settings_store.set_user_settings(user_settings, cx).unwrap();
+            cx.set_global(settings_store);
+            crate::init(crate::LoadThemes::JustBase, cx);
+        });
+        let base_size = px(20.0);
+        assert_eq!(cx.read(get_buffer_font_size), base_size);
+
+        cx.update(|cx| {
+            adjust_and_persist_buffer_font_size(fs.clone(), cx, |size| *size += px(1.0))
+        });
+        cx.run_until_parked();
+        assert_eq!(cx.read(get_buffer_font_size), base_size + px(1.0));
+        assert_eq!(
+            saved_setting(&fs, "zoomed_buffer_font_size").await,
+            Some((base_size + px(1.0)).0 as f64)
+        );
+        assert_eq!(
+            saved_setting(&fs, "buffer_font_size").await,
+            Some(base_size.0 as f64)
+        );
+
+        // The zoomed size takes precedence over the configured one when settings are loaded.
+        let saved_settings = fs.load(paths::settings_file()).await.unwrap();
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.set_user_settings(&saved_settings, cx).unwrap();
+            });
+            reset_buffer_font_size(cx);
+        });
+        assert_eq!(cx.read(get_buffer_font_size), base_size + px(1.0));
+
+        // Resetting returns to the configured size, which is kept in the settings file.
+        cx.update(|cx| reset_and_persist_buffer_font_size(fs.clone(), cx));
+        cx.run_until_parked();
+        assert_eq!(saved_setting(&fs, "zoomed_buffer_font_size").await, None);
+        assert_eq!(
+            saved_setting(&fs, "buffer_font_size").await,
+            Some(base_size.0 as f64)
+        );
+        let saved_settings = fs.load(paths::settings_file()).await.unwrap();
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.set_user_settings(&saved_settings, cx).unwrap();
+            });
+        });
+        assert_eq!(cx.read(get_buffer_font_size), base_size);
+
+        cx.update(|cx| {
+            adjust_and_persist_buffer_font_size(fs.clone(), cx, |size| *size = px(1000.0))
+        });
+        cx.run_until_parked();
+        assert_eq!(cx.read(get_buffer_font_size), MAX_FONT_SIZE);
+        assert_eq!(
+            saved_setting(&fs, "zoomed_buffer_font_size").await,
+            Some(MAX_FONT_SIZE.0 as f64)
+        );
+    }
+
+    async fn saved_setting(fs: &FakeFs, key: &str) -> Option<f64> {
+        let content = fs.load(paths::settings_file()).await.unwrap();
+        let settings: serde_json::Value = serde_json_lenient::from_str(&content).unwrap();
+        settings.get(key)?.as_f64()
+    }
+}
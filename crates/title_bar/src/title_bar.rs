@@ -10,7 +10,7 @@ use crate::application_menu::ApplicationMenu;
 use crate::platforms::{platform_linux, platform_mac, platform_windows};
 use auto_update::AutoUpdateStatus;
 use call::ActiveCall;
-use client::{Client, UserStore};
+use client::{Client, ClientSettings, UserStore};
 use feature_flags::{FeatureFlagAppExt, ZedPro};
 use gpui::{
     actions, div, px, Action, AnyElement, AppContext, Decorations, Element, InteractiveElement,
@@ -20,6 +20,7 @@ use gpui::{
 use project::{Project, RepositoryEntry};
 use recent_projects::RecentProjects;
 use rpc::proto::{self, DevServerStatus};
+use settings::Settings;
 use smallvec::SmallVec;
 use std::sync::Arc;
 use theme::ActiveTheme;
@@ -152,7 +153,9 @@ impl Render for TitleBar {
                             .map(|el| {
                                 let status = self.client.status();
                                 let status = &*status.borrow();
-                                if matches!(status, client::Status::Connected { .. }) {
+                                if matches!(status, client::Status::Connected { .. })
+                                    || !ClientSettings::get_global(cx).rpc_allowed()
+                                {
                                     el.child(self.render_user_menu_button(cx))
                                 } else {
                                     el.children(self.render_connection_status(status, cx))
@@ -496,6 +499,7 @@ impl TitleBar {
         Button::new("sign_in", "Sign in")
             .label_size(LabelSize::Small)
             .on_click(move |_, cx| {
+                client::enable_rpc_if_unset(cx);
                 let client = client.clone();
                 cx.spawn(move |mut cx| async move {
                     client
@@ -185,6 +185,17 @@ impl PathWithPosition {
         }
     }
 
+    /// Parses a vim-style `+row` or `+row:column` argument, as passed by tools that run
+    /// `$EDITOR +42 file`, into the position for the path argument that follows it.
+    pub fn parse_row_column_argument(argument: &str) -> Option<(u32, Option<u32>)> {
+        let position = argument.strip_prefix('+')?;
+        let (row, column) = match position.split_once(':') {
+            Some((row, column)) => (row, Some(column.parse().ok()?)),
+            None => (position, None),
+        };
+        Some((row.parse().ok()?, column))
+    }
+
     pub fn map_path<E>(
         self,
         mapping: impl FnOnce(PathBuf) -> Result<PathBuf, E>,
@@ -421,6 +432,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn row_column_argument_parsing() {
+        for (input, expected) in [
+            ("+42", Some((42, None))),
+            ("+42:7", Some((42, Some(7)))),
+            ("42", None),
+            ("+", None),
+            ("+a", None),
+            ("+42:", None),
+            ("+42:a", None),
+            ("+file.rs", None),
+        ] {
+            assert_eq!(
+                PathWithPosition::parse_row_column_argument(input),
+                expected,
+                "For input str '{input}', got a parse mismatch"
+            );
+        }
+    }
+
     // Trim off trailing `:`s for otherwise valid input.
     #[test]
     fn path_with_position_parsing_special() {
@@ -0,0 +1,282 @@
+use editor::{actions::ExpandAllHunkDiffs, Editor, MultiBuffer};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use git::log::LogEntry;
+use gpui::{
+    actions, rems, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    Render, SharedString, Subscription, Task, View, ViewContext, VisualContext, WeakView,
+    WindowContext,
+};
+use picker::{Picker, PickerDelegate};
+use project::{Item as _, Project, ProjectPath};
+use std::sync::Arc;
+use time::UtcOffset;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::notifications::{DetachAndPromptErr, NotificationId};
+use workspace::{ModalView, Toast, Workspace};
+
+actions!(file_history, [Toggle]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(FileHistory::toggle);
+    })
+    .detach();
+}
+
+/// Lists the commits that touched the active file. Confirming one opens the changes it made to it.
+pub struct FileHistory {
+    picker: View<Picker<FileHistoryDelegate>>,
+    _subscription: Subscription,
+}
+
+impl FileHistory {
+    fn new(delegate: FileHistoryDelegate, cx: &mut ViewContext<Self>) -> Self {
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        let _subscription = cx.subscribe(&picker, |_, _, _, cx| cx.emit(DismissEvent));
+        Self {
+            picker,
+            _subscription,
+        }
+    }
+
+    fn toggle(workspace: &mut Workspace, _: &Toggle, cx: &mut ViewContext<Workspace>) {
+        let Some(buffer) = workspace
+            .active_item_as::<Editor>(cx)
+            .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+        else {
+            return;
+        };
+        let Some(project_path) = buffer.read(cx).project_path(cx) else {
+            return;
+        };
+        let project = workspace.project().clone();
+        let history = project.read(cx).file_history(&buffer, cx);
+
+        cx.spawn(|workspace, mut cx| async move {
+            let entries = history.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                if entries.is_empty() {
+                    struct NoFileHistory;
+                    let message =
+                        format!("{} has no git history", project_path.path.to_string_lossy());
+                    workspace.show_toast(
+                        Toast::new(NotificationId::unique::<NoFileHistory>(), message),
+                        cx,
+                    );
+                    return;
+                }
+
+                let delegate = FileHistoryDelegate {
+                    workspace: cx.view().downgrade(),
+                    project,
+                    project_path,
+                    entries,
+                    matches: Vec::new(),
+                    selected_index: 0,
+                };
+                workspace.toggle_modal(cx, |cx| FileHistory::new(delegate, cx));
+            })
+        })
+        .detach_and_prompt_err("Failed to load file history", cx, |_, _| None);
+    }
+}
+
+impl ModalView for FileHistory {}
+impl EventEmitter<DismissEvent> for FileHistory {}
+
+impl FocusableView for FileHistory {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for FileHistory {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(40.)).child(self.picker.clone())
+    }
+}
+
+pub struct FileHistoryDelegate {
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    project_path: ProjectPath,
+    /// The commits that touched the file, newest first.
+    entries: Vec<LogEntry>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl FileHistoryDelegate {
+    fn relative_timestamp(entry: &LogEntry) -> String {
+        match entry.author_date_time() {
+            Ok(timestamp) => {
+                let local = chrono::Local::now().offset().local_minus_utc();
+                time_format::format_localized_timestamp(
+                    timestamp,
+                    time::OffsetDateTime::now_utc(),
+                    UtcOffset::from_whole_seconds(local).unwrap(),
+                    time_format::TimestampFormat::Relative,
+                )
+            }
+            Err(_) => "Error parsing date".to_string(),
+        }
+    }
+}
+
+impl PickerDelegate for FileHistoryDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Select commit...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let candidates = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(ix, entry)| StringMatchCandidate {
+                id: ix,
+                char_bag: entry.summary.chars().collect(),
+                string: entry.summary.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        cx.spawn(move |picker, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                let mut matches = fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    10000,
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await;
+                // Keep the commits in history order rather than by score.
+                matches.sort_unstable_by_key(|mat| mat.candidate_id);
+                matches
+            };
+            picker
+                .update(&mut cx, |picker, _| {
+                    let delegate = &mut picker.delegate;
+                    delegate.matches = matches;
+                    delegate.selected_index = delegate
+                        .selected_index
+                        .min(delegate.matches.len().saturating_sub(1));
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        let Some(entry_ix) = self
+            .matches
+            .get(self.selected_index)
+            .map(|mat| mat.candidate_id)
+        else {
+            return;
+        };
+        let entry = &self.entries[entry_ix];
+        // The next older commit that touched the file has its path as of the parent commit,
+        // which differs from the entry's own path if this commit renamed the file.
+        let path_at_parent = self
+            .entries
+            .get(entry_ix + 1)
+            .map_or(&entry.path, |parent| &parent.path)
+            .clone();
+        let title = format!(
+            "{} @ {}",
+            entry.path.to_string_lossy(),
+            entry.sha.display_short()
+        );
+        let open = self.project.update(cx, |project, cx| {
+            project.open_commit_diff(
+                self.project_path.clone(),
+                entry.sha,
+                entry.path.clone(),
+                path_at_parent,
+                cx,
+            )
+        });
+        let workspace = self.workspace.clone();
+        let project = self.project.clone();
+
+        cx.spawn(|picker, mut cx| async move {
+            let buffer = open.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let multibuffer =
+                    cx.new_model(|cx| MultiBuffer::singleton(buffer, cx).with_title(title));
+                let editor =
+                    cx.new_view(|cx| Editor::for_multibuffer(multibuffer, Some(project), true, cx));
+                editor.update(cx, |editor, cx| {
+                    editor.expand_all_hunk_diffs(&ExpandAllHunkDiffs, cx)
+                });
+                workspace.add_item_to_active_pane(Box::new(editor), None, true, cx);
+            })?;
+            picker.update(&mut cx, |_, cx| cx.emit(DismissEvent))
+        })
+        .detach_and_prompt_err("Failed to open commit", cx, |_, _| None);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let hit = &self.matches[ix];
+        let entry = &self.entries[hit.candidate_id];
+        Some(
+            ListItem::new(SharedString::from(format!("file-history-{ix}")))
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .start_slot(
+                    Label::new(entry.sha.display_short())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .child(HighlightedLabel::new(
+                    entry.summary.clone(),
+                    hit.positions.clone(),
+                ))
+                .end_slot(
+                    Label::new(format!(
+                        "{}, {}",
+                        entry.author,
+                        Self::relative_timestamp(entry)
+                    ))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+                ),
+        )
+    }
+}
@@ -1,3 +1,5 @@
+mod file_history;
+
 use anyhow::{Context, Result};
 use fuzzy::{StringMatch, StringMatchCandidate};
 use git::repository::Branch;
@@ -13,9 +15,12 @@ use util::ResultExt;
 use workspace::notifications::NotificationId;
 use workspace::{ModalView, Toast, Workspace};
 
+pub use file_history::{FileHistory, FileHistoryDelegate};
+
 actions!(branches, [OpenRecent]);
 
 pub fn init(cx: &mut AppContext) {
+    file_history::init(cx);
     cx.observe_new_views(|workspace: &mut Workspace, _| {
         workspace.register_action(|workspace, action, cx| {
             BranchList::open(workspace, action, cx).log_err();
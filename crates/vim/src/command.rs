@@ -588,8 +588,10 @@ fn generate_commands(_: &AppContext) -> Vec<VimCommand> {
         VimCommand::new(("rev", "ert"), editor::actions::RevertSelectedHunks).range(),
         VimCommand::new(("d", "elete"), VisualDeleteLine).range(),
         VimCommand::new(("y", "ank"), VisualYankLine).range(),
-        VimCommand::new(("sor", "t"), SortLinesCaseSensitive).range(),
-        VimCommand::new(("sort i", ""), SortLinesCaseInsensitive).range(),
+        VimCommand::new(("sor", "t"), SortLinesCaseSensitive::default())
+            .bang(SortLinesCaseSensitive { descending: true })
+            .range(),
+        VimCommand::new(("sort i", ""), SortLinesCaseInsensitive::default()).range(),
         VimCommand::str(("E", "xplore"), "project_panel::ToggleFocus"),
         VimCommand::str(("H", "explore"), "project_panel::ToggleFocus"),
         VimCommand::str(("L", "explore"), "project_panel::ToggleFocus"),
@@ -2,7 +2,7 @@ mod base_keymap_picker;
 mod base_keymap_setting;
 mod multibuffer_hint;
 
-use client::{telemetry::Telemetry, TelemetrySettings};
+use client::{telemetry::Telemetry, ClientSettings, TelemetrySettings};
 use db::kvp::KEY_VALUE_STORE;
 use gpui::{
     actions, svg, AppContext, EventEmitter, FocusHandle, FocusableView, InteractiveElement,
@@ -243,6 +243,25 @@ impl Render for WelcomePage {
                                         }
                                     });
                                 }),
+                            ))
+                            .child(CheckboxWithLabel::new(
+                                "enable-collaboration",
+                                Label::new("Connect to collaboration services"),
+                                if ClientSettings::get_global(cx).rpc_enabled == Some(true) {
+                                    ui::Selection::Selected
+                                } else {
+                                    ui::Selection::Unselected
+                                },
+                                cx.listener(move |this, selection, cx| {
+                                    this.telemetry.report_app_event(
+                                        "welcome page: toggle collaboration".to_string(),
+                                    );
+                                    this.update_settings::<ClientSettings>(
+                                        selection,
+                                        cx,
+                                        |settings, value| settings.rpc_enabled = Some(value),
+                                    );
+                                }),
                             )),
                     ),
             )
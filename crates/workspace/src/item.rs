@@ -1036,6 +1036,8 @@ pub mod test {
 
     pub struct TestItem {
         pub workspace_id: Option<WorkspaceId>,
+        /// The id of the serialized item this one was restored from.
+        pub deserialized_item_id: Option<ItemId>,
         pub state: String,
         pub label: String,
         pub save_count: usize,
@@ -1111,6 +1113,7 @@ pub mod test {
                 tab_descriptions: None,
                 tab_detail: Default::default(),
                 workspace_id: Default::default(),
+                deserialized_item_id: None,
                 focus_handle: cx.focus_handle(),
                 serialize: None,
             }
@@ -1263,6 +1266,7 @@ pub mod test {
                 tab_descriptions: None,
                 tab_detail: Default::default(),
                 workspace_id: self.workspace_id,
+                deserialized_item_id: None,
                 focus_handle: cx.focus_handle(),
                 serialize: None,
             }))
@@ -1326,10 +1330,14 @@ pub mod test {
             _project: Model<Project>,
             _workspace: WeakView<Workspace>,
             workspace_id: WorkspaceId,
-            _item_id: ItemId,
+            item_id: ItemId,
             cx: &mut ViewContext<Pane>,
         ) -> Task<anyhow::Result<View<Self>>> {
-            let view = cx.new_view(|cx| Self::new_deserialized(workspace_id, cx));
+            let view = cx.new_view(|cx| {
+                let mut this = Self::new_deserialized(workspace_id, cx);
+                this.deserialized_item_id = Some(item_id);
+                this
+            });
             Task::ready(Ok(view))
         }
 
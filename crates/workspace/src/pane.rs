@@ -4,11 +4,12 @@ use crate::{
         WeakItemHandle,
     },
     move_item,
-    notifications::NotifyResultExt,
+    notifications::{NotificationId, NotifyResultExt},
     toolbar::Toolbar,
     workspace_settings::{AutosaveSetting, TabBarSettings, WorkspaceSettings},
     CloseWindow, CopyPath, CopyRelativePath, NewFile, NewTerminal, OpenInTerminal, OpenTerminal,
-    OpenVisible, SplitDirection, ToggleFileFinder, ToggleProjectSymbols, ToggleZoom, Workspace,
+    OpenVisible, SplitDirection, Toast, ToggleFileFinder, ToggleProjectSymbols, ToggleZoom,
+    Workspace,
 };
 use anyhow::Result;
 use collections::{BTreeSet, HashMap, HashSet, VecDeque};
@@ -193,6 +194,7 @@ pub enum Event {
     JoinAll,
     JoinIntoNext,
     ChangeItemTitle,
+    ReorderItems,
     Focus,
     ZoomIn,
     ZoomOut,
@@ -226,6 +228,7 @@ impl fmt::Debug for Event {
             Event::JoinAll => f.write_str("JoinAll"),
             Event::JoinIntoNext => f.write_str("JoinIntoNext"),
             Event::ChangeItemTitle => f.write_str("ChangeItemTitle"),
+            Event::ReorderItems => f.write_str("ReorderItems"),
             Event::Focus => f.write_str("Focus"),
             Event::ZoomIn => f.write_str("ZoomIn"),
             Event::ZoomOut => f.write_str("ZoomOut"),
@@ -974,6 +977,61 @@ impl Pane {
         self.items.get(ix).map(|i| i.as_ref())
     }
 
+    /// Reveals the given entry, or the active item's one, in the project panel.
+    /// Items without a file can't be revealed, which is reported with a toast.
+    pub fn reveal_in_project_panel(
+        &mut self,
+        action: &RevealInProjectPanel,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let entry_id = action
+            .entry_id
+            .map(ProjectEntryId::from_proto)
+            .or_else(|| self.active_item()?.project_entry_ids(cx).first().copied());
+        if let Some(entry_id) = entry_id {
+            self.project.update(cx, |_, cx| {
+                cx.emit(project::Event::RevealInProjectPanel(entry_id))
+            });
+        } else {
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    workspace.show_toast(
+                        Toast::new(
+                            NotificationId::unique::<RevealInProjectPanel>(),
+                            "The active item has no file to reveal in the project panel",
+                        ),
+                        cx,
+                    )
+                })
+                .ok();
+        }
+    }
+
+    /// Moves the item at `from_ix` to `to_ix`, keeping it among the pinned or unpinned tabs it belongs to.
+    pub fn reorder_item(&mut self, from_ix: usize, to_ix: usize, cx: &mut ViewContext<Self>) {
+        if from_ix >= self.items.len() {
+            return;
+        }
+        let to_ix = if self.is_tab_pinned(from_ix) {
+            to_ix.min(self.pinned_tab_count - 1)
+        } else {
+            to_ix.clamp(self.pinned_tab_count, self.items.len() - 1)
+        };
+        if from_ix == to_ix {
+            return;
+        }
+
+        let active_item_id = self.active_item().map(|item| item.item_id());
+        let item = self.items.remove(from_ix);
+        self.items.insert(to_ix, item);
+        if let Some(active_item_ix) = active_item_id.and_then(|id| self.index_for_item_id(id)) {
+            self.active_item_index = active_item_ix;
+        }
+
+        cx.emit(Event::ReorderItems);
+        cx.notify();
+    }
+
     pub fn toggle_zoom(&mut self, _: &ToggleZoom, cx: &mut ViewContext<Self>) {
         if self.zoomed {
             cx.emit(Event::ZoomOut);
@@ -2326,39 +2384,58 @@ impl Pane {
                     let old_ix = from_pane.read(cx).index_for_item_id(item_id);
                     if to_pane == from_pane {
                         if let Some(old_index) = old_ix {
-                            to_pane.update(cx, |this, _| {
-                                if old_index < this.pinned_tab_count
-                                    && (ix == this.items.len() || ix > this.pinned_tab_count)
-                                {
-                                    this.pinned_tab_count -= 1;
-                                } else if this.has_pinned_tabs()
-                                    && old_index >= this.pinned_tab_count
-                                    && ix < this.pinned_tab_count
-                                {
-                                    this.pinned_tab_count += 1;
-                                }
+                            to_pane.update(cx, |this, cx| {
+                                this.reorder_dragged_item(old_index, ix, cx);
                             });
                         }
-                    } else {
-                        to_pane.update(cx, |this, _| {
-                            if this.has_pinned_tabs() && ix < this.pinned_tab_count {
-                                this.pinned_tab_count += 1;
-                            }
-                        });
-                        from_pane.update(cx, |this, _| {
-                            if let Some(index) = old_ix {
-                                if this.pinned_tab_count > index {
-                                    this.pinned_tab_count -= 1;
-                                }
-                            }
-                        })
+                        return;
                     }
+                    to_pane.update(cx, |this, _| {
+                        if this.has_pinned_tabs() && ix < this.pinned_tab_count {
+                            this.pinned_tab_count += 1;
+                        }
+                    });
+                    from_pane.update(cx, |this, _| {
+                        if let Some(index) = old_ix {
+                            if this.pinned_tab_count > index {
+                                this.pinned_tab_count -= 1;
+                            }
+                        }
+                    });
                     move_item(&from_pane, &to_pane, item_id, ix, cx);
                 });
             })
             .log_err();
     }
 
+    /// Moves a tab dragged within this pane, pinning or unpinning it when it's dropped on the
+    /// other side of the pinned tabs.
+    fn reorder_dragged_item(&mut self, from_ix: usize, to_ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(item_id) = self.items.get(from_ix).map(|item| item.item_id()) else {
+            return;
+        };
+        let mut from_ix = from_ix;
+        if from_ix < self.pinned_tab_count
+            && (to_ix == self.items.len() || to_ix > self.pinned_tab_count)
+        {
+            // Move the tab to the end of the pinned tabs, where unpinning it leaves it in place.
+            self.reorder_item(from_ix, self.pinned_tab_count - 1, cx);
+            from_ix = self.pinned_tab_count - 1;
+            self.pinned_tab_count -= 1;
+        } else if self.has_pinned_tabs()
+            && from_ix >= self.pinned_tab_count
+            && to_ix < self.pinned_tab_count
+        {
+            self.reorder_item(from_ix, self.pinned_tab_count, cx);
+            from_ix = self.pinned_tab_count;
+            self.pinned_tab_count += 1;
+        }
+        self.reorder_item(from_ix, to_ix, cx);
+        if let Some(ix) = self.index_for_item_id(item_id) {
+            self.activate_item(ix, true, true, cx);
+        }
+    }
+
     fn handle_dragged_selection_drop(
         &mut self,
         dragged_selection: &DraggedSelection,
@@ -2635,19 +2712,7 @@ impl Render for Pane {
                     }
                 }),
             )
-            .on_action(
-                cx.listener(|pane: &mut Self, action: &RevealInProjectPanel, cx| {
-                    let entry_id = action
-                        .entry_id
-                        .map(ProjectEntryId::from_proto)
-                        .or_else(|| pane.active_item()?.project_entry_ids(cx).first().copied());
-                    if let Some(entry_id) = entry_id {
-                        pane.project.update(cx, |_, cx| {
-                            cx.emit(project::Event::RevealInProjectPanel(entry_id))
-                        });
-                    }
-                }),
-            )
+            .on_action(cx.listener(Self::reveal_in_project_panel))
             .when(self.active_item().is_some() && display_tab_bar, |pane| {
                 pane.child(self.render_tab_bar(cx))
             })
@@ -2996,6 +3061,7 @@ mod tests {
     use gpui::{TestAppContext, VisualTestContext};
     use project::FakeFs;
     use settings::SettingsStore;
+    use std::cell::RefCell;
     use theme::LoadThemes;
 
     #[gpui::test]
@@ -3442,6 +3508,208 @@ mod tests {
         assert_item_labels(&pane, [], cx);
     }
 
+    #[gpui::test]
+    async fn test_close_items_with_dirty_items(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        pane.update(cx, |pane, cx| {
+            for (id, label, is_dirty) in [
+                (1, "A", false),
+                (2, "B", true),
+                (3, "C", false),
+                (4, "D", true),
+                (5, "E", false),
+            ] {
+                let item = cx.new_view(|cx| {
+                    TestItem::new(cx)
+                        .with_label(label)
+                        .with_dirty(is_dirty)
+                        .with_project_items(&[TestProjectItem::new(
+                            id,
+                            &format!("{label}.txt"),
+                            cx,
+                        )])
+                });
+                pane.add_item(Box::new(item), false, false, None, cx);
+            }
+            pane.activate_item(2, false, false, cx);
+        });
+        assert_item_labels(&pane, ["A", "B^", "C*", "D^", "E"], cx);
+
+        // Closing the items to the right prompts to save the dirty one among them.
+        let close = pane
+            .update(cx, |pane, cx| {
+                pane.close_items_to_the_right(&CloseItemsToTheRight, cx)
+            })
+            .unwrap();
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(1); // Don't Save
+        close.await.unwrap();
+        assert!(!cx.has_pending_prompt());
+        assert_item_labels(&pane, ["A", "B^", "C*"], cx);
+
+        // Cancelling the prompt keeps the dirty item, and the ones after it, open.
+        let close = pane
+            .update(cx, |pane, cx| {
+                pane.close_inactive_items(&CloseInactiveItems { save_intent: None }, cx)
+            })
+            .unwrap();
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(2); // Cancel
+        close.await.unwrap();
+        assert_item_labels(&pane, ["B*^", "C"], cx);
+
+        // Closing all the items only prompts for the dirty one.
+        let close = pane
+            .update(cx, |pane, cx| {
+                pane.close_all_items(&CloseAllItems { save_intent: None }, cx)
+            })
+            .unwrap();
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(1); // Don't Save
+        close.await.unwrap();
+        assert!(!cx.has_pending_prompt());
+        assert_item_labels(&pane, [], cx);
+    }
+
+    #[gpui::test]
+    async fn test_reorder_item(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        set_labeled_items(&pane, ["A", "B*", "C", "D"], cx);
+
+        pane.update(cx, |pane, cx| pane.reorder_item(0, 2, cx));
+        assert_item_labels(&pane, ["B*", "C", "A", "D"], cx);
+
+        pane.update(cx, |pane, cx| pane.reorder_item(3, 0, cx));
+        assert_item_labels(&pane, ["D", "B*", "C", "A"], cx);
+
+        // Out of bounds destinations move the item to the end.
+        pane.update(cx, |pane, cx| pane.reorder_item(1, 10, cx));
+        assert_item_labels(&pane, ["D", "C", "A", "B*"], cx);
+
+        // Unpinned items can't be moved among the pinned ones, and vice versa.
+        pane.update(cx, |pane, cx| {
+            pane.set_pinned_count(1);
+            pane.reorder_item(3, 0, cx);
+        });
+        assert_item_labels(&pane, ["D", "B*", "C", "A"], cx);
+        pane.update(cx, |pane, cx| pane.reorder_item(0, 3, cx));
+        assert_item_labels(&pane, ["D", "B*", "C", "A"], cx);
+    }
+
+    #[gpui::test]
+    async fn test_drag_tab_within_pane(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+        let reorder_count = Rc::new(RefCell::new(0));
+        cx.update(|cx| {
+            let reorder_count = reorder_count.clone();
+            cx.subscribe(&pane, move |_, event, _| {
+                if let Event::ReorderItems = event {
+                    *reorder_count.borrow_mut() += 1;
+                }
+            })
+            .detach();
+        });
+
+        set_labeled_items(&pane, ["A", "B*", "C", "D"], cx);
+
+        // Dragged tabs are reordered in place and activated.
+        drag_tab(&pane, 0, 2, cx);
+        assert_item_labels(&pane, ["B", "C", "A*", "D"], cx);
+        assert_eq!(*reorder_count.borrow(), 1);
+
+        // Dropping a tab among the pinned ones pins it.
+        pane.update(cx, |pane, _| pane.set_pinned_count(1));
+        drag_tab(&pane, 3, 0, cx);
+        assert_item_labels(&pane, ["D*", "B", "C", "A"], cx);
+        assert_eq!(pane.read_with(cx, |pane, _| pane.pinned_count()), 2);
+
+        // Dropping a pinned tab after the others unpins it.
+        drag_tab(&pane, 0, 4, cx);
+        assert_item_labels(&pane, ["B", "C", "A", "D*"], cx);
+        assert_eq!(pane.read_with(cx, |pane, _| pane.pinned_count()), 1);
+    }
+
+    #[gpui::test]
+    async fn test_reveal_in_project_panel(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/root", serde_json::json!({ "dir": { "file.txt": "" } }))
+            .await;
+
+        let project = Project::test(fs, ["/root".as_ref()], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        let revealed_paths = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let revealed_paths = revealed_paths.clone();
+            cx.subscribe(&project, move |project, event, cx| {
+                if let project::Event::RevealInProjectPanel(entry_id) = event {
+                    let path = project.read(cx).path_for_entry(*entry_id, cx).unwrap();
+                    revealed_paths.borrow_mut().push(path.path.to_path_buf());
+                }
+            })
+            .detach();
+        });
+
+        let entry_id = project.update(cx, |project, cx| {
+            let worktree_id = project.worktrees(cx).next().unwrap().read(cx).id();
+            project
+                .entry_for_path(&(worktree_id, "dir/file.txt").into(), cx)
+                .unwrap()
+                .id
+        });
+        let item = cx.new_view(|cx| {
+            TestItem::new(cx).with_project_items(&[TestProjectItem::new(
+                entry_id.to_proto(),
+                "dir/file.txt",
+                cx,
+            )])
+        });
+        pane.update(cx, |pane, cx| {
+            pane.add_item(Box::new(item), true, true, None, cx);
+            pane.reveal_in_project_panel(&RevealInProjectPanel { entry_id: None }, cx);
+        });
+        assert_eq!(
+            revealed_paths.borrow_mut().drain(..).collect::<Vec<_>>(),
+            [PathBuf::from("dir/file.txt")]
+        );
+
+        // Items without a file are not revealed, and a message is shown instead.
+        let item = cx.new_view(TestItem::new);
+        pane.update(cx, |pane, cx| {
+            pane.add_item(Box::new(item), true, true, None, cx);
+            pane.reveal_in_project_panel(&RevealInProjectPanel { entry_id: None }, cx);
+        });
+        assert!(revealed_paths.borrow().is_empty());
+        workspace.update(cx, |workspace, _| {
+            assert_eq!(
+                workspace.notification_ids(),
+                [NotificationId::unique::<RevealInProjectPanel>()]
+            );
+        });
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings_store = SettingsStore::test(cx);
@@ -3496,6 +3764,20 @@ mod tests {
     }
 
     // Assert the item label, with the active item label suffixed with a '*'
+    fn drag_tab(pane: &View<Pane>, from_ix: usize, to_ix: usize, cx: &mut VisualTestContext) {
+        pane.update(cx, |pane, cx| {
+            let dragged_tab = DraggedTab {
+                pane: cx.view().clone(),
+                item: pane.items[from_ix].boxed_clone(),
+                ix: from_ix,
+                detail: 0,
+                is_active: from_ix == pane.active_item_index,
+            };
+            pane.handle_tab_drop(&dragged_tab, to_ix, cx);
+        });
+        cx.run_until_parked();
+    }
+
     fn assert_item_labels<const COUNT: usize>(
         pane: &View<Pane>,
         expected_states: [&str; COUNT],
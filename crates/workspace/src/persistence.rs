@@ -4,6 +4,7 @@ use std::path::Path;
 
 use anyhow::{anyhow, bail, Context, Result};
 use client::DevServerProjectId;
+use collections::HashSet;
 use db::{define_connection, query, sqlez::connection::Connection, sqlez_macros::sql};
 use gpui::{point, size, Axis, Bounds, WindowBounds, WindowId};
 
@@ -20,13 +21,36 @@ use crate::WorkspaceId;
 
 use model::{
     GroupId, LocalPaths, PaneId, SerializedItem, SerializedPane, SerializedPaneGroup,
-    SerializedWorkspace,
+    SerializedWorkspace, WorkspaceLookup,
 };
 
 use self::model::{
     DockStructure, LocalPathsOrder, SerializedDevServerProject, SerializedWorkspaceLocation,
 };
 
+/// The most recent search queries that are kept for each workspace and kind of search.
+pub const MAX_PERSISTED_SEARCH_HISTORY: usize = 50;
+
+/// The search bar whose queries are stored in a workspace's search history.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SearchHistoryKind {
+    /// Searches within the active item.
+    Buffer,
+    /// Searches across the whole project.
+    Project,
+}
+
+impl StaticColumnCount for SearchHistoryKind {}
+impl Bind for SearchHistoryKind {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        match self {
+            SearchHistoryKind::Buffer => "Buffer",
+            SearchHistoryKind::Project => "Project",
+        }
+        .bind(statement, start_index)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub(crate) struct SerializedAxis(pub(crate) gpui::Axis);
 impl sqlez::bindable::StaticColumnCount for SerializedAxis {}
@@ -354,6 +378,18 @@ define_connection! {
     ),
     sql!(
         ALTER TABLE panes ADD COLUMN pinned_count INTEGER DEFAULT 0;
+    ),
+    sql!(
+        CREATE TABLE search_history(
+            workspace_id INTEGER NOT NULL,
+            kind TEXT NOT NULL, // Enum: 'Buffer' / 'Project'
+            position INTEGER NOT NULL, // Oldest queries come first
+            search_query TEXT NOT NULL,
+            FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE,
+            PRIMARY KEY(workspace_id, kind, position)
+        ) STRICT;
     )
     ];
 }
@@ -694,6 +730,37 @@ impl WorkspaceDb {
         }
     }
 
+    /// Looks up the workspace stored for the given worktree roots, allocating a new
+    /// workspace for them if none is stored yet. The returned [`WorkspaceLookup`]
+    /// tells callers whether they should restore persisted state or apply defaults.
+    pub(crate) async fn workspace_for_roots_or_create<P: AsRef<Path>>(
+        &self,
+        worktree_roots: &[P],
+    ) -> Result<WorkspaceLookup> {
+        if let Some(workspace) = self.workspace_for_roots(worktree_roots) {
+            return Ok(WorkspaceLookup::Existing(workspace));
+        }
+
+        if worktree_roots.is_empty() {
+            return Ok(WorkspaceLookup::Created(self.next_id().await?));
+        }
+
+        let local_paths = LocalPaths::new(worktree_roots);
+        let local_paths_order = LocalPathsOrder::default_for_paths(&local_paths);
+        let workspace_id = self
+            .write(move |conn| {
+                conn.select_row_bound::<_, WorkspaceId>(sql!(
+                    INSERT INTO workspaces(local_paths, local_paths_order)
+                    VALUES (?, ?)
+                    RETURNING workspace_id
+                ))?((&local_paths, &local_paths_order))?
+                .ok_or_else(|| anyhow!("Could not retrieve inserted workspace_id"))
+            })
+            .await?;
+
+        Ok(WorkspaceLookup::Created(workspace_id))
+    }
+
     query! {
         fn recent_workspaces() -> Result<Vec<(WorkspaceId, LocalPaths, LocalPathsOrder, Option<u64>)>> {
             SELECT workspace_id, local_paths, local_paths_order, dev_server_project_id
@@ -704,8 +771,25 @@ impl WorkspaceDb {
     }
 
     query! {
-        fn session_workspaces(session_id: String) -> Result<Vec<(LocalPaths, Option<u64>)>> {
-            SELECT local_paths, window_id
+        fn local_workspaces() -> Result<Vec<(WorkspaceId, LocalPaths)>> {
+            SELECT workspace_id, local_paths
+            FROM workspaces
+            WHERE local_paths IS NOT NULL
+            ORDER BY timestamp DESC
+        }
+    }
+
+    query! {
+        pub(crate) fn local_paths_for_workspace(workspace_id: WorkspaceId) -> Result<Option<LocalPaths>> {
+            SELECT local_paths
+            FROM workspaces
+            WHERE workspace_id = ? AND local_paths IS NOT NULL
+        }
+    }
+
+    query! {
+        fn session_workspaces(session_id: String) -> Result<Vec<(WorkspaceId, LocalPaths, Option<u64>)>> {
+            SELECT workspace_id, local_paths, window_id
             FROM workspaces
             WHERE session_id = ?1 AND dev_server_project_id IS NULL
             ORDER BY timestamp DESC
@@ -795,18 +879,29 @@ impl WorkspaceDb {
         Ok(result)
     }
 
-    pub async fn last_workspace(&self) -> Result<Option<LocalPaths>> {
+    pub async fn last_workspace(&self) -> Result<Option<(WorkspaceId, LocalPaths)>> {
         Ok(self
             .recent_workspaces_on_disk()
             .await?
             .into_iter()
-            .filter_map(|(_, location)| match location {
-                SerializedWorkspaceLocation::Local(local_paths, _) => Some(local_paths),
+            .filter_map(|(id, location)| match location {
+                SerializedWorkspaceLocation::Local(local_paths, _) => Some((id, local_paths)),
                 SerializedWorkspaceLocation::DevServer(_) => None,
             })
             .next())
     }
 
+    /// Returns the local workspaces with a root that contains the given path, most recently used
+    /// first. When roots are nested, every workspace with a containing root is returned.
+    pub fn workspaces_containing(&self, path: &Path) -> Result<Vec<WorkspaceId>> {
+        Ok(self
+            .local_workspaces()?
+            .into_iter()
+            .filter(|(_, location)| location.paths().iter().any(|root| path.starts_with(root)))
+            .map(|(id, _)| id)
+            .collect())
+    }
+
     // Returns the locations of the workspaces that were still opened when the last
     // session was closed (i.e. when Zed was quit).
     // If `last_session_window_order` is provided, the returned locations are ordered
@@ -815,19 +910,19 @@ impl WorkspaceDb {
         &self,
         last_session_id: &str,
         last_session_window_stack: Option<Vec<WindowId>>,
-    ) -> Result<Vec<LocalPaths>> {
+    ) -> Result<Vec<(WorkspaceId, LocalPaths)>> {
         let mut workspaces = Vec::new();
 
-        for (location, window_id) in self.session_workspaces(last_session_id.to_owned())? {
+        for (id, location, window_id) in self.session_workspaces(last_session_id.to_owned())? {
             if location.paths().iter().all(|path| path.exists())
                 && location.paths().iter().any(|path| path.is_dir())
             {
-                workspaces.push((location, window_id.map(WindowId::from)));
+                workspaces.push((id, location, window_id.map(WindowId::from)));
             }
         }
 
         if let Some(stack) = last_session_window_stack {
-            workspaces.sort_by_key(|(_, window_id)| {
+            workspaces.sort_by_key(|(_, _, window_id)| {
                 window_id
                     .and_then(|id| stack.iter().position(|&order_id| order_id == id))
                     .unwrap_or(usize::MAX)
@@ -836,7 +931,7 @@ impl WorkspaceDb {
 
         Ok(workspaces
             .into_iter()
-            .map(|(paths, _)| paths)
+            .map(|(id, paths, _)| (id, paths))
             .collect::<Vec<_>>())
     }
 
@@ -1048,6 +1143,53 @@ impl WorkspaceDb {
         }
     }
 
+    /// Replaces the stored search history of the given kind for a workspace. Queries are
+    /// ordered from oldest to newest. Only the most recent occurrence of a repeated query
+    /// is kept, and only the newest [`MAX_PERSISTED_SEARCH_HISTORY`] queries are stored.
+    pub async fn save_search_history(
+        &self,
+        workspace_id: WorkspaceId,
+        kind: SearchHistoryKind,
+        queries: Vec<String>,
+    ) -> Result<()> {
+        let mut seen = HashSet::default();
+        let mut queries = queries
+            .into_iter()
+            .rev()
+            .filter(|query| seen.insert(query.clone()))
+            .take(MAX_PERSISTED_SEARCH_HISTORY)
+            .collect::<Vec<_>>();
+        queries.reverse();
+
+        self.write(move |conn| {
+            conn.with_savepoint("save_search_history", || {
+                conn.exec_bound(sql!(
+                    DELETE FROM search_history WHERE workspace_id = ? AND kind = ?
+                ))?((workspace_id, kind))
+                .context("Clearing old search history")?;
+
+                let mut insert = conn.exec_bound(sql!(
+                    INSERT INTO search_history(workspace_id, kind, position, search_query)
+                    VALUES (?, ?, ?, ?)
+                ))?;
+                for (position, query) in queries.iter().enumerate() {
+                    insert((workspace_id, kind, position, query.as_str()))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    query! {
+        pub fn search_history_for_workspace(workspace_id: WorkspaceId, kind: SearchHistoryKind) -> Result<Vec<String>> {
+            SELECT search_query
+            FROM search_history
+            WHERE workspace_id = ? AND kind = ?
+            ORDER BY position
+        }
+    }
+
     query! {
         pub(crate) async fn set_centered_layout(workspace_id: WorkspaceId, centered_layout: bool) -> Result<()> {
             UPDATE workspaces
@@ -1061,9 +1203,12 @@ impl WorkspaceDb {
 mod tests {
     use super::*;
     use crate::persistence::model::SerializedWorkspace;
-    use crate::persistence::model::{SerializedItem, SerializedPane, SerializedPaneGroup};
+    use crate::persistence::model::{
+        DockData, SerializedItem, SerializedPane, SerializedPaneGroup,
+    };
     use db::open_test_db;
     use gpui::{self};
+    use std::sync::Arc;
 
     #[gpui::test]
     async fn test_next_id_stability() {
@@ -1372,6 +1517,84 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_workspace_for_roots_or_create() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_workspace_for_roots_or_create").await);
+
+        let WorkspaceLookup::Created(workspace_id) = db
+            .workspace_for_roots_or_create(&["/tmp", "/tmp2"])
+            .await
+            .unwrap()
+        else {
+            panic!("expected the first lookup to create a workspace");
+        };
+
+        // Roots are treated as a set, so the second lookup finds the same workspace
+        let WorkspaceLookup::Existing(existing_workspace) = db
+            .workspace_for_roots_or_create(&["/tmp2", "/tmp"])
+            .await
+            .unwrap()
+        else {
+            panic!("expected the second lookup to find the existing workspace");
+        };
+        assert_eq!(existing_workspace.id, workspace_id);
+
+        // Different roots get a workspace of their own
+        let lookup = db.workspace_for_roots_or_create(&["/tmp3"]).await.unwrap();
+        assert!(matches!(lookup, WorkspaceLookup::Created(id) if id != workspace_id));
+    }
+
+    #[gpui::test]
+    async fn test_concurrent_workspace_access() {
+        env_logger::try_init().ok();
+
+        let db = Arc::new(WorkspaceDb(
+            open_test_db("test_concurrent_workspace_access").await,
+        ));
+
+        let threads = (0..8)
+            .map(|ix| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    let root = format!("/tmp/concurrent-{ix}");
+                    for iteration in 0..20 {
+                        let mut workspace =
+                            default_workspace(&[root.as_str()], &Default::default());
+                        workspace.id = WorkspaceId(ix + 1);
+                        workspace.docks.bottom = DockData {
+                            visible: iteration % 2 == 0,
+                            active_panel: Some(format!("panel-{iteration}")),
+                            zoom: false,
+                        };
+                        db::smol::block_on(db.save_workspace(workspace));
+                        db.workspace_for_roots(&[root.as_str()]);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            assert!(thread.join().is_ok());
+        }
+
+        for ix in 0..8 {
+            let workspace = db
+                .workspace_for_roots(&[format!("/tmp/concurrent-{ix}")])
+                .unwrap();
+            assert_eq!(workspace.id, WorkspaceId(ix + 1));
+            assert_eq!(
+                workspace.docks.bottom,
+                DockData {
+                    visible: false,
+                    active_panel: Some("panel-19".into()),
+                    zoom: false,
+                }
+            );
+        }
+    }
+
     #[gpui::test]
     async fn test_session_workspaces() {
         env_logger::try_init().ok();
@@ -1444,6 +1667,56 @@ mod tests {
         assert_eq!(locations[0].1, Some(30));
     }
 
+    #[gpui::test]
+    async fn test_workspaces_containing() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_workspaces_containing").await);
+
+        let workspaces = [
+            (WorkspaceId(1), vec!["/code"]),
+            (WorkspaceId(2), vec!["/code/zed"]),
+            (WorkspaceId(3), vec!["/notes", "/code/zed/crates/editor"]),
+            (WorkspaceId(4), vec!["/code/zed-fork"]),
+        ];
+        for (id, roots) in workspaces {
+            db.save_workspace(SerializedWorkspace {
+                id,
+                location: SerializedWorkspaceLocation::from_local_paths(roots),
+                center_group: Default::default(),
+                window_bounds: Default::default(),
+                display: Default::default(),
+                docks: Default::default(),
+                centered_layout: false,
+                session_id: None,
+                window_id: None,
+            })
+            .await;
+        }
+
+        let containing = |path: &str| {
+            let mut ids = db.workspaces_containing(Path::new(path)).unwrap();
+            ids.sort();
+            ids
+        };
+        assert_eq!(
+            containing("/code/zed/crates/editor/src/editor.rs"),
+            [WorkspaceId(1), WorkspaceId(2), WorkspaceId(3)]
+        );
+        assert_eq!(
+            containing("/code/zed/Cargo.toml"),
+            [WorkspaceId(1), WorkspaceId(2)]
+        );
+        // Roots only contain paths below them, not paths sharing a prefix.
+        assert_eq!(
+            containing("/code/zed-fork/README.md"),
+            [WorkspaceId(1), WorkspaceId(4)]
+        );
+        assert_eq!(containing("/notes/todo.md"), [WorkspaceId(3)]);
+        assert_eq!(containing("/code/zed"), [WorkspaceId(1), WorkspaceId(2)]);
+        assert!(containing("/tmp/scratch.txt").is_empty());
+    }
+
     fn default_workspace<P: AsRef<Path>>(
         workspace_id: &[P],
         center_group: &SerializedPaneGroup,
@@ -1506,10 +1779,34 @@ mod tests {
             .last_session_workspace_locations("one-session", stack)
             .unwrap();
         assert_eq!(have.len(), 4);
-        assert_eq!(have[0], LocalPaths::new([dir4.path().to_str().unwrap()]));
-        assert_eq!(have[1], LocalPaths::new([dir3.path().to_str().unwrap()]));
-        assert_eq!(have[2], LocalPaths::new([dir2.path().to_str().unwrap()]));
-        assert_eq!(have[3], LocalPaths::new([dir1.path().to_str().unwrap()]));
+        assert_eq!(
+            have[0],
+            (
+                WorkspaceId(4),
+                LocalPaths::new([dir4.path().to_str().unwrap()])
+            )
+        );
+        assert_eq!(
+            have[1],
+            (
+                WorkspaceId(3),
+                LocalPaths::new([dir3.path().to_str().unwrap()])
+            )
+        );
+        assert_eq!(
+            have[2],
+            (
+                WorkspaceId(2),
+                LocalPaths::new([dir2.path().to_str().unwrap()])
+            )
+        );
+        assert_eq!(
+            have[3],
+            (
+                WorkspaceId(1),
+                LocalPaths::new([dir1.path().to_str().unwrap()])
+            )
+        );
     }
 
     #[gpui::test]
@@ -1567,6 +1864,52 @@ mod tests {
         assert_eq!(workspace.center_group, new_workspace.center_group);
     }
 
+    #[gpui::test]
+    async fn test_horizontal_split_with_flexes() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_horizontal_split_with_flexes").await);
+
+        //  -----------------
+        //  | 1, 2  | 3     |
+        //  -----------------
+        let center_pane = SerializedPaneGroup::Group {
+            axis: SerializedAxis(Axis::Horizontal),
+            flexes: Some(vec![0.75, 1.25]),
+            children: vec![
+                SerializedPaneGroup::Pane(SerializedPane::new(
+                    vec![
+                        SerializedItem::new("Editor", 1, false, false),
+                        SerializedItem::new("Editor", 2, true, false),
+                    ],
+                    true,
+                    0,
+                )),
+                SerializedPaneGroup::Pane(SerializedPane::new(
+                    vec![SerializedItem::new("Editor", 3, true, false)],
+                    false,
+                    0,
+                )),
+            ],
+        };
+
+        let workspace = default_workspace(&["/tmp"], &center_pane);
+        db.save_workspace(workspace.clone()).await;
+        let restored = db.workspace_for_roots(&["/tmp"]).unwrap();
+        assert_eq!(restored.center_group, center_pane);
+
+        // Re-saving the workspace with a different layout replaces the old one.
+        let center_pane = SerializedPaneGroup::Pane(SerializedPane::new(
+            vec![SerializedItem::new("Editor", 3, true, false)],
+            true,
+            0,
+        ));
+        let workspace = default_workspace(&["/tmp"], &center_pane);
+        db.save_workspace(workspace.clone()).await;
+        let restored = db.workspace_for_roots(&["/tmp"]).unwrap();
+        assert_eq!(restored.center_group, center_pane);
+    }
+
     #[gpui::test]
     async fn test_cleanup_panes() {
         env_logger::try_init().ok();
@@ -1642,4 +1985,65 @@ mod tests {
 
         assert_eq!(workspace.center_group, new_workspace.center_group);
     }
+
+    #[gpui::test]
+    async fn test_search_history() {
+        env_logger::try_init().ok();
+
+        let db = WorkspaceDb(open_test_db("test_search_history").await);
+        let workspace_id = db.next_id().await.unwrap();
+        let other_workspace_id = db.next_id().await.unwrap();
+
+        let queries = |queries: &[&str]| queries.iter().map(|query| query.to_string()).collect();
+
+        // Repeated queries only keep their most recent position.
+        db.save_search_history(
+            workspace_id,
+            SearchHistoryKind::Buffer,
+            queries(&["one", "two", "one", "three", "two"]),
+        )
+        .await
+        .unwrap();
+        db.save_search_history(workspace_id, SearchHistoryKind::Project, queries(&["four"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.search_history_for_workspace(workspace_id, SearchHistoryKind::Buffer)
+                .unwrap(),
+            ["one", "three", "two"]
+        );
+        assert_eq!(
+            db.search_history_for_workspace(workspace_id, SearchHistoryKind::Project)
+                .unwrap(),
+            ["four"]
+        );
+        assert!(db
+            .search_history_for_workspace(other_workspace_id, SearchHistoryKind::Buffer)
+            .unwrap()
+            .is_empty());
+
+        // Saving again replaces the stored history, keeping only the newest queries.
+        let many_queries = (0..MAX_PERSISTED_SEARCH_HISTORY + 10)
+            .map(|ix| ix.to_string())
+            .collect::<Vec<_>>();
+        db.save_search_history(
+            workspace_id,
+            SearchHistoryKind::Buffer,
+            many_queries.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            db.search_history_for_workspace(workspace_id, SearchHistoryKind::Buffer)
+                .unwrap(),
+            &many_queries[10..]
+        );
+
+        // The history is removed along with its workspace.
+        db.delete_workspace_by_id(workspace_id).await.unwrap();
+        assert!(db
+            .search_history_for_workspace(workspace_id, SearchHistoryKind::Project)
+            .unwrap()
+            .is_empty());
+    }
 }
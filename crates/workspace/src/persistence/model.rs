@@ -219,6 +219,15 @@ pub(crate) struct SerializedWorkspace {
     pub(crate) window_id: Option<u64>,
 }
 
+/// The outcome of looking up a workspace by its worktree roots.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum WorkspaceLookup {
+    /// No workspace was stored for the roots, so a fresh one was allocated.
+    Created(WorkspaceId),
+    /// A workspace was already stored for the roots.
+    Existing(SerializedWorkspace),
+}
+
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct DockStructure {
     pub(crate) left: DockData,
@@ -49,10 +49,13 @@ use node_runtime::NodeRuntime;
 use notifications::{simple_message_notification::MessageNotification, NotificationHandle};
 pub use pane::*;
 pub use pane_group::*;
-use persistence::{model::SerializedWorkspace, SerializedWindowBounds, DB};
 pub use persistence::{
     model::{ItemId, LocalPaths, SerializedDevServerProject, SerializedWorkspaceLocation},
-    WorkspaceDb, DB as WORKSPACE_DB,
+    SearchHistoryKind, WorkspaceDb, DB as WORKSPACE_DB,
+};
+use persistence::{
+    model::{SerializedWorkspace, WorkspaceLookup},
+    SerializedWindowBounds, DB,
 };
 use postage::stream::Stream;
 use project::{
@@ -197,6 +200,13 @@ pub struct CloseInactiveTabsAndPanes {
 #[derive(Clone, Deserialize, PartialEq)]
 pub struct SendKeystrokes(pub String);
 
+/// Opens the file at `path` as of the given git `revision`, in a read-only editor.
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct OpenAtRevision {
+    pub path: PathBuf,
+    pub revision: String,
+}
+
 #[derive(Clone, Deserialize, PartialEq, Default)]
 pub struct Reload {
     pub binary_path: Option<PathBuf>,
@@ -219,6 +229,7 @@ impl_actions!(
         ActivatePaneInDirection,
         CloseAllItemsAndPanes,
         CloseInactiveTabsAndPanes,
+        OpenAtRevision,
         OpenTerminal,
         Reload,
         Save,
@@ -1080,8 +1091,17 @@ impl Workspace {
         );
 
         cx.spawn(|mut cx| async move {
-            let serialized_workspace: Option<SerializedWorkspace> =
-                persistence::DB.workspace_for_roots(abs_paths.as_slice());
+            let (workspace_id, serialized_workspace) = match persistence::DB
+                .workspace_for_roots_or_create(abs_paths.as_slice())
+                .await
+                .log_err()
+            {
+                Some(WorkspaceLookup::Existing(serialized_workspace)) => {
+                    (serialized_workspace.id, Some(serialized_workspace))
+                }
+                Some(WorkspaceLookup::Created(workspace_id)) => (workspace_id, None),
+                None => (Default::default(), None),
+            };
 
             let mut paths_to_open = abs_paths;
 
@@ -1126,12 +1146,6 @@ impl Workspace {
                 }
             }
 
-            let workspace_id = if let Some(serialized_workspace) = serialized_workspace.as_ref() {
-                serialized_workspace.id
-            } else {
-                DB.next_id().await.unwrap_or_else(|_| Default::default())
-            };
-
             let window = if let Some(window) = requesting_window {
                 cx.update_window(window.into(), |_, cx| {
                     cx.replace_root_view(|cx| {
@@ -1201,6 +1215,26 @@ impl Workspace {
         })
     }
 
+    /// Opens a new window for a workspace that was previously persisted to the database,
+    /// restoring its worktrees, docks, open items and window bounds.
+    pub fn restore(
+        workspace_id: WorkspaceId,
+        app_state: Arc<AppState>,
+        cx: &mut AppContext,
+    ) -> Task<anyhow::Result<WindowHandle<Workspace>>> {
+        cx.spawn(|mut cx| async move {
+            let local_paths = persistence::DB
+                .local_paths_for_workspace(workspace_id)?
+                .ok_or_else(|| anyhow!("no local workspace found with id {workspace_id:?}"))?;
+            let (window, _) = cx
+                .update(|cx| {
+                    Workspace::new_local(local_paths.paths().to_vec(), app_state, None, None, cx)
+                })?
+                .await?;
+            Ok(window)
+        })
+    }
+
     pub fn weak_handle(&self) -> WeakView<Self> {
         self.weak_self.clone()
     }
@@ -2996,7 +3030,7 @@ impl Workspace {
                 }
                 self.update_window_edited(cx);
             }
-            pane::Event::RemoveItem { .. } => {}
+            pane::Event::RemoveItem { .. } | pane::Event::ReorderItems => {}
             pane::Event::RemovedItem { item_id } => {
                 cx.emit(Event::ActiveItemChanged);
                 self.update_window_edited(cx);
@@ -4649,6 +4683,21 @@ fn notify_if_database_failed(workspace: WindowHandle<Workspace>, cx: &mut AsyncA
                         })
                     },
                 );
+            } else if (*db::DB_RECOVERED_FROM_CORRUPTION).load(std::sync::atomic::Ordering::Acquire)
+            {
+                struct DatabaseRecoveredNotification;
+
+                workspace.show_notification_once(
+                    NotificationId::unique::<DatabaseRecoveredNotification>(),
+                    cx,
+                    |cx| {
+                        cx.new_view(|_| {
+                            MessageNotification::new(
+                                "The database file was corrupt and has been recreated. Previously saved workspaces were lost.",
+                            )
+                        })
+                    },
+                );
             }
         })
         .log_err();
@@ -5002,14 +5051,14 @@ pub fn activate_workspace_for_project(
     None
 }
 
-pub async fn last_opened_workspace_paths() -> Option<LocalPaths> {
+pub async fn last_opened_workspace() -> Option<(WorkspaceId, LocalPaths)> {
     DB.last_workspace().await.log_err().flatten()
 }
 
-pub fn last_session_workspace_locations(
+pub fn last_session_workspaces(
     last_session_id: &str,
     last_session_window_stack: Option<Vec<WindowId>>,
-) -> Option<Vec<LocalPaths>> {
+) -> Option<Vec<(WorkspaceId, LocalPaths)>> {
     DB.last_session_workspace_locations(last_session_id, last_session_window_stack)
         .log_err()
 }
@@ -5293,6 +5342,8 @@ pub struct OpenOptions {
     pub open_new_workspace: Option<bool>,
     pub replace_window: Option<WindowHandle<Workspace>>,
     pub env: Option<HashMap<String, String>>,
+    /// Whether the editors opened for the given paths should be read-only.
+    pub readonly: bool,
 }
 
 #[allow(clippy::type_complexity)]
@@ -6228,6 +6279,56 @@ mod tests {
         assert!(task.await.unwrap());
     }
 
+    #[gpui::test]
+    async fn test_reordered_items_are_restored(cx: &mut TestAppContext) {
+        init_test(cx);
+        let app_state = cx.update(|cx| {
+            register_serializable_item::<TestItem>(cx);
+            AppState::test(cx)
+        });
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree("/reordered", json!({}))
+            .await;
+
+        let window = open_persisted_workspace("/reordered", &app_state, cx).await;
+        let item_ids = window
+            .update(cx, |workspace, cx| {
+                for _ in 0..3 {
+                    let item = cx.new_view(TestItem::new);
+                    workspace.add_item_to_active_pane(Box::new(item), None, true, cx);
+                }
+                workspace.active_pane().update(cx, |pane, cx| {
+                    pane.reorder_item(2, 0, cx);
+                    pane.items()
+                        .map(|item| item.item_id().as_u64())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .unwrap();
+
+        // Advance the clock to make sure the workspace is serialized
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+
+        let restored = open_persisted_workspace("/reordered", &app_state, cx).await;
+        let restored_item_ids = restored
+            .update(cx, |workspace, cx| {
+                workspace
+                    .active_pane()
+                    .read(cx)
+                    .items()
+                    .map(|item| {
+                        let item = item.downcast::<TestItem>().unwrap();
+                        item.read(cx).deserialized_item_id.unwrap()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap();
+        assert_eq!(restored_item_ids, item_ids);
+    }
+
     #[gpui::test]
     async fn test_close_pane_items(cx: &mut TestAppContext) {
         init_test(cx);
@@ -6618,6 +6719,83 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_activate_pane_in_direction(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+        let app_state = cx.update(|cx| {
+            register_serializable_item::<TestItem>(cx);
+            AppState::test(cx)
+        });
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree("/activate_in_direction", json!({}))
+            .await;
+        let window = open_persisted_workspace("/activate_in_direction", &app_state, cx).await;
+        let workspace = window.root(cx).unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+
+        //  -----------------------------
+        //  | top_left    | top_right    |
+        //  | - - - - - - | - - - - - -  |
+        //  | bottom_left | bottom_right |
+        //  -----------------------------
+        let top_left = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+        let (top_right, bottom_left, bottom_right) = workspace.update(cx, |workspace, cx| {
+            let top_right = workspace.split_pane(top_left.clone(), SplitDirection::Right, cx);
+            let bottom_left = workspace.split_pane(top_left.clone(), SplitDirection::Down, cx);
+            let bottom_right = workspace.split_pane(top_right.clone(), SplitDirection::Down, cx);
+            (top_right, bottom_left, bottom_right)
+        });
+        // Panes without items aren't restored.
+        for pane in [&top_left, &top_right, &bottom_left, &bottom_right] {
+            pane.update(cx, |pane, cx| {
+                let item = cx.new_view(TestItem::new);
+                pane.add_item(Box::new(item), false, false, None, cx);
+            });
+        }
+        workspace.update(cx, |_, cx| cx.focus_view(&top_left));
+        cx.run_until_parked();
+
+        let mut activate_in_direction = |direction, expected: &View<Pane>| {
+            workspace.update(cx, |workspace, cx| {
+                workspace.activate_pane_in_direction(direction, cx)
+            });
+            cx.run_until_parked();
+            workspace.update(cx, |workspace, cx| {
+                assert_eq!(workspace.active_pane(), expected);
+                assert!(expected.read(cx).has_focus(cx));
+            });
+        };
+
+        activate_in_direction(SplitDirection::Right, &top_right);
+        activate_in_direction(SplitDirection::Down, &bottom_right);
+        activate_in_direction(SplitDirection::Left, &bottom_left);
+        activate_in_direction(SplitDirection::Up, &top_left);
+
+        // There's no pane beyond the edges of the layout.
+        activate_in_direction(SplitDirection::Up, &top_left);
+        activate_in_direction(SplitDirection::Left, &top_left);
+
+        // The pane activated last is the active one when the workspace is restored.
+        activate_in_direction(SplitDirection::Down, &bottom_left);
+        let active_item_id =
+            bottom_left.update(cx, |pane, _| pane.active_item().unwrap().item_id().as_u64());
+        // Advance the clock to make sure the workspace is serialized
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+
+        let restored = open_persisted_workspace("/activate_in_direction", &app_state, cx).await;
+        let restored_item_id = restored
+            .update(cx, |workspace, cx| {
+                let item = workspace.active_pane().read(cx).active_item().unwrap();
+                let item = item.downcast::<TestItem>().unwrap();
+                item.read(cx).deserialized_item_id
+            })
+            .unwrap();
+        assert_eq!(restored_item_id, Some(active_item_id));
+    }
+
     #[gpui::test]
     async fn test_toggle_docks_and_panels(cx: &mut gpui::TestAppContext) {
         init_test(cx);
@@ -7510,6 +7688,23 @@ mod tests {
         }
     }
 
+    /// Opens a local workspace on `root` that is persisted to the database, so that opening it
+    /// again restores it.
+    async fn open_persisted_workspace(
+        root: &str,
+        app_state: &Arc<AppState>,
+        cx: &mut TestAppContext,
+    ) -> WindowHandle<Workspace> {
+        let (window, _) = cx
+            .update(|cx| {
+                Workspace::new_local(vec![PathBuf::from(root)], app_state.clone(), None, None, cx)
+            })
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        window
+    }
+
     pub fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings_store = SettingsStore::test(cx);
@@ -7,7 +7,7 @@ use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
 use anyhow::{anyhow, Context as _, Result};
 use clock::ReplicaId;
 use collections::{HashMap, HashSet, VecDeque};
-use fs::{copy_recursive, Fs, PathEvent, RemoveOptions, Watcher};
+use fs::{copy_recursive, Fs, PathEvent, RemoveOptions, TrashUnavailable, Watcher};
 use futures::{
     channel::{
         mpsc::{self, UnboundedSender},
@@ -60,7 +60,7 @@ use std::{
     time::{Duration, Instant, SystemTime},
 };
 use sum_tree::{Bias, Edit, SeekTarget, SumTree, TreeMap, TreeSet};
-use text::{LineEnding, Rope};
+use text::{Encoding, LineEnding, Rope};
 use util::{paths::home_dir, ResultExt};
 pub use worktree_settings::WorktreeSettings;
 
@@ -98,6 +98,10 @@ pub struct LoadedFile {
     pub file: Arc<File>,
     pub text: String,
     pub diff_base: Option<String>,
+    /// Whether the file's permissions prevent it from being written to.
+    pub is_read_only: bool,
+    /// The encoding the file's text was decoded from.
+    pub encoding: Encoding,
 }
 
 pub struct LocalWorktree {
@@ -679,10 +683,11 @@ impl Worktree {
         path: &Path,
         text: Rope,
         line_ending: LineEnding,
+        encoding: Encoding,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Arc<File>>> {
         match self {
-            Worktree::Local(this) => this.write_file(path, text, line_ending, cx),
+            Worktree::Local(this) => this.write_file(path, text, line_ending, encoding, cx),
             Worktree::Remote(_) => {
                 Task::ready(Err(anyhow!("remote worktree can't yet write files")))
             }
@@ -1245,7 +1250,8 @@ impl LocalWorktree {
 
         cx.spawn(|this, mut cx| async move {
             let abs_path = abs_path?;
-            let text = fs.load(&abs_path).await?;
+            let (text, encoding) = fs.load_with_encoding(&abs_path).await?;
+            let is_read_only = fs.is_read_only(&abs_path).await;
             let mut index_task = None;
             let snapshot = this.update(&mut cx, |this, _| this.as_local().unwrap().snapshot())?;
             if let Some(repo) = snapshot.repository_for_path(&path) {
@@ -1297,6 +1303,8 @@ impl LocalWorktree {
                 file,
                 text,
                 diff_base,
+                is_read_only,
+                encoding,
             })
         })
     }
@@ -1383,6 +1391,7 @@ impl LocalWorktree {
         path: impl Into<Arc<Path>>,
         text: Rope,
         line_ending: LineEnding,
+        encoding: Encoding,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Arc<File>>> {
         let path = path.into();
@@ -1395,7 +1404,10 @@ impl LocalWorktree {
         let write = cx.background_executor().spawn({
             let fs = fs.clone();
             let abs_path = abs_path.clone();
-            async move { fs.save(&abs_path, &text, line_ending).await }
+            async move {
+                fs.save_with_encoding(&abs_path, &text, line_ending, encoding)
+                    .await
+            }
         });
 
         cx.spawn(move |this, mut cx| async move {
@@ -1444,30 +1456,35 @@ impl LocalWorktree {
         let fs = self.fs.clone();
 
         let delete = cx.background_executor().spawn(async move {
-            if entry.is_file() {
-                if trash {
-                    fs.trash_file(&abs_path?, Default::default()).await?;
+            let abs_path = abs_path?;
+            let options = if entry.is_file() {
+                RemoveOptions::default()
+            } else {
+                RemoveOptions {
+                    recursive: true,
+                    ignore_if_not_exists: false,
+                }
+            };
+            if trash {
+                let result = if entry.is_file() {
+                    fs.trash_file(&abs_path, options).await
                 } else {
-                    fs.remove_file(&abs_path?, Default::default()).await?;
+                    fs.trash_dir(&abs_path, options).await
+                };
+                match result {
+                    Ok(()) => return anyhow::Ok(entry.path),
+                    Err(error) if error.is::<TrashUnavailable>() => {
+                        log::warn!("{error}, deleting {abs_path:?} permanently");
+                    }
+                    // Other failures are returned, so that the caller can confirm before the
+                    // entry is deleted permanently.
+                    Err(error) => return Err(error),
                 }
-            } else if trash {
-                fs.trash_dir(
-                    &abs_path?,
-                    RemoveOptions {
-                        recursive: true,
-                        ignore_if_not_exists: false,
-                    },
-                )
-                .await?;
+            }
+            if entry.is_file() {
+                fs.remove_file(&abs_path, options).await?;
             } else {
-                fs.remove_dir(
-                    &abs_path?,
-                    RemoveOptions {
-                        recursive: true,
-                        ignore_if_not_exists: false,
-                    },
-                )
-                .await?;
+                fs.remove_dir(&abs_path, options).await?;
             }
             anyhow::Ok(entry.path)
         });
@@ -3135,12 +3152,12 @@ impl language::LocalFile for File {
         }
     }
 
-    fn load(&self, cx: &AppContext) -> Task<Result<String>> {
+    fn load(&self, cx: &AppContext) -> Task<Result<(String, Encoding)>> {
         let worktree = self.worktree.read(cx).as_local().unwrap();
         let abs_path = worktree.absolutize(&self.path);
         let fs = worktree.fs.clone();
         cx.background_executor()
-            .spawn(async move { fs.load(&abs_path?).await })
+            .spawn(async move { fs.load_with_encoding(&abs_path?).await })
     }
 }
 
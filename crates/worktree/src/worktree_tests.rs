@@ -3,7 +3,7 @@ use crate::{
     WorktreeModelHandle,
 };
 use anyhow::Result;
-use fs::{FakeFs, Fs, RealFs, RemoveOptions};
+use fs::{FakeFs, FakeTrash, Fs, RealFs, RemoveOptions};
 use git::{repository::GitFileStatus, GITIGNORE};
 use gpui::{BorrowAppContext, ModelContext, Task, TestAppContext};
 use parking_lot::Mutex;
@@ -12,7 +12,13 @@ use pretty_assertions::assert_eq;
 use rand::prelude::*;
 use serde_json::json;
 use settings::{Settings, SettingsStore};
-use std::{env, fmt::Write, mem, path::Path, sync::Arc};
+use std::{
+    env,
+    fmt::Write,
+    mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use util::{test::temp_tree, ResultExt};
 
 #[gpui::test]
@@ -388,6 +394,75 @@ async fn test_renaming_case_only(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_trashing_entry(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "a.txt": "",
+            "b.txt": "",
+            "dir": { "c.txt": "" },
+        }),
+    )
+    .await;
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    let entry_id = |path: &str, cx: &mut TestAppContext| {
+        tree.read_with(cx, |tree, _| tree.entry_for_path(path).unwrap().id)
+    };
+
+    let a_id = entry_id("a.txt", cx);
+    let dir_id = entry_id("dir", cx);
+    for entry_id in [a_id, dir_id] {
+        tree.update(cx, |tree, cx| tree.delete_entry(entry_id, true, cx))
+            .unwrap()
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        fs.trashed_paths(),
+        [PathBuf::from("/root/a.txt"), PathBuf::from("/root/dir")]
+    );
+
+    // Other trash failures are returned without deleting anything.
+    fs.set_trash(FakeTrash::Failing);
+    let b_id = entry_id("b.txt", cx);
+    tree.update(cx, |tree, cx| tree.delete_entry(b_id, true, cx))
+        .unwrap()
+        .await
+        .unwrap_err();
+    assert!(fs.is_file(Path::new("/root/b.txt")).await);
+
+    // Without a trash, entries are deleted permanently.
+    fs.set_trash(FakeTrash::Unavailable);
+    tree.update(cx, |tree, cx| tree.delete_entry(b_id, true, cx))
+        .unwrap()
+        .await
+        .unwrap();
+    assert!(!fs.is_file(Path::new("/root/b.txt")).await);
+    assert_eq!(fs.trashed_paths().len(), 2);
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            [Path::new("")]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_open_gitignored_files(cx: &mut TestAppContext) {
     init_test(cx);
@@ -854,6 +929,7 @@ async fn test_write_file(cx: &mut TestAppContext) {
             Path::new("tracked-dir/file.txt"),
             "hello".into(),
             Default::default(),
+            Default::default(),
             cx,
         )
     })
@@ -864,6 +940,7 @@ async fn test_write_file(cx: &mut TestAppContext) {
             Path::new("ignored-dir/file.txt"),
             "world".into(),
             Default::default(),
+            Default::default(),
             cx,
         )
     })
@@ -1732,8 +1809,13 @@ fn randomly_mutate_worktree(
                 })
             } else {
                 log::info!("overwriting file {:?} ({})", entry.path, entry.id.0);
-                let task =
-                    worktree.write_file(entry.path.clone(), "".into(), Default::default(), cx);
+                let task = worktree.write_file(
+                    entry.path.clone(),
+                    "".into(),
+                    Default::default(),
+                    Default::default(),
+                    cx,
+                );
                 cx.background_executor().spawn(async move {
                     task.await?;
                     Ok(())
@@ -11,7 +11,7 @@ use assistant::PromptBuilder;
 use chrono::Offset;
 use clap::{command, Parser};
 use cli::FORCE_CLI_MODE_ENV_VAR_NAME;
-use client::{parse_zed_link, Client, DevServerToken, UserStore};
+use client::{parse_zed_link, Client, ClientSettings, DevServerToken, UserStore};
 use collab_ui::channel_view::ChannelView;
 use db::kvp::KEY_VALUE_STORE;
 use editor::Editor;
@@ -29,6 +29,7 @@ use log::LevelFilter;
 use assets::Assets;
 use node_runtime::RealNodeRuntime;
 use parking_lot::Mutex;
+use project::project_settings::ProjectSettings;
 use recent_projects::open_ssh_project;
 use release_channel::{AppCommitSha, AppVersion};
 use session::{AppSession, Session};
@@ -45,12 +46,12 @@ use std::{
 };
 use theme::{ActiveTheme, SystemAppearance, ThemeRegistry, ThemeSettings};
 use time::UtcOffset;
-use util::{maybe, parse_env_output, ResultExt, TryFutureExt};
+use util::{maybe, parse_env_output, paths::PathWithPosition, ResultExt, TryFutureExt};
 use uuid::Uuid;
 use welcome::{show_welcome_view, BaseKeymap, FIRST_OPEN};
 use workspace::{
     notifications::{simple_message_notification::MessageNotification, NotificationId},
-    AppState, WorkspaceSettings, WorkspaceStore,
+    AppState, Workspace, WorkspaceId, WorkspaceSettings, WorkspaceStore,
 };
 use zed::{
     app_menus, build_window_options, handle_cli_connection, handle_keymap_file_changes,
@@ -448,6 +449,11 @@ fn main() {
         handle_settings_file_changes(user_settings_file_rx, cx, handle_settings_changed);
         handle_keymap_file_changes(user_keymap_file_rx, cx, handle_keymap_changed);
 
+        let args = Args::parse();
+        if args.offline {
+            client::force_offline();
+        }
+
         client::init_settings(cx);
         let client = Client::production(cx);
         cx.set_http_client(client.http_client().clone());
@@ -466,6 +472,8 @@ fn main() {
 
         zed::init(cx);
         project::Project::init(&client, cx);
+        cx.background_executor()
+            .set_num_cpus_hint(ProjectSettings::get_global(cx).max_background_threads);
         client::init(&client, cx);
         language::init(cx);
         let telemetry = client.telemetry();
@@ -495,53 +503,20 @@ fn main() {
         reliability::init(client.http_client(), installation_id, cx);
         let prompt_builder = init_common(app_state.clone(), cx);
 
-        let args = Args::parse();
-        let urls: Vec<_> = args
-            .paths_or_urls
-            .iter()
-            .filter_map(|arg| parse_url_arg(arg, cx).log_err())
-            .collect();
-
-        if !urls.is_empty() {
-            open_listener.open_urls(urls)
-        }
-
-        match open_rx
-            .try_next()
-            .ok()
-            .flatten()
-            .and_then(|urls| OpenRequest::parse(urls, cx).log_err())
-        {
-            Some(request) => {
-                handle_open_request(request, app_state.clone(), prompt_builder.clone(), cx);
-            }
-            None => {
-                if let Some(dev_server_token) = args.dev_server_token {
-                    let task =
-                        init_headless(DevServerToken(dev_server_token), app_state.clone(), cx);
-                    cx.spawn(|cx| async move {
-                        if let Err(e) = task.await {
-                            log::error!("{}", e);
-                            cx.update(|cx| cx.quit()).log_err();
-                        } else {
-                            log::info!("connected!");
-                        }
-                    })
-                    .detach();
-                } else {
-                    init_ui(app_state.clone(), prompt_builder.clone(), cx).unwrap();
-                    cx.spawn({
-                        let app_state = app_state.clone();
-                        |mut cx| async move {
-                            if let Err(e) = restore_or_create_workspace(app_state, &mut cx).await {
-                                fail_to_open_window_async(e, &mut cx)
-                            }
-                        }
-                    })
-                    .detach();
-                }
-            }
-        }
+        let request = startup_open_request(&args.paths_or_urls, cx).or_else(|| {
+            open_rx
+                .try_next()
+                .ok()
+                .flatten()
+                .and_then(|urls| OpenRequest::parse(urls, cx).log_err())
+        });
+        run_startup(
+            request,
+            args.dev_server_token,
+            app_state.clone(),
+            prompt_builder.clone(),
+            cx,
+        );
 
         let app_state = app_state.clone();
         let prompt_builder = prompt_builder.clone();
@@ -559,6 +534,72 @@ fn main() {
     });
 }
 
+/// Parses the paths and urls passed on the command line into the request to handle at startup.
+///
+/// A vim-style `+row` or `+row:column` argument applies to the path that follows it.
+fn startup_open_request(paths_or_urls: &[String], cx: &AppContext) -> Option<OpenRequest> {
+    let mut urls = Vec::new();
+    let mut position = None;
+    for arg in paths_or_urls {
+        if let Some(row_column) = PathWithPosition::parse_row_column_argument(arg) {
+            position = Some(row_column);
+            continue;
+        }
+        // The position belongs to this argument even if it can't be opened.
+        let position = position.take();
+        let Some(url) = parse_url_arg(arg, cx).log_err() else {
+            continue;
+        };
+        match position {
+            Some((row, Some(column))) if url.starts_with("file://") => {
+                urls.push(format!("{url}:{row}:{column}"))
+            }
+            Some((row, None)) if url.starts_with("file://") => urls.push(format!("{url}:{row}")),
+            _ => urls.push(url),
+        }
+    }
+    if let Some((row, _)) = position {
+        log::warn!("ignoring +{row} argument that isn't followed by a path");
+    }
+    if urls.is_empty() {
+        return None;
+    }
+    OpenRequest::parse(urls, cx).log_err()
+}
+
+/// Handles the request Zed was launched with. Without one, Zed either connects as a dev server
+/// or restores the previous session.
+fn run_startup(
+    request: Option<OpenRequest>,
+    dev_server_token: Option<String>,
+    app_state: Arc<AppState>,
+    prompt_builder: Arc<PromptBuilder>,
+    cx: &mut AppContext,
+) {
+    if let Some(request) = request {
+        handle_open_request(request, app_state, prompt_builder, cx);
+    } else if let Some(dev_server_token) = dev_server_token {
+        let task = init_headless(DevServerToken(dev_server_token), app_state, cx);
+        cx.spawn(|cx| async move {
+            if let Err(e) = task.await {
+                log::error!("{}", e);
+                cx.update(|cx| cx.quit()).log_err();
+            } else {
+                log::info!("connected!");
+            }
+        })
+        .detach();
+    } else {
+        init_ui(app_state.clone(), prompt_builder, cx).unwrap();
+        cx.spawn(|mut cx| async move {
+            if let Err(e) = restore_or_create_workspace(app_state, &mut cx).await {
+                fail_to_open_window_async(e, &mut cx)
+            }
+        })
+        .detach();
+    }
+}
+
 fn handle_keymap_changed(error: Option<anyhow::Error>, cx: &mut AppContext) {
     struct KeymapParseErrorNotification;
     let id = NotificationId::unique::<KeymapParseErrorNotification>();
@@ -670,8 +711,9 @@ fn handle_open_request(
                 }
                 let client = app_state.client.clone();
                 // we continue even if authentication fails as join_channel/ open channel notes will
-                // show a visible error message.
-                authenticate(client, &cx).await.log_err();
+                // show a visible error message. Opening a channel link is a request to connect, so
+                // it isn't treated as a first run.
+                authenticate(client, false, &cx).await.log_err();
 
                 if let Some(channel_id) = request.join_channel {
                     cx.update(|cx| {
@@ -721,6 +763,13 @@ fn handle_open_request(
 }
 
 async fn authenticate(client: Arc<Client>, cx: &AsyncAppContext) -> Result<()> {
+    // Until the user chooses to use collaboration features, nothing is sent to the server, and
+    // the keychain isn't read, as that can prompt the user.
+    let rpc_enabled = cx.update(|cx| ClientSettings::get_global(cx).rpc_enabled)?;
+    if rpc_enabled != Some(true) {
+        return Ok(());
+    }
+
     if stdout_is_a_pty() {
         if *client::ZED_DEVELOPMENT_AUTH {
             client.authenticate_and_connect(true, cx).await?;
@@ -763,17 +812,10 @@ async fn restore_or_create_workspace(
     app_state: Arc<AppState>,
     cx: &mut AsyncAppContext,
 ) -> Result<()> {
-    if let Some(locations) = restorable_workspace_locations(cx, &app_state).await {
-        for location in locations {
-            cx.update(|cx| {
-                workspace::open_paths(
-                    location.paths().as_ref(),
-                    app_state.clone(),
-                    workspace::OpenOptions::default(),
-                    cx,
-                )
-            })?
-            .await?;
+    if let Some(workspaces) = restorable_workspaces(cx, &app_state).await {
+        for (workspace_id, _) in workspaces {
+            cx.update(|cx| Workspace::restore(workspace_id, app_state.clone(), cx))?
+                .await?;
         }
     } else if matches!(KEY_VALUE_STORE.read_kvp(FIRST_OPEN), Ok(None)) {
         cx.update(|cx| show_welcome_view(app_state, cx))?.await?;
@@ -789,10 +831,15 @@ async fn restore_or_create_workspace(
     Ok(())
 }
 
-pub(crate) async fn restorable_workspace_locations(
+pub(crate) async fn restorable_workspaces(
     cx: &mut AsyncAppContext,
     app_state: &Arc<AppState>,
-) -> Option<Vec<workspace::LocalPaths>> {
+) -> Option<Vec<(WorkspaceId, workspace::LocalPaths)>> {
+    // Checked here rather than by the callers, so that launching from the CLI skips the session too.
+    if cx.update(|cx| reliability::is_crash_looping(cx)).ok()? {
+        return None;
+    }
+
     let mut restore_behavior = cx
         .update(|cx| WorkspaceSettings::get(None, cx).restore_on_startup)
         .ok()?;
@@ -819,30 +866,26 @@ pub(crate) async fn restorable_workspace_locations(
     }
 
     match restore_behavior {
-        workspace::RestoreOnStartupBehavior::LastWorkspace => {
-            workspace::last_opened_workspace_paths()
-                .await
-                .map(|location| vec![location])
-        }
+        workspace::RestoreOnStartupBehavior::LastWorkspace => workspace::last_opened_workspace()
+            .await
+            .map(|workspace| vec![workspace]),
         workspace::RestoreOnStartupBehavior::LastSession => {
             if let Some(last_session_id) = last_session_id {
                 let ordered = last_session_window_stack.is_some();
 
-                let mut locations = workspace::last_session_workspace_locations(
-                    &last_session_id,
-                    last_session_window_stack,
-                )
-                .filter(|locations| !locations.is_empty());
+                let mut workspaces =
+                    workspace::last_session_workspaces(&last_session_id, last_session_window_stack)
+                        .filter(|workspaces| !workspaces.is_empty());
 
                 // Since last_session_window_order returns the windows ordered front-to-back
                 // we need to open the window that was frontmost last.
                 if ordered {
-                    if let Some(locations) = locations.as_mut() {
-                        locations.reverse();
+                    if let Some(workspaces) = workspaces.as_mut() {
+                        workspaces.reverse();
                     }
                 }
 
-                locations
+                workspaces
             } else {
                 None
             }
@@ -1063,6 +1106,10 @@ struct Args {
     /// Instructs zed to run as a dev server on this machine. (not implemented)
     #[arg(long)]
     dev_server_token: Option<String>,
+
+    /// Disables all connections to the collaboration server.
+    #[arg(long)]
+    offline: bool,
 }
 
 fn parse_url_arg(arg: &str, cx: &AppContext) -> Result<String> {
@@ -1236,3 +1283,181 @@ fn watch_file_types(fs: Arc<dyn fs::Fs>, cx: &mut AppContext) {
 
 #[cfg(not(debug_assertions))]
 fn watch_file_types(_fs: Arc<dyn fs::Fs>, _cx: &mut AppContext) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use serde_json::json;
+    use util::test::temp_tree;
+
+    #[gpui::test]
+    fn test_startup_open_request(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            cx.set_global(SettingsStore::test(cx));
+            client::init_settings(cx);
+        });
+
+        let dir = temp_tree(json!({
+            "src": {
+                "main.rs": "",
+            },
+        }));
+        let root = dir.path().canonicalize().unwrap();
+        let args = [
+            root.join("src").to_string_lossy().into_owned(),
+            root.join("src/main.rs").to_string_lossy().into_owned(),
+            root.join("missing.rs").to_string_lossy().into_owned(),
+            "zed://channel/zed-7".to_string(),
+        ];
+
+        let request = cx
+            .update(|cx| startup_open_request(&args, cx))
+            .expect("arguments should produce an open request");
+        assert_eq!(
+            request.open_paths,
+            [
+                PathWithPosition::from_path(root.join("src")),
+                PathWithPosition::from_path(root.join("src/main.rs")),
+            ],
+            "paths that don't exist are skipped"
+        );
+        assert_eq!(request.join_channel, Some(7));
+        assert!(request.cli_connection.is_none());
+        assert!(request.ssh_connection.is_none());
+
+        assert!(cx.update(|cx| startup_open_request(&[], cx)).is_none());
+    }
+
+    #[gpui::test]
+    fn test_startup_open_request_with_row_column_arguments(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            cx.set_global(SettingsStore::test(cx));
+            client::init_settings(cx);
+        });
+
+        let dir = temp_tree(json!({
+            "a.rs": "",
+            "b.rs": "",
+            "c.rs": "",
+        }));
+        let root = dir.path().canonicalize().unwrap();
+        let path_arg = |name: &str| root.join(name).to_string_lossy().into_owned();
+
+        let request = cx
+            .update(|cx| {
+                startup_open_request(
+                    &[
+                        "+42".to_string(),
+                        path_arg("a.rs"),
+                        "+42:7".to_string(),
+                        path_arg("b.rs"),
+                        path_arg("c.rs"),
+                    ],
+                    cx,
+                )
+            })
+            .unwrap();
+        assert_eq!(
+            request.open_paths,
+            [
+                PathWithPosition {
+                    path: root.join("a.rs"),
+                    row: Some(42),
+                    column: None,
+                },
+                PathWithPosition {
+                    path: root.join("b.rs"),
+                    row: Some(42),
+                    column: Some(7),
+                },
+                PathWithPosition::from_path(root.join("c.rs")),
+            ]
+        );
+
+        let request = cx
+            .update(|cx| startup_open_request(&[path_arg("a.rs"), "+42".to_string()], cx))
+            .unwrap();
+        assert_eq!(
+            request.open_paths,
+            [PathWithPosition::from_path(root.join("a.rs"))],
+            "a trailing +row argument is ignored"
+        );
+        let request = cx
+            .update(|cx| {
+                startup_open_request(
+                    &["+42".to_string(), path_arg("missing.rs"), path_arg("a.rs")],
+                    cx,
+                )
+            })
+            .unwrap();
+        assert_eq!(
+            request.open_paths,
+            [PathWithPosition::from_path(root.join("a.rs"))],
+            "a +row argument before a path that can't be opened doesn't apply to the next path"
+        );
+        assert!(cx
+            .update(|cx| startup_open_request(&["+42".to_string()], cx))
+            .is_none());
+    }
+
+    #[gpui::test]
+    async fn test_no_authentication_until_rpc_is_chosen(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            cx.set_global(SettingsStore::test(cx));
+            client::init_settings(cx);
+        });
+
+        let client = cx.update(|cx| {
+            Client::new(
+                Arc::new(clock::FakeSystemClock::default()),
+                http_client::FakeHttpClient::with_404_response(),
+                cx,
+            )
+        });
+        let authentications = Arc::new(Mutex::new(0));
+        client.override_authenticate({
+            let authentications = authentications.clone();
+            move |cx| {
+                *authentications.lock() += 1;
+                cx.background_executor()
+                    .spawn(async move { Err(anyhow!("authentication isn't expected")) })
+            }
+        });
+        let connections = Arc::new(Mutex::new(0));
+        client.override_establish_connection({
+            let connections = connections.clone();
+            move |_, cx| {
+                *connections.lock() += 1;
+                cx.background_executor()
+                    .spawn(async move { Err(anyhow!("connecting isn't expected").into()) })
+            }
+        });
+
+        // A fresh install without credentials.
+        authenticate(client.clone(), &cx.to_async()).await.unwrap();
+
+        // An install that signed in before, but hasn't chosen yet.
+        let server_url = cx.update(|cx| ClientSettings::get_global(cx).server_url.clone());
+        cx.simulate_keychain_credentials(&server_url, "1", b"token");
+        authenticate(client.clone(), &cx.to_async()).await.unwrap();
+
+        // An install that turned collaboration off. Its credentials stay untouched.
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<ClientSettings>(cx, |settings| {
+                    settings.rpc_enabled = Some(false)
+                });
+            });
+        });
+        authenticate(client.clone(), &cx.to_async()).await.unwrap();
+
+        assert_eq!(*authentications.lock(), 0);
+        assert_eq!(*connections.lock(), 0);
+        assert_eq!(cx.keychain_read_count(), 0);
+        assert!(matches!(
+            *client.status().borrow(),
+            client::Status::SignedOut
+        ));
+    }
+}
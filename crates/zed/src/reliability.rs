@@ -2,20 +2,25 @@ use anyhow::{Context, Result};
 use backtrace::{self, Backtrace};
 use chrono::Utc;
 use client::telemetry;
+use collections::HashMap;
 use db::kvp::KEY_VALUE_STORE;
-use gpui::{AppContext, SemanticVersion};
+use gpui::{AppContext, EntityId, Global, SemanticVersion, VisualContext};
 use http_client::Method;
 use isahc::config::Configurable;
 
 use http_client::{self, HttpClient, HttpClientWithUrl};
+use parking_lot::Mutex;
 use paths::{crashes_dir, crashes_retired_dir};
 use release_channel::ReleaseChannel;
 use release_channel::RELEASE_CHANNEL;
+use serde::{Deserialize, Serialize};
 use settings::Settings;
 use smol::stream::StreamExt;
 use std::{
+    cell::Cell,
     env,
     ffi::OsStr,
+    path::{Path, PathBuf},
     sync::{atomic::Ordering, Arc},
 };
 use std::{io::Write, panic, sync::atomic::AtomicU32, thread};
@@ -23,10 +28,212 @@ use telemetry_events::LocationData;
 use telemetry_events::Panic;
 use telemetry_events::PanicRequest;
 use util::ResultExt;
+use workspace::{
+    notifications::{simple_message_notification::MessageNotification, NotificationId},
+    Workspace,
+};
 
 use crate::stdout_is_a_pty;
 static PANIC_COUNT: AtomicU32 = AtomicU32::new(0);
 
+/// The paths open in each workspace, recorded so they can be included in crash reports.
+static OPEN_PATHS: Mutex<Option<HashMap<EntityId, Vec<PathBuf>>>> = Mutex::new(None);
+
+/// The number of crash reports kept in the logs directory.
+const MAX_CRASH_REPORTS: usize = 10;
+
+/// A summary of a panic, written to the logs directory for the user to inspect or attach to
+/// an issue. Unlike panic files, crash reports are never uploaded.
+#[derive(Serialize, Deserialize)]
+pub struct CrashReport {
+    /// The time the panic occurred (UNIX millisecond timestamp)
+    pub crashed_on: i64,
+    /// Zed version number
+    pub app_version: String,
+    /// Zed release channel (stable, preview, dev)
+    pub release_channel: String,
+    /// The panic message
+    pub payload: String,
+    /// The location of the panic (file, line number)
+    pub location_data: Option<LocationData>,
+    /// The files open at the time of the panic, relative to the home directory where possible
+    pub open_paths: Vec<String>,
+}
+
+impl CrashReport {
+    fn new(panic: &Panic, open_paths: impl IntoIterator<Item = PathBuf>, home_dir: &Path) -> Self {
+        Self {
+            crashed_on: panic.panicked_on,
+            app_version: panic.app_version.clone(),
+            release_channel: panic.release_channel.clone(),
+            payload: panic.payload.clone(),
+            location_data: panic.location_data.as_ref().map(|location| LocationData {
+                file: location.file.clone(),
+                line: location.line,
+            }),
+            open_paths: open_paths
+                .into_iter()
+                .map(|path| match path.strip_prefix(home_dir) {
+                    Ok(relative_path) => Path::new("~").join(relative_path),
+                    Err(_) => path,
+                })
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect(),
+        }
+    }
+}
+
+/// Writes the crash report to the given directory, removing the oldest reports so that at most
+/// [`MAX_CRASH_REPORTS`] remain.
+fn write_crash_report(report: &CrashReport, dir: &Path) -> Result<PathBuf> {
+    let timestamp = chrono::DateTime::from_timestamp_millis(report.crashed_on)
+        .unwrap_or_default()
+        .format("%Y_%m_%d %H_%M_%S%.3f");
+    let path = dir.join(format!("zed-{timestamp}.crash"));
+    std::fs::write(&path, serde_json::to_string_pretty(report)?)
+        .with_context(|| format!("writing crash report to {path:?}"))?;
+
+    // Timestamps sort chronologically, so the oldest reports come first.
+    let mut reports = std::fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension() == Some(OsStr::new("crash"))
+                && path
+                    .file_name()
+                    .map_or(false, |name| name.to_string_lossy().starts_with("zed-"))
+        })
+        .collect::<Vec<_>>();
+    reports.sort();
+    let excess = reports.len().saturating_sub(MAX_CRASH_REPORTS);
+    for old_report in &reports[..excess] {
+        std::fs::remove_file(old_report).log_err();
+    }
+    Ok(path)
+}
+
+/// After this many launches in a row end without a clean quit, the previous session isn't restored.
+const CRASH_LOOP_THRESHOLD: u32 = 3;
+
+/// Counts the launches in a row that didn't end with a clean quit, so that Zed doesn't keep
+/// restoring a session that crashes it. The count is kept in a marker file that is removed on quit.
+pub struct UncleanExits {
+    marker_path: PathBuf,
+    previous_count: u32,
+}
+
+impl Global for UncleanExits {}
+
+impl UncleanExits {
+    /// Counts this launch as an unclean exit until [`Self::record_clean_exit`] is called.
+    pub fn record_launch(marker_path: PathBuf) -> Self {
+        let previous_count = std::fs::read_to_string(&marker_path)
+            .ok()
+            .and_then(|count| count.trim().parse().ok())
+            .unwrap_or(0);
+        std::fs::write(&marker_path, (previous_count + 1).to_string()).log_err();
+        Self {
+            marker_path,
+            previous_count,
+        }
+    }
+
+    pub fn record_clean_exit(&self) {
+        std::fs::remove_file(&self.marker_path).log_err();
+    }
+
+    /// Whether the previous launch quit normally, rather than crashing or being killed.
+    pub fn previous_exit_was_clean(&self) -> bool {
+        self.previous_count == 0
+    }
+
+    /// Whether enough of the previous launches crashed that their session shouldn't be restored.
+    pub fn is_crash_looping(&self) -> bool {
+        self.previous_count >= CRASH_LOOP_THRESHOLD
+    }
+}
+
+/// Whether the previous session shouldn't be restored because restoring it keeps crashing Zed.
+pub fn is_crash_looping(cx: &AppContext) -> bool {
+    cx.try_global::<UncleanExits>()
+        .map_or(false, |unclean_exits| unclean_exits.is_crash_looping())
+}
+
+/// Explains in the first window that opens why it doesn't contain the previous session.
+fn notify_session_not_restored(cx: &mut AppContext) {
+    let notified = Cell::new(false);
+    cx.observe_new_views(move |workspace: &mut Workspace, cx| {
+        if notified.replace(true) {
+            return;
+        }
+        struct CrashLoopNotification;
+        workspace.show_notification(
+            NotificationId::unique::<CrashLoopNotification>(),
+            cx,
+            |cx| {
+                cx.new_view(|_| {
+                    MessageNotification::new(
+                        "Zed didn't exit cleanly the last few times it was launched, \
+                        so your previous session wasn't restored.",
+                    )
+                })
+            },
+        );
+    })
+    .detach();
+}
+
+fn track_unclean_exits(cx: &mut AppContext) {
+    // Release channels run side by side and restore separate sessions, so each has its own count.
+    let unclean_exits = UncleanExits::record_launch(
+        paths::support_dir().join(format!("unclean_exits-{}", RELEASE_CHANNEL.dev_name())),
+    );
+    if unclean_exits.is_crash_looping() {
+        log::warn!(
+            "the last {} launches didn't exit cleanly, skipping session restore",
+            unclean_exits.previous_count
+        );
+        notify_session_not_restored(cx);
+    } else if !unclean_exits.previous_exit_was_clean() {
+        log::warn!("the previous launch didn't exit cleanly");
+    }
+    cx.set_global(unclean_exits);
+    cx.on_app_quit(|cx| {
+        cx.global::<UncleanExits>().record_clean_exit();
+        async {}
+    })
+    .detach();
+}
+
+fn track_open_paths(_: &mut Workspace, cx: &mut gpui::ViewContext<Workspace>) {
+    let workspace_handle = cx.view().clone();
+    let workspace_id = workspace_handle.entity_id();
+    cx.subscribe(&workspace_handle, move |workspace, _, event, cx| {
+        if matches!(
+            event,
+            workspace::Event::ItemAdded
+                | workspace::Event::ItemRemoved
+                | workspace::Event::ActiveItemChanged
+        ) {
+            let project = workspace.project().read(cx);
+            let paths = workspace
+                .items(cx)
+                .filter_map(|item| project.absolute_path(&item.project_path(cx)?, cx))
+                .collect();
+            OPEN_PATHS
+                .lock()
+                .get_or_insert_with(HashMap::default)
+                .insert(workspace_id, paths);
+        }
+    })
+    .detach();
+    cx.on_release(move |_, _, _| {
+        if let Some(open_paths) = OPEN_PATHS.lock().as_mut() {
+            open_paths.remove(&workspace_id);
+        }
+    })
+    .detach();
+}
+
 pub fn init_panic_hook(
     installation_id: Option<String>,
     app_version: SemanticVersion,
@@ -124,6 +331,23 @@ pub fn init_panic_hook(
                     panic_file.flush().log_err();
                 }
             }
+
+            // Don't block on the lock, in case the panic happened while it was held.
+            let open_paths = OPEN_PATHS
+                .try_lock()
+                .and_then(|open_paths| {
+                    Some(
+                        open_paths
+                            .as_ref()?
+                            .values()
+                            .flatten()
+                            .cloned()
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .unwrap_or_default();
+            let crash_report = CrashReport::new(&panic_data, open_paths, paths::home_dir());
+            write_crash_report(&crash_report, paths::logs_dir()).log_err();
         }
 
         std::process::abort();
@@ -138,6 +362,9 @@ pub fn init(
     #[cfg(target_os = "macos")]
     monitor_main_thread_hangs(http_client.clone(), installation_id.clone(), cx);
 
+    cx.observe_new_views(track_open_paths).detach();
+    track_unclean_exits(cx);
+
     upload_panics_and_crashes(http_client, installation_id, cx)
 }
 
@@ -519,3 +746,126 @@ async fn upload_previous_crashes(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn simulated_panic(panicked_on: i64) -> Panic {
+        Panic {
+            thread: "main".into(),
+            payload: "called `Option::unwrap()` on a `None` value".into(),
+            location_data: Some(LocationData {
+                file: "crates/editor/src/editor.rs".into(),
+                line: 42,
+            }),
+            backtrace: vec!["editor::Editor::new".into()],
+            app_version: "0.150.0".into(),
+            release_channel: "Zed Stable".into(),
+            os_name: "macOS".into(),
+            os_version: None,
+            architecture: "aarch64".into(),
+            panicked_on,
+            installation_id: None,
+            session_id: "session".into(),
+        }
+    }
+
+    #[test]
+    fn test_write_crash_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let home_dir = Path::new("/home/jane");
+        let report = CrashReport::new(
+            &simulated_panic(1_700_000_000_000),
+            [
+                home_dir.join("project/src/main.rs"),
+                PathBuf::from("/tmp/scratch.txt"),
+            ],
+            home_dir,
+        );
+        let first_report_path = write_crash_report(&report, dir.path()).unwrap();
+
+        let content: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&first_report_path).unwrap()).unwrap();
+        assert_eq!(content["crashed_on"], 1_700_000_000_000i64);
+        assert_eq!(content["app_version"], "0.150.0");
+        assert_eq!(content["release_channel"], "Zed Stable");
+        assert_eq!(
+            content["payload"],
+            "called `Option::unwrap()` on a `None` value"
+        );
+        assert_eq!(
+            content["location_data"],
+            json!({ "file": "crates/editor/src/editor.rs", "line": 42 })
+        );
+        assert_eq!(
+            content["open_paths"],
+            json!([
+                Path::new("~").join("project/src/main.rs"),
+                "/tmp/scratch.txt"
+            ])
+        );
+
+        // Old reports are removed once there are too many.
+        let mut last_report_path = first_report_path.clone();
+        for i in 1..=MAX_CRASH_REPORTS as i64 {
+            let report = CrashReport::new(&simulated_panic(1_700_000_000_000 + i), [], home_dir);
+            last_report_path = write_crash_report(&report, dir.path()).unwrap();
+        }
+        assert_eq!(
+            std::fs::read_dir(dir.path()).unwrap().count(),
+            MAX_CRASH_REPORTS
+        );
+        assert!(!first_report_path.exists());
+        assert!(last_report_path.exists());
+    }
+
+    #[test]
+    fn test_unclean_exit_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker_path = dir.path().join("unclean_exits");
+
+        let first_run = UncleanExits::record_launch(marker_path.clone());
+        assert!(first_run.previous_exit_was_clean());
+        assert!(marker_path.exists());
+        first_run.record_clean_exit();
+        assert!(!marker_path.exists());
+
+        // The second run is killed before it can quit.
+        let second_run = UncleanExits::record_launch(marker_path.clone());
+        assert!(second_run.previous_exit_was_clean());
+        drop(second_run);
+        assert!(marker_path.exists());
+
+        let third_run = UncleanExits::record_launch(marker_path.clone());
+        assert!(!third_run.previous_exit_was_clean());
+        third_run.record_clean_exit();
+
+        assert!(UncleanExits::record_launch(marker_path).previous_exit_was_clean());
+    }
+
+    #[test]
+    fn test_crash_loop_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker_path = dir.path().join("unclean_exits");
+
+        // Launches that quit cleanly never count towards a crash loop.
+        for _ in 0..CRASH_LOOP_THRESHOLD + 1 {
+            let unclean_exits = UncleanExits::record_launch(marker_path.clone());
+            assert!(!unclean_exits.is_crash_looping());
+            unclean_exits.record_clean_exit();
+        }
+        assert!(!marker_path.exists());
+
+        for _ in 0..CRASH_LOOP_THRESHOLD {
+            assert!(!UncleanExits::record_launch(marker_path.clone()).is_crash_looping());
+        }
+        let unclean_exits = UncleanExits::record_launch(marker_path.clone());
+        assert!(unclean_exits.is_crash_looping());
+
+        // A clean quit after starting without the previous session resets the count.
+        unclean_exits.record_clean_exit();
+        assert!(!UncleanExits::record_launch(marker_path).is_crash_looping());
+    }
+}
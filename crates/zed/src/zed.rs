@@ -1,4 +1,5 @@
 mod app_menus;
+mod diagnostics_archive;
 pub mod inline_completion_registry;
 #[cfg(target_os = "linux")]
 pub(crate) mod linux_prompts;
@@ -60,6 +61,7 @@ actions!(
     zed,
     [
         DebugElements,
+        ExportDiagnostics,
         Hide,
         HideOthers,
         Minimize,
@@ -203,6 +205,7 @@ pub fn initialize_workspace(
             activity_indicator::ActivityIndicator::new(workspace, app_state.languages.clone(), cx);
         let active_buffer_language =
             cx.new_view(|_| language_selector::ActiveBufferLanguage::new(workspace));
+        let active_buffer_encoding = cx.new_view(|_| language_selector::ActiveBufferEncoding::new());
         let vim_mode_indicator = cx.new_view(vim::ModeIndicator::new);
         let cursor_position =
             cx.new_view(|_| go_to_line::cursor_position::CursorPosition::new(workspace));
@@ -211,6 +214,7 @@ pub fn initialize_workspace(
             status_bar.add_left_item(activity_indicator, cx);
             status_bar.add_right_item(inline_completion_button, cx);
             status_bar.add_right_item(active_buffer_language, cx);
+            status_bar.add_right_item(active_buffer_encoding, cx);
             status_bar.add_right_item(vim_mode_indicator, cx);
             status_bar.add_right_item(cursor_position, cx);
         });
@@ -312,14 +316,17 @@ pub fn initialize_workspace(
                 OpenListener::global(cx).open_urls(vec![action.url.clone()])
             })
             .register_action(|_, action: &OpenBrowser, cx| cx.open_url(&action.url))
-            .register_action(move |_, _: &zed_actions::IncreaseBufferFontSize, cx| {
-                theme::adjust_buffer_font_size(cx, |size| *size += px(1.0))
+            .register_action(|workspace, _: &zed_actions::IncreaseBufferFontSize, cx| {
+                let fs = workspace.app_state().fs.clone();
+                theme::adjust_and_persist_buffer_font_size(fs, cx, |size| *size += px(1.0))
             })
-            .register_action(move |_, _: &zed_actions::DecreaseBufferFontSize, cx| {
-                theme::adjust_buffer_font_size(cx, |size| *size -= px(1.0))
+            .register_action(|workspace, _: &zed_actions::DecreaseBufferFontSize, cx| {
+                let fs = workspace.app_state().fs.clone();
+                theme::adjust_and_persist_buffer_font_size(fs, cx, |size| *size -= px(1.0))
             })
-            .register_action(move |_, _: &zed_actions::ResetBufferFontSize, cx| {
-                theme::reset_buffer_font_size(cx)
+            .register_action(|workspace, _: &zed_actions::ResetBufferFontSize, cx| {
+                let fs = workspace.app_state().fs.clone();
+                theme::reset_and_persist_buffer_font_size(fs, cx)
             })
             .register_action(move |_, _: &zed_actions::IncreaseUiFontSize, cx| {
                 theme::adjust_ui_font_size(cx, |size| *size += px(1.0))
@@ -330,15 +337,6 @@ pub fn initialize_workspace(
             .register_action(move |_, _: &zed_actions::ResetUiFontSize, cx| {
                 theme::reset_ui_font_size(cx)
             })
-            .register_action(move |_, _: &zed_actions::IncreaseBufferFontSize, cx| {
-                theme::adjust_buffer_font_size(cx, |size| *size += px(1.0))
-            })
-            .register_action(move |_, _: &zed_actions::DecreaseBufferFontSize, cx| {
-                theme::adjust_buffer_font_size(cx, |size| *size -= px(1.0))
-            })
-            .register_action(move |_, _: &zed_actions::ResetBufferFontSize, cx| {
-                theme::reset_buffer_font_size(cx)
-            })
             .register_action(|_, _: &install_cli::Install, cx| {
                 cx.spawn(|workspace, mut cx| async move {
                     if cfg!(target_os = "linux") {
@@ -403,6 +401,9 @@ pub fn initialize_workspace(
             .register_action(|workspace, _: &OpenLog, cx| {
                 open_log_file(workspace, cx);
             })
+            .register_action(|workspace, _: &ExportDiagnostics, cx| {
+                diagnostics_archive::export_diagnostics(workspace, cx);
+            })
             .register_action(|workspace, _: &zed_actions::OpenLicenses, cx| {
                 open_bundled_file(
                     workspace,
@@ -573,13 +574,34 @@ fn feature_gate_zed_pro_actions(cx: &mut AppContext) {
 }
 
 fn initialize_pane(workspace: &mut Workspace, pane: &View<Pane>, cx: &mut ViewContext<Workspace>) {
+    // Every pane's buffer search bar shares the same history.
+    let buffer_search_history = workspace
+        .panes()
+        .iter()
+        .filter(|other_pane| *other_pane != pane)
+        .find_map(|other_pane| {
+            other_pane
+                .read(cx)
+                .toolbar()
+                .read(cx)
+                .item_of_type::<search::BufferSearchBar>()
+        })
+        .map(|search_bar| search_bar.read(cx).search_history().clone())
+        .unwrap_or_else(|| {
+            let workspace_id = workspace.database_id();
+            cx.new_model(|cx| search::BufferSearchHistory::new(workspace_id, cx))
+        });
     pane.update(cx, |pane, cx| {
         pane.toolbar().update(cx, |toolbar, cx| {
             let multibuffer_hint = cx.new_view(|_| MultibufferHint::new());
             toolbar.add_item(multibuffer_hint, cx);
             let breadcrumbs = cx.new_view(|_| Breadcrumbs::new());
             toolbar.add_item(breadcrumbs, cx);
-            let buffer_search_bar = cx.new_view(search::BufferSearchBar::new);
+            let buffer_search_bar = cx.new_view(|cx| {
+                let mut buffer_search_bar = search::BufferSearchBar::new(cx);
+                buffer_search_bar.set_search_history(buffer_search_history);
+                buffer_search_bar
+            });
             toolbar.add_item(buffer_search_bar.clone(), cx);
 
             let quick_action_bar =
@@ -1065,6 +1087,7 @@ mod tests {
     };
     use theme::{ThemeRegistry, ThemeSettings};
     use workspace::{
+        dock::{test::TestPanel, DockPosition},
         item::{Item, ItemHandle},
         open_new, open_paths, pane, NewFile, OpenVisible, SaveIntent, SplitDirection,
         WorkspaceHandle,
@@ -1593,6 +1616,174 @@ mod tests {
             .unwrap();
     }
 
+    #[gpui::test]
+    async fn test_restore_workspace_by_id(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree("/root", json!({"a": "hey", "b": "there"}))
+            .await;
+
+        cx.update(|cx| {
+            open_paths(
+                &[PathBuf::from("/root")],
+                app_state.clone(),
+                workspace::OpenOptions::default(),
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+        let window = cx.update(|cx| cx.windows()[0].downcast::<Workspace>().unwrap());
+        window
+            .update(cx, |workspace, cx| {
+                workspace.open_abs_path(PathBuf::from("/root/b"), true, cx)
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        window
+            .update(cx, |workspace, cx| {
+                let panel = cx.new_view(|cx| TestPanel::new(DockPosition::Left, cx));
+                workspace.add_panel(panel, cx);
+                workspace.focus_panel::<TestPanel>(cx);
+            })
+            .unwrap();
+        cx.run_until_parked();
+
+        // Advance the clock to make sure the workspace is serialized
+        cx.executor().advance_clock(Duration::from_secs(1));
+        let workspace_id = window
+            .read_with(cx, |workspace, _| workspace.database_id())
+            .unwrap()
+            .unwrap();
+        assert!(!VisualTestContext::from_window(window.into(), cx).simulate_close());
+        cx.run_until_parked();
+        assert_eq!(cx.update(|cx| cx.windows().len()), 0);
+
+        let window = cx
+            .update(|cx| Workspace::restore(workspace_id, app_state.clone(), cx))
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        window
+            .update(cx, |workspace, cx| {
+                assert_eq!(workspace.database_id(), Some(workspace_id));
+                let editor = workspace
+                    .active_item(cx)
+                    .unwrap()
+                    .downcast::<Editor>()
+                    .unwrap();
+                assert_eq!(editor.read(cx).text(cx), "there");
+
+                // Panels are registered after the workspace is restored, at which point
+                // the dock's persisted state is applied to them.
+                let panel = cx.new_view(|cx| TestPanel::new(DockPosition::Left, cx));
+                workspace.add_panel(panel.clone(), cx);
+                let left_dock = workspace.left_dock().read(cx);
+                assert!(left_dock.is_open());
+                assert_eq!(
+                    left_dock.visible_panel().unwrap().panel_id(),
+                    panel.entity_id()
+                );
+            })
+            .unwrap();
+    }
+
+    #[gpui::test]
+    async fn test_restore_on_startup_disabled(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        let _dir = open_restorable_workspace(&app_state, cx).await;
+
+        for behavior in [
+            workspace::RestoreOnStartupBehavior::LastWorkspace,
+            workspace::RestoreOnStartupBehavior::LastSession,
+        ] {
+            set_restore_on_startup(behavior, cx);
+            assert!(restorable_workspaces(&app_state, cx).await.is_some());
+        }
+
+        // When restoring is disabled, launching without paths opens an empty window instead.
+        set_restore_on_startup(workspace::RestoreOnStartupBehavior::None, cx);
+        assert!(restorable_workspaces(&app_state, cx).await.is_none());
+    }
+
+    #[gpui::test]
+    async fn test_restore_skipped_when_crash_looping(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        let _dir = open_restorable_workspace(&app_state, cx).await;
+        set_restore_on_startup(workspace::RestoreOnStartupBehavior::LastWorkspace, cx);
+        assert!(restorable_workspaces(&app_state, cx).await.is_some());
+
+        let marker_dir = tempfile::tempdir().unwrap();
+        let marker_path = marker_dir.path().join("unclean_exits");
+        std::fs::write(&marker_path, "2").unwrap();
+        cx.update(|cx| {
+            cx.set_global(crate::reliability::UncleanExits::record_launch(
+                marker_path.clone(),
+            ))
+        });
+        assert!(restorable_workspaces(&app_state, cx).await.is_some());
+
+        // The launch above didn't exit cleanly either, which reaches the threshold.
+        cx.update(|cx| cx.set_global(crate::reliability::UncleanExits::record_launch(marker_path)));
+        assert!(restorable_workspaces(&app_state, cx).await.is_none());
+    }
+
+    /// Opens a workspace on a directory that also exists on disk, since only those are restored.
+    async fn open_restorable_workspace(
+        app_state: &Arc<AppState>,
+        cx: &mut TestAppContext,
+    ) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree(dir.path(), json!({"a": "hey"}))
+            .await;
+        cx.update(|cx| {
+            open_paths(
+                &[dir.path().to_path_buf()],
+                app_state.clone(),
+                workspace::OpenOptions::default(),
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+        cx.run_until_parked();
+
+        // Advance the clock to make sure the workspace is serialized
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        dir
+    }
+
+    fn set_restore_on_startup(
+        behavior: workspace::RestoreOnStartupBehavior,
+        cx: &mut TestAppContext,
+    ) {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.restore_on_startup = Some(behavior)
+                });
+            });
+        });
+    }
+
+    async fn restorable_workspaces(
+        app_state: &Arc<AppState>,
+        cx: &mut TestAppContext,
+    ) -> Option<Vec<(workspace::WorkspaceId, workspace::LocalPaths)>> {
+        cx.spawn({
+            let app_state = app_state.clone();
+            |mut cx| async move { crate::restorable_workspaces(&mut cx, &app_state).await }
+        })
+        .await
+    }
+
     #[gpui::test]
     async fn test_new_empty_workspace(cx: &mut TestAppContext) {
         let app_state = init_test(cx);
@@ -3195,6 +3386,89 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_invalid_keymap_bindings_are_skipped(cx: &mut gpui::TestAppContext) {
+        let executor = cx.executor();
+        let app_state = init_keymap_test(cx);
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+
+        actions!(test3, [A, B]);
+        // From the Atom keymap
+        use workspace::ActivatePreviousPane;
+        workspace
+            .update(cx, |workspace, _| {
+                workspace
+                    .register_action(|_, _: &A, _| {})
+                    .register_action(|_, _: &B, _| {});
+            })
+            .unwrap();
+        app_state
+            .fs
+            .save(
+                "/settings.json".as_ref(),
+                &r#"
+                {
+                    "base_keymap": "Atom"
+                }
+                "#
+                .into(),
+                Default::default(),
+            )
+            .await
+            .unwrap();
+        app_state
+            .fs
+            .save(
+                "/keymap.json".as_ref(),
+                &r#"
+                [
+                    {
+                        "bindings": {
+                            "backspace": "test3::A",
+                            "ctrl-x-y": "test3::B",
+                            "ctrl-z": "test3::DoesNotExist"
+                        }
+                    }
+                ]
+                "#
+                .into(),
+                Default::default(),
+            )
+            .await
+            .unwrap();
+
+        cx.update(|cx| {
+            let settings_rx = watch_config_file(
+                &executor,
+                app_state.fs.clone(),
+                PathBuf::from("/settings.json"),
+            );
+            let keymap_rx = watch_config_file(
+                &executor,
+                app_state.fs.clone(),
+                PathBuf::from("/keymap.json"),
+            );
+
+            handle_settings_file_changes(settings_rx, cx, |_, _| {});
+            handle_keymap_file_changes(keymap_rx, cx, |_, _| {});
+        });
+
+        cx.background_executor.run_until_parked();
+
+        // The valid user binding and the default bindings are still loaded.
+        assert_key_bindings_for(
+            workspace.into(),
+            cx,
+            vec![("backspace", &A), ("k", &ActivatePreviousPane)],
+            line!(),
+        );
+        let bindings = cx
+            .update(|cx| workspace.update(cx, |_, cx| cx.bindings_for_action(&B)))
+            .unwrap();
+        assert!(bindings.is_empty());
+    }
+
     #[gpui::test]
     async fn test_disabled_keymap_binding(cx: &mut gpui::TestAppContext) {
         let executor = cx.executor();
@@ -0,0 +1,271 @@
+use anyhow::{Context as _, Result};
+use async_zip::{base::write::ZipFileWriter, Compression, ZipEntryBuilder};
+use db::kvp::KEY_VALUE_STORE;
+use futures::{AsyncWrite, AsyncWriteExt as _};
+use gpui::ViewContext;
+use settings::parse_json_with_comments;
+use std::{collections::BTreeMap, path::Path};
+use workspace::{notifications::DetachAndPromptErr, Workspace};
+
+const REDACTED: &str = "<redacted>";
+
+/// Settings whose keys contain any of these words have their values redacted.
+const SENSITIVE_SETTING_KEYS: &[&str] = &["token", "password", "secret", "api_key"];
+
+/// The files collected for a diagnostics archive, before redaction.
+pub(crate) struct DiagnosticsInputs {
+    pub log: String,
+    pub settings: String,
+    pub client_snapshot: String,
+    pub key_value_store: Vec<(String, String)>,
+}
+
+/// Removes credentials and the user's home directory from the contents of a diagnostics archive.
+pub(crate) struct Redactor {
+    secrets: Vec<String>,
+    home_dir: String,
+}
+
+impl Redactor {
+    pub fn new(secrets: impl IntoIterator<Item = String>, home_dir: &Path) -> Self {
+        Self {
+            secrets: secrets
+                .into_iter()
+                .filter(|secret| !secret.is_empty())
+                .collect(),
+            home_dir: home_dir
+                .to_string_lossy()
+                .trim_end_matches(std::path::is_separator)
+                .to_string(),
+        }
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for secret in &self.secrets {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+        // Replacing a root home directory would mangle every path.
+        if !self.home_dir.is_empty() {
+            text = self.redact_home_dir(&text);
+        }
+        text
+    }
+
+    /// Replaces the home directory with `~` in the paths mentioned in the text. Only paths
+    /// within it are replaced, so `/home/janet` is kept as-is for a home directory of `/home/jan`.
+    fn redact_home_dir(&self, text: &str) -> String {
+        let mut redacted = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(ix) = rest.find(self.home_dir.as_str()) {
+            let (before, candidate) = rest.split_at(ix);
+            redacted.push_str(before);
+
+            let path_len = candidate.find(is_path_delimiter).unwrap_or(candidate.len());
+            let (path, after) = candidate.split_at(path_len);
+            let starts_path = before.chars().last().map_or(true, is_path_delimiter);
+            if starts_path && Path::new(path).strip_prefix(&self.home_dir).is_ok() {
+                redacted.push('~');
+                redacted.push_str(&path[self.home_dir.len()..]);
+            } else {
+                redacted.push_str(path);
+            }
+            rest = after;
+        }
+        redacted.push_str(rest);
+        redacted
+    }
+
+    /// Redacts the user's settings file, also hiding the values of settings that hold credentials.
+    pub fn redact_settings(&self, content: &str) -> String {
+        match parse_json_with_comments::<serde_json::Value>(content) {
+            Ok(mut settings) => {
+                redact_sensitive_settings(&mut settings);
+                self.redact(&serde_json::to_string_pretty(&settings).unwrap_or_default())
+            }
+            // Without parsing, credentials can't be found by key, so leave the settings out.
+            Err(error) => format!("// failed to parse settings: {error}"),
+        }
+    }
+}
+
+/// Whether the character ends a path mentioned in a log line or a JSON string.
+fn is_path_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '"' | '\'' | '`' | ',' | ';' | '(' | ')' | '[' | ']')
+}
+
+fn redact_sensitive_settings(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                let key = key.to_lowercase();
+                if SENSITIVE_SETTING_KEYS
+                    .iter()
+                    .any(|sensitive_key| key.contains(sensitive_key))
+                {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_sensitive_settings(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_sensitive_settings),
+        _ => {}
+    }
+}
+
+/// Writes the redacted diagnostics into a zip archive.
+pub(crate) async fn write_diagnostics_archive<W: AsyncWrite + Unpin>(
+    inputs: DiagnosticsInputs,
+    redactor: &Redactor,
+    writer: W,
+) -> Result<W> {
+    let key_value_store = inputs
+        .key_value_store
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    let entries = [
+        ("zed.log", redactor.redact(&inputs.log)),
+        ("settings.json", redactor.redact_settings(&inputs.settings)),
+        ("client.json", redactor.redact(&inputs.client_snapshot)),
+        (
+            "key_value_store.json",
+            redactor.redact(&serde_json::to_string_pretty(&key_value_store)?),
+        ),
+    ];
+
+    let mut writer = ZipFileWriter::new(writer);
+    for (name, content) in entries {
+        let entry = ZipEntryBuilder::new(name.to_string().into(), Compression::Deflate);
+        writer
+            .write_entry_whole(entry, content.as_bytes())
+            .await
+            .with_context(|| format!("writing {name} to the diagnostics archive"))?;
+    }
+    Ok(writer.close().await?)
+}
+
+/// Prompts for a location and saves a zip archive with Zed's log, settings, connection state and
+/// key-value store there, for attaching to bug reports.
+pub(crate) fn export_diagnostics(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    let client = workspace.client().clone();
+    let fs = workspace.app_state().fs.clone();
+    let path = cx.prompt_for_new_path(paths::home_dir());
+    cx.spawn(|_, _| async move {
+        let Some(path) = path.await?? else {
+            return Ok(());
+        };
+
+        let client_snapshot = serde_json::to_string_pretty(&client.debug_snapshot())?;
+        let inputs = DiagnosticsInputs {
+            log: fs.load(paths::log_file()).await.unwrap_or_default(),
+            settings: fs.load(paths::settings_file()).await.unwrap_or_default(),
+            client_snapshot,
+            key_value_store: KEY_VALUE_STORE.key_value_pairs().await?,
+        };
+        let redactor = Redactor::new(client.access_token(), paths::home_dir());
+
+        let file = smol::fs::File::create(&path)
+            .await
+            .with_context(|| format!("creating {path:?}"))?;
+        let mut file = write_diagnostics_archive(inputs, &redactor, file).await?;
+        file.flush().await?;
+        anyhow::Ok(())
+    })
+    .detach_and_prompt_err("Failed to export diagnostics", cx, |_, _| None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_zip::base::read::mem::ZipFileReader;
+    use futures::{io::Cursor, AsyncReadExt as _};
+    use std::path::PathBuf;
+
+    #[gpui::test]
+    async fn test_write_diagnostics_archive() {
+        let token = "secret-access-token".to_string();
+        let redactor = Redactor::new([token.clone()], &PathBuf::from("/home/jane"));
+        let inputs = DiagnosticsInputs {
+            log: format!("opened /home/jane/project\nauthenticating with {token}\n"),
+            settings: r#"{
+                // Comments are allowed in settings.
+                "theme": "One Dark",
+                "language_models": {
+                    "openai": { "api_key": "sk-123", "api_url": "https://example.com" }
+                },
+                "lsp": { "rust-analyzer": { "binary": { "path": "/home/jane/bin/ra" } } }
+            }"#
+            .to_string(),
+            client_snapshot: format!(r#"{{"status": "Connected", "credentials": "{token}"}}"#),
+            key_value_store: vec![("last_project".into(), "/home/jane/project".into())],
+        };
+
+        let archive = write_diagnostics_archive(inputs, &redactor, Cursor::new(Vec::new()))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let reader = ZipFileReader::new(archive).await.unwrap();
+        let mut files = BTreeMap::new();
+        for index in 0..reader.file().entries().len() {
+            let name = reader.file().entries()[index]
+                .filename()
+                .as_str()
+                .unwrap()
+                .to_string();
+            let mut content = String::new();
+            reader
+                .reader_without_entry(index)
+                .await
+                .unwrap()
+                .read_to_string(&mut content)
+                .await
+                .unwrap();
+            files.insert(name, content);
+        }
+
+        assert_eq!(
+            files.keys().map(String::as_str).collect::<Vec<_>>(),
+            [
+                "client.json",
+                "key_value_store.json",
+                "settings.json",
+                "zed.log"
+            ]
+        );
+        for content in files.values() {
+            assert!(!content.contains(&token), "token leaked in {content:?}");
+            assert!(
+                !content.contains("/home/jane"),
+                "home dir leaked in {content:?}"
+            );
+        }
+        assert_eq!(
+            files["zed.log"],
+            "opened ~/project\nauthenticating with <redacted>\n"
+        );
+        assert!(!files["settings.json"].contains("sk-123"));
+        assert!(files["settings.json"].contains("https://example.com"));
+        assert!(files["settings.json"].contains("~/bin/ra"));
+        assert!(files["key_value_store.json"].contains("~/project"));
+    }
+
+    #[test]
+    fn test_redact_home_dir_by_whole_components() {
+        let redactor = Redactor::new([], Path::new("/home/jan/"));
+        assert_eq!(
+            redactor
+                .redact(r#"opened /home/jan/a, /home/janet/b and "/home/jan" (/srv/home/jan/c)"#),
+            r#"opened ~/a, /home/janet/b and "~" (/srv/home/jan/c)"#
+        );
+    }
+
+    #[test]
+    fn test_redact_unparseable_settings() {
+        let redactor = Redactor::new([], Path::new("/home/jane"));
+        let redacted = redactor.redact_settings(r#"{ "api_key": "sk-123" "#);
+        assert!(redacted.starts_with("// failed to parse settings"));
+        assert!(!redacted.contains("sk-123"));
+    }
+}
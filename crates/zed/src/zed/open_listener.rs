@@ -1,4 +1,4 @@
-use crate::restorable_workspace_locations;
+use crate::restorable_workspaces;
 use crate::{handle_open_request, init_headless, init_ui};
 use anyhow::{anyhow, Context, Result};
 use assistant::PromptBuilder;
@@ -214,6 +214,7 @@ pub async fn open_paths_with_positions(
         })
         .collect::<Vec<_>>();
 
+    let readonly = open_options.readonly;
     let (workspace, items) = cx
         .update(|cx| workspace::open_paths(&paths, app_state, open_options, cx))?
         .await?;
@@ -222,6 +223,17 @@ pub async fn open_paths_with_positions(
         let Some(Ok(item)) = item else {
             continue;
         };
+        if readonly {
+            // Only lock the editor, so that other editors for the same buffer stay editable.
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor
+                    .update(cx, |editor, cx| {
+                        editor.set_read_only(true);
+                        cx.notify();
+                    })
+                    .log_err();
+            }
+        }
         let Some(point) = caret_positions.remove(path) else {
             continue;
         };
@@ -255,6 +267,7 @@ pub async fn handle_cli_connection(
                 urls,
                 paths,
                 wait,
+                readonly,
                 open_new_workspace,
                 dev_server_token,
                 env,
@@ -332,6 +345,7 @@ pub async fn handle_cli_connection(
                     open_new_workspace,
                     &responses,
                     wait,
+                    readonly,
                     app_state.clone(),
                     env,
                     &mut cx,
@@ -350,6 +364,7 @@ async fn open_workspaces(
     open_new_workspace: Option<bool>,
     responses: &IpcSender<CliResponse>,
     wait: bool,
+    readonly: bool,
     app_state: Arc<AppState>,
     env: Option<collections::HashMap<String, String>>,
     cx: &mut AsyncAppContext,
@@ -359,13 +374,13 @@ async fn open_workspaces(
         if open_new_workspace == Some(true) {
             Vec::new()
         } else {
-            let locations = restorable_workspace_locations(cx, &app_state).await;
-            locations
+            let workspaces = restorable_workspaces(cx, &app_state).await;
+            workspaces
                 .into_iter()
-                .flat_map(|locations| {
-                    locations
+                .flat_map(|workspaces| {
+                    workspaces
                         .into_iter()
-                        .map(|location| {
+                        .map(|(_, location)| {
                             location
                                 .paths()
                                 .iter()
@@ -420,6 +435,7 @@ async fn open_workspaces(
                 workspace_paths,
                 open_new_workspace,
                 wait,
+                readonly,
                 responses,
                 env.as_ref(),
                 &app_state,
@@ -444,6 +460,7 @@ async fn open_workspace(
     workspace_paths: Vec<PathWithPosition>,
     open_new_workspace: Option<bool>,
     wait: bool,
+    readonly: bool,
     responses: &IpcSender<CliResponse>,
     env: Option<&HashMap<String, String>>,
     app_state: &Arc<AppState>,
@@ -457,6 +474,7 @@ async fn open_workspace(
         workspace::OpenOptions {
             open_new_workspace,
             env: env.cloned(),
+            readonly,
             ..Default::default()
         },
         cx,
@@ -657,6 +675,49 @@ mod tests {
             .unwrap();
     }
 
+    #[gpui::test]
+    async fn test_open_workspace_read_only(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree("/root", json!({ "file.txt": "content" }))
+            .await;
+
+        let (response_tx, _) = ipc::channel::<CliResponse>().unwrap();
+        let errored = cx
+            .spawn({
+                let app_state = app_state.clone();
+                |mut cx| async move {
+                    open_workspace(
+                        vec![PathWithPosition::from_path(PathBuf::from("/root/file.txt"))],
+                        None,
+                        false,
+                        true,
+                        &response_tx,
+                        None,
+                        &app_state,
+                        &mut cx,
+                    )
+                    .await
+                }
+            })
+            .await;
+        assert!(!errored);
+
+        // Only the opened editor is read-only, not the buffer shared with other editors.
+        let workspace = cx.windows()[0].downcast::<Workspace>().unwrap();
+        workspace
+            .update(cx, |workspace, cx| {
+                let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+                let editor = editor.read(cx);
+                assert!(editor.read_only(cx));
+                let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+                assert!(!buffer.read(cx).read_only());
+            })
+            .unwrap();
+    }
+
     async fn open_workspace_file(
         path: &str,
         open_new_workspace: Option<bool>,
@@ -678,6 +739,7 @@ mod tests {
                     workspace_paths,
                     open_new_workspace,
                     false,
+                    false,
                     &response_tx,
                     None,
                     &app_state,